@@ -0,0 +1,59 @@
+use bible_ref::BibleSearch;
+use sqlx::postgres::PgPoolOptions;
+
+use crate::render::Verse;
+
+/// The fetch function reads the verses of each search from the active
+/// version of a translation in the API's database, in the order the
+/// searches were given.
+pub async fn fetch(
+    database_url: &str,
+    translation: &str,
+    searches: &[BibleSearch],
+) -> Result<Vec<Vec<Verse>>, String> {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(database_url)
+        .await
+        .map_err(|err| format!("can not connect to the database: {}", err))?;
+
+    let mut passages = vec![];
+    for bible_search in searches {
+        let (chapters, verses): (Vec<i32>, Vec<i32>) =
+            crate::get_verses(bible_search).into_iter().unzip();
+
+        let rows = sqlx::query_as!(
+            Verse,
+            r#"
+                SELECT
+                    v.title as title,
+                    v.chapter_num as chapter,
+                    v.num as verse,
+                    v.contents as text,
+                    v.paragraph_start as paragraph_start
+                FROM unnest($2::int[], $3::int[]) AS w(chapter, verse)
+                    INNER JOIN verses v ON v.title = $1
+                        AND v.chapter_num = w.chapter
+                        AND v.num = w.verse
+                WHERE v.version = (
+                    SELECT t.version FROM translation_versions t
+                    WHERE t.translation = $4 AND t.state = 'active'
+                )
+              ORDER BY w.chapter, w.verse
+            "#,
+            bible_search.title,
+            &chapters[..],
+            &verses[..],
+            translation,
+        )
+        .fetch_all(&pool)
+        .await
+        .map_err(|err| format!("can not read the verses: {}", err))?;
+
+        passages.push(rows);
+    }
+
+    pool.close().await;
+
+    Ok(passages)
+}
@@ -0,0 +1,75 @@
+use bible_ref::BibleSearch;
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::render::Verse;
+
+/// The TRANSLATION is the translation of the text built in.
+pub const TRANSLATION: &str = "kjv";
+
+/// The TEXT is the KJV, a verse per line in the same shape as the API's
+/// ndjson format, built into the binary so it runs without a database.
+const TEXT: &str = include_str!("../../db/kjv.ndjson");
+
+// The verses are kept by book and chapter, in verse order
+type Chapters = HashMap<(String, i32), Vec<Verse>>;
+
+/// The fetch function returns the verses of each search from the text built
+/// in, in the order the searches were given.
+pub fn fetch(searches: &[BibleSearch]) -> Vec<Vec<Verse>> {
+    let chapters = get_chapters();
+
+    searches
+        .iter()
+        .map(|bible_search| {
+            crate::get_verses(bible_search)
+                .into_iter()
+                .filter_map(|(chapter, verse)| {
+                    let verses = chapters.get(&(bible_search.title.clone(), chapter))?;
+                    let index = verses
+                        .binary_search_by_key(&verse, |found| found.verse)
+                        .ok()?;
+                    Some(verses[index].clone())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// The text is only read the first time it is needed
+fn get_chapters() -> &'static Chapters {
+    static CHAPTERS: OnceLock<Chapters> = OnceLock::new();
+
+    CHAPTERS.get_or_init(|| {
+        let mut chapters: Chapters = HashMap::new();
+        for line in TEXT.lines().filter(|line| !line.trim().is_empty()) {
+            let verse: Verse = serde_json::from_str(line).expect("the text built in is valid");
+            chapters
+                .entry((verse.title.clone(), verse.chapter))
+                .or_default()
+                .push(verse);
+        }
+
+        for verses in chapters.values_mut() {
+            verses.sort_by_key(|verse| verse.verse);
+        }
+
+        chapters
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetch_finds_the_verses_of_each_search() {
+        let searches =
+            bible_ref::search_passages("John 3:16; Jude", &Default::default(), false).unwrap();
+        let verses = fetch(&searches);
+
+        assert_eq!(verses[0].len(), 1);
+        assert!(verses[0][0].text.starts_with("For God so loved the world"));
+        assert_eq!(verses[1].len(), 25);
+        assert_eq!(verses[1][24].verse, 25);
+    }
+}
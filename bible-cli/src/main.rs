@@ -0,0 +1,128 @@
+mod db;
+mod embedded;
+mod render;
+
+use bible_ref::{get_reference, search::is_whole_chapter, search_passages, BibleSearch};
+use clap::Parser;
+use std::process;
+
+use render::{Format, Passage};
+
+/// The Cli is the command line of the bible tool: the reference to look up,
+/// and where its verses are read from and how they are printed.
+#[derive(Debug, Parser)]
+#[command(name = "bible", version, about)]
+struct Cli {
+    /// The reference to look up (ex: John 3:16-18; Romans 8:28), which does
+    /// not need to be quoted
+    #[arg(required = true)]
+    reference: Vec<String>,
+    /// How the verses are printed
+    #[arg(long, short, value_enum, default_value_t = Format::Plain)]
+    format: Format,
+    /// The translation to print (ex: kjv). Only the KJV is built in, the
+    /// others are read from a database
+    #[arg(long, short, default_value = embedded::TRANSLATION)]
+    translation: String,
+    /// The Postgres database of the API to read the verses from, instead of
+    /// the text built in
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: Option<String>,
+}
+
+/// The main function looks up the reference and prints its verses, exiting
+/// with a non-zero status when the reference can not be read or has no
+/// verses.
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let reference = cli.reference.join(" ");
+    let translation = cli.translation.trim().to_lowercase();
+
+    let searches = search_passages(&reference, &Default::default(), false)
+        .unwrap_or_else(|err| fail(&err.to_string()));
+
+    let verses = match cli.database_url.as_deref() {
+        Some(database_url) => db::fetch(database_url, &translation, &searches).await,
+        None if translation == embedded::TRANSLATION => Ok(embedded::fetch(&searches)),
+        None => Err(format!(
+            "only {} is built in, give a --database-url to read {} from",
+            embedded::TRANSLATION,
+            translation
+        )),
+    }
+    .unwrap_or_else(|err| fail(&err));
+
+    let passages = searches
+        .iter()
+        .zip(verses)
+        .map(|(bible_search, verses)| Passage {
+            reference: get_reference(bible_search),
+            verses,
+        })
+        .collect::<Vec<Passage>>();
+    if passages.iter().all(|passage| passage.verses.is_empty()) {
+        fail("No Results Found");
+    }
+
+    match render::render(cli.format, &passages) {
+        Ok(output) => print!("{}", output),
+        Err(err) => fail(&format!("can not write the verses: {}", err)),
+    }
+}
+
+/// The get_verses function returns the chapter and verse of every verse of a
+/// search, in order, with the superscription of each chapter when the search
+/// is of whole chapters (ex: Psalms 3).
+pub fn get_verses(bible_search: &BibleSearch) -> Vec<(i32, i32)> {
+    let whole_chapter = is_whole_chapter(bible_search);
+
+    bible_search
+        .chapters
+        .iter()
+        .flat_map(|chapter| {
+            let mut verses = chapter.verses.iter().copied().collect::<Vec<u8>>();
+            if whole_chapter {
+                verses.push(bible_ref::verse::SUPERSCRIPTION_VERSE);
+            }
+            verses.sort_unstable();
+
+            verses
+                .into_iter()
+                .map(|verse| (i32::from(chapter.chapter), i32::from(verse)))
+        })
+        .collect()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_verses_adds_the_superscription_of_whole_chapters() {
+        let psalm = bible_ref::search("Psalms 3").unwrap();
+        let verses = get_verses(&psalm);
+
+        assert_eq!(verses.len(), 9);
+        assert_eq!(verses[0], (3, 0));
+        assert_eq!(
+            get_verses(&bible_ref::search("John 3:17, 16").unwrap()),
+            vec![(3, 16), (3, 17)]
+        );
+    }
+
+    #[test]
+    fn cli_reads_a_reference_that_is_not_quoted() {
+        let cli = Cli::try_parse_from(["bible", "-f", "md", "1", "John", "3:16"]).unwrap();
+
+        assert_eq!(cli.reference.join(" "), "1 John 3:16");
+        assert_eq!(cli.format, Format::Md);
+        assert!(Cli::try_parse_from(["bible"]).is_err());
+        assert!(Cli::try_parse_from(["bible", "--format", "html", "John 3"]).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The Verse is a verse as the bible tool prints it, in the same shape as
+/// the API's ndjson format.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Verse {
+    pub title: String,
+    pub chapter: i32,
+    pub verse: i32,
+    pub text: String,
+    pub paragraph_start: bool,
+}
+
+/// The Passage is the verses of one passage of the reference looked up,
+/// with the passage's canonical reference (ex: John 3:16-18).
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Passage {
+    pub reference: String,
+    pub verses: Vec<Verse>,
+}
+
+/// The Format is how the verses are printed.
+/// - Plain (plain) is the reference, then a line per paragraph with each
+///   verse led by its number (the default)
+/// - Json (json) is every passage with its verses, for scripts
+/// - Md (md) is Markdown with the reference as a heading and the verse
+///   numbers in bold
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum Format {
+    Plain,
+    Json,
+    Md,
+}
+
+/// The render function writes the passages in a format, each passage
+/// separated from the next by a blank line.
+pub fn render(format: Format, passages: &[Passage]) -> Result<String, serde_json::Error> {
+    let render_passage = match format {
+        Format::Json => return serde_json::to_string_pretty(passages).map(|json| json + "\n"),
+        Format::Plain => render_plain,
+        Format::Md => render_markdown,
+    };
+
+    Ok(passages
+        .iter()
+        .filter(|passage| !passage.verses.is_empty())
+        .map(render_passage)
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn render_plain(passage: &Passage) -> String {
+    let body = paragraphs(&passage.verses)
+        .into_iter()
+        .map(|paragraph| {
+            join_verses(
+                paragraph,
+                |verse| verse.text.clone(),
+                |verse| format!("{} {}", verse.verse, verse.text),
+            )
+        })
+        .collect::<Vec<String>>();
+
+    format!("{}\n{}\n", passage.reference, body.join("\n"))
+}
+
+fn render_markdown(passage: &Passage) -> String {
+    let body = paragraphs(&passage.verses)
+        .into_iter()
+        .map(|paragraph| {
+            join_verses(
+                paragraph,
+                |verse| format!("*{}*", verse.text),
+                |verse| format!("**{}** {}", verse.verse, verse.text),
+            )
+        })
+        .collect::<Vec<String>>();
+
+    format!("## {}\n\n{}\n", passage.reference, body.join("\n\n"))
+}
+
+// Join the verses of a paragraph, writing a superscription one way and any
+// other verse the other
+fn join_verses(
+    paragraph: &[Verse],
+    superscription: impl Fn(&Verse) -> String,
+    verse: impl Fn(&Verse) -> String,
+) -> String {
+    paragraph
+        .iter()
+        .map(|found| match found.verse {
+            0 => superscription(found),
+            _ => verse(found),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Split the verses where a paragraph starts or the chapter changes
+fn paragraphs(verses: &[Verse]) -> Vec<&[Verse]> {
+    let mut paragraphs = vec![];
+    let mut start = 0;
+
+    for (index, pair) in verses.windows(2).enumerate() {
+        if pair[1].paragraph_start || pair[1].chapter != pair[0].chapter {
+            paragraphs.push(&verses[start..=index]);
+            start = index + 1;
+        }
+    }
+
+    if start < verses.len() {
+        paragraphs.push(&verses[start..]);
+    }
+
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passage() -> Passage {
+        let verse = |verse, text: &str, paragraph_start| Verse {
+            title: String::from("Psalms"),
+            chapter: 3,
+            verse,
+            text: text.to_owned(),
+            paragraph_start,
+        };
+
+        Passage {
+            reference: String::from("Psalms 3:0-2"),
+            verses: vec![
+                verse(0, "A Psalm of David.", false),
+                verse(1, "Lord, how are they increased", true),
+                verse(2, "Many there be", false),
+            ],
+        }
+    }
+
+    #[test]
+    fn render_writes_plain_text_a_line_per_paragraph() {
+        assert_eq!(
+            render(Format::Plain, &[passage()]).unwrap(),
+            "Psalms 3:0-2\nA Psalm of David.\n1 Lord, how are they increased 2 Many there be\n"
+        );
+    }
+
+    #[test]
+    fn render_writes_markdown_with_the_reference_as_a_heading() {
+        assert_eq!(
+            render(Format::Md, &[passage()]).unwrap(),
+            "## Psalms 3:0-2\n\n*A Psalm of David.*\n\n**1** Lord, how are they increased **2** Many there be\n"
+        );
+    }
+
+    #[test]
+    fn render_writes_json_and_separates_passages() {
+        let json = render(Format::Json, &[passage()]).unwrap();
+        let read: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(read[0]["reference"], "Psalms 3:0-2");
+        assert_eq!(read[0]["verses"][1]["verse"], 1);
+        assert_eq!(
+            render(Format::Plain, &[passage(), passage()])
+                .unwrap()
+                .matches("Psalms 3:0-2\n")
+                .count(),
+            2
+        );
+    }
+}
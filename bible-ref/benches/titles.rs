@@ -0,0 +1,44 @@
+//! The titles benchmark times resolving the book of a query, which every
+//! search does first. The queries are a mix of full titles, abbreviations,
+//! numbered books and titles no book has.
+//!
+//! cargo bench -p bible-ref --bench titles
+
+use bible_ref::book::{get_title, get_title_candidates};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const QUERIES: [&str; 12] = [
+    "Genesis 1:1",
+    "gen 1",
+    "Ps 23",
+    "first jn 3:16-18, 20",
+    "iii John 4",
+    "Song of Solomon 2:1",
+    "Rev. 22:21",
+    "jude 3",
+    "ju 1",
+    "Philemon 1:4",
+    "Mal 4:6",
+    "Hezekiah 1:1",
+];
+
+fn titles(c: &mut Criterion) {
+    c.bench_function("get_title", |b| {
+        b.iter(|| {
+            for query in QUERIES {
+                black_box(get_title(black_box(query)));
+            }
+        })
+    });
+
+    c.bench_function("get_title_candidates", |b| {
+        b.iter(|| {
+            for query in QUERIES {
+                black_box(get_title_candidates(black_box(query)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, titles);
+criterion_main!(benches);
@@ -0,0 +1,1638 @@
+use regex::{Captures, Regex, RegexSet};
+use serde::Serialize;
+use std::{str::FromStr, sync::OnceLock};
+
+use crate::{
+    canon::{get_canon, get_deuterocanonical_books, Canon},
+    chapter::{get_books, BOOKS, OSIS_BOOKS},
+    reference::{split_book, REFERENCE_PUNCTUATION},
+};
+
+/// The ONES, TWOS, and THREES constants are used to build the regex pattern
+/// to match the optional book number at the beginning of a bible search.
+/// This number can have many forms, such as: 2st, i, one, 1, fst, first, etc.
+const ONES: &str = r"(?i)one|fst|first|1(st)?|i\s+";
+const TWOS: &str = r"(?i)two|sec(o(n(d)?)?)?|2(nd)?|ii\s+";
+const THREES: &str = r"(?i)thr(e(e)?)?|thi(r(d)?)?|3(rd)?|iii\s+";
+
+/// The BOOK_TEXT constant is used to build the regex pattern to match the
+/// book title. The book title can be any non-digit character. This is
+/// because the book title can be any number of words.
+/// (e.g. 1 John, Song of Solomon)
+const BOOK_TEXT: &str = r"(?i)(?<book_text>\D+)";
+
+/// The NON_NAME_CHARS matches any non-name characters at the end of the
+/// title. This is used to remove any non-name characters from the title,
+/// including the period of an abbreviation (ex: Rom.) and any trailing
+/// reference punctuation.
+const NON_NAME_CHARS: &str = r"[\d|:|-|_|\s|.|,|;]";
+
+/// The AmbiguityPolicy decides which book wins when the matchers of more than
+/// one book accept the same title. It is set once (see set_ambiguity_policy).
+/// - LongestMatch (longest-match) picks the book whose title the query spells
+///   out the most of, falling back to canonical order on a tie (the default)
+/// - CanonicalOrder (canonical-order) picks the book that comes first in the bible
+/// - Reject (reject) refuses to pick, so the query does not match any book
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AmbiguityPolicy {
+    LongestMatch,
+    CanonicalOrder,
+    Reject,
+}
+
+impl FromStr for AmbiguityPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy.trim().to_lowercase().as_str() {
+            "longest-match" => Ok(AmbiguityPolicy::LongestMatch),
+            "canonical-order" => Ok(AmbiguityPolicy::CanonicalOrder),
+            "reject" | "reject-as-ambiguous" => Ok(AmbiguityPolicy::Reject),
+            other => Err(format!("Unknown ambiguity policy: {}", other)),
+        }
+    }
+}
+
+static AMBIGUITY_POLICY: OnceLock<AmbiguityPolicy> = OnceLock::new();
+
+/// The set_ambiguity_policy function sets the AmbiguityPolicy for the rest
+/// of the process. It has to be called before the first title is looked up,
+/// and returns false when it is too late. Until it is set, LongestMatch is
+/// used.
+pub fn set_ambiguity_policy(policy: AmbiguityPolicy) -> bool {
+    AMBIGUITY_POLICY.set(policy).is_ok()
+}
+
+fn get_ambiguity_policy() -> AmbiguityPolicy {
+    *AMBIGUITY_POLICY.get_or_init(|| AmbiguityPolicy::LongestMatch)
+}
+
+/// The get_title function takes a query passed in by a user and returns either
+/// the proper name for the book as it exists in the DB, or None if the query
+/// does not match a book.
+pub fn get_title(query: &str) -> Option<String> {
+    // Get the title as the user typed it
+    let title = get_raw_title(query)?;
+
+    // Get the proper title using the search data provided
+    let proper_title = get_proper_title(title.as_str());
+
+    // Return the title
+    proper_title
+}
+
+/// The get_osis_title function takes an OSIS book id (ex: 1John) and returns
+/// the proper name of the book, or None if it is not one. The case of the id
+/// is not checked.
+pub fn get_osis_title(osis_book: &str) -> Option<&'static str> {
+    OSIS_BOOKS
+        .iter()
+        .position(|id| id.eq_ignore_ascii_case(osis_book))
+        .map(|index| BOOKS[index])
+        .or_else(|| {
+            get_deuterocanonical_books()
+                .find(|book| book.osis.eq_ignore_ascii_case(osis_book))
+                .map(|book| book.title)
+        })
+}
+
+/// The get_raw_title function returns the book portion of the query as the
+/// user typed it, with the book number normalized (ex: "first jo" -> "1 jo").
+pub fn get_raw_title(query: &str) -> Option<String> {
+    // Get the regex to match the book title
+    let matcher = get_book_regex();
+
+    // Get the captures from the regex
+    let captures = matcher.captures(query)?;
+
+    // Get the title from the captures
+    get_title_from_captures(captures)
+}
+
+/// The TitleCandidate is a book a query could refer to. The score is 1 when
+/// the book's own matcher accepts the query, otherwise it is the share of the
+/// book title the query spells out (ex: "ju" is half of "jude").
+#[derive(Debug, PartialEq, Clone)]
+pub struct TitleCandidate {
+    pub title: String,
+    pub score: f32,
+}
+
+/// The get_title_candidates function returns every book the query could
+/// refer to, best first. Books whose matcher accepts the query come first,
+/// followed by books whose title merely starts with what was typed.
+pub fn get_title_candidates(query: &str) -> Vec<TitleCandidate> {
+    let raw_title = match get_raw_title(query) {
+        Some(raw_title) => raw_title,
+        None => return Vec::new(),
+    };
+
+    let typed = get_comparable_title(&raw_title);
+    let matching = get_matching_titles(&raw_title);
+
+    let mut candidates: Vec<TitleCandidate> = get_books()
+        .iter()
+        .filter_map(|book| {
+            let comparable = get_comparable_title(book);
+
+            let score = if matching.contains(book) {
+                1.0
+            } else if typed.chars().filter(|c| c.is_alphabetic()).count() >= 2
+                && comparable.starts_with(&typed)
+            {
+                typed.len() as f32 / comparable.len() as f32
+            } else {
+                return None;
+            };
+
+            Some(TitleCandidate {
+                title: book.to_string(),
+                score,
+            })
+        })
+        .collect();
+
+    // The books are in canonical order, so a stable sort breaks ties
+    // canonically
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    candidates
+}
+
+// Lowercase the title and drop the spaces and punctuation so "1 Jo",
+// "1jo" and "1 Jo." compare equal
+fn get_comparable_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| !c.is_whitespace() && !REFERENCE_PUNCTUATION.contains(c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The get_params function returns everything after the book title in the
+/// query (ex: "2:3-5" for "1 John 2:3-5"), or None if there is nothing after it.
+pub fn get_params(query: &str) -> Option<String> {
+    // The tokenizer knows where the book ends and the params begin
+    let (_, params) = split_book(query);
+
+    params.map(str::to_owned)
+}
+
+fn get_proper_title(title: &str) -> Option<String> {
+    // Find every book that matches, then let the policy pick between them
+    let matching = get_matching_titles(title);
+
+    choose_title(title, &matching, get_ambiguity_policy()).map(str::to_owned)
+}
+
+// The get_matching_titles function returns every book of the canon whose
+// matcher accepts the title, rather than just the first one found. Every
+// matcher is tried in a single pass over the title, and the books come back
+// in canonical order, so the result is the same from run to run.
+fn get_matching_titles(title: &str) -> Vec<&'static str> {
+    get_matching_titles_in(title, get_canon())
+}
+
+fn get_matching_titles_in(title: &str, canon: Canon) -> Vec<&'static str> {
+    let (books, matchers) = get_matcher_set();
+
+    matchers
+        .matches(title)
+        .into_iter()
+        .map(|index| books[index])
+        .filter(|book| canon.contains(book))
+        .collect()
+}
+
+// The book matchers are compiled into one set the first time a title is
+// looked up, and kept for every lookup after it. The books are in the order
+// of the matchers in the set. The set holds the books of every canon, so a
+// book outside the canon is dropped from what it matches.
+fn get_matcher_set() -> &'static (Vec<&'static str>, RegexSet) {
+    static MATCHER_SET: OnceLock<(Vec<&'static str>, RegexSet)> = OnceLock::new();
+
+    MATCHER_SET.get_or_init(|| {
+        let (books, matchers): (Vec<&str>, Vec<String>) = get_book_matchers().into_iter().unzip();
+        (books, RegexSet::new(matchers).unwrap())
+    })
+}
+
+// The choose_title function applies the ambiguity policy to the matching
+// books, which must be in canonical order.
+fn choose_title<'a>(title: &str, matching: &[&'a str], policy: AmbiguityPolicy) -> Option<&'a str> {
+    match (matching, policy) {
+        ([], _) => None,
+        ([only], _) => Some(only),
+        (_, AmbiguityPolicy::Reject) => None,
+        ([first, ..], AmbiguityPolicy::CanonicalOrder) => Some(first),
+        (_, AmbiguityPolicy::LongestMatch) => {
+            let typed = get_comparable_title(title).len() as f32;
+
+            // Keep the first of equally good matches so ties go canonically
+            matching
+                .iter()
+                .copied()
+                .fold(None, |best, book| {
+                    let coverage = typed / get_comparable_title(book).len() as f32;
+                    match best {
+                        Some((_, best_coverage)) if best_coverage >= coverage => best,
+                        _ => Some((book, coverage)),
+                    }
+                })
+                .map(|(book, _)| book)
+        }
+    }
+}
+
+fn get_book_matchers() -> Vec<(&'static str, String)> {
+    // This is a list of regex to recognize the proper title of a book
+    // and return it upon a match, in canonical order. The first item is
+    // the proper title and the second is the regex to match the title.
+    vec![
+        (
+            "Genesis",
+            format!("(?i)^ge(n(e(s(i(s)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Exodus",
+            format!("(?i)^ex(o(d(u(s)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Leviticus",
+            format!("(?i)^le(v(i(t(i(c(u(s)?)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Numbers",
+            format!("(?i)^nu(m(b(e(r(s)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Deuteronomy",
+            format!(
+                "(?i)^d[e|u]([e|u](t(e(r(o(n(o(m(y)?)?)?)?)?)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Joshua",
+            format!("(?i)^jos(h(u(a)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        ("Judges", format!("(?i)^judg(e(s)?)?{}*$", NON_NAME_CHARS)),
+        ("Ruth", format!("(?i)^ru(t(h)?)?{}*$", NON_NAME_CHARS)),
+        (
+            "1 Samuel",
+            format!(r"(?ix)^({})\s*sam(u(e(l)?)?)?{}*$", ONES, NON_NAME_CHARS),
+        ),
+        (
+            "2 Samuel",
+            format!(
+                r"(?i)^({})\s*s(a(m(u(e(l)?)?)?)?)?{}*$",
+                TWOS, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "1 Kings",
+            format!(r"(?i)^({})\s*k(i(n(g(s)?)?)?)?{}*$", ONES, NON_NAME_CHARS),
+        ),
+        (
+            "2 Kings",
+            format!(r"(?i)^({})\s*k(i(n(g(s)?)?)?)?{}*$", TWOS, NON_NAME_CHARS),
+        ),
+        (
+            "1 Chronicles",
+            format!(
+                r"(?i)^({})\s*ch(r(o(n(i(c(l(e(s)?)?)?)?)?)?)?)?{}*$",
+                ONES, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "2 Chronicles",
+            format!(
+                r"(?i)^({})\s*ch(r(o(n(i(c(l(e(s)?)?)?)?)?)?)?)?{}*$",
+                TWOS, NON_NAME_CHARS
+            ),
+        ),
+        ("Ezra", format!("(?i)^ezr(a)?{}*$", NON_NAME_CHARS)),
+        (
+            "Nehemiah",
+            format!("(?i)^ne(h(e(m(i(a(h)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Esther",
+            format!("(?i)^es(t(h(e(r)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        ("Job", format!("(?i)^job{}*$", NON_NAME_CHARS)),
+        (
+            "Psalms",
+            format!("(?i)^ps(a(l(m(s)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Proverbs",
+            format!("(?i)^pr(o(v(e(r(b(s)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Ecclesiastes",
+            format!(
+                "(?i)^ec(c(l(e(s(i(a(s(t(e(s)?)?)?)?)?)?)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Song of Solomon",
+            format!(
+                r"(?i)^s(o(n(g\s*(o(f\s*(s(o(l(o(m(o(n)?)?)?)?)?)?)?)?)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Isaiah",
+            format!("(?i)^is(a(i(a(h)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Jeremiah",
+            format!("(?i)^je(r(e(m(i(a(h)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Lamentations",
+            format!(
+                "(?i)^la(m(e(n(t(a(t(i(o(n(s)?)?)?)?)?)?)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Ezekiel",
+            format!("(?i)^eze(k(i(e(l)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Daniel",
+            format!("(?i)^da(n(i(e(l)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        ("Hosea", format!("(?i)^ho(s(e(a)?)?)?{}*$", NON_NAME_CHARS)),
+        ("Joel", format!("(?i)^joe(l)?{}*$", NON_NAME_CHARS)),
+        ("Amos", format!("(?i)^am(o(s)?)?{}*$", NON_NAME_CHARS)),
+        (
+            "Obadiah",
+            format!("(?i)^o(b(a(d(i(a(h)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        ("Jonah", format!("(?i)^jon(a(h)?)?{}*$", NON_NAME_CHARS)),
+        ("Micah", format!("(?i)^mi(c(a(h)?)?)?{}*$", NON_NAME_CHARS)),
+        ("Nahum", format!("(?i)^na(h(u(m)?)?)?{}*$", NON_NAME_CHARS)),
+        (
+            "Habakkuk",
+            format!("(?i)^hab(a(k(k(u(k)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Zephaniah",
+            format!("(?i)^zep(h(a(n(i(a(h)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Haggai",
+            format!("(?i)^hag(g(a(i)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Zechariah",
+            format!("(?i)^zec(h(a(r(i(a(h)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Malachi",
+            format!("(?i)^mal(a(c(h(i)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Matthew",
+            format!("(?i)^mat(t(h(e(w)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        ("Mark", format!("(?i)^mar(k)?{}*$", NON_NAME_CHARS)),
+        ("Luke", format!("(?i)^lu(k(e)?)?{}*$", NON_NAME_CHARS)),
+        ("John", format!("(?i)^(joh(n)?|jn){}*$", NON_NAME_CHARS)),
+        ("Acts", format!("(?i)^ac(t(s)?)?{}*$", NON_NAME_CHARS)),
+        (
+            "Romans",
+            format!("(?i)^ro(m(a(n(s)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "1 Corinthians",
+            format!(
+                r"(?i)^({})\s*co(r(i(n(t(h(i(a(n(s)?)?)?)?)?)?)?)?)?{}*$",
+                ONES, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "2 Corinthians",
+            format!(
+                r"(?i)^({})\s*co(r(i(n(t(h(i(a(n(s)?)?)?)?)?)?)?)?)?{}*$",
+                TWOS, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Galatians",
+            format!("(?i)^ga(l(a(t(i(a(n(s)?)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Ephesians",
+            format!("(?i)^ep(h(e(s(i(a(n(s)?)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Philippians",
+            format!("(?i)^phili(p(p(i(a(n(s)?)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Colossians",
+            format!(
+                "(?i)^co(l(o(s(s(i(a(n(s)?)?)?)?)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        (
+            "1 Thessalonians",
+            format!(
+                r"(?i)^({})\s*th(e(s(s(a(l(o(n(i(a(n(s)?)?)?)?)?)?)?)?)?)?)?{}*$",
+                ONES, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "2 Thessalonians",
+            format!(
+                r"(?i)^({})\s*th(e(s(s(a(l(o(n(i(a(n(s)?)?)?)?)?)?)?)?)?)?)?{}*$",
+                TWOS, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "1 Timothy",
+            format!(
+                r"(?i)^({})\s*ti(m(o(t(h(y)?)?)?)?)?{}*$",
+                ONES, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "2 Timothy",
+            format!(
+                r"(?i)^({})\s*ti(m(o(t(h(y)?)?)?)?)?{}*$",
+                TWOS, NON_NAME_CHARS
+            ),
+        ),
+        ("Titus", format!("(?i)^ti(t(u(s)?)?)?{}*$", NON_NAME_CHARS)),
+        (
+            "Philemon",
+            format!("(?i)^phile(m(o(n)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Hebrews",
+            format!("(?i)^he(b(r(e(w(s)?)?)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        ("James", format!("(?i)^ja(m(e(s)?)?)?{}*$", NON_NAME_CHARS)),
+        (
+            "1 Peter",
+            format!(r"(?i)^({})\s*p(e(t(e(r)?)?)?)?{}*$", ONES, NON_NAME_CHARS),
+        ),
+        (
+            "2 Peter",
+            format!(r"(?i)^({})\s*p(e(t(e(r)?)?)?)?{}*$", TWOS, NON_NAME_CHARS),
+        ),
+        (
+            "1 John",
+            format!(r"(?i)^({})\s*j(o(h(n)?)?|n)?{}*$", ONES, NON_NAME_CHARS),
+        ),
+        (
+            "2 John",
+            format!(r"(?i)^({})\s*j(o(h(n)?)?|n)?{}*$", TWOS, NON_NAME_CHARS),
+        ),
+        (
+            "3 John",
+            format!(r"(?i)^({})\s*j(o(h(n)?)?|n){}*$", THREES, NON_NAME_CHARS),
+        ),
+        ("Jude", format!("(?i)^jude{}*$", NON_NAME_CHARS)),
+        (
+            "Revelation",
+            format!(
+                "(?i)^re(v(e(l(a(t(i(o(n)?)?)?)?)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        // The deuterocanonical books, which only match in a canon that
+        // holds them
+        ("Tobit", format!("(?i)^tob(i(t)?)?{}*$", NON_NAME_CHARS)),
+        (
+            "Judith",
+            format!("(?i)^(judi(t(h)?)?|jdt){}*$", NON_NAME_CHARS),
+        ),
+        (
+            "Wisdom",
+            format!(
+                r"(?i)^wis(d(o(m(\s*of\s*solomon)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Sirach",
+            format!(
+                "(?i)^(sir(a(c(h)?)?)?|ecclus|ecclesiasticus){}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Baruch",
+            format!("(?i)^bar(u(c(h)?)?)?{}*$", NON_NAME_CHARS),
+        ),
+        (
+            "1 Maccabees",
+            format!(
+                r"(?i)^({})\s*mac(c(a(b(e(e(s)?)?)?)?)?)?{}*$",
+                ONES, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "2 Maccabees",
+            format!(
+                r"(?i)^({})\s*mac(c(a(b(e(e(s)?)?)?)?)?)?{}*$",
+                TWOS, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "1 Esdras",
+            format!(r"(?i)^({})\s*esd(r(a(s)?)?)?{}*$", ONES, NON_NAME_CHARS),
+        ),
+        (
+            "3 Maccabees",
+            format!(
+                r"(?i)^({})\s*mac(c(a(b(e(e(s)?)?)?)?)?)?{}*$",
+                THREES, NON_NAME_CHARS
+            ),
+        ),
+        (
+            "Prayer of Manasseh",
+            format!(
+                r"(?i)^pr(a(y(e(r)?)?)?)?\s*(of\s*)?man(a(s(s(e(h)?)?)?)?)?{}*$",
+                NON_NAME_CHARS
+            ),
+        ),
+    ]
+}
+
+/// The BookAliases are the spellings of a book the parser accepts, spelled
+/// out from the book's matcher. A numbered book is written as one of its
+/// numbers, then optionally a space, then one of its names (ex: 1st jn).
+/// Case does not matter, and a name can be followed by a period.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BookAliases {
+    pub title: &'static str,
+    pub numbers: Vec<String>,
+    pub names: Vec<String>,
+}
+
+/// The get_aliases function returns the spellings of every book of the
+/// canon, in canonical order. They are only spelled out once.
+pub fn get_aliases() -> &'static [BookAliases] {
+    static ALIASES: OnceLock<Vec<BookAliases>> = OnceLock::new();
+
+    ALIASES.get_or_init(|| {
+        get_book_matchers()
+            .iter()
+            .filter(|(title, _)| get_canon().contains(title))
+            .map(|(title, matcher)| read_aliases(title, matcher))
+            .collect()
+    })
+}
+
+// Spell out the numbers and names a book's matcher accepts. A name is only
+// kept when the parser takes it to be the book, since where matchers
+// overlap the ambiguity policy gives the name to one of them
+fn read_aliases(title: &'static str, matcher: &str) -> BookAliases {
+    let pattern = matcher
+        .trim_start_matches("(?ix)")
+        .trim_start_matches("(?i)")
+        .trim_start_matches('^');
+    let pattern = pattern
+        .strip_suffix(&format!("{}*$", NON_NAME_CHARS))
+        .unwrap_or(pattern);
+
+    let (numbers, pattern) = [ONES, TWOS, THREES]
+        .iter()
+        .find_map(|number| {
+            let name = pattern.strip_prefix(&format!(r"({})\s*", number))?;
+            Some((get_spellings(number.trim_start_matches("(?i)")), name))
+        })
+        .unwrap_or((vec![], pattern));
+    let prefix = match numbers.first() {
+        Some(number) => format!("{} ", number),
+        None => String::new(),
+    };
+
+    // The title as get_raw_title gives it, checked against every matcher the
+    // way get_proper_title does
+    let names = get_spellings(pattern)
+        .into_iter()
+        .filter(|name| {
+            let raw_title = format!("{}{}", prefix, name);
+            let matching = get_matching_titles(&raw_title);
+
+            choose_title(&raw_title, &matching, get_ambiguity_policy()) == Some(title)
+        })
+        .collect();
+
+    BookAliases {
+        title,
+        numbers,
+        names,
+    }
+}
+
+// Every string a pattern accepts, trimmed and lower case, shortest first
+fn get_spellings(pattern: &str) -> Vec<String> {
+    let chars = pattern.chars().collect::<Vec<char>>();
+    let mut position = 0;
+
+    let mut spellings = expand_alternatives(&chars, &mut position)
+        .into_iter()
+        .map(|spelling| spelling.trim().to_lowercase())
+        .filter(|spelling| !spelling.is_empty())
+        .collect::<Vec<String>>();
+    spellings.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    spellings.dedup();
+    spellings
+}
+
+// The expand functions only know what the matchers are written with:
+// letters, groups of alternatives, character classes, \s, and ? * + after
+// an item, where * and + are taken to repeat it at most once
+fn expand_alternatives(chars: &[char], position: &mut usize) -> Vec<String> {
+    let mut spellings = expand_sequence(chars, position);
+    while chars.get(*position) == Some(&'|') {
+        *position += 1;
+        spellings.extend(expand_sequence(chars, position));
+    }
+
+    spellings
+}
+
+fn expand_sequence(chars: &[char], position: &mut usize) -> Vec<String> {
+    let mut spellings = vec![String::new()];
+
+    while let Some(&c) = chars.get(*position) {
+        let mut item = match c {
+            '|' | ')' => break,
+            '(' => {
+                *position += 1;
+                let group = expand_alternatives(chars, position);
+                // Step over the closing parenthesis
+                *position += 1;
+                group
+            }
+            '[' => {
+                let end = chars[*position..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map_or(chars.len(), |end| *position + end);
+                let class = chars[*position + 1..end]
+                    .iter()
+                    .filter(|c| **c != '|')
+                    .map(char::to_string)
+                    .collect();
+                *position = end + 1;
+                class
+            }
+            '\\' => {
+                let escaped = chars.get(*position + 1);
+                *position += 2;
+                match escaped {
+                    Some('s') => vec![String::from(" ")],
+                    Some(c) => vec![c.to_string()],
+                    None => vec![],
+                }
+            }
+            c => {
+                *position += 1;
+                vec![c.to_string()]
+            }
+        };
+
+        match chars.get(*position) {
+            Some('?' | '*') => {
+                item.push(String::new());
+                *position += 1;
+            }
+            Some('+') => *position += 1,
+            _ => (),
+        }
+
+        spellings = spellings
+            .iter()
+            .flat_map(|spelling| item.iter().map(move |c| format!("{}{}", spelling, c)))
+            .collect();
+    }
+
+    spellings
+}
+
+/// The get_regex function exists to make the regex pattern more readable.
+/// If we end up trying to add to or take away from the pattern it is much
+/// easier to digest chunked up into pieces. The regex pattern is built
+/// from the constants defined above, and compiled only once.
+fn get_book_regex() -> &'static Regex {
+    static BOOK_REGEX: OnceLock<Regex> = OnceLock::new();
+
+    BOOK_REGEX.get_or_init(|| {
+        // Combine the book number constants into a single string
+        // that looks for all patterns that match the book number.
+        let book_num = format!(r"(?<book_num>{}|{}|{})", ONES, TWOS, THREES);
+
+        // Combine the book number string with the book text string
+        // Note the book number is marked as optional, and any number
+        // of spaces is allowed between the number and the string
+        let book_title = format!(r"\s*{}?\s*{}\s*", book_num, BOOK_TEXT);
+
+        // Create the regex matcher string and retun
+        Regex::new(&book_title).unwrap()
+    })
+}
+
+fn get_title_from_captures(captures: Captures) -> Option<String> {
+    // The book_num is optional, so we need to check if it exists
+    // and if not we want to return an empty string. Note, if
+    // a book_number greater than 3 is present it will panic.
+    let book_num = match captures.name("book_num") {
+        Some(data) => get_book_num_string(data.as_str()),
+        None => "",
+    };
+
+    // Get the book_text from the captures
+    let book_text = captures.name("book_text")?.as_str();
+
+    // Format the book_num and book_text into a single string and return
+    format_title(book_num, book_text)
+}
+
+fn get_book_num_string(book_num: &str) -> &'static str {
+    // The book number patterns, compiled once, with the number each stands
+    // for. The threes are tried first, as "iii" starts with "i".
+    static BOOK_NUMS: OnceLock<[(Regex, &str); 3]> = OnceLock::new();
+    let book_nums = BOOK_NUMS.get_or_init(|| {
+        [(THREES, "3 "), (TWOS, "2 "), (ONES, "1 ")]
+            .map(|(pattern, number)| (Regex::new(pattern).unwrap(), number))
+    });
+
+    // If the book_num matches any of the regex patterns return the
+    // corresponding book number string. If no match is found panic.
+    book_nums
+        .iter()
+        .find(|(pattern, _)| pattern.is_match(book_num))
+        .map(|(_, number)| *number)
+        .unwrap_or_else(|| panic!("Invalid book number: {}", book_num))
+}
+
+fn format_title(book_num: &str, book_text: &str) -> Option<String> {
+    let trimmed = book_text.trim();
+
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(format!("{}{}", book_num, trimmed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canon::DEUTEROCANONICAL_BOOKS;
+    use rand::prelude::*;
+    use std::collections::HashMap;
+
+    // This function will generate a list of test cases for the get_title function to
+    // test a regex for a specific book. Each book has a title and a minimum number
+    // of charcters it can be recognized by. This function will grab the smallest
+    // varation up to the complete title and return a list of test cases.
+    fn get_book_title_variations(book_title: &str, minimum_length: usize) -> Vec<String> {
+        let mut variants: Vec<String> = Vec::new();
+        let splits = book_title.split_at(minimum_length);
+
+        for (i, _) in splits.1.chars().enumerate() {
+            let sub_splits = splits.1.split_at(i);
+            variants.push(format!("{}{}", splits.0, sub_splits.0).to_owned());
+        }
+
+        variants.push(book_title.to_owned());
+
+        variants
+    }
+
+    // This will add the book numbers with various spaces to the book title to
+    // test out the regex
+    fn add_numbered_variants(book_title: &str, variants: Vec<&str>) -> Vec<String> {
+        let no_spaces: Vec<String> = variants
+            .iter()
+            .map(|s| format!("{}{}", s, book_title))
+            .collect();
+        let center_spaces: Vec<String> = variants
+            .iter()
+            .map(|s| format!("{}  {}", s, book_title))
+            .collect();
+        let leading_spaces: Vec<String> = variants
+            .iter()
+            .map(|s| format!("  {}  {}", s, book_title))
+            .collect();
+        let trailing_spaces: Vec<String> = variants
+            .iter()
+            .map(|s| format!("{}  {}  ", s, book_title))
+            .collect();
+        let all_spaces: Vec<String> = variants
+            .iter()
+            .map(|s| format!("  {}  {}  ", s, book_title))
+            .collect();
+
+        // return all the variants
+        [
+            no_spaces,
+            center_spaces,
+            leading_spaces,
+            trailing_spaces,
+            all_spaces,
+        ]
+        .concat()
+    }
+
+    // Our test cases are all lowercase, but the function under test
+    // should be able to handle any case. To test this we will randomly
+    // capitalize the input string and check the result.
+    fn randomly_capitalize(input: &str) -> String {
+        let mut rng = rand::thread_rng();
+        let mut output = String::new();
+
+        for c in input.chars() {
+            if rng.gen::<bool>() {
+                output.push(c.to_uppercase().next().unwrap());
+            } else {
+                output.push(c);
+            }
+        }
+
+        output
+    }
+
+    // This function will loop through the test queries, randomize the case,
+    // call the function under test, and check the result.
+    fn run_and_check_result(test_queries: Vec<String>, expected: &str) {
+        test_queries.iter().for_each(|test| {
+            // randomize the case of the input
+            let random_case_input = randomly_capitalize(test);
+
+            // call the function under test
+            let result = get_title(random_case_input.as_str()).unwrap();
+
+            // check the result
+            println!("{} -> {}", random_case_input, result);
+            assert_eq!(result, expected);
+        });
+    }
+
+    fn run_book_test(
+        book_title: &str,
+        min_title_chars: usize,
+        num_variations: Vec<&str>,
+        expected: &str,
+    ) {
+        let book_title_variations = get_book_title_variations(book_title, min_title_chars);
+        let book_variations: Vec<String> = book_title_variations
+            .iter()
+            .flat_map(|s| {
+                let result = add_numbered_variants(s, num_variations.clone());
+                let random_case = result
+                    .iter()
+                    .map(|s| randomly_capitalize(s))
+                    .collect::<Vec<String>>();
+
+                random_case
+            })
+            .collect();
+        run_and_check_result(book_variations, expected);
+    }
+
+    #[test]
+    fn get_osis_title_reads_the_osis_book_ids() {
+        assert_eq!(get_osis_title("1John"), Some("1 John"));
+        assert_eq!(get_osis_title("song"), Some("Song of Solomon"));
+        assert_eq!(get_osis_title("Phlm"), Some("Philemon"));
+        assert_eq!(get_osis_title("1 John"), None);
+        assert_eq!(get_osis_title("Tob"), None);
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_chronicles() {
+        run_book_test(
+            "chronicles",
+            2,
+            vec!["one", "fst", "first", "1", "1st", "i "],
+            "1 Chronicles",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_corinthians() {
+        run_book_test(
+            "corinthians",
+            2,
+            vec!["one", "fst", "first", "1", "1st", "i "],
+            "1 Corinthians",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_john() {
+        run_book_test(
+            "john",
+            2,
+            vec!["one", "fst", "first", "1", "1st", "i "],
+            "1 John",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_kings() {
+        run_book_test(
+            "kings",
+            1,
+            vec!["one", "fst", "first", "1", "1st", "i "],
+            "1 Kings",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_peter() {
+        run_book_test(
+            "peter",
+            1,
+            vec!["one", "fst", "first", "1", "1st", "i "],
+            "1 Peter",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_samuel() {
+        run_book_test(
+            "samuel",
+            3,
+            vec!["one", "first", "1", "1st", "i "],
+            "1 Samuel",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_thessalonians() {
+        run_book_test(
+            "thessalonians",
+            2,
+            vec!["one", "fst", "first", "1", "1st", "i "],
+            "1 Thessalonians",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_one_timothy() {
+        run_book_test(
+            "timothy",
+            2,
+            vec!["one", "fst", "first", "1", "1st", "i "],
+            "1 Timothy",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_chronicles() {
+        run_book_test(
+            "chronicles",
+            2,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 Chronicles",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_corinthians() {
+        run_book_test(
+            "corinthians",
+            2,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 Corinthians",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_john() {
+        run_book_test(
+            "john",
+            1,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 John",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_kings() {
+        run_book_test(
+            "kings",
+            1,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 Kings",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_peter() {
+        run_book_test(
+            "peter",
+            1,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 Peter",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_samuel() {
+        run_book_test(
+            "samuel",
+            1,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 Samuel",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_thessalonians() {
+        run_book_test(
+            "thessalonians",
+            2,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 Thessalonians",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_two_timothy() {
+        run_book_test(
+            "timothy",
+            2,
+            vec!["two", "sec", "seco", "secon", "second", "2", "2nd", "ii "],
+            "2 Timothy",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_three_john() {
+        run_book_test(
+            "john",
+            2,
+            vec![
+                "thr", "thre", "three", "thi", "thir", "third", "3", "3rd", "iii ",
+            ],
+            "3 John",
+        );
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_acts() {
+        run_book_test("acts", 2, vec![""], "Acts");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_amos() {
+        run_book_test("amos", 2, vec![""], "Amos");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_colossians() {
+        run_book_test("colossians", 2, vec![""], "Colossians");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_daniel() {
+        run_book_test("daniel", 2, vec![""], "Daniel");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_deuteronomy() {
+        run_book_test("deuteronomy", 2, vec![""], "Deuteronomy");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_ecclesiastes() {
+        run_book_test("ecclesiastes", 2, vec![""], "Ecclesiastes");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_ephesians() {
+        run_book_test("ephesians", 2, vec![""], "Ephesians");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_esther() {
+        run_book_test("esther", 2, vec![""], "Esther");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_exodus() {
+        run_book_test("exodus", 2, vec![""], "Exodus");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_ezekiel() {
+        run_book_test("ezekiel", 3, vec![""], "Ezekiel");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_ezra() {
+        run_book_test("ezra", 3, vec![""], "Ezra");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_galatians() {
+        run_book_test("galatians", 2, vec![""], "Galatians");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_genesis() {
+        run_book_test("genesis", 2, vec![""], "Genesis");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_habakkuk() {
+        run_book_test("habakkuk", 3, vec![""], "Habakkuk");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_haggai() {
+        run_book_test("haggai", 3, vec![""], "Haggai");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_hebrews() {
+        run_book_test("hebrews", 2, vec![""], "Hebrews");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_hosea() {
+        run_book_test("hosea", 2, vec![""], "Hosea");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_isaiah() {
+        run_book_test("isaiah", 2, vec![""], "Isaiah");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_james() {
+        run_book_test("james", 2, vec![""], "James");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_jeremiah() {
+        run_book_test("jeremiah", 2, vec![""], "Jeremiah");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_job() {
+        run_book_test("job", 3, vec![""], "Job");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_joel() {
+        run_book_test("joel", 3, vec![""], "Joel");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_john() {
+        run_book_test("john", 3, vec![""], "John");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_jonah() {
+        run_book_test("jonah", 3, vec![""], "Jonah");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_joshua() {
+        run_book_test("joshua", 3, vec![""], "Joshua");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_jude() {
+        run_book_test("jude", 4, vec![""], "Jude");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_judges() {
+        run_book_test("judges", 4, vec![""], "Judges");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_lamentations() {
+        run_book_test("lamentations", 2, vec![""], "Lamentations");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_leviticus() {
+        run_book_test("leviticus", 2, vec![""], "Leviticus");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_luke() {
+        run_book_test("luke", 2, vec![""], "Luke");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_malachi() {
+        run_book_test("malachi", 3, vec![""], "Malachi");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_mark() {
+        run_book_test("mark", 3, vec![""], "Mark");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_matthew() {
+        run_book_test("matthew", 3, vec![""], "Matthew");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_micah() {
+        run_book_test("micah", 3, vec![""], "Micah");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_nahum() {
+        run_book_test("nahum", 2, vec![""], "Nahum");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_nehemiah() {
+        run_book_test("nehemia", 2, vec![""], "Nehemiah");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_numbers() {
+        run_book_test("numbers", 2, vec![""], "Numbers");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_obadiah() {
+        run_book_test("obadiah", 2, vec![""], "Obadiah");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_philemon() {
+        run_book_test("philemon", 5, vec![""], "Philemon");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_philippians() {
+        run_book_test("philippians", 5, vec![""], "Philippians");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_proverbs() {
+        run_book_test("proverbs", 2, vec![""], "Proverbs");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_psalms() {
+        run_book_test("psalms", 2, vec![""], "Psalms");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_revelation() {
+        run_book_test("revelation", 2, vec![""], "Revelation");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_romans() {
+        run_book_test("romans", 2, vec![""], "Romans");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_ruth() {
+        run_book_test("ruth", 2, vec![""], "Ruth");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_song_of_solomon() {
+        run_book_test("song of solomon", 1, vec![""], "Song of Solomon");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_titus() {
+        run_book_test("titus", 2, vec![""], "Titus");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_zechariah() {
+        run_book_test("zechariah", 3, vec![""], "Zechariah");
+    }
+
+    #[test]
+    fn get_title_gets_proper_title_for_zephaniah() {
+        run_book_test("zephaniah", 3, vec![""], "Zephaniah");
+    }
+
+    #[test]
+    fn get_title_candidates_ranks_prefix_matches_by_how_much_was_typed() {
+        let candidates = get_title_candidates("Ju 3");
+
+        assert_eq!(
+            candidates,
+            vec![
+                TitleCandidate {
+                    title: String::from("Jude"),
+                    score: 0.5,
+                },
+                TitleCandidate {
+                    title: String::from("Judges"),
+                    score: 2.0 / 6.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn get_title_candidates_puts_the_matched_book_first() {
+        let candidates = get_title_candidates("joh 3:16");
+
+        assert_eq!(candidates[0].title, "John");
+        assert_eq!(candidates[0].score, 1.0);
+    }
+
+    #[test]
+    fn ambiguity_policy_parses_each_policy_name() {
+        assert_eq!(
+            "longest-match".parse::<AmbiguityPolicy>(),
+            Ok(AmbiguityPolicy::LongestMatch)
+        );
+        assert_eq!(
+            "Canonical-Order".parse::<AmbiguityPolicy>(),
+            Ok(AmbiguityPolicy::CanonicalOrder)
+        );
+        assert_eq!(
+            "reject".parse::<AmbiguityPolicy>(),
+            Ok(AmbiguityPolicy::Reject)
+        );
+        assert!("coin-flip".parse::<AmbiguityPolicy>().is_err());
+    }
+
+    #[test]
+    fn choose_title_with_longest_match_prefers_the_most_spelled_out_book() {
+        assert_eq!(
+            choose_title("jud", &["Judges", "Jude"], AmbiguityPolicy::LongestMatch),
+            Some("Jude")
+        );
+    }
+
+    #[test]
+    fn get_matching_titles_reads_the_title_against_every_book() {
+        assert_eq!(get_matching_titles("jude"), vec!["Jude"]);
+        assert_eq!(get_matching_titles("judg"), vec!["Judges"]);
+        assert_eq!(get_matching_titles("1 jn"), vec!["1 John"]);
+        assert!(get_matching_titles("hezekiah").is_empty());
+    }
+
+    #[test]
+    fn get_matching_titles_only_reads_deuterocanonical_books_in_their_canon() {
+        let matching = |title, canon| get_matching_titles_in(title, canon);
+
+        assert!(matching("tobit", Canon::Protestant).is_empty());
+        assert_eq!(matching("tobit", Canon::Catholic), vec!["Tobit"]);
+        assert_eq!(matching("2 macc", Canon::Catholic), vec!["2 Maccabees"]);
+        assert!(matching("3 macc", Canon::Catholic).is_empty());
+        assert_eq!(matching("3 macc", Canon::Orthodox), vec!["3 Maccabees"]);
+        assert_eq!(matching("1 esd", Canon::Orthodox), vec!["1 Esdras"]);
+        assert_eq!(matching("ecclus", Canon::Catholic), vec!["Sirach"]);
+        assert_eq!(
+            matching("wisdom of solomon", Canon::Catholic),
+            vec!["Wisdom"]
+        );
+        assert_eq!(
+            matching("prayer of manasseh", Canon::Orthodox),
+            vec!["Prayer of Manasseh"]
+        );
+    }
+
+    #[test]
+    fn deuterocanonical_matchers_leave_the_other_books_alone() {
+        for (title, book) in [
+            ("judi", "Judith"),
+            ("jdt", "Judith"),
+            ("judg", "Judges"),
+            ("jude", "Jude"),
+            ("eccl", "Ecclesiastes"),
+            ("ecclesiastes", "Ecclesiastes"),
+            ("ecclesiasticus", "Sirach"),
+            ("pr", "Proverbs"),
+            ("prman", "Prayer of Manasseh"),
+            ("1 macc", "1 Maccabees"),
+            ("1 mac", "1 Maccabees"),
+        ] {
+            assert_eq!(
+                get_matching_titles_in(title, Canon::Orthodox),
+                vec![book],
+                "{}",
+                title
+            );
+        }
+    }
+
+    #[test]
+    fn choose_title_with_canonical_order_prefers_the_first_book() {
+        assert_eq!(
+            choose_title("jud", &["Judges", "Jude"], AmbiguityPolicy::CanonicalOrder),
+            Some("Judges")
+        );
+    }
+
+    #[test]
+    fn choose_title_with_reject_refuses_ambiguous_titles() {
+        assert_eq!(
+            choose_title("jud", &["Judges", "Jude"], AmbiguityPolicy::Reject),
+            None
+        );
+        assert_eq!(
+            choose_title("jude", &["Jude"], AmbiguityPolicy::Reject),
+            Some("Jude")
+        );
+    }
+
+    // Every book with the book text and the fewest characters it can be
+    // abbreviated to, taken from the get_title tests above.
+    const ABBREVIATIONS: [(&str, &str, usize); 66] = [
+        ("1 Chronicles", "chronicles", 2),
+        ("1 Corinthians", "corinthians", 2),
+        ("1 John", "john", 2),
+        ("1 Kings", "kings", 1),
+        ("1 Peter", "peter", 1),
+        ("1 Samuel", "samuel", 3),
+        ("1 Thessalonians", "thessalonians", 2),
+        ("1 Timothy", "timothy", 2),
+        ("2 Chronicles", "chronicles", 2),
+        ("2 Corinthians", "corinthians", 2),
+        ("2 John", "john", 1),
+        ("2 Kings", "kings", 1),
+        ("2 Peter", "peter", 1),
+        ("2 Samuel", "samuel", 1),
+        ("2 Thessalonians", "thessalonians", 2),
+        ("2 Timothy", "timothy", 2),
+        ("3 John", "john", 2),
+        ("Acts", "acts", 2),
+        ("Amos", "amos", 2),
+        ("Colossians", "colossians", 2),
+        ("Daniel", "daniel", 2),
+        ("Deuteronomy", "deuteronomy", 2),
+        ("Ecclesiastes", "ecclesiastes", 2),
+        ("Ephesians", "ephesians", 2),
+        ("Esther", "esther", 2),
+        ("Exodus", "exodus", 2),
+        ("Ezekiel", "ezekiel", 3),
+        ("Ezra", "ezra", 3),
+        ("Galatians", "galatians", 2),
+        ("Genesis", "genesis", 2),
+        ("Habakkuk", "habakkuk", 3),
+        ("Haggai", "haggai", 3),
+        ("Hebrews", "hebrews", 2),
+        ("Hosea", "hosea", 2),
+        ("Isaiah", "isaiah", 2),
+        ("James", "james", 2),
+        ("Jeremiah", "jeremiah", 2),
+        ("Job", "job", 3),
+        ("Joel", "joel", 3),
+        ("John", "john", 3),
+        ("Jonah", "jonah", 3),
+        ("Joshua", "joshua", 3),
+        ("Jude", "jude", 4),
+        ("Judges", "judges", 4),
+        ("Lamentations", "lamentations", 2),
+        ("Leviticus", "leviticus", 2),
+        ("Luke", "luke", 2),
+        ("Malachi", "malachi", 3),
+        ("Mark", "mark", 3),
+        ("Matthew", "matthew", 3),
+        ("Micah", "micah", 3),
+        ("Nahum", "nahum", 2),
+        ("Nehemiah", "nehemia", 2),
+        ("Numbers", "numbers", 2),
+        ("Obadiah", "obadiah", 2),
+        ("Philemon", "philemon", 5),
+        ("Philippians", "philippians", 5),
+        ("Proverbs", "proverbs", 2),
+        ("Psalms", "psalms", 2),
+        ("Revelation", "revelation", 2),
+        ("Romans", "romans", 2),
+        ("Ruth", "ruth", 2),
+        ("Song of Solomon", "song of solomon", 1),
+        ("Titus", "titus", 2),
+        ("Zechariah", "zechariah", 3),
+        ("Zephaniah", "zephaniah", 3),
+    ];
+
+    // The spellings of each book number, indexed by the number
+    const BOOK_NUMBERS: [&[&str]; 4] = [
+        &[""],
+        &["1", "1st", "one", "fst", "first", "i "],
+        &["2", "2nd", "two", "sec", "second", "ii "],
+        &["3", "3rd", "three", "thr", "third", "iii "],
+    ];
+
+    #[test]
+    fn every_abbreviation_matches_exactly_one_book() {
+        // The queries are built without any randomness, so a failure here is
+        // the same failure on every run
+        for (title, book_text, min_title_chars) in ABBREVIATIONS {
+            let book_num = match title.split_once(' ') {
+                Some((number, _)) => number.parse::<usize>().unwrap_or(0),
+                None => 0,
+            };
+
+            for abbreviation in get_book_title_variations(book_text, min_title_chars) {
+                for number in BOOK_NUMBERS[book_num] {
+                    let query = format!("{}{} 3:16", number, abbreviation);
+                    let raw_title = get_raw_title(&query).unwrap();
+
+                    assert_eq!(get_matching_titles(&raw_title), vec![title], "{}", query);
+                    assert_eq!(get_title(&query).as_deref(), Some(title), "{}", query);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn get_spellings_spells_out_a_matcher() {
+        assert_eq!(get_spellings("ru(t(h)?)?"), ["ru", "rut", "ruth"]);
+        assert_eq!(get_spellings("(joh(n)?|jn)"), ["jn", "joh", "john"]);
+        assert_eq!(get_spellings(r"d[e|u]"), ["de", "du"]);
+        assert_eq!(
+            get_spellings(ONES.trim_start_matches("(?i)")),
+            ["1", "i", "1st", "fst", "one", "first"]
+        );
+    }
+
+    #[test]
+    fn get_aliases_lists_what_the_parser_accepts() {
+        let aliases = get_aliases();
+        let titles: Vec<&str> = aliases.iter().map(|aliases| aliases.title).collect();
+        assert_eq!(titles, BOOKS);
+
+        let john = &aliases[BOOKS.iter().position(|book| *book == "John").unwrap()];
+        assert_eq!(john.names, ["jn", "joh", "john"]);
+        assert!(john.numbers.is_empty());
+
+        let first_john = &aliases[BOOKS.iter().position(|book| *book == "1 John").unwrap()];
+        assert!(first_john.numbers.contains(&String::from("first")));
+        assert!(first_john.names.contains(&String::from("jn")));
+
+        // The shortest and longest spellings of each book are parsed back
+        for book in aliases {
+            let number = book.numbers.last().cloned().unwrap_or_default();
+            for name in [book.names.first(), book.names.last()] {
+                let query = format!("{} {}. 1:1", number, name.unwrap());
+                assert_eq!(get_title(&query).as_deref(), Some(book.title), "{}", query);
+            }
+        }
+    }
+
+    #[test]
+    fn get_book_matchers_are_in_canonical_order() {
+        let titles: Vec<&str> = get_book_matchers()
+            .into_iter()
+            .map(|(title, _)| title)
+            .collect();
+        let books: Vec<&str> = BOOKS
+            .iter()
+            .copied()
+            .chain(DEUTEROCANONICAL_BOOKS.iter().map(|book| book.title))
+            .collect();
+
+        assert_eq!(titles, books);
+    }
+
+    #[test]
+    fn get_title_ignores_abbreviation_periods_and_trailing_punctuation() {
+        let tests = HashMap::from([
+            ("Jn.", "John"),
+            ("1 Cor. 13", "1 Corinthians"),
+            ("Rom. 8:28.", "Romans"),
+            ("Gen.1:1;", "Genesis"),
+            ("Ps, 23", "Psalms"),
+        ]);
+
+        for (key, value) in tests.into_iter() {
+            assert_eq!(get_title(key).as_deref(), Some(value), "{}", key);
+        }
+    }
+
+    #[test]
+    fn get_params_strips_off_everything_after_book_title() {
+        let tests = HashMap::from([
+            ("John  ", ""),
+            ("Job 1", "1"),
+            ("  Psalms    1:  2", "1:  2"),
+            ("1 Song of Solomon 2 : 3 - 5 : 6", "2 : 3 - 5 : 6"),
+        ]);
+
+        for (key, value) in tests.into_iter() {
+            let result = get_params(key).unwrap_or(String::from(""));
+            assert_eq!(result, value);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use crate::chapter::BOOKS;
+
+/// The Canon is the set of books a deployment serves. Each canon holds every
+/// book of the one before it. It is set once (see set_canon).
+/// - Protestant (protestant) is the 66 books of BOOKS (the default)
+/// - Catholic (catholic) adds Tobit, Judith, Wisdom, Sirach, Baruch and 1-2
+///   Maccabees
+/// - Orthodox (orthodox) also adds 1 Esdras, 3 Maccabees and the Prayer of
+///   Manasseh
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Canon {
+    Protestant,
+    Catholic,
+    Orthodox,
+}
+
+impl Canon {
+    /// The as_str function returns the canon as it is written in settings
+    /// (ex: catholic).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Canon::Protestant => "protestant",
+            Canon::Catholic => "catholic",
+            Canon::Orthodox => "orthodox",
+        }
+    }
+
+    /// The contains function returns true when the book is in the canon. A
+    /// book that is in no canon is in none of them.
+    pub fn contains(self, book: &str) -> bool {
+        BOOKS.contains(&book)
+            || DEUTEROCANONICAL_BOOKS.iter().any(|deuterocanonical| {
+                deuterocanonical.title == book && deuterocanonical.canon <= self
+            })
+    }
+}
+
+impl FromStr for Canon {
+    type Err = String;
+
+    fn from_str(canon: &str) -> Result<Self, Self::Err> {
+        match canon.trim().to_lowercase().as_str() {
+            "protestant" => Ok(Canon::Protestant),
+            "catholic" => Ok(Canon::Catholic),
+            "orthodox" => Ok(Canon::Orthodox),
+            other => Err(format!("Unknown canon: {}", other)),
+        }
+    }
+}
+
+static CANON: OnceLock<Canon> = OnceLock::new();
+
+/// The set_canon function sets the Canon for the rest of the process. It has
+/// to be called before the first title is looked up, and returns false when
+/// it is too late. Until it is set, Protestant is used.
+pub fn set_canon(canon: Canon) -> bool {
+    CANON.set(canon).is_ok()
+}
+
+/// The get_canon function returns the Canon the process serves.
+pub fn get_canon() -> Canon {
+    *CANON.get_or_init(|| Canon::Protestant)
+}
+
+/// The DeuterocanonicalBook is a book of the Old Testament that only some
+/// canons hold. The canon is the first canon the book is in, and the
+/// verse_counts are the verses of each of its chapters, from chapter 1.
+#[derive(Debug, PartialEq)]
+pub struct DeuterocanonicalBook {
+    pub title: &'static str,
+    pub osis: &'static str,
+    pub short: &'static str,
+    pub usfm: &'static str,
+    pub canon: Canon,
+    pub verse_counts: &'static [u8],
+}
+
+/// The DEUTEROCANONICAL_BOOKS constant lists the books the canons add to
+/// BOOKS, in the order their books are numbered after Revelation (ex: Tobit
+/// is book 67). The verse counts are those of the KJV Apocrypha, which the
+/// versification of a translation can correct. The Greek additions to Esther
+/// and Daniel, and Psalm 151, are not among them.
+pub const DEUTEROCANONICAL_BOOKS: [DeuterocanonicalBook; 10] = [
+    DeuterocanonicalBook {
+        title: "Tobit",
+        osis: "Tob",
+        short: "Tob",
+        usfm: "TOB",
+        canon: Canon::Catholic,
+        verse_counts: &[22, 14, 17, 21, 22, 17, 18, 21, 6, 12, 19, 22, 18, 15],
+    },
+    DeuterocanonicalBook {
+        title: "Judith",
+        osis: "Jdt",
+        short: "Jdt",
+        usfm: "JDT",
+        canon: Canon::Catholic,
+        verse_counts: &[
+            16, 28, 10, 15, 24, 21, 32, 36, 14, 23, 23, 20, 20, 19, 13, 25,
+        ],
+    },
+    DeuterocanonicalBook {
+        title: "Wisdom",
+        osis: "Wis",
+        short: "Wis",
+        usfm: "WIS",
+        canon: Canon::Catholic,
+        verse_counts: &[
+            16, 24, 19, 20, 23, 25, 30, 21, 18, 21, 26, 27, 19, 31, 19, 29, 21, 25, 22,
+        ],
+    },
+    DeuterocanonicalBook {
+        title: "Sirach",
+        osis: "Sir",
+        short: "Sir",
+        usfm: "SIR",
+        canon: Canon::Catholic,
+        verse_counts: &[
+            30, 18, 31, 31, 15, 37, 36, 19, 18, 31, 34, 18, 26, 27, 20, 30, 32, 33, 30, 32, 28, 27,
+            28, 34, 26, 29, 30, 26, 28, 25, 31, 24, 31, 26, 20, 26, 31, 34, 35, 30, 24, 25, 33, 23,
+            26, 20, 25, 25, 16, 29, 30,
+        ],
+    },
+    DeuterocanonicalBook {
+        title: "Baruch",
+        osis: "Bar",
+        short: "Bar",
+        usfm: "BAR",
+        canon: Canon::Catholic,
+        verse_counts: &[22, 35, 37, 37, 9, 73],
+    },
+    DeuterocanonicalBook {
+        title: "1 Maccabees",
+        osis: "1Macc",
+        short: "1 Macc",
+        usfm: "1MA",
+        canon: Canon::Catholic,
+        verse_counts: &[
+            64, 70, 60, 61, 68, 63, 50, 32, 73, 89, 74, 53, 53, 49, 41, 24,
+        ],
+    },
+    DeuterocanonicalBook {
+        title: "2 Maccabees",
+        osis: "2Macc",
+        short: "2 Macc",
+        usfm: "2MA",
+        canon: Canon::Catholic,
+        verse_counts: &[36, 32, 40, 50, 27, 31, 42, 36, 29, 38, 38, 45, 26, 46, 39],
+    },
+    DeuterocanonicalBook {
+        title: "1 Esdras",
+        osis: "1Esd",
+        short: "1 Esd",
+        usfm: "1ES",
+        canon: Canon::Orthodox,
+        verse_counts: &[58, 30, 24, 63, 73, 34, 15, 96, 55],
+    },
+    DeuterocanonicalBook {
+        title: "3 Maccabees",
+        osis: "3Macc",
+        short: "3 Macc",
+        usfm: "3MA",
+        canon: Canon::Orthodox,
+        verse_counts: &[29, 33, 30, 21, 51, 41, 23],
+    },
+    DeuterocanonicalBook {
+        title: "Prayer of Manasseh",
+        osis: "PrMan",
+        short: "Pr Man",
+        usfm: "MAN",
+        canon: Canon::Orthodox,
+        verse_counts: &[15],
+    },
+];
+
+/// The get_deuterocanonical_books function returns the deuterocanonical
+/// books of the canon the process serves, in the order they are numbered.
+pub fn get_deuterocanonical_books() -> impl Iterator<Item = &'static DeuterocanonicalBook> {
+    get_deuterocanonical_books_in(get_canon())
+}
+
+/// The get_deuterocanonical_book function takes a book title and returns the
+/// deuterocanonical book in an Option. If the book is not found, or the
+/// canon the process serves does not hold it, None is returned.
+pub fn get_deuterocanonical_book(book: &str) -> Option<&'static DeuterocanonicalBook> {
+    get_deuterocanonical_books().find(|deuterocanonical| deuterocanonical.title == book)
+}
+
+pub(crate) fn get_deuterocanonical_books_in(
+    canon: Canon,
+) -> impl Iterator<Item = &'static DeuterocanonicalBook> {
+    DEUTEROCANONICAL_BOOKS
+        .iter()
+        .filter(move |book| book.canon <= canon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canon_parses_each_canon_name() {
+        assert_eq!("protestant".parse::<Canon>(), Ok(Canon::Protestant));
+        assert_eq!(" Catholic".parse::<Canon>(), Ok(Canon::Catholic));
+        assert_eq!("ORTHODOX".parse::<Canon>(), Ok(Canon::Orthodox));
+        assert!("mormon".parse::<Canon>().is_err());
+    }
+
+    #[test]
+    fn each_canon_holds_the_books_of_the_one_before_it() {
+        let count = |canon| get_deuterocanonical_books_in(canon).count();
+
+        assert_eq!(count(Canon::Protestant), 0);
+        assert_eq!(count(Canon::Catholic), 7);
+        assert_eq!(count(Canon::Orthodox), 10);
+        assert!(Canon::Protestant.contains("John"));
+        assert!(!Canon::Protestant.contains("Tobit"));
+        assert!(Canon::Catholic.contains("Tobit"));
+        assert!(!Canon::Catholic.contains("3 Maccabees"));
+        assert!(Canon::Orthodox.contains("3 Maccabees"));
+        assert!(!Canon::Orthodox.contains("Hezekiah"));
+    }
+
+    #[test]
+    fn deuterocanonical_books_have_their_chapters() {
+        let chapters = DEUTEROCANONICAL_BOOKS
+            .iter()
+            .map(|book| (book.title, book.verse_counts.len()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            chapters,
+            vec![
+                ("Tobit", 14),
+                ("Judith", 16),
+                ("Wisdom", 19),
+                ("Sirach", 51),
+                ("Baruch", 6),
+                ("1 Maccabees", 16),
+                ("2 Maccabees", 15),
+                ("1 Esdras", 9),
+                ("3 Maccabees", 7),
+                ("Prayer of Manasseh", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_process_serves_the_protestant_canon_by_default() {
+        assert_eq!(get_canon(), Canon::Protestant);
+        assert_eq!(get_deuterocanonical_book("Tobit"), None);
+    }
+}
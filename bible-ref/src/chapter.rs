@@ -0,0 +1,425 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr};
+
+use crate::canon::{get_deuterocanonical_book, get_deuterocanonical_books, DEUTEROCANONICAL_BOOKS};
+
+/// The BOOKS constant lists every book title, as it exists in the DB, in
+/// canonical order (Genesis through Revelation).
+pub const BOOKS: [&str; 66] = [
+    "Genesis",
+    "Exodus",
+    "Leviticus",
+    "Numbers",
+    "Deuteronomy",
+    "Joshua",
+    "Judges",
+    "Ruth",
+    "1 Samuel",
+    "2 Samuel",
+    "1 Kings",
+    "2 Kings",
+    "1 Chronicles",
+    "2 Chronicles",
+    "Ezra",
+    "Nehemiah",
+    "Esther",
+    "Job",
+    "Psalms",
+    "Proverbs",
+    "Ecclesiastes",
+    "Song of Solomon",
+    "Isaiah",
+    "Jeremiah",
+    "Lamentations",
+    "Ezekiel",
+    "Daniel",
+    "Hosea",
+    "Joel",
+    "Amos",
+    "Obadiah",
+    "Jonah",
+    "Micah",
+    "Nahum",
+    "Habakkuk",
+    "Zephaniah",
+    "Haggai",
+    "Zechariah",
+    "Malachi",
+    "Matthew",
+    "Mark",
+    "Luke",
+    "John",
+    "Acts",
+    "Romans",
+    "1 Corinthians",
+    "2 Corinthians",
+    "Galatians",
+    "Ephesians",
+    "Philippians",
+    "Colossians",
+    "1 Thessalonians",
+    "2 Thessalonians",
+    "1 Timothy",
+    "2 Timothy",
+    "Titus",
+    "Philemon",
+    "Hebrews",
+    "James",
+    "1 Peter",
+    "2 Peter",
+    "1 John",
+    "2 John",
+    "3 John",
+    "Jude",
+    "Revelation",
+];
+
+/// The get_books function returns the titles of the books of the canon the
+/// process serves (see canon::set_canon): BOOKS, followed by the
+/// deuterocanonical books the canon holds.
+pub fn get_books() -> Vec<&'static str> {
+    BOOKS
+        .iter()
+        .copied()
+        .chain(get_deuterocanonical_books().map(|book| book.title))
+        .collect()
+}
+
+/// The get_book_number function takes a book title and returns the place of
+/// the book counted from 1 in an Option: BOOKS come first, then the
+/// DEUTEROCANONICAL_BOOKS (ex: Tobit is 67), so a book has the same number in
+/// every canon. If the book is not in the canon None is returned.
+pub fn get_book_number(book: &str) -> Option<usize> {
+    let number = match BOOKS.iter().position(|title| *title == book) {
+        Some(index) => index,
+        None => {
+            get_deuterocanonical_book(book)?;
+            BOOKS.len()
+                + DEUTEROCANONICAL_BOOKS
+                    .iter()
+                    .position(|deuterocanonical| deuterocanonical.title == book)?
+        }
+    };
+
+    Some(number + 1)
+}
+
+/// The get_book_by_number function takes the number of a book (see
+/// get_book_number) and returns its title in an Option. If there is no such
+/// book in the canon None is returned.
+pub fn get_book_by_number(number: usize) -> Option<&'static str> {
+    let index = number.checked_sub(1)?;
+
+    match BOOKS.get(index) {
+        Some(title) => Some(title),
+        None => DEUTEROCANONICAL_BOOKS
+            .get(index - BOOKS.len())
+            .map(|book| book.title)
+            .filter(|title| get_deuterocanonical_book(title).is_some()),
+    }
+}
+
+/// The OSIS_BOOKS constant lists the OSIS abbreviation of every book, in the
+/// same order as BOOKS (ex: "1 Samuel" is "1Sam").
+pub const OSIS_BOOKS: [&str; 66] = [
+    "Gen", "Exod", "Lev", "Num", "Deut", "Josh", "Judg", "Ruth", "1Sam", "2Sam", "1Kgs", "2Kgs",
+    "1Chr", "2Chr", "Ezra", "Neh", "Esth", "Job", "Ps", "Prov", "Eccl", "Song", "Isa", "Jer",
+    "Lam", "Ezek", "Dan", "Hos", "Joel", "Amos", "Obad", "Jonah", "Mic", "Nah", "Hab", "Zeph",
+    "Hag", "Zech", "Mal", "Matt", "Mark", "Luke", "John", "Acts", "Rom", "1Cor", "2Cor", "Gal",
+    "Eph", "Phil", "Col", "1Thess", "2Thess", "1Tim", "2Tim", "Titus", "Phlm", "Heb", "Jas",
+    "1Pet", "2Pet", "1John", "2John", "3John", "Jude", "Rev",
+];
+
+/// The get_osis_book function takes a book title and returns its OSIS
+/// abbreviation in an Option. If the book is not found None is returned.
+pub fn get_osis_book(book: &str) -> Option<&'static str> {
+    BOOKS
+        .iter()
+        .position(|title| *title == book)
+        .map(|index| OSIS_BOOKS[index])
+        .or_else(|| get_deuterocanonical_book(book).map(|book| book.osis))
+}
+
+/// The SHORT_BOOKS constant lists the shortest common abbreviation of every
+/// book, in the same order as BOOKS (ex: "John" is "Jn").
+pub const SHORT_BOOKS: [&str; 66] = [
+    "Gen", "Ex", "Lev", "Num", "Deut", "Josh", "Judg", "Ruth", "1 Sam", "2 Sam", "1 Kgs", "2 Kgs",
+    "1 Chr", "2 Chr", "Ezra", "Neh", "Esth", "Job", "Ps", "Prov", "Eccl", "Song", "Isa", "Jer",
+    "Lam", "Ezek", "Dan", "Hos", "Joel", "Amos", "Obad", "Jonah", "Mic", "Nah", "Hab", "Zeph",
+    "Hag", "Zech", "Mal", "Mt", "Mk", "Lk", "Jn", "Acts", "Rom", "1 Cor", "2 Cor", "Gal", "Eph",
+    "Phil", "Col", "1 Thess", "2 Thess", "1 Tim", "2 Tim", "Titus", "Phlm", "Heb", "Jas", "1 Pet",
+    "2 Pet", "1 Jn", "2 Jn", "3 Jn", "Jude", "Rev",
+];
+
+/// The get_short_book function takes a book title and returns its shortest
+/// common abbreviation in an Option. If the book is not found None is
+/// returned.
+pub fn get_short_book(book: &str) -> Option<&'static str> {
+    BOOKS
+        .iter()
+        .position(|title| *title == book)
+        .map(|index| SHORT_BOOKS[index])
+        .or_else(|| get_deuterocanonical_book(book).map(|book| book.short))
+}
+
+/// The USFM_BOOKS constant lists the USFM book code of every book, in the
+/// same order as BOOKS (ex: "1 Samuel" is "1SA").
+pub const USFM_BOOKS: [&str; 66] = [
+    "GEN", "EXO", "LEV", "NUM", "DEU", "JOS", "JDG", "RUT", "1SA", "2SA", "1KI", "2KI", "1CH",
+    "2CH", "EZR", "NEH", "EST", "JOB", "PSA", "PRO", "ECC", "SNG", "ISA", "JER", "LAM", "EZK",
+    "DAN", "HOS", "JOL", "AMO", "OBA", "JON", "MIC", "NAM", "HAB", "ZEP", "HAG", "ZEC", "MAL",
+    "MAT", "MRK", "LUK", "JHN", "ACT", "ROM", "1CO", "2CO", "GAL", "EPH", "PHP", "COL", "1TH",
+    "2TH", "1TI", "2TI", "TIT", "PHM", "HEB", "JAS", "1PE", "2PE", "1JN", "2JN", "3JN", "JUD",
+    "REV",
+];
+
+/// The get_usfm_code function takes a book title and returns its USFM book
+/// code in an Option. If the book is not found None is returned.
+pub fn get_usfm_code(book: &str) -> Option<&'static str> {
+    BOOKS
+        .iter()
+        .position(|title| *title == book)
+        .map(|index| USFM_BOOKS[index])
+        .or_else(|| get_deuterocanonical_book(book).map(|book| book.usfm))
+}
+
+/// The get_book_by_usfm_code function takes a USFM book code, in any case,
+/// and returns the book title in an Option. If the code is not found None is
+/// returned.
+pub fn get_book_by_usfm_code(code: &str) -> Option<&'static str> {
+    USFM_BOOKS
+        .iter()
+        .position(|usfm_code| usfm_code.eq_ignore_ascii_case(code.trim()))
+        .map(|index| BOOKS[index])
+        .or_else(|| {
+            get_deuterocanonical_books()
+                .find(|book| book.usfm.eq_ignore_ascii_case(code.trim()))
+                .map(|book| book.title)
+        })
+}
+
+/// The OLD_TESTAMENT_BOOKS is how many of the BOOKS, from the start, are in
+/// the Old Testament (Genesis through Malachi).
+const OLD_TESTAMENT_BOOKS: usize = 39;
+
+/// The Testament is the part of the Bible a book is in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum))]
+#[serde(rename_all = "lowercase")]
+pub enum Testament {
+    Old,
+    New,
+}
+
+impl Testament {
+    /// The as_str function returns the testament as it is written in
+    /// responses (ex: old).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Testament::Old => "old",
+            Testament::New => "new",
+        }
+    }
+}
+
+impl FromStr for Testament {
+    type Err = String;
+
+    fn from_str(testament: &str) -> Result<Self, Self::Err> {
+        match testament.trim().to_lowercase().as_str() {
+            "old" | "ot" => Ok(Testament::Old),
+            "new" | "nt" => Ok(Testament::New),
+            _ => Err(format!("Invalid testament: {} (ex: old or new)", testament)),
+        }
+    }
+}
+
+/// The get_testament function takes a book title and returns the testament
+/// it is in, or None when the book is not found. The deuterocanonical books
+/// are in the Old Testament.
+pub fn get_testament(book: &str) -> Option<Testament> {
+    get_book_number(book).map(|number| {
+        match number <= OLD_TESTAMENT_BOOKS || number > BOOKS.len() {
+            true => Testament::Old,
+            false => Testament::New,
+        }
+    })
+}
+
+/// The get_books_in_testament function returns the titles of the books in a
+/// testament, in the order of get_books.
+pub fn get_books_in_testament(testament: Testament) -> Vec<&'static str> {
+    get_books()
+        .into_iter()
+        .filter(|book| get_testament(book) == Some(testament))
+        .collect()
+}
+
+/// The get_chapter_count_by_book function takes a book name and returns the number of
+/// chapters in that book in an Option. If the book is not found None is returned.
+pub fn get_chapter_count_by_book(book: &str) -> Option<u8> {
+    let chapter_counts: HashMap<&str, u8> = HashMap::from([
+        ("1 Chronicles", 29),
+        ("1 Corinthians", 16),
+        ("1 John", 5),
+        ("1 Kings", 22),
+        ("1 Peter", 5),
+        ("1 Samuel", 31),
+        ("1 Thessalonians", 5),
+        ("1 Timothy", 6),
+        ("2 Chronicles", 36),
+        ("2 Corinthians", 13),
+        ("2 John", 1),
+        ("2 Kings", 25),
+        ("2 Peter", 3),
+        ("2 Samuel", 24),
+        ("2 Thessalonians", 3),
+        ("2 Timothy", 4),
+        ("3 John", 1),
+        ("Acts", 28),
+        ("Amos", 9),
+        ("Colossians", 4),
+        ("Daniel", 12),
+        ("Deuteronomy", 34),
+        ("Ecclesiastes", 12),
+        ("Ephesians", 6),
+        ("Esther", 10),
+        ("Exodus", 40),
+        ("Ezekiel", 48),
+        ("Ezra", 10),
+        ("Galatians", 6),
+        ("Genesis", 50),
+        ("Habakkuk", 3),
+        ("Haggai", 2),
+        ("Hebrews", 13),
+        ("Hosea", 14),
+        ("Isaiah", 66),
+        ("James", 5),
+        ("Jeremiah", 52),
+        ("Job", 42),
+        ("Joel", 3),
+        ("John", 21),
+        ("Jonah", 4),
+        ("Joshua", 24),
+        ("Jude", 1),
+        ("Judges", 21),
+        ("Lamentations", 5),
+        ("Leviticus", 27),
+        ("Luke", 24),
+        ("Malachi", 4),
+        ("Mark", 16),
+        ("Matthew", 28),
+        ("Micah", 7),
+        ("Nahum", 3),
+        ("Nehemiah", 13),
+        ("Numbers", 36),
+        ("Obadiah", 1),
+        ("Philemon", 1),
+        ("Philippians", 4),
+        ("Proverbs", 31),
+        ("Psalms", 150),
+        ("Revelation", 22),
+        ("Romans", 16),
+        ("Ruth", 4),
+        ("Song of Solomon", 8),
+        ("Titus", 3),
+        ("Zechariah", 14),
+        ("Zephaniah", 3),
+    ]);
+
+    chapter_counts
+        .get(book)
+        .copied()
+        .or_else(|| get_deuterocanonical_book(book).map(|book| book.verse_counts.len() as u8))
+}
+
+/// The chapter_exists_in_book function takes a book name and a chapter number
+/// and returns a bool indicating whether the chapter exists in the book.
+pub fn chapter_exists_in_book(book: &str, chapter: u8) -> bool {
+    let num_chapters = match get_chapter_count_by_book(book) {
+        Some(num_chapters) => num_chapters,
+        None => return false,
+    };
+
+    chapter >= 1 && chapter <= num_chapters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_chapter_count_by_book_returns_50_for_genesis() {
+        assert_eq!(get_chapter_count_by_book("Genesis"), Some(50));
+    }
+
+    #[test]
+    fn get_chapter_count_by_book_returns_none_for_invalid_book() {
+        assert_eq!(get_chapter_count_by_book("Book of Robert"), None);
+    }
+
+    #[test]
+    fn get_chapter_exists_in_book_returns_true_if_book_has_that_chapter() {
+        assert!(chapter_exists_in_book("Job", 3));
+    }
+
+    #[test]
+    fn get_chapter_exists_in_book_returns_false_if_that_chapter_not_in_book() {
+        assert!(!chapter_exists_in_book("Job", 100));
+    }
+
+    #[test]
+    fn get_osis_book_returns_the_abbreviation_of_a_title() {
+        assert_eq!(get_osis_book("1 Samuel"), Some("1Sam"));
+        assert_eq!(get_osis_book("Song of Solomon"), Some("Song"));
+        assert_eq!(get_osis_book("Revelation"), Some("Rev"));
+        assert_eq!(get_osis_book("Hezekiah"), None);
+    }
+
+    #[test]
+    fn get_usfm_code_returns_the_code_of_a_title() {
+        assert_eq!(get_usfm_code("1 Samuel"), Some("1SA"));
+        assert_eq!(get_usfm_code("Song of Solomon"), Some("SNG"));
+        assert_eq!(get_usfm_code("Hezekiah"), None);
+    }
+
+    #[test]
+    fn get_book_by_usfm_code_returns_the_title_of_a_code() {
+        assert_eq!(get_book_by_usfm_code("1SA"), Some("1 Samuel"));
+        assert_eq!(get_book_by_usfm_code("jhn"), Some("John"));
+        assert_eq!(get_book_by_usfm_code("TOB"), None);
+    }
+
+    #[test]
+    fn get_testament_splits_the_books_at_matthew() {
+        assert_eq!(get_testament("Malachi"), Some(Testament::Old));
+        assert_eq!(get_testament("Matthew"), Some(Testament::New));
+        assert_eq!(get_testament("Hezekiah"), None);
+        assert_eq!(get_books_in_testament(Testament::New).len(), 27);
+        assert_eq!("NT".parse::<Testament>(), Ok(Testament::New));
+    }
+
+    #[test]
+    fn get_book_number_counts_the_books_from_genesis() {
+        assert_eq!(get_book_number("Genesis"), Some(1));
+        assert_eq!(get_book_number("Revelation"), Some(66));
+        assert_eq!(get_book_by_number(43), Some("John"));
+        assert_eq!(get_book_by_number(0), None);
+    }
+
+    #[test]
+    fn deuterocanonical_books_are_not_in_the_default_canon() {
+        assert_eq!(get_books(), BOOKS);
+        assert_eq!(get_book_number("Tobit"), None);
+        assert_eq!(get_book_by_number(67), None);
+        assert_eq!(get_chapter_count_by_book("Sirach"), None);
+        assert_eq!(get_testament("Judith"), None);
+    }
+
+    #[test]
+    fn books_all_have_a_chapter_count() {
+        assert!(BOOKS
+            .iter()
+            .all(|book| get_chapter_count_by_book(book).is_some()));
+    }
+}
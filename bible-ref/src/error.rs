@@ -0,0 +1,49 @@
+use thiserror::Error;
+
+/// The ReferenceError is what can go wrong resolving a reference to the
+/// verses it covers.
+/// - EmptyQuery is a query with no reference in it (ex: ;)
+/// - ParseError is a reference that was read, but can not be searched for
+///   (ex: John 3:5-2)
+/// - UnknownBook is a reference whose book could not be found
+/// - ChapterOutOfRange is a strict search for a chapter the book does not
+///   have
+/// - VerseOutOfRange is a strict search for a verse the chapter does not
+///   have
+#[derive(Debug, PartialEq, Clone, Error)]
+pub enum ReferenceError {
+    #[error("No Results Found")]
+    EmptyQuery,
+    #[error("{0}")]
+    ParseError(String),
+    #[error("No Matching Book Found: {0}")]
+    UnknownBook(String),
+    #[error("{title} has no chapter {chapter}")]
+    ChapterOutOfRange { title: String, chapter: u8 },
+    #[error("{title} {chapter} has no verse {verse}")]
+    VerseOutOfRange {
+        title: String,
+        chapter: u8,
+        verse: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_error_names_the_reference() {
+        let out_of_range = ReferenceError::VerseOutOfRange {
+            title: String::from("John"),
+            chapter: 3,
+            verse: String::from("99"),
+        };
+
+        assert_eq!(out_of_range.to_string(), "John 3 has no verse 99");
+        assert_eq!(
+            ReferenceError::UnknownBook(String::from("Robert 1")).to_string(),
+            "No Matching Book Found: Robert 1"
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! The bible-ref crate reads bible references the way people write them (ex:
+//! 1st jn 3:16-18, 20; John chapter three; John.3.16) and resolves them to
+//! the chapters and verses they cover. It knows the books, chapters and
+//! verses of the 66 book canon, and of the deuterocanonical books the
+//! Catholic and Orthodox canons add (see canon::set_canon), and needs no
+//! database.
+//!
+//! ```
+//! use bible_ref::{get_reference, search};
+//!
+//! let bible_search = search("first jn 3:16-18, 20").unwrap();
+//!
+//! assert_eq!(bible_search.title, "1 John");
+//! assert_eq!(bible_search.verse_count(), 4);
+//! assert_eq!(get_reference(&bible_search), "1 John 3:16-18, 20");
+//! ```
+
+pub mod book;
+pub mod canon;
+pub mod chapter;
+pub mod error;
+pub mod params;
+pub mod reference;
+pub mod search;
+pub mod spoken;
+pub mod verse;
+pub mod versification;
+
+pub use book::get_title;
+pub use canon::Canon;
+pub use error::ReferenceError;
+pub use search::{
+    get_reference, get_reference_with, search, search_passages, search_with, BibleSearch, Chapter,
+};
+pub use versification::Versification;
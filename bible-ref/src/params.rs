@@ -0,0 +1,447 @@
+use crate::{
+    book::get_osis_title,
+    error::ReferenceError,
+    reference::{parse, PassageSpan, ReferenceAst},
+    versification::Versification,
+};
+use serde::Serialize;
+
+/// The SearchType enum exists to identify the type of a bible search.
+/// - Book (ex: Job)
+/// - Chapter (ex: Job 1)
+/// - ChapterRange (ex: Job 1-3)
+/// - Verse (ex: Job 1:2)
+/// - VerseRange (ex: Job 1:2-3)
+#[derive(Debug, PartialEq, Serialize)]
+pub enum SearchType {
+    Book,
+    Chapter,
+    ChapterRange,
+    Verse,
+    VerseRange,
+}
+
+/// The BookParams are the parts of a query a search is built from. The
+/// chapter_end is only set for a range that ends in another chapter than it
+/// starts in, with verse_end being a verse of that chapter (ex: John 3:16-4:2)
+/// or None when the range ends with the whole chapter (ex: Genesis 1-3). A
+/// range can end in an earlier chapter (ex: Mark 16:9-8:1 or John 4-3),
+/// which is left for the search to refuse.
+#[derive(Debug, PartialEq)]
+pub struct BookParams {
+    pub search_type: SearchType,
+    pub title: String,
+    pub chapter: Option<u8>,
+    pub verse_start: Option<u8>,
+    pub verse_end: Option<u8>,
+    pub chapter_end: Option<u8>,
+}
+
+/// The get_search_params function takes the search query, parses it into a
+/// ReferenceAst, then determines the search type from the first passage and
+/// finally builds and returns a BookParams. The query is refused when its
+/// book can not be found or its passages can not be read.
+pub fn get_search_params(query: &str) -> Result<BookParams, ReferenceError> {
+    let reference = parse(query)?;
+
+    Ok(get_reference_params(&reference).remove(0))
+}
+
+/// The get_reference_params function returns a BookParams for each passage
+/// of a ReferenceAst, in order (ex: John 3:16 and John 3:18-20 for John
+/// 3:16, 18-20). A reference without passages is the whole book.
+pub fn get_reference_params(reference: &ReferenceAst) -> Vec<BookParams> {
+    // If there are no passages, then return the book.
+    if reference.passages.is_empty() {
+        return vec![get_book(&reference.title)];
+    }
+
+    reference
+        .passages
+        .iter()
+        .map(|passage| get_passage_params(&reference.title, passage))
+        .collect()
+}
+
+// The get_passage_params function determines the search type of a passage.
+fn get_passage_params(title: &str, passage: &PassageSpan) -> BookParams {
+    let start = passage.start;
+
+    match (start.verse, passage.end) {
+        // Ex: Job 1:2-3
+        (Some(verse_start), Some(end)) if end.chapter == start.chapter => BookParams {
+            search_type: SearchType::VerseRange,
+            title: title.to_owned(),
+            chapter: Some(start.chapter),
+            verse_start: Some(verse_start),
+            verse_end: end.verse,
+            chapter_end: None,
+        },
+        // Ex: Job 1:2-2:3 runs on into the chapter it ends in
+        (Some(verse_start), Some(end)) if end.chapter > start.chapter => BookParams {
+            search_type: SearchType::VerseRange,
+            title: title.to_owned(),
+            chapter: Some(start.chapter),
+            verse_start: Some(verse_start),
+            verse_end: end.verse,
+            chapter_end: Some(end.chapter),
+        },
+        // Ex: Job 2:3-1:2 is backwards, which the search refuses
+        (Some(verse_start), Some(end)) => BookParams {
+            search_type: SearchType::VerseRange,
+            title: title.to_owned(),
+            chapter: Some(start.chapter),
+            verse_start: Some(verse_start),
+            verse_end: end.verse,
+            chapter_end: Some(end.chapter),
+        },
+        // Ex: Job 1:2
+        (Some(verse_start), None) => BookParams {
+            search_type: SearchType::Verse,
+            title: title.to_owned(),
+            chapter: Some(start.chapter),
+            verse_start: Some(verse_start),
+            verse_end: None,
+            chapter_end: None,
+        },
+        // Ex: Job 1-3 (or Job 2-1, which is backwards, so the search
+        // refuses it)
+        (None, Some(end)) if end.chapter != start.chapter && end.verse.is_none() => BookParams {
+            search_type: SearchType::ChapterRange,
+            title: title.to_owned(),
+            chapter: Some(start.chapter),
+            verse_start: None,
+            verse_end: None,
+            chapter_end: Some(end.chapter),
+        },
+        // Ex: Job 1-2:3 starts at the first verse of the chapter
+        (None, Some(end)) if end.chapter != start.chapter => BookParams {
+            search_type: SearchType::VerseRange,
+            title: title.to_owned(),
+            chapter: Some(start.chapter),
+            verse_start: Some(1),
+            verse_end: end.verse,
+            chapter_end: Some(end.chapter),
+        },
+        // Ex: Job 1
+        (None, _) => BookParams {
+            search_type: SearchType::Chapter,
+            title: title.to_owned(),
+            chapter: Some(start.chapter),
+            verse_start: None,
+            verse_end: None,
+            chapter_end: None,
+        },
+    }
+}
+
+// Ex: Job
+fn get_book(title: &str) -> BookParams {
+    BookParams {
+        search_type: SearchType::Book,
+        title: title.to_owned(),
+        chapter: None,
+        verse_start: None,
+        verse_end: None,
+        chapter_end: None,
+    }
+}
+
+/// The normalize_osis function writes an OSIS reference (ex:
+/// 1John.2.3-1John.2.5) as the reference a person would write (ex: 1 John
+/// 2:3-5), so it can be searched like one. A range that runs into another
+/// book (ex: John.21.25-Acts.1.1) is refused. Any other query is returned as
+/// it is. A range to a whole chapter ends with its last verse in the built in
+/// versification.
+pub fn normalize_osis(query: &str) -> Result<String, ReferenceError> {
+    normalize_osis_with(query, &Versification::default())
+}
+
+/// The normalize_osis_with function writes out an OSIS reference the same
+/// way as normalize_osis, ending a range to a whole chapter with its last
+/// verse in the versification of a translation.
+pub fn normalize_osis_with(
+    query: &str,
+    versification: &Versification,
+) -> Result<String, ReferenceError> {
+    read_osis(query.trim(), versification).unwrap_or_else(|| Ok(query.to_owned()))
+}
+
+fn read_osis(query: &str, versification: &Versification) -> Option<Result<String, ReferenceError>> {
+    // A bare book id is the book (ex: 1John)
+    if let Some(title) = get_osis_title(query) {
+        return Some(Ok(title.to_owned()));
+    }
+
+    let (start, end) = match query.split_once('-') {
+        Some((start, end)) => (start, Some(read_osis_id(end)?)),
+        None => (query, None),
+    };
+    let (title, chapter, verse) = read_osis_id(start)?;
+
+    let reference = match (verse, end) {
+        (None, None) => format!("{} {}", title, chapter),
+        (Some(verse), None) => format!("{} {}:{}", title, chapter, verse),
+        (_, Some((end_title, _, _))) if end_title != title => {
+            return Some(Err(ReferenceError::ParseError(format!(
+                "{} runs from {} into {}",
+                query, title, end_title
+            ))))
+        }
+        (None, Some((_, end_chapter, None))) => format!("{} {}-{}", title, chapter, end_chapter),
+        (verse, Some((_, end_chapter, end_verse))) => {
+            let verse = verse.unwrap_or(1);
+            // A range to a whole chapter ends with its last verse
+            let end_verse = match end_verse {
+                Some(end_verse) => end_verse,
+                None => versification.get_verse_count(title, end_chapter)?,
+            };
+            match end_chapter == chapter {
+                true => format!("{} {}:{}-{}", title, chapter, verse, end_verse),
+                false => format!(
+                    "{} {}:{}-{}:{}",
+                    title, chapter, verse, end_chapter, end_verse
+                ),
+            }
+        }
+    };
+
+    Some(Ok(reference))
+}
+
+// An OSIS id of a chapter or verse is the book, the chapter and maybe the
+// verse, separated by periods (ex: John.3 or John.3.16)
+fn read_osis_id(id: &str) -> Option<(&'static str, u8, Option<u8>)> {
+    let mut parts = id.split('.');
+    let title = get_osis_title(parts.next()?)?;
+    let chapter = parts.next()?.parse::<u8>().ok()?;
+    let verse = match parts.next() {
+        Some(verse) => Some(verse.parse::<u8>().ok()?),
+        None => None,
+    };
+
+    match parts.next() {
+        Some(_) => None,
+        None => Some((title, chapter, verse)),
+    }
+}
+
+/// The get_passages function splits the query on semicolons into the
+/// passages it asks for (ex: John 3:16; Romans 8:28), leaving out empty ones.
+pub fn get_passages(query: &str) -> Vec<&str> {
+    query
+        .split(';')
+        .map(str::trim)
+        .filter(|passage| !passage.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_search_params_for_book_query() {
+        assert_eq!(
+            get_search_params(" 3 John ").unwrap(),
+            BookParams {
+                search_type: SearchType::Book,
+                title: String::from("3 John"),
+                chapter: None,
+                verse_start: None,
+                verse_end: None,
+                chapter_end: None,
+            }
+        );
+    }
+
+    #[test]
+    fn get_search_params_for_chapter_query() {
+        assert_eq!(
+            get_search_params(" 3 John 5").unwrap(),
+            BookParams {
+                search_type: SearchType::Chapter,
+                title: String::from("3 John"),
+                chapter: Some(5),
+                verse_start: None,
+                verse_end: None,
+                chapter_end: None,
+            }
+        );
+    }
+
+    #[test]
+    fn get_search_params_for_verse_query() {
+        assert_eq!(
+            get_search_params(" 3 John 125:221").unwrap(),
+            BookParams {
+                search_type: SearchType::Verse,
+                title: String::from("3 John"),
+                chapter: Some(125),
+                verse_start: Some(221),
+                verse_end: None,
+                chapter_end: None,
+            }
+        );
+    }
+
+    #[test]
+    fn get_search_params_for_verse_range_query() {
+        assert_eq!(
+            get_search_params(" 3 John 125:221-225").unwrap(),
+            BookParams {
+                search_type: SearchType::VerseRange,
+                title: String::from("3 John"),
+                chapter: Some(125),
+                verse_start: Some(221),
+                verse_end: Some(225),
+                chapter_end: None,
+            }
+        );
+    }
+
+    #[test]
+    fn get_search_params_for_chapter_range_query() {
+        assert_eq!(
+            get_search_params("Genesis 1-3").unwrap(),
+            BookParams {
+                search_type: SearchType::ChapterRange,
+                title: String::from("Genesis"),
+                chapter: Some(1),
+                verse_start: None,
+                verse_end: None,
+                chapter_end: Some(3),
+            }
+        );
+    }
+
+    #[test]
+    fn get_search_params_for_chapter_to_verse_range_starts_at_verse_one() {
+        assert_eq!(
+            get_search_params("Genesis 1-2:3").unwrap(),
+            BookParams {
+                search_type: SearchType::VerseRange,
+                title: String::from("Genesis"),
+                chapter: Some(1),
+                verse_start: Some(1),
+                verse_end: Some(3),
+                chapter_end: Some(2),
+            }
+        );
+    }
+
+    #[test]
+    fn get_search_params_keeps_the_end_of_a_backwards_chapter_range() {
+        assert_eq!(
+            get_search_params("Job 2-1").unwrap(),
+            BookParams {
+                search_type: SearchType::ChapterRange,
+                title: String::from("Job"),
+                chapter: Some(2),
+                verse_start: None,
+                verse_end: None,
+                chapter_end: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn get_search_params_returns_none_on_invalid_format() {
+        assert!(get_search_params(" 3 John *125-:225").is_err());
+    }
+
+    #[test]
+    fn get_search_params_refuses_an_unknown_book() {
+        assert!(matches!(
+            get_search_params("Book of Robert 1"),
+            Err(ReferenceError::UnknownBook(_))
+        ));
+    }
+
+    #[test]
+    fn get_search_params_for_cross_chapter_range_ends_in_the_later_chapter() {
+        assert_eq!(
+            get_search_params("John 3:16-4:2").unwrap(),
+            BookParams {
+                search_type: SearchType::VerseRange,
+                title: String::from("John"),
+                chapter: Some(3),
+                verse_start: Some(16),
+                verse_end: Some(2),
+                chapter_end: Some(4),
+            }
+        );
+    }
+
+    #[test]
+    fn get_reference_params_reads_each_passage() {
+        let params = get_reference_params(&parse("John 3:16, 18-20").unwrap());
+
+        assert_eq!(
+            params
+                .iter()
+                .map(|params| (&params.search_type, params.verse_start))
+                .collect::<Vec<_>>(),
+            vec![
+                (&SearchType::Verse, Some(16)),
+                (&SearchType::VerseRange, Some(18))
+            ]
+        );
+        assert_eq!(
+            get_reference_params(&parse("Job").unwrap()),
+            vec![get_book("Job")]
+        );
+    }
+
+    #[test]
+    fn get_passages_splits_the_query_on_semicolons() {
+        assert_eq!(
+            get_passages(" John 3:16; Rom 8:28 ;; Ps 23; "),
+            vec!["John 3:16", "Rom 8:28", "Ps 23"]
+        );
+        assert_eq!(get_passages("John 3:16, 18"), vec!["John 3:16, 18"]);
+    }
+
+    #[test]
+    fn normalize_osis_writes_out_osis_references() {
+        let normalize = |query: &str| normalize_osis(query).unwrap();
+
+        assert_eq!(normalize("1John.2.3-1John.2.5"), "1 John 2:3-5");
+        assert_eq!(normalize("John.3.16-John.4.2"), "John 3:16-4:2");
+        assert_eq!(normalize("Gen.1-Gen.3"), "Genesis 1-3");
+        assert_eq!(normalize("Ps.23"), "Psalms 23");
+        assert_eq!(normalize("Jude.1.24-Jude.1"), "Jude 1:24-25");
+        assert_eq!(normalize("Phlm"), "Philemon");
+    }
+
+    #[test]
+    fn normalize_osis_with_ends_a_range_with_the_last_verse_of_the_translation() {
+        let versification = Versification::from_counts([(String::from("Leviticus"), 6, 30)]);
+
+        assert_eq!(normalize_osis("Lev.6.1-Lev.6").unwrap(), "Leviticus 6:1-23");
+        assert_eq!(
+            normalize_osis_with("Lev.6.1-Lev.6", &versification).unwrap(),
+            "Leviticus 6:1-30"
+        );
+    }
+
+    #[test]
+    fn normalize_osis_refuses_a_range_into_another_book() {
+        assert_eq!(
+            normalize_osis("John.3.16-Acts.1.1"),
+            Err(ReferenceError::ParseError(String::from(
+                "John.3.16-Acts.1.1 runs from John into Acts"
+            )))
+        );
+        assert!(normalize_osis("Gen.1.1-Exod.1.1").is_err());
+    }
+
+    #[test]
+    fn normalize_osis_leaves_other_queries_alone() {
+        let normalize = |query: &str| normalize_osis(query).unwrap();
+
+        assert_eq!(normalize("John 3:16"), "John 3:16");
+        assert_eq!(normalize("John.3.16.2"), "John.3.16.2");
+        assert_eq!(normalize("Jn.3.16"), "Jn.3.16");
+    }
+}
@@ -0,0 +1,487 @@
+use serde::Serialize;
+
+use crate::{
+    book::{get_params, get_title},
+    error::ReferenceError,
+};
+
+/// The REFERENCE_PUNCTUATION characters can end an abbreviation or a
+/// reference (ex: the periods in "Rom. 8:28.") without changing its meaning.
+pub const REFERENCE_PUNCTUATION: [char; 3] = ['.', ',', ';'];
+
+/// The ZERO_WIDTH_CHARS are invisible characters that web pages put inside
+/// text, and that are carried along when a reference is copied from one.
+const ZERO_WIDTH_CHARS: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+/// The Token enum is the output of the tokenizer. A query such as
+/// "1 John 2:3-5, 7" becomes Number, Word, Number, Colon, Number, Dash,
+/// Number, Comma, Number. Whitespace only separates tokens, and en and em
+/// dashes are read as dashes (ex: John 3:16–18).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Token<'a> {
+    Word(&'a str),
+    Number(&'a str),
+    Colon,
+    Dash,
+    Comma,
+    Semicolon,
+    Period,
+    Other(char),
+}
+
+/// The ReferencePoint is a single location in a book. A point without a
+/// verse refers to the whole chapter (ex: John 3).
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct ReferencePoint {
+    pub chapter: u8,
+    pub verse: Option<u8>,
+}
+
+/// The PassageSpan is a point or a range between two points. The end of a
+/// range can be in another chapter (ex: John 3:16-4:2).
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct PassageSpan {
+    pub start: ReferencePoint,
+    pub end: Option<ReferencePoint>,
+}
+
+/// The ReferenceAst is the typed form of a whole query: the proper title of
+/// the book and the comma separated list of passages that follow it.
+#[derive(Debug, PartialEq)]
+pub struct ReferenceAst {
+    pub title: String,
+    pub passages: Vec<PassageSpan>,
+}
+
+/// The normalize_whitespace function drops zero width characters and turns
+/// every other kind of whitespace (tabs, non-breaking and thin spaces, etc.)
+/// into a single plain space, so a query copied from a web page matches the
+/// same way as one typed by hand.
+pub fn normalize_whitespace(query: &str) -> String {
+    let mut normalized = String::with_capacity(query.len());
+
+    for c in query.chars().filter(|c| !ZERO_WIDTH_CHARS.contains(c)) {
+        if !c.is_whitespace() {
+            normalized.push(c);
+        } else if !normalized.ends_with(' ') {
+            normalized.push(' ');
+        }
+    }
+
+    normalized
+}
+
+/// The tokenize function splits a query into tokens, keeping the byte offset
+/// each token starts at so callers can slice the original query.
+pub fn tokenize(query: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let token = if c.is_ascii_digit() || c.is_alphabetic() {
+            // Consume the rest of the run of digits or letters
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, next)) = chars.peek() {
+                if next.is_ascii_digit() != c.is_ascii_digit() || !next.is_alphanumeric() {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+
+            if c.is_ascii_digit() {
+                Token::Number(&query[start..end])
+            } else {
+                Token::Word(&query[start..end])
+            }
+        } else {
+            match c {
+                ':' => Token::Colon,
+                '-' | '\u{2013}' | '\u{2014}' => Token::Dash,
+                ',' => Token::Comma,
+                ';' => Token::Semicolon,
+                '.' => Token::Period,
+                other => Token::Other(other),
+            }
+        };
+
+        tokens.push((start, token));
+    }
+
+    tokens
+}
+
+/// The split_book function splits a query into the book portion and the
+/// params portion. The params start at the first number that follows a word,
+/// since a number before any word is the book number (ex: the 1 in 1 John),
+/// or at the first character that is no part of a reference, so it is not
+/// skipped over (ex: the fullwidth ３ of John ３:16).
+pub fn split_book(query: &str) -> (&str, Option<&str>) {
+    let mut seen_word = false;
+
+    for (offset, token) in tokenize(query) {
+        match token {
+            Token::Word(_) => seen_word = true,
+            Token::Number(_) | Token::Other(_) if seen_word => {
+                return (&query[..offset], Some(&query[offset..]))
+            }
+            _ => {}
+        }
+    }
+
+    (query, None)
+}
+
+/// The parse function turns a query into a ReferenceAst. It fails when the
+/// book can not be identified, or when the passages can not be read. A query
+/// with no recognizable chapter or verse returns an AST with no passages,
+/// which means the whole book.
+pub fn parse(query: &str) -> Result<ReferenceAst, ReferenceError> {
+    let title = get_title(query).ok_or_else(|| ReferenceError::UnknownBook(query.to_owned()))?;
+
+    let passages = match get_params(query) {
+        Some(params) => parse_passages(&params)?,
+        None => Vec::new(),
+    };
+
+    Ok(ReferenceAst { title, passages })
+}
+
+/// The parse_passages function parses the comma separated passage list that
+/// follows the book. Parsing stops at the first token that does not fit the
+/// grammar, so trailing text is ignored, but a character that is no part of
+/// a reference (ex: the fullwidth ３ of ３:16) fails the parse, as reading
+/// around it would change what was asked for:
+///
+/// passages := passage (',' passage)*
+/// passage  := point ('-' point)?
+/// point    := NUMBER (':' NUMBER)?
+///
+/// A bare NUMBER is a chapter, unless it follows a verse, in which case it is
+/// another verse of the same chapter (ex: the 5 in John 3:16-18, 5).
+pub fn parse_passages(params: &str) -> Result<Vec<PassageSpan>, ReferenceError> {
+    let tokens = tokenize(params);
+    let other = tokens.iter().find_map(|(_, token)| match token {
+        Token::Other(c) => Some(*c),
+        _ => None,
+    });
+    if let Some(c) = other {
+        return Err(ReferenceError::ParseError(format!(
+            "\"{}\" is not part of a reference",
+            c
+        )));
+    }
+
+    let mut parser = Parser::new(tokens);
+    let mut passages: Vec<PassageSpan> = Vec::new();
+
+    while passages.is_empty() || parser.eat(Token::Comma) {
+        let verse_chapter = passages.last().and_then(get_verse_chapter);
+
+        match parse_passage(&mut parser, verse_chapter) {
+            Some(passage) => passages.push(passage),
+            None => break,
+        }
+    }
+
+    Ok(passages)
+}
+
+// If a passage ends on a verse, a bare number after it is a verse in the
+// chapter the passage ends in.
+fn get_verse_chapter(passage: &PassageSpan) -> Option<u8> {
+    let last = passage.end.unwrap_or(passage.start);
+    last.verse.map(|_| last.chapter)
+}
+
+fn parse_passage(parser: &mut Parser, verse_chapter: Option<u8>) -> Option<PassageSpan> {
+    let start = parse_point(parser, verse_chapter)?;
+    let checkpoint = parser.position;
+
+    let mut end = None;
+    if parser.eat(Token::Dash) {
+        end = parse_point(parser, start.verse.map(|_| start.chapter));
+
+        // A dash with nothing usable after it is not a range
+        if end.is_none() {
+            parser.position = checkpoint;
+        }
+    }
+
+    Some(PassageSpan { start, end })
+}
+
+fn parse_point(parser: &mut Parser, verse_chapter: Option<u8>) -> Option<ReferencePoint> {
+    let number = parser.number()?;
+    let checkpoint = parser.position;
+
+    if parser.eat(Token::Colon) {
+        if let Some(verse) = parser.number() {
+            return Some(ReferencePoint {
+                chapter: number,
+                verse: Some(verse),
+            });
+        }
+
+        // A colon with no verse after it leaves just the chapter
+        parser.position = checkpoint;
+    }
+
+    Some(match verse_chapter {
+        Some(chapter) => ReferencePoint {
+            chapter,
+            verse: Some(number),
+        },
+        None => ReferencePoint {
+            chapter: number,
+            verse: None,
+        },
+    })
+}
+
+// The Parser walks the token list, and can be rewound by resetting position.
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: Vec<(usize, Token<'a>)>) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).map(|(_, token)| *token)
+    }
+
+    // Consume the next token if it is the expected one
+    fn eat(&mut self, expected: Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Consume the next token if it is a number that fits in a u8
+    fn number(&mut self) -> Option<u8> {
+        match self.peek() {
+            Some(Token::Number(digits)) => {
+                let number = digits.parse::<u8>().ok()?;
+                self.position += 1;
+                Some(number)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(chapter: u8) -> ReferencePoint {
+        ReferencePoint {
+            chapter,
+            verse: None,
+        }
+    }
+
+    fn verse(chapter: u8, verse: u8) -> ReferencePoint {
+        ReferencePoint {
+            chapter,
+            verse: Some(verse),
+        }
+    }
+
+    #[test]
+    fn tokenize_splits_numbers_words_and_punctuation() {
+        let tokens: Vec<Token> = tokenize("1John 2:3-5, 7")
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Number("1"),
+                Token::Word("John"),
+                Token::Number("2"),
+                Token::Colon,
+                Token::Number("3"),
+                Token::Dash,
+                Token::Number("5"),
+                Token::Comma,
+                Token::Number("7"),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_reads_en_and_em_dashes_as_dashes() {
+        let tokens: Vec<Token> = tokenize("3:16\u{2013}18\u{2014}20")
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Number("3"),
+                Token::Colon,
+                Token::Number("16"),
+                Token::Dash,
+                Token::Number("18"),
+                Token::Dash,
+                Token::Number("20"),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalize_whitespace_replaces_unicode_spaces_and_drops_zero_width_chars() {
+        assert_eq!(
+            normalize_whitespace("1\u{00A0}Jo\u{200B}hn\t\t2:3\u{2009}-\u{FEFF}5"),
+            "1 John 2:3 -5"
+        );
+    }
+
+    #[test]
+    fn split_book_keeps_the_book_number_with_the_book() {
+        assert_eq!(split_book("1 John 2:3"), ("1 John ", Some("2:3")));
+        assert_eq!(split_book("first john"), ("first john", None));
+    }
+
+    #[test]
+    fn split_book_starts_the_params_at_a_character_that_is_no_part_of_a_reference() {
+        assert_eq!(
+            split_book("John \u{FF13}:16"),
+            ("John ", Some("\u{FF13}:16"))
+        );
+    }
+
+    #[test]
+    fn parse_refuses_an_unknown_book() {
+        assert_eq!(
+            parse("Book of Robert 1:2"),
+            Err(ReferenceError::UnknownBook(String::from(
+                "Book of Robert 1:2"
+            )))
+        );
+    }
+
+    #[test]
+    fn parse_returns_no_passages_for_a_book_query() {
+        assert_eq!(
+            parse("Job").unwrap(),
+            ReferenceAst {
+                title: String::from("Job"),
+                passages: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_passages_reads_a_verse_range() {
+        assert_eq!(
+            parse_passages("2 : 3 - 5").unwrap(),
+            vec![PassageSpan {
+                start: verse(2, 3),
+                end: Some(verse(2, 5)),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_passages_reads_a_cross_chapter_range() {
+        assert_eq!(
+            parse_passages("3:16-4:2").unwrap(),
+            vec![PassageSpan {
+                start: verse(3, 16),
+                end: Some(verse(4, 2)),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_passages_reads_a_chapter_range() {
+        assert_eq!(
+            parse_passages("1-3").unwrap(),
+            vec![PassageSpan {
+                start: chapter(1),
+                end: Some(chapter(3)),
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_passages_reads_bare_numbers_after_a_verse_as_verses() {
+        assert_eq!(
+            parse_passages("1:2-3, 5, 7-9").unwrap(),
+            vec![
+                PassageSpan {
+                    start: verse(1, 2),
+                    end: Some(verse(1, 3)),
+                },
+                PassageSpan {
+                    start: verse(1, 5),
+                    end: None,
+                },
+                PassageSpan {
+                    start: verse(1, 7),
+                    end: Some(verse(1, 9)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_passages_ignores_trailing_text_that_does_not_fit() {
+        assert_eq!(
+            parse_passages("4: - abc").unwrap(),
+            vec![PassageSpan {
+                start: chapter(4),
+                end: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_reads_an_abbreviated_book_with_trailing_punctuation() {
+        assert_eq!(
+            parse("Rom. 8:28.").unwrap(),
+            ReferenceAst {
+                title: String::from("Romans"),
+                passages: vec![PassageSpan {
+                    start: verse(8, 28),
+                    end: None,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_passages_returns_nothing_for_numbers_that_are_too_large() {
+        assert_eq!(parse_passages("300").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn parse_passages_refuses_a_character_that_is_no_part_of_a_reference() {
+        assert_eq!(
+            parse_passages("\u{FF13}:16"),
+            Err(ReferenceError::ParseError(String::from(
+                "\"\u{FF13}\" is not part of a reference"
+            )))
+        );
+        assert!(parse_passages("3:16 * 2").is_err());
+        assert!(parse("John \u{FF13}:16").is_err());
+    }
+}
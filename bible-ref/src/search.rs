@@ -0,0 +1,1349 @@
+use crate::{
+    error::ReferenceError,
+    params::{get_passages, get_reference_params, normalize_osis_with, BookParams, SearchType},
+    reference::{normalize_whitespace, parse, tokenize, Token, REFERENCE_PUNCTUATION},
+    spoken::normalize_spoken,
+    verse::SUPERSCRIPTION_VERSE,
+    versification::Versification,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// The BibleSearch is the verses of a book a query resolves to, with a
+/// Chapter for each chapter it reaches into, in order (ex: John 3:16-4:2 is
+/// John 3:16-36 and John 4:1-2).
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct BibleSearch {
+    pub title: String,
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Chapter {
+    pub chapter: u8,
+    pub verses: HashSet<u8>,
+}
+
+impl BibleSearch {
+    /// The from_verses function builds the search of a book from its
+    /// chapters and verses, given in order.
+    pub fn from_verses(title: &str, verses: impl IntoIterator<Item = (u8, u8)>) -> Self {
+        let mut chapters: Vec<Chapter> = vec![];
+        for (chapter, verse) in verses {
+            match chapters.last_mut() {
+                Some(last) if last.chapter == chapter => {
+                    last.verses.insert(verse);
+                }
+                _ => chapters.push(Chapter {
+                    chapter,
+                    verses: HashSet::from([verse]),
+                }),
+            }
+        }
+
+        BibleSearch {
+            title: title.to_owned(),
+            chapters,
+        }
+    }
+
+    /// The get_verses function returns the chapter and verse of every verse
+    /// of the search, in order.
+    pub fn get_verses(&self) -> Vec<(u8, u8)> {
+        self.chapters
+            .iter()
+            .flat_map(|chapter| {
+                let mut verses = chapter.verses.iter().copied().collect::<Vec<u8>>();
+                verses.sort_unstable();
+                verses.into_iter().map(|verse| (chapter.chapter, verse))
+            })
+            .collect()
+    }
+
+    /// The verse_count function returns how many verses the search covers,
+    /// across all of its chapters.
+    pub fn verse_count(&self) -> usize {
+        self.chapters
+            .iter()
+            .map(|chapter| chapter.verses.len())
+            .sum()
+    }
+}
+
+/// The search function resolves a query against the built in versification.
+pub fn search(query: &str) -> Result<BibleSearch, ReferenceError> {
+    search_with(query, &Versification::default())
+}
+
+/// The search_with function resolves a query against the versification of a
+/// translation, so the chapters and verses it checks are the ones the
+/// translation has (ex: Leviticus 6:30 in the KJV).
+pub fn search_with(
+    query: &str,
+    versification: &Versification,
+) -> Result<BibleSearch, ReferenceError> {
+    resolve(query, versification, false)
+}
+
+// A strict search does not fall back when the query asks for a chapter or
+// verse that does not exist (ex: to John 3 for John 3:99), the error says
+// what was missing instead
+fn resolve(
+    query: &str,
+    versification: &Versification,
+    strict: bool,
+) -> Result<BibleSearch, ReferenceError> {
+    // Clean up any whitespace the query was copied along with, and write out
+    // a reference that was given as an OSIS id (ex: John.3.16) or spoken (ex:
+    // John chapter three)
+    let query = normalize_spoken(&normalize_osis_with(
+        &normalize_whitespace(query),
+        versification,
+    )?);
+
+    // A number too big to be a chapter or verse is skipped by the parser
+    if strict {
+        let too_big = tokenize(&query)
+            .into_iter()
+            .find_map(|(_, token)| match token {
+                Token::Number(digits) if digits.parse::<u8>().is_err() => Some(digits),
+                _ => None,
+            });
+        if let Some(digits) = too_big {
+            return Err(ReferenceError::ParseError(format!(
+                "{} is not a chapter or verse",
+                digits
+            )));
+        }
+    }
+
+    // A query of nothing but punctuation asks for nothing
+    if query
+        .trim_matches(|c: char| c.is_whitespace() || REFERENCE_PUNCTUATION.contains(&c))
+        .is_empty()
+    {
+        return Err(ReferenceError::EmptyQuery);
+    }
+
+    // Get the typed search parameters of each passage of the query
+    let reference = parse(&query)?;
+    let mut passages = get_reference_params(&reference).into_iter();
+
+    // Process the first passage, then add the verses of the ones after it
+    // (ex: the 5 of John 3:16-4:2, 5 is John 4:5)
+    let mut bible_search = match passages.next() {
+        Some(params) => process_query(params, versification, strict)?,
+        None => return Err(ReferenceError::EmptyQuery),
+    };
+    for params in passages {
+        let chapters = process_sub_passage(params, versification, strict)?;
+        add_chapters(&mut bible_search, chapters);
+    }
+
+    Ok(bible_search)
+}
+
+/// The search_passages function resolves each passage of a query (ex: John
+/// 3:16; Romans 8:28), in order. A strict search fails with what is missing
+/// when a passage asks for a chapter or verse that does not exist, instead
+/// of falling back to the chapter or book.
+/// A passage without a book is in the book of the one before it (ex: the
+/// 4:2 of John 3:16; 4:2).
+pub fn search_passages(
+    query: &str,
+    versification: &Versification,
+    strict: bool,
+) -> Result<Vec<BibleSearch>, ReferenceError> {
+    let mut searches: Vec<BibleSearch> = vec![];
+
+    for passage in get_passages(query) {
+        let bible_search = match (resolve(passage, versification, strict), searches.last()) {
+            (Ok(bible_search), _) => bible_search,
+            (Err(err), None) => return Err(err),
+            (Err(err), Some(last)) => resolve(
+                &format!("{} {}", last.title, passage),
+                versification,
+                strict,
+            )
+            .map_err(|_| err)?,
+        };
+        searches.push(bible_search);
+    }
+
+    match searches.is_empty() {
+        true => Err(ReferenceError::EmptyQuery),
+        false => Ok(searches),
+    }
+}
+
+/// The is_whole_chapter function returns true when the search covers every
+/// verse of each of its chapters, which is when the chapters'
+/// superscriptions belong with it. The chapters are the built in ones.
+pub fn is_whole_chapter(bible_search: &BibleSearch) -> bool {
+    is_whole_chapter_with(bible_search, &Versification::default())
+}
+
+/// The is_whole_chapter_with function is is_whole_chapter for the chapters
+/// of a translation (ex: 3 John 1 has 15 verses in some, not 14).
+pub fn is_whole_chapter_with(bible_search: &BibleSearch, versification: &Versification) -> bool {
+    !bible_search.chapters.is_empty()
+        && bible_search.chapters.iter().all(|chapter| {
+            match versification.get_verse_count(&bible_search.title, chapter.chapter) {
+                Some(verse_count) => (1..=verse_count).all(|verse| chapter.verses.contains(&verse)),
+                None => false,
+            }
+        })
+}
+
+/// The get_reference function writes a search back out as its canonical
+/// reference, with runs of verses joined into ranges (ex: John 3:16-18, 20).
+/// A whole chapter is just the chapter (ex: John 3), as is a run of whole
+/// chapters (ex: Genesis 1-3), and a range that runs into the next chapter
+/// is written with both chapters (ex: John 3:16-4:2). The chapters are the
+/// built in ones.
+pub fn get_reference(bible_search: &BibleSearch) -> String {
+    get_reference_with(bible_search, &Versification::default())
+}
+
+/// The get_reference_with function is get_reference for the chapters of a
+/// translation, so its whole chapters and the ends of them are the ones it
+/// has.
+pub fn get_reference_with(bible_search: &BibleSearch, versification: &Versification) -> String {
+    let title = &bible_search.title;
+    let is_run = bible_search
+        .chapters
+        .windows(2)
+        .all(|pair| pair[0].chapter + 1 == pair[1].chapter);
+
+    match bible_search.chapters.as_slice() {
+        [] => return title.to_owned(),
+        [chapter]
+            if chapter.verses.is_empty() || is_whole_chapter_with(bible_search, versification) =>
+        {
+            return format!("{} {}", title, chapter.chapter)
+        }
+        [first, .., last] if is_run && is_whole_chapter_with(bible_search, versification) => {
+            return format!("{} {}-{}", title, first.chapter, last.chapter)
+        }
+        _ => {}
+    }
+
+    // A run goes on into the next chapter when it reaches the last verse of
+    // its chapter and the next chapter starts at verse 1
+    let mut ranges: Vec<((u8, u8), (u8, u8))> = vec![];
+    for (chapter, verse) in bible_search.get_verses() {
+        match ranges.last_mut() {
+            Some((_, end)) if is_next_verse(title, *end, (chapter, verse), versification) => {
+                *end = (chapter, verse)
+            }
+            _ => ranges.push(((chapter, verse), (chapter, verse))),
+        }
+    }
+
+    // The chapter is only written again when it changes
+    let mut current = None;
+    let mut reference = String::new();
+    for (start, end) in ranges {
+        match current == Some(start.0) {
+            true => reference.push_str(&format!(", {}", start.1)),
+            false if current.is_none() => reference.push_str(&format!("{}:{}", start.0, start.1)),
+            false => reference.push_str(&format!("; {}:{}", start.0, start.1)),
+        }
+        match (start == end, start.0 == end.0) {
+            (true, _) => {}
+            (false, true) => reference.push_str(&format!("-{}", end.1)),
+            (false, false) => reference.push_str(&format!("-{}:{}", end.0, end.1)),
+        }
+        current = Some(end.0);
+    }
+
+    format!("{} {}", title, reference)
+}
+
+fn is_next_verse(
+    title: &str,
+    (chapter, verse): (u8, u8),
+    next: (u8, u8),
+    versification: &Versification,
+) -> bool {
+    match versification.get_verse_count(title, chapter) {
+        Some(verse_count) if verse == verse_count => {
+            Some(next) == chapter.checked_add(1).map(|chapter| (chapter, 1))
+        }
+        _ => Some(next) == verse.checked_add(1).map(|verse| (chapter, verse)),
+    }
+}
+
+fn process_query(
+    params: BookParams,
+    versification: &Versification,
+    strict: bool,
+) -> Result<BibleSearch, ReferenceError> {
+    // Turn the typed parameters into a BibleSearch using the handlers
+    match params.search_type {
+        SearchType::Book => book_to_bible_search(params, versification),
+        SearchType::Chapter => chapter_to_bible_search(params, versification, strict),
+        SearchType::ChapterRange => chapter_range_to_bible_search(params, versification, strict),
+        SearchType::Verse => verse_to_bible_search(params, versification, strict),
+        SearchType::VerseRange => verse_range_to_bible_search(params, versification, strict),
+    }
+}
+
+// A passage after the first (ex: the 5 or 11-13 of Psalms 96:1-3, 5, 11-13)
+// does not fall back to its chapter or book. Only the verses of it the
+// chapters have are kept, unless the search is strict.
+fn process_sub_passage(
+    params: BookParams,
+    versification: &Versification,
+    strict: bool,
+) -> Result<Vec<Chapter>, ReferenceError> {
+    let existing = get_existing_verses(&params, versification);
+
+    match process_query(params, versification, true) {
+        Ok(bible_search) => Ok(bible_search.chapters),
+        Err(err @ ReferenceError::ParseError(_)) => Err(err),
+        Err(err) if strict => Err(err),
+        Err(_) => Ok(existing),
+    }
+}
+
+// The verses the chapters have from the start of the passage to its end
+fn get_existing_verses(params: &BookParams, versification: &Versification) -> Vec<Chapter> {
+    let Some(chapter) = params.chapter else {
+        return vec![];
+    };
+    let chapter_end = params.chapter_end.unwrap_or(chapter);
+    let verse_end = match params.search_type {
+        SearchType::Verse => params.verse_start,
+        _ => params.verse_end,
+    };
+
+    (chapter..=chapter_end)
+        .filter_map(|current| {
+            let start = match current == chapter {
+                true => params.verse_start.unwrap_or(1),
+                false => 1,
+            };
+            let end = match current == chapter_end {
+                true => verse_end.unwrap_or(u8::MAX),
+                false => u8::MAX,
+            };
+            let verses = versification.get_verse_range(&params.title, current, start..=end)?;
+
+            Some(Chapter {
+                chapter: current,
+                verses,
+            })
+        })
+        .collect()
+}
+
+// Add the chapters of a passage to the search, keeping the chapters in order
+// and joining the verses of a chapter it already has
+fn add_chapters(bible_search: &mut BibleSearch, chapters: Vec<Chapter>) {
+    for chapter in chapters {
+        match bible_search
+            .chapters
+            .binary_search_by_key(&chapter.chapter, |existing| existing.chapter)
+        {
+            Ok(index) => bible_search.chapters[index].verses.extend(chapter.verses),
+            Err(index) => bible_search.chapters.insert(index, chapter),
+        }
+    }
+}
+
+fn book_to_bible_search(
+    params: BookParams,
+    versification: &Versification,
+) -> Result<BibleSearch, ReferenceError> {
+    // A book search is a search for its first chapter, and a chapter search
+    // that fails falls back to the book, so a book the translation does not
+    // have (or starts after chapter 1) has to stop here
+    if !versification.chapter_exists(&params.title, 1) {
+        return match versification.get_chapter_count(&params.title) {
+            Some(_) => Err(ReferenceError::ChapterOutOfRange {
+                title: params.title,
+                chapter: 1,
+            }),
+            None => Err(ReferenceError::UnknownBook(params.title)),
+        };
+    }
+
+    let updated_params = BookParams {
+        search_type: SearchType::Chapter,
+        title: params.title,
+        chapter: Some(1),
+        verse_start: None,
+        verse_end: None,
+        chapter_end: None,
+    };
+
+    chapter_to_bible_search(updated_params, versification, false)
+}
+
+fn chapter_to_bible_search(
+    params: BookParams,
+    versification: &Versification,
+    strict: bool,
+) -> Result<BibleSearch, ReferenceError> {
+    // Get the chapter start
+    let chapter = match unwrap_chapter(&params.title, params.chapter, versification) {
+        Ok(value) => value,
+        Err(err) if strict => return Err(err),
+        Err(_) => return revert_to_book_search(params.title, versification),
+    };
+
+    // On a chapter search you just include ALL of the verses in the chapter.
+    // This should never fail as it should have been checked during the params
+    // processing, and the chapter and book are already validated here, so panic if it does.
+    let verses_in_chapter = versification
+        .get_verse_count(&params.title, chapter)
+        .unwrap();
+
+    // Build the BibleSearch
+    Ok(BibleSearch {
+        title: params.title,
+        chapters: vec![Chapter {
+            chapter,
+            verses: HashSet::from_iter(1..=verses_in_chapter),
+        }],
+    })
+}
+
+fn chapter_range_to_bible_search(
+    params: BookParams,
+    versification: &Versification,
+    strict: bool,
+) -> Result<BibleSearch, ReferenceError> {
+    // A range that ends before it starts is refused, as there is nothing
+    // sensible to fall back to
+    check_range_order(&params)?;
+
+    // Get the chapter start
+    let chapter = match unwrap_chapter(&params.title, params.chapter, versification) {
+        Ok(value) => value,
+        Err(err) if strict => return Err(err),
+        Err(_) => return revert_to_book_search(params.title, versification),
+    };
+
+    // Every verse of the starting chapter, then of each chapter after it up
+    // to the end of the range or the book
+    let verses_in_chapter = versification
+        .get_verse_count(&params.title, chapter)
+        .unwrap();
+    let mut chapters = vec![Chapter {
+        chapter,
+        verses: HashSet::from_iter(1..=verses_in_chapter),
+    }];
+    chapters.extend(get_following_chapters(
+        &params.title,
+        chapter,
+        params.chapter_end.unwrap_or(chapter),
+        None,
+        versification,
+    ));
+    if strict {
+        check_range_end(&params, &chapters, versification)?;
+    }
+
+    // Build the BibleSearch
+    Ok(BibleSearch {
+        title: params.title,
+        chapters,
+    })
+}
+
+fn verse_to_bible_search(
+    params: BookParams,
+    versification: &Versification,
+    strict: bool,
+) -> Result<BibleSearch, ReferenceError> {
+    // Get the chapter start
+    let chapter = match unwrap_chapter(&params.title, params.chapter, versification) {
+        Ok(value) => value,
+        Err(err) if strict => return Err(err),
+        Err(_) => return revert_to_book_search(params.title, versification),
+    };
+
+    // Get the verse start
+    let verses_start = match unwrap_verse(&params.title, chapter, params.verse_start, versification)
+    {
+        Ok(value) => value,
+        Err(err) if strict => return Err(err),
+        Err(_) => return revert_to_chapter_search(params.title, chapter, versification),
+    };
+
+    // Build the BibleSearch
+    Ok(BibleSearch {
+        title: params.title,
+        chapters: vec![Chapter {
+            chapter,
+            verses: HashSet::from([verses_start]),
+        }],
+    })
+}
+
+fn verse_range_to_bible_search(
+    params: BookParams,
+    versification: &Versification,
+    strict: bool,
+) -> Result<BibleSearch, ReferenceError> {
+    // A range that ends before it starts is refused, as there is nothing
+    // sensible to fall back to
+    check_range_order(&params)?;
+
+    // Get the chapter start
+    let chapter = match unwrap_chapter(&params.title, params.chapter, versification) {
+        Ok(value) => value,
+        Err(err) if strict => return Err(err),
+        Err(_) => return revert_to_book_search(params.title, versification),
+    };
+
+    // A range that ends in a later chapter reads to the end of this one
+    let verse_end = match params.chapter_end {
+        Some(_) => Some(u8::MAX),
+        None => params.verse_end,
+    };
+
+    // Get the verse range
+    let verses_range = match unwrap_verse_range(
+        &params.title,
+        chapter,
+        params.verse_start,
+        verse_end,
+        versification,
+    ) {
+        Ok(value) => value,
+        Err(err) if strict => return Err(err),
+        Err(_) => return revert_to_chapter_search(params.title, chapter, versification),
+    };
+
+    let mut chapters = vec![Chapter {
+        chapter,
+        verses: verses_range,
+    }];
+    if let Some(chapter_end) = params.chapter_end {
+        chapters.extend(get_following_chapters(
+            &params.title,
+            chapter,
+            chapter_end,
+            params.verse_end,
+            versification,
+        ));
+    }
+    if strict {
+        check_range_end(&params, &chapters, versification)?;
+    }
+
+    // Build the BibleSearch
+    Ok(BibleSearch {
+        title: params.title,
+        chapters,
+    })
+}
+
+// A range ends before it starts when it ends in an earlier chapter (ex: Mark
+// 16:9-8:1 or John 4-3), or on an earlier verse of the same one (ex: John
+// 3:16-10)
+fn check_range_order(params: &BookParams) -> Result<(), ReferenceError> {
+    let Some(chapter) = params.chapter else {
+        return Ok(());
+    };
+
+    let is_reversed = match (params.chapter_end, params.verse_start, params.verse_end) {
+        (Some(chapter_end), _, _) if chapter_end != chapter => chapter_end < chapter,
+        (_, Some(verse_start), Some(verse_end)) => verse_end < verse_start,
+        _ => false,
+    };
+    if !is_reversed {
+        return Ok(());
+    }
+
+    let start = match params.verse_start {
+        Some(verse_start) => format!("{}:{}", chapter, verse_start),
+        None => chapter.to_string(),
+    };
+    let end = match (params.chapter_end, params.verse_end) {
+        (Some(chapter_end), Some(verse_end)) => format!("{}:{}", chapter_end, verse_end),
+        (Some(chapter_end), None) => chapter_end.to_string(),
+        (None, verse_end) => verse_end.unwrap_or_default().to_string(),
+    };
+    Err(ReferenceError::ParseError(format!(
+        "{} {}-{} ends before it starts",
+        params.title, start, end
+    )))
+}
+
+// The chapters after the first of a range, up to the verse it ends on. A
+// range that ends after the last chapter of the book stops there.
+fn get_following_chapters(
+    title: &str,
+    chapter: u8,
+    chapter_end: u8,
+    verse_end: Option<u8>,
+    versification: &Versification,
+) -> Vec<Chapter> {
+    (chapter.saturating_add(1)..=chapter_end)
+        .take_while(|chapter| versification.chapter_exists(title, *chapter))
+        .filter_map(|chapter| {
+            let end = match chapter == chapter_end {
+                true => verse_end.unwrap_or(u8::MAX),
+                false => u8::MAX,
+            };
+            let verses = versification.get_verse_range(title, chapter, 1..=end)?;
+
+            Some(Chapter { chapter, verses })
+        })
+        .collect()
+}
+
+// A strict search has to end where its range says, not where the chapter or
+// the book does
+fn check_range_end(
+    params: &BookParams,
+    chapters: &[Chapter],
+    versification: &Versification,
+) -> Result<(), ReferenceError> {
+    let title = &params.title;
+    let last = chapters.last().map_or(0, |chapter| chapter.chapter);
+    if let Some(chapter_end) = params.chapter_end {
+        if last != chapter_end {
+            return Err(ReferenceError::ChapterOutOfRange {
+                title: title.to_owned(),
+                chapter: chapter_end,
+            });
+        }
+    }
+
+    let verse_count = versification.get_verse_count(title, last).unwrap_or(0);
+    match params.verse_end {
+        Some(verse_end) if verse_end > verse_count => Err(ReferenceError::VerseOutOfRange {
+            title: title.to_owned(),
+            chapter: last,
+            verse: verse_end.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn revert_to_book_search(
+    title: String,
+    versification: &Versification,
+) -> Result<BibleSearch, ReferenceError> {
+    let updated_params = BookParams {
+        search_type: SearchType::Book,
+        title,
+        chapter: None,
+        verse_start: None,
+        verse_end: None,
+        chapter_end: None,
+    };
+
+    book_to_bible_search(updated_params, versification)
+}
+
+fn revert_to_chapter_search(
+    title: String,
+    chapter: u8,
+    versification: &Versification,
+) -> Result<BibleSearch, ReferenceError> {
+    let updated_params = BookParams {
+        search_type: SearchType::Chapter,
+        title,
+        chapter: Some(chapter),
+        verse_start: None,
+        verse_end: None,
+        chapter_end: None,
+    };
+
+    chapter_to_bible_search(updated_params, versification, false)
+}
+
+fn unwrap_chapter(
+    book: &str,
+    chapter: Option<u8>,
+    versification: &Versification,
+) -> Result<u8, ReferenceError> {
+    match chapter {
+        Some(chapter_num) => {
+            if versification.chapter_exists(book, chapter_num) {
+                Ok(chapter_num)
+            } else {
+                Err(ReferenceError::ChapterOutOfRange {
+                    title: book.to_owned(),
+                    chapter: chapter_num,
+                })
+            }
+        }
+
+        None => Err(ReferenceError::ParseError(String::from(
+            "No Chapter Start Found",
+        ))),
+    }
+}
+
+fn unwrap_verse(
+    book: &str,
+    chapter: u8,
+    verse: Option<u8>,
+    versification: &Versification,
+) -> Result<u8, ReferenceError> {
+    match verse {
+        Some(verse_num) => {
+            // The superscription can be asked for directly (ex: Psalms 3:0),
+            // where the chapter has one
+            if (verse_num == SUPERSCRIPTION_VERSE
+                && versification.has_superscription(book, chapter))
+                || versification.verse_exists(book, chapter, verse_num)
+            {
+                Ok(verse_num)
+            } else {
+                Err(ReferenceError::VerseOutOfRange {
+                    title: book.to_owned(),
+                    chapter,
+                    verse: verse_num.to_string(),
+                })
+            }
+        }
+
+        None => Err(ReferenceError::ParseError(String::from(
+            "No Verse Start Found For Verse Search",
+        ))),
+    }
+}
+
+fn unwrap_verse_range(
+    book: &str,
+    chapter: u8,
+    verse_start: Option<u8>,
+    verse_end: Option<u8>,
+    versification: &Versification,
+) -> Result<HashSet<u8>, ReferenceError> {
+    // The start should be checked before it gets here, so panic if it is a none
+    let start = verse_start.unwrap();
+
+    // This end should be checked before it gets here, so panic if it is a none
+    let end = verse_end.unwrap();
+
+    // Get the clamped range or return an error
+    match versification.get_verse_range(book, chapter, start..=end) {
+        Some(range) => Ok(range),
+        None if start <= end => Err(ReferenceError::VerseOutOfRange {
+            title: book.to_owned(),
+            chapter,
+            verse: start.to_string(),
+        }),
+        None => Err(ReferenceError::ParseError(format!(
+            "{} {}:{}-{} ends before it starts",
+            book, chapter, start, end
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_reference_joins_runs_of_verses_into_ranges() {
+        let reference = |query: &str| get_reference(&search(query).unwrap());
+
+        assert_eq!(reference("John 3:16"), "John 3:16");
+        assert_eq!(reference("John 3:16-18,20"), "John 3:16-18, 20");
+        assert_eq!(reference("jn 3"), "John 3");
+        assert_eq!(reference("John 3:16-4:2"), "John 3:16-4:2");
+        assert_eq!(reference("Genesis 1-3"), "Genesis 1-3");
+        assert_eq!(reference("Genesis 1:1-2:25"), "Genesis 1-2");
+        assert_eq!(reference("John 3:36-4:1, 5"), "John 3:36-4:1, 5");
+    }
+
+    #[test]
+    fn search_can_process_a_book_query() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]),
+            }],
+        };
+
+        let result = search("1 John").unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_can_process_a_chapter_query() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]),
+            }],
+        };
+
+        let result = search("1 John 1").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_processing_a_failed_chapter_query_will_revert_to_book_query() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]),
+            }],
+        };
+
+        let result = search("1 John").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_can_process_a_verse_query() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 2,
+                verses: HashSet::from([3]),
+            }],
+        };
+
+        let result = search("1 John 2:3").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_processing_a_failed_verse_query_due_to_bad_chapter_will_revert_to_book_query() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]),
+            }],
+        };
+
+        let result = search("1 John 223:3").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_processing_a_failed_verse_query_due_to_bad_verse_will_revert_to_chapter_query() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 4,
+                verses: HashSet::from([
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
+                ]),
+            }],
+        };
+
+        let result = search("1 John 4:345").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_can_process_a_verse_range_query() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 2,
+                verses: HashSet::from([3, 4, 5]),
+            }],
+        };
+
+        let result = search("1 John 2:3-5").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_processing_a_failed_verse_range_query_due_to_bad_chapter_will_revert_to_book_query(
+    ) {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([1, 2, 3, 4, 5, 6, 7, 8, 9, 10]),
+            }],
+        };
+
+        let result = search("1 John 223:3-4").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_processing_a_failed_verse_range_query_due_to_bad_verse_range_will_revert_to_chapter_query(
+    ) {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 4,
+                verses: HashSet::from([
+                    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
+                ]),
+            }],
+        };
+
+        let result = search("1 John 4:98-99").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_can_process_a_verse_range_across_chapters() {
+        let expected = BibleSearch {
+            title: String::from("John"),
+            chapters: vec![
+                Chapter {
+                    chapter: 3,
+                    verses: HashSet::from([35, 36]),
+                },
+                Chapter {
+                    chapter: 4,
+                    verses: HashSet::from([1, 2]),
+                },
+            ],
+        };
+
+        let result = search("John 3:35-4:2").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_reads_every_chapter_in_the_middle_of_a_range() {
+        let result = search("1 John 1:10-3:1").unwrap();
+
+        assert_eq!(
+            result.chapters,
+            vec![
+                Chapter {
+                    chapter: 1,
+                    verses: HashSet::from([10]),
+                },
+                Chapter {
+                    chapter: 2,
+                    verses: HashSet::from_iter(1..=29),
+                },
+                Chapter {
+                    chapter: 3,
+                    verses: HashSet::from([1]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn search_stops_a_range_at_the_last_chapter_of_the_book() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 5,
+                verses: HashSet::from([20, 21]),
+            }],
+        };
+
+        let result = search("1 John 5:20-7:3").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_refuses_a_range_that_ends_in_an_earlier_chapter() {
+        let reversed = Err(ReferenceError::ParseError(String::from(
+            "Mark 16:9-8:1 ends before it starts",
+        )));
+
+        assert_eq!(search("Mark 16:9-8:1"), reversed);
+        assert_eq!(
+            resolve("Mark 16:9-8:1", &Versification::default(), true),
+            reversed
+        );
+    }
+
+    #[test]
+    fn search_refuses_a_range_that_ends_on_an_earlier_verse_of_its_chapter() {
+        let reversed = Err(ReferenceError::ParseError(String::from(
+            "John 3:16-10 ends before it starts",
+        )));
+
+        assert_eq!(search("John 3:16-3:10"), reversed);
+        assert_eq!(search("John 3:16-10"), reversed);
+        assert_eq!(
+            resolve("John 3:16-3:10", &Versification::default(), true),
+            reversed
+        );
+    }
+
+    #[test]
+    fn search_refuses_a_chapter_range_that_ends_in_an_earlier_chapter() {
+        let reversed = Err(ReferenceError::ParseError(String::from(
+            "John 4-3 ends before it starts",
+        )));
+
+        assert_eq!(search("John 4-3"), reversed);
+        assert_eq!(
+            resolve("John 4-3", &Versification::default(), true),
+            reversed
+        );
+        assert_eq!(
+            search("John 4-3:2"),
+            Err(ReferenceError::ParseError(String::from(
+                "John 4:1-3:2 ends before it starts"
+            )))
+        );
+    }
+
+    #[test]
+    fn search_can_process_a_chapter_range_query() {
+        // Jude only has the one chapter
+        let result = search("Jude 1-3").unwrap();
+        assert_eq!(result.chapters.len(), 1);
+
+        let result = search("1 John 3-5").unwrap();
+        assert_eq!(
+            result
+                .chapters
+                .iter()
+                .map(|chapter| (chapter.chapter, chapter.verses.len()))
+                .collect::<Vec<(u8, usize)>>(),
+            vec![(3, 24), (4, 21), (5, 21)]
+        );
+        assert!(is_whole_chapter(&result));
+    }
+
+    #[test]
+    fn search_adds_sub_queries_to_the_chapter_a_range_ends_in() {
+        let result = search("John 3:36-4:1, 5").unwrap();
+
+        assert_eq!(result.chapters[0].verses, HashSet::from([36]));
+        assert_eq!(result.chapters[1].verses, HashSet::from([1, 5]));
+    }
+
+    #[test]
+    fn search_adds_every_passage_of_the_list_in_order_of_its_chapters() {
+        assert_eq!(
+            search("John 3:16, 4:2").unwrap().get_verses(),
+            vec![(3, 16), (4, 2)]
+        );
+        assert_eq!(
+            search("John 4:1, 3:16").unwrap().get_verses(),
+            vec![(3, 16), (4, 1)]
+        );
+        assert_eq!(search("John 3:16, 22:1"), search("John 3:16"));
+        assert_eq!(
+            resolve("John 3:16, 22:1", &Versification::default(), true),
+            Err(ReferenceError::ChapterOutOfRange {
+                title: String::from("John"),
+                chapter: 22,
+            })
+        );
+    }
+
+    #[test]
+    fn search_passages_resolves_each_passage_in_order() {
+        let references = search_passages(
+            "John 3:16; Rom 8:28; Ps 23",
+            &Versification::default(),
+            false,
+        )
+        .unwrap()
+        .iter()
+        .map(get_reference)
+        .collect::<Vec<String>>();
+
+        assert_eq!(references, vec!["John 3:16", "Romans 8:28", "Psalms 23"]);
+    }
+
+    #[test]
+    fn search_passages_reads_a_passage_without_a_book_in_the_book_before_it() {
+        let references = search_passages("John 3:16; 4:2", &Versification::default(), false)
+            .unwrap()
+            .iter()
+            .map(get_reference)
+            .collect::<Vec<String>>();
+
+        assert_eq!(references, vec!["John 3:16", "John 4:2"]);
+        assert!(search_passages("4:2; John 3:16", &Versification::default(), false).is_err());
+    }
+
+    #[test]
+    fn resolve_when_strict_refuses_what_search_falls_back_from() {
+        let strict = |query: &str| {
+            resolve(query, &Versification::default(), true).map_err(|err| err.to_string())
+        };
+
+        assert_eq!(
+            strict("1 John 223:3"),
+            Err("1 John has no chapter 223".into())
+        );
+        assert_eq!(
+            strict("1 John 4:99"),
+            Err("1 John 4 has no verse 99".into())
+        );
+        assert_eq!(
+            strict("1 John 4:345"),
+            Err("345 is not a chapter or verse".into())
+        );
+        assert_eq!(
+            strict("1 John 1:8-20"),
+            Err("1 John 1 has no verse 20".into())
+        );
+        assert_eq!(strict("1 John 4-7"), Err("1 John has no chapter 7".into()));
+        assert_eq!(
+            strict("1 John 1:2, 15"),
+            Err("1 John 1 has no verse 15".into())
+        );
+        assert_eq!(
+            strict("1 John 1:5-2"),
+            Err("1 John 1:5-2 ends before it starts".into())
+        );
+
+        assert_eq!(
+            strict("1 John 1:8-10, 2"),
+            Ok(search("1 John 1:8-10, 2").unwrap())
+        );
+        assert_eq!(strict("1 John"), Ok(search("1 John").unwrap()));
+        assert_eq!(strict("John 3:16,"), Ok(search("John 3:16").unwrap()));
+        assert_eq!(
+            strict("Gen.1.1-Exod.1.1"),
+            Err("Gen.1.1-Exod.1.1 runs from Genesis into Exodus".into())
+        );
+    }
+
+    #[test]
+    fn search_when_doing_sub_queries_on_verse_query_adds_verses_that_are_not_there() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([2, 3, 5, 7, 9]),
+            }],
+        };
+
+        let result = search("1 John 1:2, 3, 5, 7, 9").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_doing_sub_queries_on_verse_query_will_not_add_non_existant_verses() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([2, 3, 5, 7, 9]),
+            }],
+        };
+
+        let result = search("1 John 1:2, 3, 5, 7, 9, 11, 13, 15").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_doing_sub_queries_on_verse_range_query_adds_verses_that_are_not_there() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([2, 3, 5, 7, 9]),
+            }],
+        };
+
+        let result = search("1 John 1:2-3, 5, 7, 9").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_doing_sub_queries_on_verse_range_query_will_not_add_non_existant_verses() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([2, 3, 5, 7, 9]),
+            }],
+        };
+
+        let result = search("1 John 1:2-3, 5, 7, 9, 11, 13, 15").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_when_doing_sub_queries_adds_ranges_of_verses() {
+        let expected = BibleSearch {
+            title: String::from("Psalms"),
+            chapters: vec![Chapter {
+                chapter: 96,
+                verses: HashSet::from([1, 2, 3, 11, 12, 13]),
+            }],
+        };
+
+        assert_eq!(search("Psalms 96:1-3, 11-13").unwrap(), expected);
+        assert_eq!(search("Psalms 96:1-3, 11-20").unwrap(), expected);
+    }
+
+    #[test]
+    fn search_reads_en_and_em_dashes_as_dashes() {
+        let expected = search("John 3:16-18").unwrap();
+
+        assert_eq!(search("jn 3:16\u{2013}18").unwrap(), expected);
+        assert_eq!(search("jn 3:16\u{2014}18").unwrap(), expected);
+    }
+
+    #[test]
+    fn search_refuses_a_character_that_is_no_part_of_a_reference() {
+        let refused = Err(ReferenceError::ParseError(String::from(
+            "\"\u{FF13}\" is not part of a reference",
+        )));
+
+        assert_eq!(search("John \u{FF13}:16"), refused);
+        assert_eq!(
+            resolve("John \u{FF13}:16", &Versification::default(), true),
+            refused
+        );
+    }
+
+    #[test]
+    fn search_can_process_a_query_copied_with_unicode_whitespace() {
+        let expected = BibleSearch {
+            title: String::from("1 John"),
+            chapters: vec![Chapter {
+                chapter: 1,
+                verses: HashSet::from([2, 3]),
+            }],
+        };
+
+        let result = search("1\u{00A0}John\u{200B}\t1:2\u{2009}-\u{2009}3").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_can_process_a_spoken_query() {
+        let expected = BibleSearch {
+            title: String::from("Psalms"),
+            chapters: vec![Chapter {
+                chapter: 119,
+                verses: HashSet::from([105]),
+            }],
+        };
+
+        let result = search("Psalm one hundred nineteen verse one hundred five").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_with_checks_verses_against_the_translation() {
+        let versification = Versification::from_counts([(String::from("Leviticus"), 6, 30)]);
+
+        let result = search_with("Leviticus 6:30", &versification).unwrap();
+        assert_eq!(result.chapters[0].verses, HashSet::from([30]));
+
+        // The built in versification ends the chapter at verse 23
+        let result = search("Leviticus 6:30").unwrap();
+        assert_eq!(result.chapters[0].verses, HashSet::from_iter(1..=23));
+    }
+
+    #[test]
+    fn search_with_refuses_a_book_the_translation_does_not_have() {
+        let versification = Versification::from_verses([(String::from("John"), 1, 1, None)]);
+
+        assert_eq!(
+            search_with("Genesis 1:1", &versification),
+            Err(ReferenceError::UnknownBook(String::from("Genesis")))
+        );
+        assert_eq!(
+            search_with("Genesis", &versification),
+            Err(ReferenceError::UnknownBook(String::from("Genesis")))
+        );
+        assert_eq!(
+            search_with("John 2", &versification).unwrap(),
+            search_with("John 1", &versification).unwrap()
+        );
+    }
+
+    #[test]
+    fn search_with_refuses_a_book_the_translation_has_only_some_chapters_of() {
+        // The counts of a translation that has Leviticus from chapter 5 on
+        let versification = Versification::from_counts([
+            (String::from("Leviticus"), 5, 19),
+            (String::from("Leviticus"), 6, 30),
+        ]);
+        let no_chapter_1 = Err(ReferenceError::ChapterOutOfRange {
+            title: String::from("Leviticus"),
+            chapter: 1,
+        });
+
+        assert_eq!(search_with("Leviticus", &versification), no_chapter_1);
+        assert_eq!(search_with("Leviticus 3:1", &versification), no_chapter_1);
+        assert_eq!(search_with("Leviticus 7-8", &versification), no_chapter_1);
+        assert_eq!(
+            search_passages("Leviticus 2; 6:30", &versification, false),
+            no_chapter_1.map(|bible_search| vec![bible_search])
+        );
+        assert_eq!(
+            resolve("Leviticus 3", &versification, true),
+            Err(ReferenceError::ChapterOutOfRange {
+                title: String::from("Leviticus"),
+                chapter: 3,
+            })
+        );
+        assert!(search_with("Leviticus 6:30", &versification).is_ok());
+    }
+
+    #[test]
+    fn search_can_address_a_superscription_as_verse_zero() {
+        let expected = BibleSearch {
+            title: String::from("Psalms"),
+            chapters: vec![Chapter {
+                chapter: 3,
+                verses: HashSet::from([0]),
+            }],
+        };
+
+        let result = search("Psalms 3:0").unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn search_for_verse_zero_of_a_chapter_without_a_superscription_is_the_chapter() {
+        assert_eq!(search("John 3:0").unwrap(), search("John 3").unwrap());
+        assert_eq!(
+            resolve("John 3:0", &Versification::default(), true).map_err(|err| err.to_string()),
+            Err("John 3 has no verse 0".into())
+        );
+    }
+
+    #[test]
+    fn is_whole_chapter_is_true_only_when_every_verse_is_included() {
+        assert!(is_whole_chapter(&search("Psalms 3").unwrap()));
+        assert!(is_whole_chapter(&search("Psalms 3:1-8").unwrap()));
+        assert!(!is_whole_chapter(&search("Psalms 3:2-8").unwrap()));
+        assert!(!is_whole_chapter(&search("Psalms 3:1-4:7").unwrap()));
+    }
+
+    #[test]
+    fn is_whole_chapter_with_uses_the_chapters_of_the_translation() {
+        let versification = Versification::from_counts([(String::from("3 John"), 1, 15)]);
+        let fourteen = search("3 John 1:1-14").unwrap();
+        let fifteen = search_with("3 John 1:1-15", &versification).unwrap();
+
+        assert!(is_whole_chapter(&fourteen));
+        assert!(!is_whole_chapter_with(&fourteen, &versification));
+        assert!(is_whole_chapter_with(&fifteen, &versification));
+        assert_eq!(
+            get_reference_with(&fourteen, &versification),
+            "3 John 1:1-14"
+        );
+        assert_eq!(get_reference_with(&fifteen, &versification), "3 John 1");
+    }
+
+    #[test]
+    fn get_reference_with_runs_on_from_the_last_verse_of_the_translation() {
+        let versification = Versification::from_counts([(String::from("Leviticus"), 6, 30)]);
+        let bible_search = BibleSearch::from_verses("Leviticus", [(6, 29), (6, 30), (7, 1)]);
+
+        assert_eq!(
+            get_reference_with(&bible_search, &versification),
+            "Leviticus 6:29-7:1"
+        );
+        assert_eq!(get_reference(&bible_search), "Leviticus 6:29-30; 7:1");
+    }
+}
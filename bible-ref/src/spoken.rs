@@ -0,0 +1,408 @@
+/// The UNITS are the number words below twenty, as they are spoken and as
+/// ordinals, indexed by their value.
+const UNITS: [(&str, &str); 20] = [
+    ("zero", "zeroth"),
+    ("one", "first"),
+    ("two", "second"),
+    ("three", "third"),
+    ("four", "fourth"),
+    ("five", "fifth"),
+    ("six", "sixth"),
+    ("seven", "seventh"),
+    ("eight", "eighth"),
+    ("nine", "ninth"),
+    ("ten", "tenth"),
+    ("eleven", "eleventh"),
+    ("twelve", "twelfth"),
+    ("thirteen", "thirteenth"),
+    ("fourteen", "fourteenth"),
+    ("fifteen", "fifteenth"),
+    ("sixteen", "sixteenth"),
+    ("seventeen", "seventeenth"),
+    ("eighteen", "eighteenth"),
+    ("nineteen", "nineteenth"),
+];
+
+/// The TENS are the number words for twenty to ninety, as they are spoken
+/// and as ordinals, starting at twenty.
+const TENS: [(&str, &str); 8] = [
+    ("twenty", "twentieth"),
+    ("thirty", "thirtieth"),
+    ("forty", "fortieth"),
+    ("fifty", "fiftieth"),
+    ("sixty", "sixtieth"),
+    ("seventy", "seventieth"),
+    ("eighty", "eightieth"),
+    ("ninety", "ninetieth"),
+];
+
+const CHAPTER_WORDS: [&str; 2] = ["chapter", "chapters"];
+const VERSE_WORDS: [&str; 2] = ["verse", "verses"];
+const RANGE_WORDS: [&str; 5] = ["through", "thru", "to", "till", "until"];
+
+// A Piece is a word of the query, or a number written in digits (ex: 3 or
+// 3rd) or spoken as words (ex: three or third)
+#[derive(Debug, PartialEq, Clone)]
+enum Piece {
+    Word(String),
+    Number {
+        value: u16,
+        ordinal: bool,
+        spoken: bool,
+    },
+}
+
+/// The normalize_spoken function rewrites a reference the way it is spoken
+/// (ex: "John chapter three verse sixteen", "the third chapter of John" or
+/// "Psalm one hundred nineteen") into the way it is written (ex: John 3:16).
+/// A query that uses none of the spoken forms is returned as it was, so
+/// written references are never changed. The query is expected to have had
+/// its whitespace normalized already.
+pub fn normalize_spoken(query: &str) -> String {
+    let mut pieces = read_pieces(query);
+    let is_spoken = pieces.iter().any(|piece| match piece {
+        Piece::Number { spoken, .. } => *spoken,
+        Piece::Word(word) => is_marker(word),
+    });
+
+    if !is_spoken {
+        return query.to_owned();
+    }
+
+    move_leading_chapter(&mut pieces);
+    write_pieces(&pieces)
+}
+
+// Split the query into words, reading each run of number words as a number
+fn read_pieces(query: &str) -> Vec<Piece> {
+    let words = query
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .flat_map(split_hyphenated)
+        .collect::<Vec<String>>();
+
+    let mut pieces = vec![];
+    let mut index = 0;
+    while index < words.len() {
+        if let Some((value, ordinal)) = read_digits(&words[index]) {
+            pieces.push(Piece::Number {
+                value,
+                ordinal,
+                spoken: false,
+            });
+            index += 1;
+            continue;
+        }
+
+        match read_number(&words[index..]) {
+            Some((value, ordinal, length)) => {
+                pieces.push(Piece::Number {
+                    value,
+                    ordinal,
+                    spoken: true,
+                });
+                index += length;
+            }
+            None => {
+                pieces.push(Piece::Word(words[index].clone()));
+                index += 1;
+            }
+        }
+    }
+
+    pieces
+}
+
+// A hyphenated number (ex: twenty-one) is two number words
+fn split_hyphenated(word: &str) -> Vec<String> {
+    let parts = word.split('-').collect::<Vec<&str>>();
+
+    match parts.len() > 1 && parts.iter().all(|part| get_number_word(part).is_some()) {
+        true => parts.into_iter().map(str::to_owned).collect(),
+        false => vec![word.to_owned()],
+    }
+}
+
+// Read a number written in digits, which may be an ordinal (ex: 3rd)
+fn read_digits(word: &str) -> Option<(u16, bool)> {
+    let word = trim(word);
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix));
+
+    match digits {
+        Some(digits) => Some((digits.parse().ok()?, true)),
+        None => Some((word.parse().ok()?, false)),
+    }
+}
+
+// Read the number the words start with, if they start with one, returning its
+// value, whether it was an ordinal and how many words it took. A unit can
+// only follow a tens word or hundred, so "three sixteen" is two numbers while
+// "one hundred nineteen" is one.
+fn read_number(words: &[String]) -> Option<(u16, bool, usize)> {
+    let mut value = 0;
+    let mut length = 0;
+    let mut ordinal = false;
+    let mut last = None;
+
+    while let Some(word) = words.get(length) {
+        let Some((kind, number, is_ordinal)) = get_number_word(word) else {
+            // "and" may join the hundreds to the rest (ex: one hundred and ten)
+            let next = words.get(length + 1).and_then(|next| get_number_word(next));
+            if trim(word) == "and" && last == Some(NumberKind::Hundred) && next.is_some() {
+                length += 1;
+                continue;
+            }
+            break;
+        };
+
+        let fits = match (last, kind) {
+            (None, _) => true,
+            (Some(NumberKind::Tens), NumberKind::Unit) => number > 0 && number < 10,
+            (Some(NumberKind::Unit), NumberKind::Hundred) => value > 0 && value < 10,
+            (Some(NumberKind::Hundred), NumberKind::Unit | NumberKind::Tens) => true,
+            _ => false,
+        };
+        if !fits {
+            break;
+        }
+
+        match kind {
+            NumberKind::Hundred => value = value.max(1) * 100,
+            _ => value += number,
+        }
+        length += 1;
+        last = Some(kind);
+
+        // An ordinal always ends the number (ex: the third chapter)
+        if is_ordinal {
+            ordinal = true;
+            break;
+        }
+    }
+
+    (length > 0).then_some((value, ordinal, length))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum NumberKind {
+    Unit,
+    Tens,
+    Hundred,
+}
+
+fn get_number_word(word: &str) -> Option<(NumberKind, u16, bool)> {
+    let word = trim(word);
+    let find = |words: &[(&str, &str)]| {
+        words
+            .iter()
+            .enumerate()
+            .find_map(|(index, (spoken, ordinal))| match word.as_str() {
+                w if w == *spoken => Some((index as u16, false)),
+                w if w == *ordinal => Some((index as u16, true)),
+                _ => None,
+            })
+    };
+
+    if let Some((number, ordinal)) = find(&UNITS) {
+        return Some((NumberKind::Unit, number, ordinal));
+    }
+    if let Some((index, ordinal)) = find(&TENS) {
+        return Some((NumberKind::Tens, (index + 2) * 10, ordinal));
+    }
+
+    match word.as_str() {
+        "hundred" => Some((NumberKind::Hundred, 100, false)),
+        "hundredth" => Some((NumberKind::Hundred, 100, true)),
+        _ => None,
+    }
+}
+
+// "the third chapter of John" is John 3. The book is everything after "of" up
+// to the first number or marker word, and the chapter is put right after it.
+fn move_leading_chapter(pieces: &mut Vec<Piece>) {
+    let start = match pieces.first() {
+        Some(Piece::Word(word)) if trim(word) == "the" => 1,
+        _ => 0,
+    };
+
+    let (
+        Some(Piece::Number { ordinal: true, .. }),
+        Some(Piece::Word(chapter)),
+        Some(Piece::Word(of)),
+    ) = (
+        pieces.get(start),
+        pieces.get(start + 1),
+        pieces.get(start + 2),
+    )
+    else {
+        return;
+    };
+    if !is_one_of(chapter, &CHAPTER_WORDS) || trim(of) != "of" {
+        return;
+    }
+
+    let number = pieces[start].clone();
+    let mut rest = pieces.split_off(start + 3);
+
+    // A book number (ex: first John) belongs to the book
+    let book_start = usize::from(matches!(rest.first(), Some(Piece::Number { .. })));
+    let book_end = rest[book_start..]
+        .iter()
+        .position(|piece| match piece {
+            Piece::Number { .. } => true,
+            Piece::Word(word) => is_marker(word),
+        })
+        .map_or(rest.len(), |position| book_start + position);
+
+    rest.insert(book_end, number);
+    *pieces = rest;
+}
+
+// Write the pieces back out as a written reference. Marker words are dropped
+// or turned into the punctuation they stand for.
+fn write_pieces(pieces: &[Piece]) -> String {
+    let mut written = String::new();
+    // Whether the last piece was a number, and if so whether it was an ordinal
+    let mut last_number: Option<bool> = None;
+    let mut has_verse = false;
+
+    for (index, piece) in pieces.iter().enumerate() {
+        let next_is_number = matches!(pieces.get(index + 1), Some(Piece::Number { .. }));
+
+        let word = match piece {
+            Piece::Number { value, ordinal, .. } => {
+                // Two numbers in a row are a chapter and a verse
+                if last_number == Some(false) && !ordinal && !has_verse && !is_separated(&written) {
+                    written.push(':');
+                    has_verse = true;
+                } else {
+                    separate(&mut written);
+                }
+                written.push_str(&value.to_string());
+                last_number = Some(*ordinal);
+                continue;
+            }
+            Piece::Word(word) => word,
+        };
+
+        if next_is_number {
+            let trimmed = trim(word);
+            if last_number.is_some() {
+                if is_one_of(&trimmed, &VERSE_WORDS) && !has_verse {
+                    written.push(':');
+                    has_verse = true;
+                    continue;
+                }
+                if is_one_of(&trimmed, &RANGE_WORDS) {
+                    written.push('-');
+                    continue;
+                }
+                if trimmed == "and" {
+                    written.push_str(", ");
+                    continue;
+                }
+            }
+            if is_marker(&trimmed) {
+                separate(&mut written);
+                continue;
+            }
+        }
+
+        separate(&mut written);
+        written.push_str(word);
+        last_number = None;
+    }
+
+    written
+}
+
+fn is_separated(written: &str) -> bool {
+    written.is_empty() || written.ends_with([':', '-', ' '])
+}
+
+fn separate(written: &mut String) {
+    if !is_separated(written) {
+        written.push(' ');
+    }
+}
+
+// The marker words say what the number after them is
+fn is_marker(word: &str) -> bool {
+    is_one_of(word, &CHAPTER_WORDS) || is_one_of(word, &VERSE_WORDS)
+}
+
+fn is_one_of(word: &str, words: &[&str]) -> bool {
+    words.contains(&trim(word).as_str())
+}
+
+// Transcribed speech is punctuated loosely, so commas and periods around a
+// word are ignored when it is compared
+fn trim(word: &str) -> String {
+    word.trim_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_spoken_leaves_written_references_alone() {
+        for query in [
+            "John 3:16",
+            "1 John 2:3-5, 7",
+            "Acts 2 and 3",
+            "Song of Solomon 2",
+        ] {
+            assert_eq!(normalize_spoken(query), query);
+        }
+    }
+
+    #[test]
+    fn normalize_spoken_reads_chapter_and_verse_words() {
+        assert_eq!(normalize_spoken("John chapter 3"), "John 3");
+        assert_eq!(
+            normalize_spoken("John chapter three verse sixteen"),
+            "John 3:16"
+        );
+        assert_eq!(
+            normalize_spoken("John chapter 3, verses 16 through 18"),
+            "John 3:16-18"
+        );
+        assert_eq!(
+            normalize_spoken("first John chapter one verses eight and nine"),
+            "1 John 1:8, 9"
+        );
+    }
+
+    #[test]
+    fn normalize_spoken_reads_number_words() {
+        assert_eq!(normalize_spoken("Psalm one hundred nineteen"), "Psalm 119");
+        assert_eq!(
+            normalize_spoken("Psalm one hundred and nineteen"),
+            "Psalm 119"
+        );
+        assert_eq!(normalize_spoken("Psalm twenty-three"), "Psalm 23");
+        assert_eq!(normalize_spoken("John three sixteen"), "John 3:16");
+    }
+
+    #[test]
+    fn normalize_spoken_reads_the_nth_chapter_of_a_book() {
+        assert_eq!(normalize_spoken("the third chapter of John"), "John 3");
+        assert_eq!(
+            normalize_spoken("the second chapter of first Peter verse nine"),
+            "1 Peter 2:9"
+        );
+    }
+
+    #[test]
+    fn read_number_stops_where_a_new_number_starts() {
+        let words = ["three", "sixteen"].map(String::from);
+        assert_eq!(read_number(&words), Some((3, false, 1)));
+
+        let words = ["twenty", "first", "chapter"].map(String::from);
+        assert_eq!(read_number(&words), Some((21, true, 2)));
+    }
+}
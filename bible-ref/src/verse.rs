@@ -1,5 +1,11 @@
-use std::collections::{HashMap, HashSet};
-use std::ops::RangeInclusive;
+use std::collections::HashMap;
+
+use crate::{canon::get_deuterocanonical_book, chapter::chapter_exists_in_book};
+
+/// The SUPERSCRIPTION_VERSE is the verse number a chapter's superscription or
+/// introduction is stored under (ex: "A Psalm of David..." is Psalms 3:0).
+/// It comes before verse 1 and is not counted as one of the chapter's verses.
+pub const SUPERSCRIPTION_VERSE: u8 = 0;
 
 pub fn get_verse_count_by_book_and_chapter(book: &str, chapter: u8) -> Option<u8> {
     let verse_counts: HashMap<&str, HashMap<u8, u8>> = HashMap::from([
@@ -856,6 +862,7 @@ pub fn get_verse_count_by_book_and_chapter(book: &str, chapter: u8) -> Option<u8
                 (24, 33),
             ]),
         ),
+        ("Jude", HashMap::from([(1, 25)])),
         (
             "Judges",
             HashMap::from([
@@ -1120,7 +1127,7 @@ pub fn get_verse_count_by_book_and_chapter(book: &str, chapter: u8) -> Option<u8
             ]),
         ),
         (
-            "Psalm",
+            "Psalms",
             HashMap::from([
                 (1, 6),
                 (2, 12),
@@ -1361,26 +1368,27 @@ pub fn get_verse_count_by_book_and_chapter(book: &str, chapter: u8) -> Option<u8
 
     match verse_counts.get(book) {
         Some(chap) => chap.get(&chapter).copied(),
-        None => None,
+        None => {
+            let verse_counts = get_deuterocanonical_book(book)?.verse_counts;
+            verse_counts
+                .get(usize::from(chapter).checked_sub(1)?)
+                .copied()
+        }
     }
 }
 
-pub fn get_verse_range_from_params(
-    book: &str,
-    chapter: u8,
-    requested_range: RangeInclusive<u8>,
-) -> Option<HashSet<u8>> {
-    let num_verses = get_verse_count_by_book_and_chapter(book, chapter)?;
-    let min = requested_range.clone().min()?;
-    let max = requested_range.max()?;
-    let start = min.clamp(1, num_verses);
-    let end = max.clamp(1, num_verses);
+/// The UNTITLED_PSALMS are the psalms that have no superscription (ex: Psalm
+/// 1 begins with its first verse). Every other psalm has one.
+const UNTITLED_PSALMS: [u8; 34] = [
+    1, 2, 10, 33, 43, 71, 91, 93, 94, 95, 96, 97, 99, 104, 105, 106, 107, 111, 112, 113, 114, 115,
+    116, 117, 118, 119, 135, 136, 137, 146, 147, 148, 149, 150,
+];
 
-    if min > num_verses || min > max {
-        return None;
-    }
-
-    Some(HashSet::from_iter(start..=end))
+/// The chapter_has_superscription function takes a book name and a chapter
+/// number and returns a bool indicating whether the chapter opens with a
+/// superscription, which can be addressed as its verse 0 (ex: Psalms 3:0).
+pub fn chapter_has_superscription(book: &str, chapter: u8) -> bool {
+    book == "Psalms" && chapter_exists_in_book(book, chapter) && !UNTITLED_PSALMS.contains(&chapter)
 }
 
 pub fn verse_exists_in_chapter(book: &str, chapter: u8, verse: u8) -> bool {
@@ -1405,6 +1413,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_verse_count_by_book_and_chapter_knows_every_book() {
+        assert!(crate::chapter::BOOKS
+            .iter()
+            .all(|book| get_verse_count_by_book_and_chapter(book, 1).is_some()));
+    }
+
     #[test]
     fn get_verse_count_by_book_and_chapter_returns_none_if_book_does_not_exist() {
         assert_eq!(get_verse_count_by_book_and_chapter("Roberticus", 5), None);
@@ -1419,25 +1434,11 @@ mod tests {
     }
 
     #[test]
-    fn get_verse_range_from_params_clamps_the_min_to_1() {
-        assert_eq!(
-            get_verse_range_from_params("Job", 5, 0..=5).unwrap(),
-            HashSet::from([1, 2, 3, 4, 5])
-        );
-    }
-
-    #[test]
-    fn get_verse_range_from_params_clamps_the_max_to_number_of_verses_in_chapter() {
-        assert_eq!(
-            get_verse_range_from_params("Job", 5, 1..=100).unwrap(),
-            HashSet::from_iter(1..=27)
-        );
-    }
-
-    #[test]
-    fn get_verse_range_from_params_returns_none_if_start_is_higher_than_number_of_verses_in_chapter(
-    ) {
-        assert_eq!(get_verse_range_from_params("Job", 5, 39..=100), None);
+    fn chapter_has_superscription_is_true_only_for_titled_psalms() {
+        assert!(chapter_has_superscription("Psalms", 3));
+        assert!(!chapter_has_superscription("Psalms", 1));
+        assert!(!chapter_has_superscription("Psalms", 151));
+        assert!(!chapter_has_superscription("John", 3));
     }
 
     #[test]
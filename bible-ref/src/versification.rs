@@ -0,0 +1,294 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+};
+
+use crate::{
+    chapter::{chapter_exists_in_book, get_chapter_count_by_book},
+    verse::{
+        chapter_has_superscription, get_verse_count_by_book_and_chapter, verse_exists_in_chapter,
+        SUPERSCRIPTION_VERSE,
+    },
+};
+
+/// The Versification is how a translation numbers its verses: how many
+/// verses each chapter of each book has. Translations differ (ex: the KJV
+/// ends Leviticus 5 at verse 19 where the Hebrew numbering goes on to 26).
+/// Books the translation does not have counts for use the built in counts,
+/// so the default Versification is the built in one, unless it was read
+/// from every verse of the translation.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Versification {
+    verse_counts: HashMap<String, HashMap<u8, u8>>,
+    // The verses before the last of a chapter that the translation leaves out
+    missing_verses: HashMap<String, HashMap<u8, HashSet<u8>>>,
+    // The chapters that the translation gives a superscription
+    superscriptions: HashMap<String, HashSet<u8>>,
+    // Whether the translation has only the books it has counts for
+    is_complete: bool,
+}
+
+impl Versification {
+    /// The from_counts function builds a Versification from the number of
+    /// verses in each chapter, given as (book, chapter, verse count).
+    pub fn from_counts(counts: impl IntoIterator<Item = (String, u8, u8)>) -> Self {
+        let mut verse_counts: HashMap<String, HashMap<u8, u8>> = HashMap::new();
+
+        for (book, chapter, verse_count) in counts {
+            verse_counts
+                .entry(book)
+                .or_default()
+                .insert(chapter, verse_count);
+        }
+
+        Versification {
+            verse_counts,
+            ..Default::default()
+        }
+    }
+
+    /// The from_verses function builds a Versification from every verse a
+    /// translation has, given as (book, chapter, verse, bridged_to). A verse
+    /// bridged to later ones (ex: \v 4-5) has them too. A verse it leaves
+    /// out, and a book or chapter it does not have, do not exist. A verse 0
+    /// is the superscription of its chapter.
+    pub fn from_verses(verses: impl IntoIterator<Item = (String, u8, u8, Option<u8>)>) -> Self {
+        let mut present: HashMap<String, HashMap<u8, HashSet<u8>>> = HashMap::new();
+        for (book, chapter, verse, bridged_to) in verses {
+            present
+                .entry(book)
+                .or_default()
+                .entry(chapter)
+                .or_default()
+                .extend(verse..=bridged_to.unwrap_or(verse).max(verse));
+        }
+
+        let mut verse_counts: HashMap<String, HashMap<u8, u8>> = HashMap::new();
+        let mut missing_verses: HashMap<String, HashMap<u8, HashSet<u8>>> = HashMap::new();
+        let mut superscriptions: HashMap<String, HashSet<u8>> = HashMap::new();
+        for (book, chapters) in present {
+            for (chapter, verses) in chapters {
+                if verses.contains(&SUPERSCRIPTION_VERSE) {
+                    superscriptions
+                        .entry(book.clone())
+                        .or_default()
+                        .insert(chapter);
+                }
+
+                let verse_count = verses.iter().copied().max().unwrap_or_default();
+                let missing = (1..verse_count)
+                    .filter(|verse| !verses.contains(verse))
+                    .collect::<HashSet<u8>>();
+
+                if !missing.is_empty() {
+                    missing_verses
+                        .entry(book.clone())
+                        .or_default()
+                        .insert(chapter, missing);
+                }
+                verse_counts
+                    .entry(book.clone())
+                    .or_default()
+                    .insert(chapter, verse_count);
+            }
+        }
+
+        Versification {
+            verse_counts,
+            missing_verses,
+            superscriptions,
+            is_complete: true,
+        }
+    }
+
+    /// The get_verse_count function returns the number of verses in a
+    /// chapter of a book, or None when the chapter is not found.
+    pub fn get_verse_count(&self, book: &str, chapter: u8) -> Option<u8> {
+        match self.verse_counts.get(book) {
+            Some(chapters) => chapters.get(&chapter).copied(),
+            None if self.is_complete => None,
+            None => get_verse_count_by_book_and_chapter(book, chapter),
+        }
+    }
+
+    /// The get_chapter_count function returns the number of chapters in a
+    /// book, or None when the book is not found.
+    pub fn get_chapter_count(&self, book: &str) -> Option<u8> {
+        match self.verse_counts.get(book) {
+            Some(chapters) => chapters.keys().max().copied(),
+            None if self.is_complete => None,
+            None => get_chapter_count_by_book(book),
+        }
+    }
+
+    /// The chapter_exists function returns whether a book has the chapter.
+    pub fn chapter_exists(&self, book: &str, chapter: u8) -> bool {
+        match self.verse_counts.get(book) {
+            Some(chapters) => chapters.contains_key(&chapter),
+            None if self.is_complete => false,
+            None => chapter_exists_in_book(book, chapter),
+        }
+    }
+
+    /// The verse_exists function returns whether a chapter of a book has the
+    /// verse. The superscription is not counted as a verse.
+    pub fn verse_exists(&self, book: &str, chapter: u8, verse: u8) -> bool {
+        let is_missing = self
+            .missing_verses
+            .get(book)
+            .and_then(|chapters| chapters.get(&chapter))
+            .is_some_and(|missing| missing.contains(&verse));
+
+        match self.verse_counts.get(book) {
+            Some(chapters) => chapters
+                .get(&chapter)
+                .is_some_and(|num_verses| verse >= 1 && verse <= *num_verses && !is_missing),
+            None if self.is_complete => false,
+            None => verse_exists_in_chapter(book, chapter, verse),
+        }
+    }
+
+    /// The has_superscription function returns whether a chapter of a book
+    /// opens with a superscription, so it can be asked for as verse 0. A
+    /// Versification read from every verse only knows the superscriptions the
+    /// translation has.
+    pub fn has_superscription(&self, book: &str, chapter: u8) -> bool {
+        match self.is_complete {
+            true => self
+                .superscriptions
+                .get(book)
+                .is_some_and(|chapters| chapters.contains(&chapter)),
+            false => chapter_has_superscription(book, chapter),
+        }
+    }
+
+    /// The get_verse_range function returns the verses of a requested range
+    /// that are in the chapter, with the range clamped to the chapter. None
+    /// is returned when the range starts after the last verse or is reversed.
+    pub fn get_verse_range(
+        &self,
+        book: &str,
+        chapter: u8,
+        requested_range: RangeInclusive<u8>,
+    ) -> Option<HashSet<u8>> {
+        let num_verses = self.get_verse_count(book, chapter)?;
+        let min = requested_range.clone().min()?;
+        let max = requested_range.max()?;
+        let start = min.clamp(1, num_verses);
+        let end = max.clamp(1, num_verses);
+
+        if min > num_verses || min > max {
+            return None;
+        }
+
+        Some(HashSet::from_iter(start..=end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kjv_leviticus() -> Versification {
+        Versification::from_counts([
+            (String::from("Leviticus"), 5, 19),
+            (String::from("Leviticus"), 6, 30),
+        ])
+    }
+
+    #[test]
+    fn versification_uses_the_counts_of_the_translation() {
+        let versification = kjv_leviticus();
+
+        assert!(versification.verse_exists("Leviticus", 6, 30));
+        assert!(!versification.verse_exists("Leviticus", 5, 20));
+        assert!(!Versification::default().verse_exists("Leviticus", 6, 30));
+    }
+
+    #[test]
+    fn versification_falls_back_to_the_built_in_counts() {
+        let versification = kjv_leviticus();
+
+        assert_eq!(versification.get_verse_count("Genesis", 5), Some(32));
+        assert_eq!(versification.get_chapter_count("Genesis"), Some(50));
+        assert_eq!(versification.get_chapter_count("Leviticus"), Some(6));
+        assert!(versification.chapter_exists("Genesis", 50));
+        assert!(!versification.chapter_exists("Genesis", 51));
+    }
+
+    #[test]
+    fn versification_from_verses_leaves_out_what_the_translation_does_not_have() {
+        let versification = Versification::from_verses(
+            [19, 20, 22, 23]
+                .into_iter()
+                .map(|verse| (String::from("Acts"), 8, verse, None)),
+        );
+
+        assert!(versification.verse_exists("Acts", 8, 22));
+        assert!(!versification.verse_exists("Acts", 8, 21));
+        assert!(!versification.verse_exists("Acts", 8, 24));
+        assert_eq!(versification.get_verse_count("Acts", 8), Some(23));
+        assert!(!versification.chapter_exists("Acts", 9));
+        assert_eq!(versification.get_chapter_count("Acts"), Some(8));
+        assert_eq!(versification.get_chapter_count("Genesis"), None);
+        assert!(!versification.chapter_exists("Genesis", 1));
+        assert!(!versification.verse_exists("Genesis", 1, 1));
+    }
+
+    #[test]
+    fn versification_from_verses_knows_the_superscriptions_of_the_translation() {
+        let versification = Versification::from_verses(
+            [(3, 0), (3, 1), (4, 1)]
+                .into_iter()
+                .map(|(chapter, verse)| (String::from("Psalms"), chapter, verse, None)),
+        );
+
+        assert!(versification.has_superscription("Psalms", 3));
+        assert!(!versification.has_superscription("Psalms", 4));
+        assert!(!versification.verse_exists("Psalms", 3, 0));
+        assert_eq!(versification.get_verse_count("Psalms", 3), Some(1));
+        assert!(Versification::default().has_superscription("Psalms", 4));
+        assert!(!Versification::default().has_superscription("John", 3));
+    }
+
+    #[test]
+    fn versification_from_verses_has_every_verse_of_a_bridge() {
+        let versification = Versification::from_verses([
+            (String::from("Jude"), 1, 1, None),
+            (String::from("Jude"), 1, 2, Some(3)),
+            (String::from("Jude"), 1, 4, Some(25)),
+        ]);
+
+        assert!(versification.verse_exists("Jude", 1, 3));
+        assert!(versification.verse_exists("Jude", 1, 25));
+        assert_eq!(versification.get_verse_count("Jude", 1), Some(25));
+    }
+
+    #[test]
+    fn get_verse_range_clamps_the_min_to_1() {
+        assert_eq!(
+            Versification::default()
+                .get_verse_range("Job", 5, 0..=5)
+                .unwrap(),
+            HashSet::from([1, 2, 3, 4, 5])
+        );
+    }
+
+    #[test]
+    fn get_verse_range_clamps_the_max_to_number_of_verses_in_chapter() {
+        assert_eq!(
+            Versification::default()
+                .get_verse_range("Job", 5, 1..=100)
+                .unwrap(),
+            HashSet::from_iter(1..=27)
+        );
+    }
+
+    #[test]
+    fn get_verse_range_returns_none_if_start_is_higher_than_number_of_verses_in_chapter() {
+        assert_eq!(
+            Versification::default().get_verse_range("Job", 5, 39..=100),
+            None
+        );
+    }
+}
@@ -0,0 +1,101 @@
+BEGIN TRANSACTION;
+
+-- Add the word alignment behind include=alignment to a database loaded from
+-- an earlier kjv-pg.db. The source tokens are the words of the Hebrew,
+-- Aramaic or Greek text of a verse, in their order, with their lemma, Strong's
+-- number and morphology where it is known. A word of a translation is aligned
+-- to the tokens it renders, by its index among the words of the verse's plain
+-- text counted from 0, and kept without its punctuation. Words that render no
+-- token (ex: an article the source does not have) are not aligned. Verses are
+-- only aligned where the data is available; more are loaded the same way.
+CREATE TABLE IF NOT EXISTS public.source_tokens (
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    verse_num INTEGER NOT NULL,
+    position SMALLINT NOT NULL CHECK (position > 0),
+    language varchar(3) NOT NULL CHECK (language IN ('hbo', 'arc', 'grc')),
+    text TEXT NOT NULL,
+    lemma TEXT NOT NULL,
+    strongs TEXT NOT NULL,
+    morphology TEXT,
+	PRIMARY KEY(title, chapter_num, verse_num, position)
+);
+
+CREATE TABLE IF NOT EXISTS public.word_alignments (
+    translation varchar(8) NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    verse_num INTEGER NOT NULL,
+    word_index SMALLINT NOT NULL CHECK (word_index >= 0),
+    word TEXT NOT NULL,
+    position SMALLINT NOT NULL,
+	PRIMARY KEY(translation, title, chapter_num, verse_num, word_index, position),
+    FOREIGN KEY (title, chapter_num, verse_num, position)
+        REFERENCES source_tokens(title, chapter_num, verse_num, position) ON DELETE CASCADE
+);
+
+-- Genesis 1:1
+INSERT INTO source_tokens (title, chapter_num, verse_num, position, language, text, lemma, strongs, morphology) VALUES
+    ('Genesis', 1, 1, 1, 'hbo', 'בְּרֵאשִׁית', 'רֵאשִׁית', 'H7225', NULL),
+    ('Genesis', 1, 1, 2, 'hbo', 'בָּרָא', 'בָּרָא', 'H1254', NULL),
+    ('Genesis', 1, 1, 3, 'hbo', 'אֱלֹהִים', 'אֱלֹהִים', 'H430', NULL),
+    ('Genesis', 1, 1, 4, 'hbo', 'אֵת', 'אֵת', 'H853', NULL),
+    ('Genesis', 1, 1, 5, 'hbo', 'הַשָּׁמַיִם', 'שָׁמַיִם', 'H8064', NULL),
+    ('Genesis', 1, 1, 6, 'hbo', 'וְאֵת', 'אֵת', 'H853', NULL),
+    ('Genesis', 1, 1, 7, 'hbo', 'הָאָרֶץ', 'אֶרֶץ', 'H776', NULL)
+ON CONFLICT DO NOTHING;
+
+INSERT INTO word_alignments (translation, title, chapter_num, verse_num, word_index, word, position) VALUES
+    ('kjv', 'Genesis', 1, 1, 0, 'In', 1),
+    ('kjv', 'Genesis', 1, 1, 2, 'beginning', 1),
+    ('kjv', 'Genesis', 1, 1, 3, 'God', 3),
+    ('kjv', 'Genesis', 1, 1, 4, 'created', 2),
+    ('kjv', 'Genesis', 1, 1, 5, 'the', 5),
+    ('kjv', 'Genesis', 1, 1, 6, 'heaven', 5),
+    ('kjv', 'Genesis', 1, 1, 7, 'and', 6),
+    ('kjv', 'Genesis', 1, 1, 8, 'the', 7),
+    ('kjv', 'Genesis', 1, 1, 9, 'earth', 7)
+ON CONFLICT DO NOTHING;
+
+-- John 1:1
+INSERT INTO source_tokens (title, chapter_num, verse_num, position, language, text, lemma, strongs, morphology) VALUES
+    ('John', 1, 1, 1, 'grc', 'Ἐν', 'ἐν', 'G1722', 'PREP'),
+    ('John', 1, 1, 2, 'grc', 'ἀρχῇ', 'ἀρχή', 'G746', 'N-DSF'),
+    ('John', 1, 1, 3, 'grc', 'ἦν', 'εἰμί', 'G1510', 'V-IAI-3S'),
+    ('John', 1, 1, 4, 'grc', 'ὁ', 'ὁ', 'G3588', 'T-NSM'),
+    ('John', 1, 1, 5, 'grc', 'λόγος', 'λόγος', 'G3056', 'N-NSM'),
+    ('John', 1, 1, 6, 'grc', 'καὶ', 'καί', 'G2532', 'CONJ'),
+    ('John', 1, 1, 7, 'grc', 'ὁ', 'ὁ', 'G3588', 'T-NSM'),
+    ('John', 1, 1, 8, 'grc', 'λόγος', 'λόγος', 'G3056', 'N-NSM'),
+    ('John', 1, 1, 9, 'grc', 'ἦν', 'εἰμί', 'G1510', 'V-IAI-3S'),
+    ('John', 1, 1, 10, 'grc', 'πρὸς', 'πρός', 'G4314', 'PREP'),
+    ('John', 1, 1, 11, 'grc', 'τὸν', 'ὁ', 'G3588', 'T-ASM'),
+    ('John', 1, 1, 12, 'grc', 'θεόν', 'θεός', 'G2316', 'N-ASM'),
+    ('John', 1, 1, 13, 'grc', 'καὶ', 'καί', 'G2532', 'CONJ'),
+    ('John', 1, 1, 14, 'grc', 'θεὸς', 'θεός', 'G2316', 'N-NSM'),
+    ('John', 1, 1, 15, 'grc', 'ἦν', 'εἰμί', 'G1510', 'V-IAI-3S'),
+    ('John', 1, 1, 16, 'grc', 'ὁ', 'ὁ', 'G3588', 'T-NSM'),
+    ('John', 1, 1, 17, 'grc', 'λόγος', 'λόγος', 'G3056', 'N-NSM')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO word_alignments (translation, title, chapter_num, verse_num, word_index, word, position) VALUES
+    ('kjv', 'John', 1, 1, 0, 'In', 1),
+    ('kjv', 'John', 1, 1, 2, 'beginning', 2),
+    ('kjv', 'John', 1, 1, 3, 'was', 3),
+    ('kjv', 'John', 1, 1, 4, 'the', 4),
+    ('kjv', 'John', 1, 1, 5, 'Word', 5),
+    ('kjv', 'John', 1, 1, 6, 'and', 6),
+    ('kjv', 'John', 1, 1, 7, 'the', 7),
+    ('kjv', 'John', 1, 1, 8, 'Word', 8),
+    ('kjv', 'John', 1, 1, 9, 'was', 9),
+    ('kjv', 'John', 1, 1, 10, 'with', 10),
+    ('kjv', 'John', 1, 1, 11, 'God', 11),
+    ('kjv', 'John', 1, 1, 11, 'God', 12),
+    ('kjv', 'John', 1, 1, 12, 'and', 13),
+    ('kjv', 'John', 1, 1, 13, 'the', 16),
+    ('kjv', 'John', 1, 1, 14, 'Word', 17),
+    ('kjv', 'John', 1, 1, 15, 'was', 15),
+    ('kjv', 'John', 1, 1, 16, 'God', 14)
+ON CONFLICT DO NOTHING;
+
+COMMIT;
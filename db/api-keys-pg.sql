@@ -0,0 +1,11 @@
+BEGIN TRANSACTION;
+
+-- Add the key management behind /admin/keys to a database loaded from an
+-- earlier kjv-pg.db. A revoked key is kept, with when it was revoked, so the
+-- saved searches made with it are not lost, but it is no longer accepted.
+-- The label says who or what a key was made for.
+ALTER TABLE public.api_keys ADD COLUMN IF NOT EXISTS label TEXT NOT NULL DEFAULT '';
+ALTER TABLE public.api_keys ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE public.api_keys ADD COLUMN IF NOT EXISTS revoked_at TIMESTAMPTZ;
+
+COMMIT;
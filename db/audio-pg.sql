@@ -0,0 +1,29 @@
+BEGIN TRANSACTION;
+
+-- Add the chapter audio behind GET /audio to a database loaded from an
+-- earlier kjv-pg.db. Each chapter that has a recording names the URL of its
+-- file, which has to answer Range requests for seeking to work. The timings,
+-- where they are loaded, give where each verse starts and ends in the file,
+-- in seconds. Chapters without a recording are answered with a 404.
+CREATE TABLE IF NOT EXISTS public.chapter_audio (
+    translation varchar(8) NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    media_type TEXT NOT NULL DEFAULT 'audio/mpeg',
+	PRIMARY KEY(translation, title, chapter_num)
+);
+
+CREATE TABLE IF NOT EXISTS public.verse_timings (
+    translation varchar(8) NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    verse_num INTEGER NOT NULL,
+    start_seconds DOUBLE PRECISION NOT NULL CHECK (start_seconds >= 0),
+    end_seconds DOUBLE PRECISION NOT NULL CHECK (end_seconds >= start_seconds),
+	PRIMARY KEY(translation, title, chapter_num, verse_num),
+    FOREIGN KEY (translation, title, chapter_num)
+        REFERENCES chapter_audio(translation, title, chapter_num) ON DELETE CASCADE
+);
+
+COMMIT;
@@ -0,0 +1,9 @@
+BEGIN TRANSACTION;
+
+-- Keep a lightly marked up (HTML) version of each verse alongside the plain
+-- text, in a database loaded from an earlier kjv-pg.db. Verses without a
+-- formatted version are served as plain text.
+ALTER TABLE public.verses
+    ADD COLUMN IF NOT EXISTS formatted_contents TEXT;
+
+COMMIT;
@@ -0,0 +1,12 @@
+BEGIN TRANSACTION;
+
+-- Add full text search to a database loaded from an earlier kjv-pg.db. The
+-- search_vector is filled in by POST /admin/reindex, or by the UPDATE below.
+ALTER TABLE public.verses
+    ADD COLUMN IF NOT EXISTS search_vector tsvector;
+
+CREATE INDEX IF NOT EXISTS verses_search_vector_idx ON verses USING GIN (search_vector);
+
+UPDATE verses SET search_vector = to_tsvector('english', contents);
+
+COMMIT;
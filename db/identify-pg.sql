@@ -0,0 +1,21 @@
+BEGIN TRANSACTION;
+
+-- Add the normalized text behind POST /identify to a database loaded from an
+-- earlier kjv-pg.db. It is the verse in lower case with the punctuation left
+-- out and the spaces collapsed, which is how a pasted quotation is compared,
+-- and is kept up to date by Postgres as verses are imported. The trigram
+-- index finds the verses a part of the quotation is in.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+ALTER TABLE public.verses
+    ADD COLUMN IF NOT EXISTS normalized_contents TEXT GENERATED ALWAYS AS (
+        btrim(regexp_replace(
+            regexp_replace(lower(contents), '[^[:alnum:][:space:]]+', '', 'g'),
+            '\s+', ' ', 'g'
+        ))
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS verses_normalized_contents_idx
+    ON verses USING GIN (normalized_contents gin_trgm_ops);
+
+COMMIT;
@@ -0,0 +1,66 @@
+BEGIN TRANSACTION;
+
+-- Add the lectionaries behind GET /lectionary to a database loaded from an
+-- earlier kjv-pg.db. Each lectionary (rcl is the Revised Common Lectionary,
+-- catholic is the Roman Catholic one) maps a date to its readings, which are
+-- references in the numbering of the KJV. More days are loaded the same way.
+CREATE TABLE IF NOT EXISTS public.lectionary_days (
+    lectionary varchar(8) NOT NULL CHECK (lectionary IN ('rcl', 'catholic')),
+    day DATE NOT NULL,
+    name TEXT NOT NULL,
+	PRIMARY KEY(lectionary, day)
+);
+
+CREATE TABLE IF NOT EXISTS public.lectionary_readings (
+    lectionary varchar(8) NOT NULL,
+    day DATE NOT NULL,
+    position SMALLINT NOT NULL,
+    label TEXT NOT NULL,
+    reference TEXT NOT NULL,
+	PRIMARY KEY(lectionary, day, position),
+	FOREIGN KEY(lectionary, day) REFERENCES lectionary_days(lectionary, day) ON DELETE CASCADE
+);
+
+-- Advent and Christmas 2025 (Year A)
+INSERT INTO lectionary_days (lectionary, day, name) VALUES
+    ('rcl', '2025-11-30', 'First Sunday of Advent'),
+    ('rcl', '2025-12-07', 'Second Sunday of Advent'),
+    ('rcl', '2025-12-14', 'Third Sunday of Advent'),
+    ('rcl', '2025-12-21', 'Fourth Sunday of Advent'),
+    ('rcl', '2025-12-25', 'Nativity of the Lord'),
+    ('catholic', '2025-11-30', 'First Sunday of Advent'),
+    ('catholic', '2025-12-25', 'The Nativity of the Lord (Mass during the Night)')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO lectionary_readings (lectionary, day, position, label, reference) VALUES
+    ('rcl', '2025-11-30', 1, 'First Reading', 'Isaiah 2:1-5'),
+    ('rcl', '2025-11-30', 2, 'Psalm', 'Psalms 122'),
+    ('rcl', '2025-11-30', 3, 'Second Reading', 'Romans 13:11-14'),
+    ('rcl', '2025-11-30', 4, 'Gospel', 'Matthew 24:36-44'),
+    ('rcl', '2025-12-07', 1, 'First Reading', 'Isaiah 11:1-10'),
+    ('rcl', '2025-12-07', 2, 'Psalm', 'Psalms 72:1-7, 18-19'),
+    ('rcl', '2025-12-07', 3, 'Second Reading', 'Romans 15:4-13'),
+    ('rcl', '2025-12-07', 4, 'Gospel', 'Matthew 3:1-12'),
+    ('rcl', '2025-12-14', 1, 'First Reading', 'Isaiah 35:1-10'),
+    ('rcl', '2025-12-14', 2, 'Psalm', 'Psalms 146:5-10'),
+    ('rcl', '2025-12-14', 3, 'Second Reading', 'James 5:7-10'),
+    ('rcl', '2025-12-14', 4, 'Gospel', 'Matthew 11:2-11'),
+    ('rcl', '2025-12-21', 1, 'First Reading', 'Isaiah 7:10-16'),
+    ('rcl', '2025-12-21', 2, 'Psalm', 'Psalms 80:1-7, 17-19'),
+    ('rcl', '2025-12-21', 3, 'Second Reading', 'Romans 1:1-7'),
+    ('rcl', '2025-12-21', 4, 'Gospel', 'Matthew 1:18-25'),
+    ('rcl', '2025-12-25', 1, 'First Reading', 'Isaiah 9:2-7'),
+    ('rcl', '2025-12-25', 2, 'Psalm', 'Psalms 96'),
+    ('rcl', '2025-12-25', 3, 'Second Reading', 'Titus 2:11-14'),
+    ('rcl', '2025-12-25', 4, 'Gospel', 'Luke 2:1-20'),
+    ('catholic', '2025-11-30', 1, 'First Reading', 'Isaiah 2:1-5'),
+    ('catholic', '2025-11-30', 2, 'Responsorial Psalm', 'Psalms 122:1-9'),
+    ('catholic', '2025-11-30', 3, 'Second Reading', 'Romans 13:11-14'),
+    ('catholic', '2025-11-30', 4, 'Gospel', 'Matthew 24:37-44'),
+    ('catholic', '2025-12-25', 1, 'First Reading', 'Isaiah 9:2-7'),
+    ('catholic', '2025-12-25', 2, 'Responsorial Psalm', 'Psalms 96:1-3, 11-13'),
+    ('catholic', '2025-12-25', 3, 'Second Reading', 'Titus 2:11-14'),
+    ('catholic', '2025-12-25', 4, 'Gospel', 'Luke 2:1-14')
+ON CONFLICT DO NOTHING;
+
+COMMIT;
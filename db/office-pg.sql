@@ -0,0 +1,25 @@
+BEGIN TRANSACTION;
+
+-- Add the lessons behind GET /office to a database loaded from an earlier
+-- kjv-pg.db. The psalms of morning and evening prayer follow the 30 day
+-- Psalter cycle and are not stored; each day and hour maps to its lessons,
+-- which are references in the numbering of the KJV. More days are loaded the
+-- same way.
+CREATE TABLE IF NOT EXISTS public.office_readings (
+    day DATE NOT NULL,
+    hour varchar(8) NOT NULL CHECK (hour IN ('morning', 'evening')),
+    position SMALLINT NOT NULL,
+    label TEXT NOT NULL,
+    reference TEXT NOT NULL,
+	PRIMARY KEY(day, hour, position)
+);
+
+-- Christmas Day 2025
+INSERT INTO office_readings (day, hour, position, label, reference) VALUES
+    ('2025-12-25', 'morning', 1, 'First Lesson', 'Isaiah 9:1-7'),
+    ('2025-12-25', 'morning', 2, 'Second Lesson', 'Luke 2:1-14'),
+    ('2025-12-25', 'evening', 1, 'First Lesson', 'Isaiah 7:10-16'),
+    ('2025-12-25', 'evening', 2, 'Second Lesson', 'Titus 3:4-8')
+ON CONFLICT DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,12 @@
+BEGIN TRANSACTION;
+
+-- Record which verses start a paragraph in a database loaded from an
+-- earlier kjv-pg.db, which had no paragraph_start column.
+ALTER TABLE public.verses
+    ADD COLUMN IF NOT EXISTS paragraph_start BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Every chapter opens a paragraph. The source text carries no pilcrows, so
+-- any other paragraph starts have to be marked separately.
+UPDATE verses SET paragraph_start = TRUE WHERE num = 1;
+
+COMMIT;
@@ -0,0 +1,138 @@
+BEGIN TRANSACTION;
+
+-- Add the people behind GET /people/:name and include=people to a database
+-- loaded from an earlier kjv-pg.db. Each person is keyed by their name as
+-- the KJV writes it, so names shared by more than one person are left out
+-- until people can be told apart by more than their name. Verses are linked
+-- to every person named in them.
+CREATE TABLE IF NOT EXISTS public.people (
+    name TEXT PRIMARY KEY,
+    description TEXT NOT NULL
+);
+
+-- The relative is the person's father, mother, spouse or sibling. Children
+-- are not stored, they are the people a person is the father or mother of.
+CREATE TABLE IF NOT EXISTS public.person_relationships (
+    name TEXT NOT NULL REFERENCES people(name) ON DELETE CASCADE,
+    relationship varchar(8) NOT NULL CHECK (relationship IN ('father', 'mother', 'spouse', 'sibling')),
+    relative TEXT NOT NULL REFERENCES people(name) ON DELETE CASCADE,
+	PRIMARY KEY(name, relationship, relative)
+);
+
+-- The appearances are the passages a person is best known from, in the
+-- numbering of the KJV
+CREATE TABLE IF NOT EXISTS public.person_appearances (
+    name TEXT NOT NULL REFERENCES people(name) ON DELETE CASCADE,
+    position SMALLINT NOT NULL,
+    label TEXT NOT NULL,
+    reference TEXT NOT NULL,
+	PRIMARY KEY(name, position)
+);
+
+INSERT INTO people (name, description) VALUES
+    ('Aaron', 'Brother of Moses and the first high priest of Israel'),
+    ('Abraham', 'Patriarch called out of Haran, father of Isaac and Ishmael'),
+    ('Bathsheba', 'Wife of David and mother of Solomon'),
+    ('Boaz', 'Kinsman of Naomi who married Ruth'),
+    ('David', 'Shepherd who became the second king of Israel'),
+    ('Elijah', 'Prophet of Israel in the days of Ahab'),
+    ('Elisha', 'Prophet of Israel who followed Elijah'),
+    ('Esau', 'Elder son of Isaac, who sold his birthright to Jacob'),
+    ('Hagar', 'Handmaid of Sarah and mother of Ishmael'),
+    ('Isaac', 'Son of Abraham and Sarah, father of Esau and Jacob'),
+    ('Ishmael', 'Son of Abraham and Hagar'),
+    ('Jacob', 'Son of Isaac, renamed Israel, father of the twelve tribes'),
+    ('Jesse', 'Bethlehemite and father of David'),
+    ('Leah', 'Elder daughter of Laban and wife of Jacob'),
+    ('Miriam', 'Prophetess and sister of Moses and Aaron'),
+    ('Moses', 'Prophet who led Israel out of Egypt and received the law'),
+    ('Naomi', 'Mother in law of Ruth'),
+    ('Obed', 'Son of Boaz and Ruth, grandfather of David'),
+    ('Rachel', 'Younger daughter of Laban and wife of Jacob'),
+    ('Rebekah', 'Wife of Isaac and mother of Esau and Jacob'),
+    ('Ruth', 'Moabitess who followed Naomi to Bethlehem'),
+    ('Sarah', 'Wife of Abraham and mother of Isaac'),
+    ('Solomon', 'Son of David and Bathsheba, the third king of Israel')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO person_relationships (name, relationship, relative) VALUES
+    ('Abraham', 'spouse', 'Sarah'),
+    ('Isaac', 'father', 'Abraham'),
+    ('Isaac', 'mother', 'Sarah'),
+    ('Ishmael', 'father', 'Abraham'),
+    ('Ishmael', 'mother', 'Hagar'),
+    ('Isaac', 'sibling', 'Ishmael'),
+    ('Isaac', 'spouse', 'Rebekah'),
+    ('Esau', 'father', 'Isaac'),
+    ('Esau', 'mother', 'Rebekah'),
+    ('Jacob', 'father', 'Isaac'),
+    ('Jacob', 'mother', 'Rebekah'),
+    ('Esau', 'sibling', 'Jacob'),
+    ('Jacob', 'spouse', 'Leah'),
+    ('Jacob', 'spouse', 'Rachel'),
+    ('Leah', 'sibling', 'Rachel'),
+    ('Moses', 'sibling', 'Aaron'),
+    ('Moses', 'sibling', 'Miriam'),
+    ('Aaron', 'sibling', 'Miriam'),
+    ('Ruth', 'spouse', 'Boaz'),
+    ('Obed', 'father', 'Boaz'),
+    ('Obed', 'mother', 'Ruth'),
+    ('Jesse', 'father', 'Obed'),
+    ('David', 'father', 'Jesse'),
+    ('David', 'spouse', 'Bathsheba'),
+    ('Solomon', 'father', 'David'),
+    ('Solomon', 'mother', 'Bathsheba')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO person_appearances (name, position, label, reference) VALUES
+    ('Aaron', 1, 'Sent to speak for Moses', 'Exodus 4:14'),
+    ('Aaron', 2, 'Made a priest', 'Exodus 28:1'),
+    ('Abraham', 1, 'Called out of Haran', 'Genesis 12:1-4'),
+    ('Abraham', 2, 'The covenant', 'Genesis 15:5-6'),
+    ('Abraham', 3, 'The offering of Isaac', 'Genesis 22:1-2'),
+    ('Bathsheba', 1, 'Seen by David', '2 Samuel 11:2-3'),
+    ('Bathsheba', 2, 'Solomon made king', '1 Kings 1:28-31'),
+    ('Boaz', 1, 'Kinsman of Naomi', 'Ruth 2:1'),
+    ('Boaz', 2, 'Marries Ruth', 'Ruth 4:13'),
+    ('David', 1, 'Anointed', '1 Samuel 16:12-13'),
+    ('David', 2, 'Goliath', '1 Samuel 17:49-50'),
+    ('David', 3, 'King over Israel', '2 Samuel 5:3-4'),
+    ('Elijah', 1, 'The drought', '1 Kings 17:1'),
+    ('Elijah', 2, 'Taken up', '2 Kings 2:11'),
+    ('Elisha', 1, 'Called', '1 Kings 19:19'),
+    ('Elisha', 2, 'The mantle', '2 Kings 2:13-14'),
+    ('Esau', 1, 'Born', 'Genesis 25:25'),
+    ('Esau', 2, 'Sells his birthright', 'Genesis 25:33-34'),
+    ('Hagar', 1, 'Given to Abraham', 'Genesis 16:1-3'),
+    ('Hagar', 2, 'In the wilderness', 'Genesis 21:17'),
+    ('Isaac', 1, 'Born', 'Genesis 21:1-3'),
+    ('Isaac', 2, 'Marries Rebekah', 'Genesis 24:67'),
+    ('Ishmael', 1, 'Born', 'Genesis 16:15'),
+    ('Ishmael', 2, 'In the wilderness', 'Genesis 21:20'),
+    ('Jacob', 1, 'Born', 'Genesis 25:26'),
+    ('Jacob', 2, 'The dream at Bethel', 'Genesis 28:12-13'),
+    ('Jacob', 3, 'Renamed Israel', 'Genesis 32:28'),
+    ('Jesse', 1, 'Samuel sent to him', '1 Samuel 16:1'),
+    ('Leah', 1, 'Daughter of Laban', 'Genesis 29:16-17'),
+    ('Leah', 2, 'Given to Jacob', 'Genesis 29:23'),
+    ('Miriam', 1, 'The song of the sea', 'Exodus 15:20-21'),
+    ('Miriam', 2, 'Speaks against Moses', 'Numbers 12:1'),
+    ('Moses', 1, 'Born', 'Exodus 2:1-10'),
+    ('Moses', 2, 'The burning bush', 'Exodus 3:1-6'),
+    ('Moses', 3, 'The law', 'Exodus 20:1-3'),
+    ('Naomi', 1, 'Widowed in Moab', 'Ruth 1:1-3'),
+    ('Naomi', 2, 'Nurses Obed', 'Ruth 4:16-17'),
+    ('Obed', 1, 'Born', 'Ruth 4:17'),
+    ('Rachel', 1, 'Meets Jacob', 'Genesis 29:9-10'),
+    ('Rachel', 2, 'Dies on the way to Bethlehem', 'Genesis 35:19'),
+    ('Rebekah', 1, 'At the well', 'Genesis 24:15'),
+    ('Rebekah', 2, 'Bears twins', 'Genesis 25:21-23'),
+    ('Ruth', 1, 'Follows Naomi', 'Ruth 1:16-17'),
+    ('Ruth', 2, 'Marries Boaz', 'Ruth 4:13'),
+    ('Sarah', 1, 'Renamed', 'Genesis 17:15-16'),
+    ('Sarah', 2, 'Bears Isaac', 'Genesis 21:1-3'),
+    ('Solomon', 1, 'Born', '2 Samuel 12:24'),
+    ('Solomon', 2, 'Asks for wisdom', '1 Kings 3:9-12')
+ON CONFLICT DO NOTHING;
+
+COMMIT;
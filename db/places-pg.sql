@@ -0,0 +1,47 @@
+BEGIN TRANSACTION;
+
+-- Add the places behind GET /places and include=places to a database loaded
+-- from an earlier kjv-pg.db. Each place has the name the KJV uses, the name
+-- of the modern site when it differs, its approximate coordinates and the
+-- references it is best known from. Verses are annotated with every place
+-- named in them, so the name has to be written exactly as it is in the text.
+CREATE TABLE IF NOT EXISTS public.places (
+    name TEXT PRIMARY KEY,
+    modern_name TEXT,
+    latitude DOUBLE PRECISION NOT NULL CHECK (latitude BETWEEN -90 AND 90),
+    longitude DOUBLE PRECISION NOT NULL CHECK (longitude BETWEEN -180 AND 180),
+    verse_references TEXT[] NOT NULL DEFAULT '{}'
+);
+
+INSERT INTO places (name, modern_name, latitude, longitude, verse_references) VALUES
+    ('Antioch', 'Antakya', 36.2021, 36.1606, '{"Acts 11:26"}'),
+    ('Athens', NULL, 37.9715, 23.7257, '{"Acts 17:16"}'),
+    ('Babylon', 'near Hillah', 32.5364, 44.4209, '{"Daniel 1:1"}'),
+    ('Beersheba', NULL, 31.2448, 34.8408, '{"Genesis 21:31"}'),
+    ('Bethany', 'al-Eizariya', 31.7712, 35.2606, '{"John 11:1", "Mark 11:1"}'),
+    ('Bethel', 'Beitin', 31.9308, 35.2208, '{"Genesis 28:19"}'),
+    ('Bethlehem', NULL, 31.7054, 35.2024, '{"Micah 5:2", "Luke 2:4"}'),
+    ('Bethsaida', NULL, 32.9097, 35.6308, '{"Mark 8:22"}'),
+    ('Caesarea', NULL, 32.5000, 34.8920, '{"Acts 10:1"}'),
+    ('Cana', 'Kafr Kanna', 32.7469, 35.3386, '{"John 2:1"}'),
+    ('Capernaum', 'Kfar Nahum', 32.8803, 35.5733, '{"Matthew 4:13", "Mark 2:1", "John 6:59"}'),
+    ('Corinth', NULL, 37.9055, 22.8797, '{"Acts 18:1"}'),
+    ('Damascus', NULL, 33.5138, 36.2765, '{"Acts 9:3"}'),
+    ('Emmaus', 'Emmaus Nicopolis', 31.8389, 34.9892, '{"Luke 24:13"}'),
+    ('Ephesus', 'near Selçuk', 37.9395, 27.3417, '{"Acts 19:1", "Revelation 2:1"}'),
+    ('Gethsemane', NULL, 31.7794, 35.2397, '{"Matthew 26:36"}'),
+    ('Hebron', NULL, 31.5326, 35.0998, '{"Genesis 23:2", "2 Samuel 2:11"}'),
+    ('Jericho', NULL, 31.8711, 35.4442, '{"Joshua 6:1-2", "Luke 19:1"}'),
+    ('Jerusalem', NULL, 31.7784, 35.2354, '{"2 Samuel 5:6-7", "Luke 19:41"}'),
+    ('Joppa', 'Jaffa', 32.0504, 34.7522, '{"Jonah 1:3", "Acts 9:36"}'),
+    ('Nazareth', NULL, 32.7021, 35.2978, '{"Luke 1:26", "Matthew 2:23"}'),
+    ('Nineveh', 'Mosul', 36.3594, 43.1528, '{"Jonah 3:3"}'),
+    ('Philippi', NULL, 41.0136, 24.2867, '{"Acts 16:12"}'),
+    ('Rome', NULL, 41.8902, 12.4922, '{"Acts 28:16", "Romans 1:7"}'),
+    ('Samaria', 'Sebastia', 32.2764, 35.1970, '{"1 Kings 16:24"}'),
+    ('Shechem', 'Tell Balata', 32.2133, 35.2817, '{"Genesis 12:6", "Joshua 24:1"}'),
+    ('Tarsus', NULL, 36.9177, 34.8925, '{"Acts 9:11"}'),
+    ('Ur', 'Tell el-Muqayyar', 30.9626, 46.1032, '{"Genesis 11:31"}')
+ON CONFLICT DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,15 @@
+BEGIN TRANSACTION;
+
+-- Add the daily verse view counts behind GET /popular to a database loaded
+-- from an earlier kjv-pg.db. Only the counts are kept, nothing about who
+-- looked a verse up or how.
+CREATE TABLE IF NOT EXISTS public.verse_views (
+    day DATE NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    num INTEGER NOT NULL,
+    views BIGINT NOT NULL DEFAULT 0,
+	PRIMARY KEY(day, title, chapter_num, num)
+);
+
+COMMIT;
@@ -0,0 +1,24 @@
+BEGIN TRANSACTION;
+
+-- Add per API key rate limit tiers to a database loaded from an earlier
+-- kjv-pg.db. Budgets are per minute. Requests without an API key get the
+-- free tier.
+CREATE TABLE IF NOT EXISTS public.rate_limit_tiers (
+    tier varchar(10) NOT NULL,
+    requests_per_minute INTEGER NOT NULL,
+    verses_per_minute INTEGER NOT NULL,
+	PRIMARY KEY(tier)
+);
+
+CREATE TABLE IF NOT EXISTS public.api_keys (
+    api_key TEXT NOT NULL,
+    tier varchar(10) NOT NULL,
+	PRIMARY KEY(api_key),
+    CONSTRAINT "api_keys_tier_fkey" FOREIGN KEY ("tier") REFERENCES "rate_limit_tiers" ("tier") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+
+INSERT INTO rate_limit_tiers VALUES('free', 60, 2000) ON CONFLICT DO NOTHING;
+INSERT INTO rate_limit_tiers VALUES('hobby', 300, 10000) ON CONFLICT DO NOTHING;
+INSERT INTO rate_limit_tiers VALUES('pro', 3000, 100000) ON CONFLICT DO NOTHING;
+
+COMMIT;
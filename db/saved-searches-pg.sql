@@ -0,0 +1,22 @@
+BEGIN TRANSACTION;
+
+-- Add the saved searches behind /saved to a database loaded from an earlier
+-- kjv-pg.db. A search is saved under a name by the holder of an API key, and
+-- goes when the key does. The total is how many verses a keyword search
+-- matched when it was last checked, so that a webhook can be told when more
+-- match.
+CREATE TABLE IF NOT EXISTS public.saved_searches (
+    api_key TEXT NOT NULL REFERENCES api_keys(api_key) ON UPDATE CASCADE ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    kind varchar(9) NOT NULL CHECK (kind IN ('reference', 'keyword')),
+    query TEXT NOT NULL,
+    webhook TEXT,
+    total BIGINT NOT NULL DEFAULT 0,
+    saved_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY(api_key, name)
+);
+
+CREATE INDEX IF NOT EXISTS saved_searches_webhook_idx ON saved_searches (kind)
+    WHERE webhook IS NOT NULL;
+
+COMMIT;
@@ -0,0 +1,42 @@
+BEGIN TRANSACTION;
+
+-- Add the events behind GET /timeline to a database loaded from an earlier
+-- kjv-pg.db. Each event is dated to the years it spans, counted from AD 1
+-- with the years BC as negative numbers (there is no year 0), and points at
+-- the passage it is told in, in the numbering of the KJV. The dates follow
+-- a common conservative chronology and are approximate; the early ones are
+-- disputed.
+CREATE TABLE IF NOT EXISTS public.timeline_events (
+    name TEXT PRIMARY KEY,
+    reference TEXT NOT NULL,
+    start_year INTEGER NOT NULL CHECK (start_year <> 0),
+    end_year INTEGER NOT NULL CHECK (end_year <> 0),
+	CHECK (start_year <= end_year)
+);
+
+INSERT INTO timeline_events (name, reference, start_year, end_year) VALUES
+    ('The call of Abram', 'Genesis 12:1-4', -2091, -2091),
+    ('Joseph sold into Egypt', 'Genesis 37:26-28', -1898, -1898),
+    ('The Exodus', 'Exodus 12:40-41', -1446, -1446),
+    ('The wandering in the wilderness', 'Numbers 14:33-34', -1446, -1406),
+    ('The crossing of the Jordan', 'Joshua 3:14-17', -1406, -1406),
+    ('Saul anointed king', '1 Samuel 10:1', -1050, -1050),
+    ('The reign of David', '2 Samuel 5:3-5', -1010, -970),
+    ('The temple of Solomon begun', '1 Kings 6:1', -966, -966),
+    ('The kingdom divided', '1 Kings 12:16-20', -931, -931),
+    ('Elijah on mount Carmel', '1 Kings 18:36-39', -860, -860),
+    ('The fall of Samaria', '2 Kings 17:5-6', -722, -722),
+    ('The fall of Jerusalem', '2 Kings 25:8-10', -586, -586),
+    ('The return under Cyrus', 'Ezra 1:1-3', -538, -538),
+    ('The temple rebuilt', 'Ezra 6:15', -516, -516),
+    ('The wall of Jerusalem rebuilt', 'Nehemiah 6:15', -445, -445),
+    ('The birth of Jesus', 'Luke 2:1-7', -6, -4),
+    ('The baptism of Jesus', 'Luke 3:21-23', 26, 27),
+    ('The crucifixion and resurrection', 'Luke 24:1-7', 30, 33),
+    ('Pentecost', 'Acts 2:1-4', 30, 33),
+    ('The conversion of Saul', 'Acts 9:3-6', 34, 35),
+    ('The council at Jerusalem', 'Acts 15:6-11', 49, 50),
+    ('Paul in Rome', 'Acts 28:16', 60, 62)
+ON CONFLICT DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,20 @@
+BEGIN TRANSACTION;
+
+-- Add the translations a deployment can serve to a database loaded from an
+-- earlier kjv-pg.db. The text of a translation is loaded by importing it as a
+-- new version (see translation_versions), and a translation is only served
+-- once it has an active version. The translation is the code it is asked for
+-- by (ex: /search?query=John 3:16&translation=web).
+CREATE TABLE IF NOT EXISTS public.translations (
+    translation varchar(15) NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL,
+    language varchar(8) NOT NULL DEFAULT 'en'
+);
+
+INSERT INTO translations (translation, name, language) VALUES
+    ('kjv', 'King James Version', 'en'),
+    ('web', 'World English Bible', 'en'),
+    ('asv', 'American Standard Version', 'en')
+    ON CONFLICT DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,14 @@
+BEGIN TRANSACTION;
+
+-- Add the hourly search counts behind GET /trending to a database loaded
+-- from an earlier kjv-pg.db. Only canonical references are counted, never
+-- the query that was typed.
+CREATE TABLE IF NOT EXISTS public.search_trends (
+    hour TIMESTAMPTZ NOT NULL,
+    scope varchar(7) NOT NULL CHECK (scope IN ('book', 'chapter', 'passage')),
+    reference TEXT NOT NULL,
+    searches BIGINT NOT NULL DEFAULT 0,
+	PRIMARY KEY(hour, scope, reference)
+);
+
+COMMIT;
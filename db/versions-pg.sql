@@ -0,0 +1,31 @@
+BEGIN TRANSACTION;
+
+-- Track an import version per translation in a database loaded from an
+-- earlier kjv-pg.db. Every verse belongs to a version, and only the active
+-- version of a translation is served. The rows of the previous version are
+-- kept until the new import has been validated, so it can be rolled back.
+CREATE TABLE IF NOT EXISTS public.translation_versions (
+    translation varchar(15) NOT NULL,
+    version INTEGER NOT NULL,
+    state varchar(11) NOT NULL CHECK (state IN ('pending', 'active', 'previous', 'rolled-back')),
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY(translation, version)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "translation_versions_active_key"
+    ON "translation_versions"("translation") WHERE state = 'active';
+
+INSERT INTO translation_versions (translation, version, state)
+    VALUES('kjv', 1, 'active')
+    ON CONFLICT DO NOTHING;
+
+ALTER TABLE public.verses
+    ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE public.verses DROP CONSTRAINT IF EXISTS verses_pkey;
+DROP INDEX IF EXISTS "verses_title_chapter_num_num_key";
+ALTER TABLE public.verses ADD PRIMARY KEY (title, chapter_num, num, version);
+CREATE UNIQUE INDEX IF NOT EXISTS "verses_title_chapter_num_num_version_key"
+    ON "verses"("title", "chapter_num", "num", "version");
+
+COMMIT;
@@ -0,0 +1,291 @@
+-- The schema of the API. Every statement can be run again, so a database
+-- loaded from db/kjv-pg.db (and the db/*-pg.sql scripts since) takes this
+-- migration as already applied instead of failing on it.
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Each import of a translation gets a new version. Only the active version
+-- is served, and the previous one is kept so a bad import can be rolled back.
+CREATE TABLE IF NOT EXISTS public.translation_versions (
+    translation varchar(15) NOT NULL,
+    version INTEGER NOT NULL,
+    state varchar(11) NOT NULL CHECK (state IN ('pending', 'active', 'previous', 'rolled-back')),
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY(translation, version)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "translation_versions_active_key"
+    ON "translation_versions"("translation") WHERE state = 'active';
+
+-- The translations a deployment can serve. A translation is only served
+-- once it has an active version.
+CREATE TABLE IF NOT EXISTS public.translations (
+    translation varchar(15) NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL,
+    language varchar(8) NOT NULL DEFAULT 'en'
+);
+
+CREATE TABLE IF NOT EXISTS public.books (
+    title varchar(15) NOT NULL,
+	PRIMARY KEY(title)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "books_name_key" ON "books"("title");
+
+CREATE TABLE IF NOT EXISTS public.chapters (
+    num INTEGER NOT NULL,
+    title varchar(15) NOT NULL,
+	PRIMARY KEY(title, num),
+    CONSTRAINT "chapters_title_fkey" FOREIGN KEY ("title") REFERENCES "books" ("title") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "chapters_num_title_key" ON "chapters"("num", "title");
+
+-- A verse numbered 0 holds the superscription or introduction of its
+-- chapter (ex: "A Psalm of David..."), which is not counted as a verse.
+-- The contents are plain text. The formatted_contents keep the light HTML
+-- markup of the source, and are left NULL when the source has none.
+-- The search_vector is the full text search form of the contents, and the
+-- normalized_contents are the form a pasted quotation is compared with. The
+-- version is the import of the translation the verse belongs to.
+CREATE TABLE IF NOT EXISTS public.verses (
+    num INTEGER NOT NULL,
+    contents TEXT NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    title varchar(15) NOT NULL,
+    paragraph_start BOOLEAN NOT NULL DEFAULT FALSE,
+    formatted_contents TEXT,
+    search_vector tsvector,
+    version INTEGER NOT NULL DEFAULT 1,
+    normalized_contents TEXT GENERATED ALWAYS AS (
+        btrim(regexp_replace(
+            regexp_replace(lower(contents), '[^[:alnum:][:space:]]+', '', 'g'),
+            '\s+', ' ', 'g'
+        ))
+    ) STORED,
+	PRIMARY KEY(title, chapter_num, num, version),
+    CONSTRAINT "verses_chapter_num_title_fkey" FOREIGN KEY ("chapter_num", "title") REFERENCES "chapters" ("num", "title") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "verses_title_chapter_num_num_version_key"
+    ON "verses"("title", "chapter_num", "num", "version");
+CREATE INDEX IF NOT EXISTS verses_search_vector_idx ON verses USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS verses_normalized_contents_idx
+    ON verses USING GIN (normalized_contents gin_trgm_ops);
+
+-- Requests are rate limited per API key, by the budgets of the key's tier.
+-- Budgets are per minute, and requests without an API key get the free tier.
+CREATE TABLE IF NOT EXISTS public.rate_limit_tiers (
+    tier varchar(10) NOT NULL,
+    requests_per_minute INTEGER NOT NULL,
+    verses_per_minute INTEGER NOT NULL,
+	PRIMARY KEY(tier)
+);
+
+-- A revoked key is kept, so the saved searches made with it are not lost,
+-- but it is no longer accepted. The label says who or what it was made for.
+CREATE TABLE IF NOT EXISTS public.api_keys (
+    api_key TEXT NOT NULL,
+    tier varchar(10) NOT NULL,
+    label TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    revoked_at TIMESTAMPTZ,
+	PRIMARY KEY(api_key),
+    CONSTRAINT "api_keys_tier_fkey" FOREIGN KEY ("tier") REFERENCES "rate_limit_tiers" ("tier") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+
+-- A search is saved under a name by the holder of an API key, and goes when
+-- the key does. The total is how many verses a keyword search matched when
+-- it was last checked, so that a webhook can be told when more match.
+CREATE TABLE IF NOT EXISTS public.saved_searches (
+    api_key TEXT NOT NULL REFERENCES api_keys(api_key) ON UPDATE CASCADE ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    kind varchar(9) NOT NULL CHECK (kind IN ('reference', 'keyword')),
+    query TEXT NOT NULL,
+    webhook TEXT,
+    total BIGINT NOT NULL DEFAULT 0,
+    saved_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY(api_key, name)
+);
+
+CREATE INDEX IF NOT EXISTS saved_searches_webhook_idx ON saved_searches (kind)
+    WHERE webhook IS NOT NULL;
+
+-- The number of times each verse was looked up, per day. Only the counts
+-- are kept, nothing about who looked a verse up or how.
+CREATE TABLE IF NOT EXISTS public.verse_views (
+    day DATE NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    num INTEGER NOT NULL,
+    views BIGINT NOT NULL DEFAULT 0,
+	PRIMARY KEY(day, title, chapter_num, num)
+);
+
+-- The number of searches for each reference, per hour, at each scope (the
+-- book, the chapter, or the passage as it was resolved). Only canonical
+-- references are counted, never the query that was typed.
+CREATE TABLE IF NOT EXISTS public.search_trends (
+    hour TIMESTAMPTZ NOT NULL,
+    scope varchar(7) NOT NULL CHECK (scope IN ('book', 'chapter', 'passage')),
+    reference TEXT NOT NULL,
+    searches BIGINT NOT NULL DEFAULT 0,
+	PRIMARY KEY(hour, scope, reference)
+);
+
+-- Every administrative action (imports, rollbacks, cache purges, reindexes
+-- and API keys) is recorded with who took it. The log is append only: rows
+-- can not be changed or removed, not even by the API's own user.
+CREATE TABLE IF NOT EXISTS public.audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    actor TEXT NOT NULL,
+    action varchar(16) NOT NULL,
+    target TEXT NOT NULL,
+    details TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_action_idx ON public.audit_log (action, id);
+
+CREATE OR REPLACE FUNCTION public.audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'the audit log is append only';
+END
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_log_append_only ON public.audit_log;
+CREATE TRIGGER audit_log_append_only BEFORE UPDATE OR DELETE ON public.audit_log
+    FOR EACH ROW EXECUTE FUNCTION public.audit_log_append_only();
+
+DROP TRIGGER IF EXISTS audit_log_no_truncate ON public.audit_log;
+CREATE TRIGGER audit_log_no_truncate BEFORE TRUNCATE ON public.audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION public.audit_log_append_only();
+
+-- Each chapter that has a recording names the URL of its file, which has to
+-- answer Range requests for seeking to work. The timings, where they are
+-- loaded, give where each verse starts and ends in the file, in seconds.
+CREATE TABLE IF NOT EXISTS public.chapter_audio (
+    translation varchar(8) NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    media_type TEXT NOT NULL DEFAULT 'audio/mpeg',
+	PRIMARY KEY(translation, title, chapter_num)
+);
+
+CREATE TABLE IF NOT EXISTS public.verse_timings (
+    translation varchar(8) NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    verse_num INTEGER NOT NULL,
+    start_seconds DOUBLE PRECISION NOT NULL CHECK (start_seconds >= 0),
+    end_seconds DOUBLE PRECISION NOT NULL CHECK (end_seconds >= start_seconds),
+	PRIMARY KEY(translation, title, chapter_num, verse_num),
+    FOREIGN KEY (translation, title, chapter_num)
+        REFERENCES chapter_audio(translation, title, chapter_num) ON DELETE CASCADE
+);
+
+-- The source tokens are the words of the Hebrew, Aramaic or Greek text of a
+-- verse, in their order. A word of a translation is aligned to the tokens it
+-- renders, by its index among the words of the verse's plain text counted
+-- from 0.
+CREATE TABLE IF NOT EXISTS public.source_tokens (
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    verse_num INTEGER NOT NULL,
+    position SMALLINT NOT NULL CHECK (position > 0),
+    language varchar(3) NOT NULL CHECK (language IN ('hbo', 'arc', 'grc')),
+    text TEXT NOT NULL,
+    lemma TEXT NOT NULL,
+    strongs TEXT NOT NULL,
+    morphology TEXT,
+	PRIMARY KEY(title, chapter_num, verse_num, position)
+);
+
+CREATE TABLE IF NOT EXISTS public.word_alignments (
+    translation varchar(8) NOT NULL,
+    title varchar(15) NOT NULL,
+    chapter_num INTEGER NOT NULL,
+    verse_num INTEGER NOT NULL,
+    word_index SMALLINT NOT NULL CHECK (word_index >= 0),
+    word TEXT NOT NULL,
+    position SMALLINT NOT NULL,
+	PRIMARY KEY(translation, title, chapter_num, verse_num, word_index, position),
+    FOREIGN KEY (title, chapter_num, verse_num, position)
+        REFERENCES source_tokens(title, chapter_num, verse_num, position) ON DELETE CASCADE
+);
+
+-- Each lectionary (rcl is the Revised Common Lectionary, catholic is the
+-- Roman Catholic one) maps a date to its readings, which are references in
+-- the numbering of the KJV.
+CREATE TABLE IF NOT EXISTS public.lectionary_days (
+    lectionary varchar(8) NOT NULL CHECK (lectionary IN ('rcl', 'catholic')),
+    day DATE NOT NULL,
+    name TEXT NOT NULL,
+	PRIMARY KEY(lectionary, day)
+);
+
+CREATE TABLE IF NOT EXISTS public.lectionary_readings (
+    lectionary varchar(8) NOT NULL,
+    day DATE NOT NULL,
+    position SMALLINT NOT NULL,
+    label TEXT NOT NULL,
+    reference TEXT NOT NULL,
+	PRIMARY KEY(lectionary, day, position),
+	FOREIGN KEY(lectionary, day) REFERENCES lectionary_days(lectionary, day) ON DELETE CASCADE
+);
+
+-- The lessons of morning and evening prayer for each day. The psalms follow
+-- the 30 day Psalter cycle and are not stored.
+CREATE TABLE IF NOT EXISTS public.office_readings (
+    day DATE NOT NULL,
+    hour varchar(8) NOT NULL CHECK (hour IN ('morning', 'evening')),
+    position SMALLINT NOT NULL,
+    label TEXT NOT NULL,
+    reference TEXT NOT NULL,
+	PRIMARY KEY(day, hour, position)
+);
+
+-- Each person is keyed by their name as the KJV writes it. The relative is
+-- the person's father, mother, spouse or sibling, and the appearances are
+-- the passages a person is best known from.
+CREATE TABLE IF NOT EXISTS public.people (
+    name TEXT PRIMARY KEY,
+    description TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.person_relationships (
+    name TEXT NOT NULL REFERENCES people(name) ON DELETE CASCADE,
+    relationship varchar(8) NOT NULL CHECK (relationship IN ('father', 'mother', 'spouse', 'sibling')),
+    relative TEXT NOT NULL REFERENCES people(name) ON DELETE CASCADE,
+	PRIMARY KEY(name, relationship, relative)
+);
+
+CREATE TABLE IF NOT EXISTS public.person_appearances (
+    name TEXT NOT NULL REFERENCES people(name) ON DELETE CASCADE,
+    position SMALLINT NOT NULL,
+    label TEXT NOT NULL,
+    reference TEXT NOT NULL,
+	PRIMARY KEY(name, position)
+);
+
+-- Each place has the name the KJV uses, the name of the modern site when it
+-- differs, its approximate coordinates and the references it is best known
+-- from.
+CREATE TABLE IF NOT EXISTS public.places (
+    name TEXT PRIMARY KEY,
+    modern_name TEXT,
+    latitude DOUBLE PRECISION NOT NULL CHECK (latitude BETWEEN -90 AND 90),
+    longitude DOUBLE PRECISION NOT NULL CHECK (longitude BETWEEN -180 AND 180),
+    verse_references TEXT[] NOT NULL DEFAULT '{}'
+);
+
+-- Each event is dated to the years it spans, with the years BC as negative
+-- numbers (there is no year 0), and points at the passage it is told in.
+CREATE TABLE IF NOT EXISTS public.timeline_events (
+    name TEXT PRIMARY KEY,
+    reference TEXT NOT NULL,
+    start_year INTEGER NOT NULL CHECK (start_year <> 0),
+    end_year INTEGER NOT NULL CHECK (end_year <> 0),
+	CHECK (start_year <= end_year)
+);
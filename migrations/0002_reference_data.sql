@@ -0,0 +1,291 @@
+-- The reference data the API serves besides the text: the rate limit
+-- tiers, the translations that can be served, and the lectionaries, daily
+-- office, people, places, timeline and word alignments. The rows already
+-- there are left as they are. The text itself is loaded by importing a
+-- translation, or with --seed.
+
+INSERT INTO rate_limit_tiers VALUES('free', 60, 2000) ON CONFLICT DO NOTHING;
+INSERT INTO rate_limit_tiers VALUES('hobby', 300, 10000) ON CONFLICT DO NOTHING;
+INSERT INTO rate_limit_tiers VALUES('pro', 3000, 100000) ON CONFLICT DO NOTHING;
+
+INSERT INTO translations (translation, name, language) VALUES
+    ('kjv', 'King James Version', 'en'),
+    ('web', 'World English Bible', 'en'),
+    ('asv', 'American Standard Version', 'en')
+    ON CONFLICT DO NOTHING;
+
+-- Advent and Christmas 2025 (Year A)
+INSERT INTO lectionary_days (lectionary, day, name) VALUES
+    ('rcl', '2025-11-30', 'First Sunday of Advent'),
+    ('rcl', '2025-12-07', 'Second Sunday of Advent'),
+    ('rcl', '2025-12-14', 'Third Sunday of Advent'),
+    ('rcl', '2025-12-21', 'Fourth Sunday of Advent'),
+    ('rcl', '2025-12-25', 'Nativity of the Lord'),
+    ('catholic', '2025-11-30', 'First Sunday of Advent'),
+    ('catholic', '2025-12-25', 'The Nativity of the Lord (Mass during the Night)')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO lectionary_readings (lectionary, day, position, label, reference) VALUES
+    ('rcl', '2025-11-30', 1, 'First Reading', 'Isaiah 2:1-5'),
+    ('rcl', '2025-11-30', 2, 'Psalm', 'Psalms 122'),
+    ('rcl', '2025-11-30', 3, 'Second Reading', 'Romans 13:11-14'),
+    ('rcl', '2025-11-30', 4, 'Gospel', 'Matthew 24:36-44'),
+    ('rcl', '2025-12-07', 1, 'First Reading', 'Isaiah 11:1-10'),
+    ('rcl', '2025-12-07', 2, 'Psalm', 'Psalms 72:1-7, 18-19'),
+    ('rcl', '2025-12-07', 3, 'Second Reading', 'Romans 15:4-13'),
+    ('rcl', '2025-12-07', 4, 'Gospel', 'Matthew 3:1-12'),
+    ('rcl', '2025-12-14', 1, 'First Reading', 'Isaiah 35:1-10'),
+    ('rcl', '2025-12-14', 2, 'Psalm', 'Psalms 146:5-10'),
+    ('rcl', '2025-12-14', 3, 'Second Reading', 'James 5:7-10'),
+    ('rcl', '2025-12-14', 4, 'Gospel', 'Matthew 11:2-11'),
+    ('rcl', '2025-12-21', 1, 'First Reading', 'Isaiah 7:10-16'),
+    ('rcl', '2025-12-21', 2, 'Psalm', 'Psalms 80:1-7, 17-19'),
+    ('rcl', '2025-12-21', 3, 'Second Reading', 'Romans 1:1-7'),
+    ('rcl', '2025-12-21', 4, 'Gospel', 'Matthew 1:18-25'),
+    ('rcl', '2025-12-25', 1, 'First Reading', 'Isaiah 9:2-7'),
+    ('rcl', '2025-12-25', 2, 'Psalm', 'Psalms 96'),
+    ('rcl', '2025-12-25', 3, 'Second Reading', 'Titus 2:11-14'),
+    ('rcl', '2025-12-25', 4, 'Gospel', 'Luke 2:1-20'),
+    ('catholic', '2025-11-30', 1, 'First Reading', 'Isaiah 2:1-5'),
+    ('catholic', '2025-11-30', 2, 'Responsorial Psalm', 'Psalms 122:1-9'),
+    ('catholic', '2025-11-30', 3, 'Second Reading', 'Romans 13:11-14'),
+    ('catholic', '2025-11-30', 4, 'Gospel', 'Matthew 24:37-44'),
+    ('catholic', '2025-12-25', 1, 'First Reading', 'Isaiah 9:2-7'),
+    ('catholic', '2025-12-25', 2, 'Responsorial Psalm', 'Psalms 96:1-3, 11-13'),
+    ('catholic', '2025-12-25', 3, 'Second Reading', 'Titus 2:11-14'),
+    ('catholic', '2025-12-25', 4, 'Gospel', 'Luke 2:1-14')
+ON CONFLICT DO NOTHING;
+
+-- Christmas Day 2025
+INSERT INTO office_readings (day, hour, position, label, reference) VALUES
+    ('2025-12-25', 'morning', 1, 'First Lesson', 'Isaiah 9:1-7'),
+    ('2025-12-25', 'morning', 2, 'Second Lesson', 'Luke 2:1-14'),
+    ('2025-12-25', 'evening', 1, 'First Lesson', 'Isaiah 7:10-16'),
+    ('2025-12-25', 'evening', 2, 'Second Lesson', 'Titus 3:4-8')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO people (name, description) VALUES
+    ('Aaron', 'Brother of Moses and the first high priest of Israel'),
+    ('Abraham', 'Patriarch called out of Haran, father of Isaac and Ishmael'),
+    ('Bathsheba', 'Wife of David and mother of Solomon'),
+    ('Boaz', 'Kinsman of Naomi who married Ruth'),
+    ('David', 'Shepherd who became the second king of Israel'),
+    ('Elijah', 'Prophet of Israel in the days of Ahab'),
+    ('Elisha', 'Prophet of Israel who followed Elijah'),
+    ('Esau', 'Elder son of Isaac, who sold his birthright to Jacob'),
+    ('Hagar', 'Handmaid of Sarah and mother of Ishmael'),
+    ('Isaac', 'Son of Abraham and Sarah, father of Esau and Jacob'),
+    ('Ishmael', 'Son of Abraham and Hagar'),
+    ('Jacob', 'Son of Isaac, renamed Israel, father of the twelve tribes'),
+    ('Jesse', 'Bethlehemite and father of David'),
+    ('Leah', 'Elder daughter of Laban and wife of Jacob'),
+    ('Miriam', 'Prophetess and sister of Moses and Aaron'),
+    ('Moses', 'Prophet who led Israel out of Egypt and received the law'),
+    ('Naomi', 'Mother in law of Ruth'),
+    ('Obed', 'Son of Boaz and Ruth, grandfather of David'),
+    ('Rachel', 'Younger daughter of Laban and wife of Jacob'),
+    ('Rebekah', 'Wife of Isaac and mother of Esau and Jacob'),
+    ('Ruth', 'Moabitess who followed Naomi to Bethlehem'),
+    ('Sarah', 'Wife of Abraham and mother of Isaac'),
+    ('Solomon', 'Son of David and Bathsheba, the third king of Israel')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO person_relationships (name, relationship, relative) VALUES
+    ('Abraham', 'spouse', 'Sarah'),
+    ('Isaac', 'father', 'Abraham'),
+    ('Isaac', 'mother', 'Sarah'),
+    ('Ishmael', 'father', 'Abraham'),
+    ('Ishmael', 'mother', 'Hagar'),
+    ('Isaac', 'sibling', 'Ishmael'),
+    ('Isaac', 'spouse', 'Rebekah'),
+    ('Esau', 'father', 'Isaac'),
+    ('Esau', 'mother', 'Rebekah'),
+    ('Jacob', 'father', 'Isaac'),
+    ('Jacob', 'mother', 'Rebekah'),
+    ('Esau', 'sibling', 'Jacob'),
+    ('Jacob', 'spouse', 'Leah'),
+    ('Jacob', 'spouse', 'Rachel'),
+    ('Leah', 'sibling', 'Rachel'),
+    ('Moses', 'sibling', 'Aaron'),
+    ('Moses', 'sibling', 'Miriam'),
+    ('Aaron', 'sibling', 'Miriam'),
+    ('Ruth', 'spouse', 'Boaz'),
+    ('Obed', 'father', 'Boaz'),
+    ('Obed', 'mother', 'Ruth'),
+    ('Jesse', 'father', 'Obed'),
+    ('David', 'father', 'Jesse'),
+    ('David', 'spouse', 'Bathsheba'),
+    ('Solomon', 'father', 'David'),
+    ('Solomon', 'mother', 'Bathsheba')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO person_appearances (name, position, label, reference) VALUES
+    ('Aaron', 1, 'Sent to speak for Moses', 'Exodus 4:14'),
+    ('Aaron', 2, 'Made a priest', 'Exodus 28:1'),
+    ('Abraham', 1, 'Called out of Haran', 'Genesis 12:1-4'),
+    ('Abraham', 2, 'The covenant', 'Genesis 15:5-6'),
+    ('Abraham', 3, 'The offering of Isaac', 'Genesis 22:1-2'),
+    ('Bathsheba', 1, 'Seen by David', '2 Samuel 11:2-3'),
+    ('Bathsheba', 2, 'Solomon made king', '1 Kings 1:28-31'),
+    ('Boaz', 1, 'Kinsman of Naomi', 'Ruth 2:1'),
+    ('Boaz', 2, 'Marries Ruth', 'Ruth 4:13'),
+    ('David', 1, 'Anointed', '1 Samuel 16:12-13'),
+    ('David', 2, 'Goliath', '1 Samuel 17:49-50'),
+    ('David', 3, 'King over Israel', '2 Samuel 5:3-4'),
+    ('Elijah', 1, 'The drought', '1 Kings 17:1'),
+    ('Elijah', 2, 'Taken up', '2 Kings 2:11'),
+    ('Elisha', 1, 'Called', '1 Kings 19:19'),
+    ('Elisha', 2, 'The mantle', '2 Kings 2:13-14'),
+    ('Esau', 1, 'Born', 'Genesis 25:25'),
+    ('Esau', 2, 'Sells his birthright', 'Genesis 25:33-34'),
+    ('Hagar', 1, 'Given to Abraham', 'Genesis 16:1-3'),
+    ('Hagar', 2, 'In the wilderness', 'Genesis 21:17'),
+    ('Isaac', 1, 'Born', 'Genesis 21:1-3'),
+    ('Isaac', 2, 'Marries Rebekah', 'Genesis 24:67'),
+    ('Ishmael', 1, 'Born', 'Genesis 16:15'),
+    ('Ishmael', 2, 'In the wilderness', 'Genesis 21:20'),
+    ('Jacob', 1, 'Born', 'Genesis 25:26'),
+    ('Jacob', 2, 'The dream at Bethel', 'Genesis 28:12-13'),
+    ('Jacob', 3, 'Renamed Israel', 'Genesis 32:28'),
+    ('Jesse', 1, 'Samuel sent to him', '1 Samuel 16:1'),
+    ('Leah', 1, 'Daughter of Laban', 'Genesis 29:16-17'),
+    ('Leah', 2, 'Given to Jacob', 'Genesis 29:23'),
+    ('Miriam', 1, 'The song of the sea', 'Exodus 15:20-21'),
+    ('Miriam', 2, 'Speaks against Moses', 'Numbers 12:1'),
+    ('Moses', 1, 'Born', 'Exodus 2:1-10'),
+    ('Moses', 2, 'The burning bush', 'Exodus 3:1-6'),
+    ('Moses', 3, 'The law', 'Exodus 20:1-3'),
+    ('Naomi', 1, 'Widowed in Moab', 'Ruth 1:1-3'),
+    ('Naomi', 2, 'Nurses Obed', 'Ruth 4:16-17'),
+    ('Obed', 1, 'Born', 'Ruth 4:17'),
+    ('Rachel', 1, 'Meets Jacob', 'Genesis 29:9-10'),
+    ('Rachel', 2, 'Dies on the way to Bethlehem', 'Genesis 35:19'),
+    ('Rebekah', 1, 'At the well', 'Genesis 24:15'),
+    ('Rebekah', 2, 'Bears twins', 'Genesis 25:21-23'),
+    ('Ruth', 1, 'Follows Naomi', 'Ruth 1:16-17'),
+    ('Ruth', 2, 'Marries Boaz', 'Ruth 4:13'),
+    ('Sarah', 1, 'Renamed', 'Genesis 17:15-16'),
+    ('Sarah', 2, 'Bears Isaac', 'Genesis 21:1-3'),
+    ('Solomon', 1, 'Born', '2 Samuel 12:24'),
+    ('Solomon', 2, 'Asks for wisdom', '1 Kings 3:9-12')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO places (name, modern_name, latitude, longitude, verse_references) VALUES
+    ('Antioch', 'Antakya', 36.2021, 36.1606, '{"Acts 11:26"}'),
+    ('Athens', NULL, 37.9715, 23.7257, '{"Acts 17:16"}'),
+    ('Babylon', 'near Hillah', 32.5364, 44.4209, '{"Daniel 1:1"}'),
+    ('Beersheba', NULL, 31.2448, 34.8408, '{"Genesis 21:31"}'),
+    ('Bethany', 'al-Eizariya', 31.7712, 35.2606, '{"John 11:1", "Mark 11:1"}'),
+    ('Bethel', 'Beitin', 31.9308, 35.2208, '{"Genesis 28:19"}'),
+    ('Bethlehem', NULL, 31.7054, 35.2024, '{"Micah 5:2", "Luke 2:4"}'),
+    ('Bethsaida', NULL, 32.9097, 35.6308, '{"Mark 8:22"}'),
+    ('Caesarea', NULL, 32.5000, 34.8920, '{"Acts 10:1"}'),
+    ('Cana', 'Kafr Kanna', 32.7469, 35.3386, '{"John 2:1"}'),
+    ('Capernaum', 'Kfar Nahum', 32.8803, 35.5733, '{"Matthew 4:13", "Mark 2:1", "John 6:59"}'),
+    ('Corinth', NULL, 37.9055, 22.8797, '{"Acts 18:1"}'),
+    ('Damascus', NULL, 33.5138, 36.2765, '{"Acts 9:3"}'),
+    ('Emmaus', 'Emmaus Nicopolis', 31.8389, 34.9892, '{"Luke 24:13"}'),
+    ('Ephesus', 'near Selçuk', 37.9395, 27.3417, '{"Acts 19:1", "Revelation 2:1"}'),
+    ('Gethsemane', NULL, 31.7794, 35.2397, '{"Matthew 26:36"}'),
+    ('Hebron', NULL, 31.5326, 35.0998, '{"Genesis 23:2", "2 Samuel 2:11"}'),
+    ('Jericho', NULL, 31.8711, 35.4442, '{"Joshua 6:1-2", "Luke 19:1"}'),
+    ('Jerusalem', NULL, 31.7784, 35.2354, '{"2 Samuel 5:6-7", "Luke 19:41"}'),
+    ('Joppa', 'Jaffa', 32.0504, 34.7522, '{"Jonah 1:3", "Acts 9:36"}'),
+    ('Nazareth', NULL, 32.7021, 35.2978, '{"Luke 1:26", "Matthew 2:23"}'),
+    ('Nineveh', 'Mosul', 36.3594, 43.1528, '{"Jonah 3:3"}'),
+    ('Philippi', NULL, 41.0136, 24.2867, '{"Acts 16:12"}'),
+    ('Rome', NULL, 41.8902, 12.4922, '{"Acts 28:16", "Romans 1:7"}'),
+    ('Samaria', 'Sebastia', 32.2764, 35.1970, '{"1 Kings 16:24"}'),
+    ('Shechem', 'Tell Balata', 32.2133, 35.2817, '{"Genesis 12:6", "Joshua 24:1"}'),
+    ('Tarsus', NULL, 36.9177, 34.8925, '{"Acts 9:11"}'),
+    ('Ur', 'Tell el-Muqayyar', 30.9626, 46.1032, '{"Genesis 11:31"}')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO timeline_events (name, reference, start_year, end_year) VALUES
+    ('The call of Abram', 'Genesis 12:1-4', -2091, -2091),
+    ('Joseph sold into Egypt', 'Genesis 37:26-28', -1898, -1898),
+    ('The Exodus', 'Exodus 12:40-41', -1446, -1446),
+    ('The wandering in the wilderness', 'Numbers 14:33-34', -1446, -1406),
+    ('The crossing of the Jordan', 'Joshua 3:14-17', -1406, -1406),
+    ('Saul anointed king', '1 Samuel 10:1', -1050, -1050),
+    ('The reign of David', '2 Samuel 5:3-5', -1010, -970),
+    ('The temple of Solomon begun', '1 Kings 6:1', -966, -966),
+    ('The kingdom divided', '1 Kings 12:16-20', -931, -931),
+    ('Elijah on mount Carmel', '1 Kings 18:36-39', -860, -860),
+    ('The fall of Samaria', '2 Kings 17:5-6', -722, -722),
+    ('The fall of Jerusalem', '2 Kings 25:8-10', -586, -586),
+    ('The return under Cyrus', 'Ezra 1:1-3', -538, -538),
+    ('The temple rebuilt', 'Ezra 6:15', -516, -516),
+    ('The wall of Jerusalem rebuilt', 'Nehemiah 6:15', -445, -445),
+    ('The birth of Jesus', 'Luke 2:1-7', -6, -4),
+    ('The baptism of Jesus', 'Luke 3:21-23', 26, 27),
+    ('The crucifixion and resurrection', 'Luke 24:1-7', 30, 33),
+    ('Pentecost', 'Acts 2:1-4', 30, 33),
+    ('The conversion of Saul', 'Acts 9:3-6', 34, 35),
+    ('The council at Jerusalem', 'Acts 15:6-11', 49, 50),
+    ('Paul in Rome', 'Acts 28:16', 60, 62)
+ON CONFLICT DO NOTHING;
+
+-- Genesis 1:1
+INSERT INTO source_tokens (title, chapter_num, verse_num, position, language, text, lemma, strongs, morphology) VALUES
+    ('Genesis', 1, 1, 1, 'hbo', 'בְּרֵאשִׁית', 'רֵאשִׁית', 'H7225', NULL),
+    ('Genesis', 1, 1, 2, 'hbo', 'בָּרָא', 'בָּרָא', 'H1254', NULL),
+    ('Genesis', 1, 1, 3, 'hbo', 'אֱלֹהִים', 'אֱלֹהִים', 'H430', NULL),
+    ('Genesis', 1, 1, 4, 'hbo', 'אֵת', 'אֵת', 'H853', NULL),
+    ('Genesis', 1, 1, 5, 'hbo', 'הַשָּׁמַיִם', 'שָׁמַיִם', 'H8064', NULL),
+    ('Genesis', 1, 1, 6, 'hbo', 'וְאֵת', 'אֵת', 'H853', NULL),
+    ('Genesis', 1, 1, 7, 'hbo', 'הָאָרֶץ', 'אֶרֶץ', 'H776', NULL)
+ON CONFLICT DO NOTHING;
+
+INSERT INTO word_alignments (translation, title, chapter_num, verse_num, word_index, word, position) VALUES
+    ('kjv', 'Genesis', 1, 1, 0, 'In', 1),
+    ('kjv', 'Genesis', 1, 1, 2, 'beginning', 1),
+    ('kjv', 'Genesis', 1, 1, 3, 'God', 3),
+    ('kjv', 'Genesis', 1, 1, 4, 'created', 2),
+    ('kjv', 'Genesis', 1, 1, 5, 'the', 5),
+    ('kjv', 'Genesis', 1, 1, 6, 'heaven', 5),
+    ('kjv', 'Genesis', 1, 1, 7, 'and', 6),
+    ('kjv', 'Genesis', 1, 1, 8, 'the', 7),
+    ('kjv', 'Genesis', 1, 1, 9, 'earth', 7)
+ON CONFLICT DO NOTHING;
+
+-- John 1:1
+INSERT INTO source_tokens (title, chapter_num, verse_num, position, language, text, lemma, strongs, morphology) VALUES
+    ('John', 1, 1, 1, 'grc', 'Ἐν', 'ἐν', 'G1722', 'PREP'),
+    ('John', 1, 1, 2, 'grc', 'ἀρχῇ', 'ἀρχή', 'G746', 'N-DSF'),
+    ('John', 1, 1, 3, 'grc', 'ἦν', 'εἰμί', 'G1510', 'V-IAI-3S'),
+    ('John', 1, 1, 4, 'grc', 'ὁ', 'ὁ', 'G3588', 'T-NSM'),
+    ('John', 1, 1, 5, 'grc', 'λόγος', 'λόγος', 'G3056', 'N-NSM'),
+    ('John', 1, 1, 6, 'grc', 'καὶ', 'καί', 'G2532', 'CONJ'),
+    ('John', 1, 1, 7, 'grc', 'ὁ', 'ὁ', 'G3588', 'T-NSM'),
+    ('John', 1, 1, 8, 'grc', 'λόγος', 'λόγος', 'G3056', 'N-NSM'),
+    ('John', 1, 1, 9, 'grc', 'ἦν', 'εἰμί', 'G1510', 'V-IAI-3S'),
+    ('John', 1, 1, 10, 'grc', 'πρὸς', 'πρός', 'G4314', 'PREP'),
+    ('John', 1, 1, 11, 'grc', 'τὸν', 'ὁ', 'G3588', 'T-ASM'),
+    ('John', 1, 1, 12, 'grc', 'θεόν', 'θεός', 'G2316', 'N-ASM'),
+    ('John', 1, 1, 13, 'grc', 'καὶ', 'καί', 'G2532', 'CONJ'),
+    ('John', 1, 1, 14, 'grc', 'θεὸς', 'θεός', 'G2316', 'N-NSM'),
+    ('John', 1, 1, 15, 'grc', 'ἦν', 'εἰμί', 'G1510', 'V-IAI-3S'),
+    ('John', 1, 1, 16, 'grc', 'ὁ', 'ὁ', 'G3588', 'T-NSM'),
+    ('John', 1, 1, 17, 'grc', 'λόγος', 'λόγος', 'G3056', 'N-NSM')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO word_alignments (translation, title, chapter_num, verse_num, word_index, word, position) VALUES
+    ('kjv', 'John', 1, 1, 0, 'In', 1),
+    ('kjv', 'John', 1, 1, 2, 'beginning', 2),
+    ('kjv', 'John', 1, 1, 3, 'was', 3),
+    ('kjv', 'John', 1, 1, 4, 'the', 4),
+    ('kjv', 'John', 1, 1, 5, 'Word', 5),
+    ('kjv', 'John', 1, 1, 6, 'and', 6),
+    ('kjv', 'John', 1, 1, 7, 'the', 7),
+    ('kjv', 'John', 1, 1, 8, 'Word', 8),
+    ('kjv', 'John', 1, 1, 9, 'was', 9),
+    ('kjv', 'John', 1, 1, 10, 'with', 10),
+    ('kjv', 'John', 1, 1, 11, 'God', 11),
+    ('kjv', 'John', 1, 1, 11, 'God', 12),
+    ('kjv', 'John', 1, 1, 12, 'and', 13),
+    ('kjv', 'John', 1, 1, 13, 'the', 16),
+    ('kjv', 'John', 1, 1, 14, 'Word', 17),
+    ('kjv', 'John', 1, 1, 15, 'was', 15),
+    ('kjv', 'John', 1, 1, 16, 'God', 14)
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,5 @@
+-- Some translations join verses into one where the text can not be split
+-- between them (ex: \v 4-5 in USFM). The joined text is kept once, at the
+-- first verse, and bridged_to is the last verse it covers. It is NULL for a
+-- verse that stands alone.
+ALTER TABLE public.verses ADD COLUMN IF NOT EXISTS bridged_to INTEGER;
@@ -0,0 +1,12 @@
+-- The deuterocanonical books some canons hold have longer titles than the
+-- 66 books (ex: Prayer of Manasseh), so every title is given room for them.
+-- Their books and chapters are added when a translation that has them is
+-- imported, as for any other book.
+ALTER TABLE public.books ALTER COLUMN title TYPE varchar(32);
+ALTER TABLE public.chapters ALTER COLUMN title TYPE varchar(32);
+ALTER TABLE public.verses ALTER COLUMN title TYPE varchar(32);
+ALTER TABLE public.verse_views ALTER COLUMN title TYPE varchar(32);
+ALTER TABLE public.chapter_audio ALTER COLUMN title TYPE varchar(32);
+ALTER TABLE public.verse_timings ALTER COLUMN title TYPE varchar(32);
+ALTER TABLE public.source_tokens ALTER COLUMN title TYPE varchar(32);
+ALTER TABLE public.word_alignments ALTER COLUMN title TYPE varchar(32);
@@ -0,0 +1,6 @@
+-- Only the SHA-256 of an API key is kept, in hex, so a copy of the database
+-- does not give away the keys. The keys already made are hashed in place,
+-- and the saved searches made with them follow through the foreign key.
+ALTER TABLE public.api_keys RENAME COLUMN api_key TO key_hash;
+ALTER TABLE public.saved_searches RENAME COLUMN api_key TO key_hash;
+UPDATE public.api_keys SET key_hash = encode(sha256(convert_to(key_hash, 'UTF8')), 'hex');
@@ -0,0 +1,28 @@
+-- An account is whoever signed in with Google, GitHub or Apple. Each of the
+-- identities it signed in with is linked to it, by the provider and the id
+-- the provider gives the user (the subject). The email is only kept when
+-- the provider says it was verified, and a new identity with the same email
+-- is linked to the account that has it.
+CREATE TABLE IF NOT EXISTS public.accounts (
+    account_id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS public.account_identities (
+    provider varchar(10) NOT NULL CHECK (provider IN ('google', 'github', 'apple')),
+    subject TEXT NOT NULL,
+    account_id BIGINT NOT NULL REFERENCES accounts(account_id) ON DELETE CASCADE,
+    email TEXT,
+    linked_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    signed_in_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+	PRIMARY KEY(provider, subject)
+);
+
+CREATE INDEX IF NOT EXISTS account_identities_email_idx ON account_identities (lower(email))
+    WHERE email IS NOT NULL;
+
+-- Each sign in makes a new API key for the account and revokes the one from
+-- the sign in before, so an account has one key at a time and the app sends
+-- the newest one. The keys made by hand belong to no account.
+ALTER TABLE public.api_keys ADD COLUMN IF NOT EXISTS account_id BIGINT
+    REFERENCES accounts(account_id) ON DELETE CASCADE;
@@ -0,0 +1,309 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audit::{self, AuditAction},
+    auth::{Admin, Editor, Reader, RequireRole, Service},
+    book::get_title,
+    cdn::{self, get_book_key, get_translation_key},
+    db::get_default_translation,
+    error::BibleApiError,
+    internal_error,
+    reindex::ReindexStatus,
+    state::AppState,
+    stats::UsageReport,
+    versions::{self, TranslationVersion},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeRequest {
+    translation: String,
+    book: Option<String>,
+}
+
+/// The purge handler serves POST /admin/purge. It purges a whole translation
+/// from the CDN, or a single book of it when a book is given.
+pub async fn purge(
+    RequireRole { actor, .. }: RequireRole<Editor>,
+    State(state): State<AppState>,
+    Json(request): Json<PurgeRequest>,
+) -> Result<StatusCode, BibleApiError> {
+    let cdn_config = state.cdn.as_ref().ok_or(BibleApiError::from((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "CDN purging is not configured".to_string(),
+    )))?;
+
+    let keys = get_invalidation_keys(Some(&request.translation), request.book.as_deref(), &[])?;
+
+    cdn::purge(cdn_config, &keys)
+        .await
+        .map_err(|err| BibleApiError::from((StatusCode::BAD_GATEWAY, err)))?;
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::Purge,
+        &request.translation,
+        &keys.join(","),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The translation_versions handler serves
+/// GET /admin/translations/:translation/versions with every import of the
+/// translation, newest first.
+pub async fn translation_versions(
+    _: RequireRole<Reader>,
+    State(state): State<AppState>,
+    Path(translation): Path<String>,
+) -> Result<Json<Vec<TranslationVersion>>, BibleApiError> {
+    versions::get_versions(&state.pool, &translation.to_lowercase())
+        .await
+        .map(Json)
+}
+
+/// The rollback handler serves POST /admin/translations/:translation/rollback.
+/// It goes back to the previous import of the translation, for when the
+/// latest one turns out to be bad, and returns the version now being served.
+/// What is cached of the version it replaces is dropped, from the passage
+/// cache and the CDN.
+pub async fn rollback(
+    RequireRole { actor, .. }: RequireRole<Admin>,
+    State(state): State<AppState>,
+    Path(translation): Path<String>,
+) -> Result<Json<TranslationVersion>, BibleApiError> {
+    let translation = translation.to_lowercase();
+    let version = versions::rollback(&state.pool, &translation).await?;
+    versions::clear_cached(state.breaker.cache(), state.cdn.as_ref(), &translation).await;
+
+    let details = format!("version {}", version.version);
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::Rollback,
+        &translation,
+        &details,
+    )
+    .await;
+
+    Ok(Json(version))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InvalidateRequest {
+    translation: Option<String>,
+    book: Option<String>,
+}
+
+/// The InvalidateResponse lists the keys that were invalidated and every
+/// cache they were cleared from.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct InvalidateResponse {
+    pub keys: Vec<String>,
+    pub cleared: Vec<&'static str>,
+}
+
+/// The invalidate handler serves POST /admin/cache/invalidate, which is used
+/// when content is corrected after an import. A book, a translation, or both
+/// narrow what is cleared, and an empty body flushes everything. The passage
+/// cache is cleared before the CDN, so the CDN does not fetch the old verses
+/// again, and each is skipped when it is not configured.
+pub async fn invalidate(
+    RequireRole { actor, .. }: RequireRole<Editor>,
+    State(state): State<AppState>,
+    request: Option<Json<InvalidateRequest>>,
+) -> Result<Json<InvalidateResponse>, BibleApiError> {
+    let Json(request) = request.unwrap_or_default();
+    let active = match request.translation {
+        Some(_) => vec![],
+        None => versions::get_active_versions(&state.pool)
+            .await
+            .map_err(internal_error)?
+            .into_iter()
+            .map(|version| version.translation)
+            .collect(),
+    };
+    let keys = get_invalidation_keys(
+        request.translation.as_deref(),
+        request.book.as_deref(),
+        &active,
+    )?;
+    let mut cleared = Vec::new();
+
+    let cache = state.breaker.cache();
+    if cache.backend_name().is_some() {
+        let translation = request.translation.as_deref().map(str::to_lowercase);
+        let title = request.book.as_deref().and_then(get_title);
+        cache
+            .invalidate(translation.as_deref(), title.as_deref())
+            .await
+            .map_err(|err| BibleApiError::from((StatusCode::BAD_GATEWAY, err)))?;
+        cleared.push("passages");
+    }
+
+    if let Some(cdn_config) = state.cdn.as_ref() {
+        cdn::purge(cdn_config, &keys)
+            .await
+            .map_err(|err| BibleApiError::from((StatusCode::BAD_GATEWAY, err)))?;
+        cleared.push("cdn");
+    }
+
+    tracing::info!("invalidated {:?} in {:?}", keys, cleared);
+    let target = request
+        .translation
+        .as_deref()
+        .unwrap_or(get_default_translation());
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::Invalidate,
+        target,
+        &keys.join(","),
+    )
+    .await;
+
+    Ok(Json(InvalidateResponse { keys, cleared }))
+}
+
+// The get_invalidation_keys function returns the surrogate keys covering the
+// scope. Leaving out the translation means every translation being served,
+// which are the active ones.
+fn get_invalidation_keys(
+    translation: Option<&str>,
+    book: Option<&str>,
+    active: &[String],
+) -> Result<Vec<String>, BibleApiError> {
+    let translations = match translation {
+        Some(translation) => vec![translation],
+        None => active.iter().map(String::as_str).collect(),
+    };
+
+    match book {
+        Some(book) => {
+            let title = get_title(book).ok_or(BibleApiError::from((
+                StatusCode::NOT_FOUND,
+                "No Matching Book Found".to_string(),
+            )))?;
+
+            Ok(translations
+                .into_iter()
+                .map(|translation| get_book_key(translation, &title))
+                .collect())
+        }
+        None => Ok(translations.into_iter().map(get_translation_key).collect()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReindexRequest {
+    translation: String,
+}
+
+/// The reindex handler serves POST /admin/reindex. It starts rebuilding the
+/// full text search vectors of a translation in the background, which is
+/// needed after a bulk import, and returns 202 with the starting status.
+pub async fn reindex(
+    RequireRole { actor, .. }: RequireRole<Service>,
+    State(state): State<AppState>,
+    Json(request): Json<ReindexRequest>,
+) -> Result<(StatusCode, Json<ReindexStatus>), BibleApiError> {
+    if !request
+        .translation
+        .eq_ignore_ascii_case(get_default_translation())
+    {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "No Matching Translation Found".to_string(),
+        )
+            .into());
+    }
+
+    let status = state
+        .reindex
+        .start(state.pool.clone(), get_default_translation())
+        .map_err(|err| BibleApiError::from((StatusCode::CONFLICT, err)))?;
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::Reindex,
+        get_default_translation(),
+        "",
+    )
+    .await;
+
+    Ok((StatusCode::ACCEPTED, Json(status)))
+}
+
+/// The reindex_status handler serves GET /admin/reindex with the progress of
+/// the latest reindex.
+pub async fn reindex_status(
+    _: RequireRole<Reader>,
+    State(state): State<AppState>,
+) -> Result<Json<ReindexStatus>, BibleApiError> {
+    state.reindex.status().map(Json).ok_or(BibleApiError::from((
+        StatusCode::NOT_FOUND,
+        "no reindex has been started".to_string(),
+    )))
+}
+
+/// The stats handler serves GET /admin/stats with the usage counts kept since
+/// the server started. It is a 404 unless USAGE_STATS is turned on.
+pub async fn stats(
+    _: RequireRole<Reader>,
+    State(state): State<AppState>,
+) -> Result<Json<UsageReport>, BibleApiError> {
+    state.stats.report().map(Json).ok_or(BibleApiError::from((
+        StatusCode::NOT_FOUND,
+        "usage stats are disabled (set USAGE_STATS=true)".to_string(),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_active() -> Vec<String> {
+        vec![String::from("kjv"), String::from("web")]
+    }
+
+    #[test]
+    fn get_invalidation_keys_narrows_to_a_book_of_a_translation() {
+        assert_eq!(
+            get_invalidation_keys(Some("KJV"), Some("jn"), &get_active()).ok(),
+            Some(vec![String::from("kjv-book-john")])
+        );
+    }
+
+    #[test]
+    fn get_invalidation_keys_covers_every_active_translation_by_default() {
+        assert_eq!(
+            get_invalidation_keys(None, None, &get_active()).ok(),
+            Some(vec![
+                String::from("translation-kjv"),
+                String::from("translation-web")
+            ])
+        );
+        assert_eq!(
+            get_invalidation_keys(None, Some("1 John"), &get_active()).ok(),
+            Some(vec![
+                String::from("kjv-book-1-john"),
+                String::from("web-book-1-john")
+            ])
+        );
+    }
+
+    #[test]
+    fn get_invalidation_keys_rejects_an_unknown_book() {
+        assert_eq!(
+            get_invalidation_keys(None, Some("Book of Robert"), &get_active())
+                .unwrap_err()
+                .status_code(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+
+use crate::{
+    annotate::group_by_verse,
+    db::{get_default_translation, SearchResult},
+};
+
+/// The SourceToken is a word of the Hebrew, Aramaic or Greek text a verse was
+/// translated from. The position counts the words of the verse from 1, and
+/// the language is an ISO 639-3 code (ex: grc). The morphology is only given
+/// where it is known.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct SourceToken {
+    pub position: i16,
+    pub language: String,
+    pub text: String,
+    pub lemma: String,
+    pub strongs: String,
+    pub morphology: Option<String>,
+}
+
+/// The AlignedWord is a word of the translation with the source tokens it
+/// renders. The index counts the words of the verse's plain text from 0, so
+/// it is the same whatever format the text is returned in.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AlignedWord {
+    pub index: i16,
+    pub word: String,
+    pub tokens: Vec<SourceToken>,
+}
+
+/// The get_alignment function finds the aligned words of each verse, in the
+/// order of the verses. A verse that has not been aligned has no words.
+pub async fn get_alignment(
+    pool: &PgPool,
+    results: &[SearchResult],
+) -> Result<Vec<Vec<AlignedWord>>, sqlx::Error> {
+    let titles = results
+        .iter()
+        .map(|result| result.title.clone())
+        .collect::<Vec<String>>();
+    let chapters = results
+        .iter()
+        .map(|result| result.chapter)
+        .collect::<Vec<i32>>();
+    let verses = results
+        .iter()
+        .map(|result| result.verse)
+        .collect::<Vec<i32>>();
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT v.index as "index!", a.word_index, a.word, t.position, t.language,
+                   t.text, t.lemma, t.strongs, t.morphology
+            FROM unnest($1::text[], $2::int[], $3::int[]) WITH ORDINALITY
+                    AS v(title, chapter, verse, index)
+                INNER JOIN word_alignments a ON a.title = v.title
+                    AND a.chapter_num = v.chapter
+                    AND a.verse_num = v.verse
+                INNER JOIN source_tokens t ON t.title = a.title
+                    AND t.chapter_num = a.chapter_num
+                    AND t.verse_num = a.verse_num
+                    AND t.position = a.position
+            WHERE a.translation = $4
+          ORDER BY v.index, a.word_index, t.position
+        "#,
+        &titles,
+        &chapters,
+        &verses,
+        get_default_translation(),
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let tokens = rows.into_iter().map(|row| {
+        let token = SourceToken {
+            position: row.position,
+            language: row.language,
+            text: row.text,
+            lemma: row.lemma,
+            strongs: row.strongs,
+            morphology: row.morphology,
+        };
+        // The ordinality counts from 1
+        (row.index as usize - 1, (row.word_index, row.word, token))
+    });
+
+    Ok(group_by_verse(results.len(), tokens)
+        .into_iter()
+        .map(group_by_word)
+        .collect())
+}
+
+// Gather the tokens of a verse under the words they are aligned to, given
+// the tokens in the order of the words
+fn group_by_word(tokens: Vec<(i16, String, SourceToken)>) -> Vec<AlignedWord> {
+    let mut words: Vec<AlignedWord> = vec![];
+
+    for (index, word, token) in tokens {
+        match words.last_mut() {
+            Some(last) if last.index == index => last.tokens.push(token),
+            _ => words.push(AlignedWord {
+                index,
+                word,
+                tokens: vec![token],
+            }),
+        }
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(position: i16, text: &str) -> SourceToken {
+        SourceToken {
+            position,
+            language: String::from("grc"),
+            text: text.to_owned(),
+            lemma: text.to_owned(),
+            strongs: String::from("G0"),
+            morphology: None,
+        }
+    }
+
+    #[test]
+    fn group_by_word_keeps_every_token_of_a_word() {
+        let words = group_by_word(vec![
+            (10, String::from("with"), token(10, "πρὸς")),
+            (11, String::from("God"), token(11, "τὸν")),
+            (11, String::from("God"), token(12, "θεόν")),
+        ]);
+
+        assert_eq!(
+            words,
+            [
+                AlignedWord {
+                    index: 10,
+                    word: String::from("with"),
+                    tokens: vec![token(10, "πρὸς")],
+                },
+                AlignedWord {
+                    index: 11,
+                    word: String::from("God"),
+                    tokens: vec![token(11, "τὸν"), token(12, "θεόν")],
+                },
+            ]
+        );
+    }
+}
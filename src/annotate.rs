@@ -0,0 +1,177 @@
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use std::str::FromStr;
+
+use crate::{
+    alignment::{self, AlignedWord},
+    db::SearchResult,
+    error::BibleApiError,
+    internal_error,
+    people::{self, PersonLink},
+    places::{self, Place},
+};
+
+/// The Include is the annotations added to each verse of a JSON response,
+/// named in the include parameter and separated by commas (ex: places).
+/// - places are the places named in the verse, with their coordinates
+/// - people are links to the people named in the verse
+/// - alignment is the source tokens behind each word, where it is known
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Include {
+    pub places: bool,
+    pub people: bool,
+    pub alignment: bool,
+}
+
+impl Include {
+    /// The is_empty function returns true when no annotations were asked for.
+    pub fn is_empty(self) -> bool {
+        self == Include::default()
+    }
+}
+
+impl FromStr for Include {
+    type Err = String;
+
+    fn from_str(include: &str) -> Result<Self, Self::Err> {
+        let mut annotations = Include::default();
+
+        for name in include
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match name.to_lowercase().as_str() {
+                "places" => annotations.places = true,
+                "people" => annotations.people = true,
+                "alignment" => annotations.alignment = true,
+                other => {
+                    return Err(format!(
+                        "Unknown include: {} (ex: places, people or alignment)",
+                        other
+                    ))
+                }
+            }
+        }
+
+        Ok(annotations)
+    }
+}
+
+/// The AnnotatedVerse is a verse with the annotations asked for. An
+/// annotation that was not asked for is left out.
+#[derive(Debug, Serialize)]
+pub struct AnnotatedVerse {
+    #[serde(flatten)]
+    pub verse: SearchResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub places: Option<Vec<Place>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub people: Option<Vec<PersonLink>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<Vec<AlignedWord>>,
+}
+
+/// The annotate function adds the annotations asked for to each verse.
+pub async fn annotate(
+    pool: &PgPool,
+    results: Vec<SearchResult>,
+    include: Include,
+) -> Result<Vec<AnnotatedVerse>, BibleApiError> {
+    let mut places = match include.places {
+        true => Some(
+            places::get_mentions(pool, &results)
+                .await
+                .map_err(internal_error)?
+                .into_iter(),
+        ),
+        false => None,
+    };
+    let mut people = match include.people {
+        true => Some(
+            people::get_mentions(pool, &results)
+                .await
+                .map_err(internal_error)?
+                .into_iter(),
+        ),
+        false => None,
+    };
+    let mut alignment = match include.alignment {
+        true => Some(
+            alignment::get_alignment(pool, &results)
+                .await
+                .map_err(internal_error)?
+                .into_iter(),
+        ),
+        false => None,
+    };
+
+    Ok(results
+        .into_iter()
+        .map(|verse| AnnotatedVerse {
+            verse,
+            places: places.as_mut().and_then(Iterator::next),
+            people: people.as_mut().and_then(Iterator::next),
+            alignment: alignment.as_mut().and_then(Iterator::next),
+        })
+        .collect())
+}
+
+/// The group_by_verse function gathers what was found in each verse, given
+/// the index of the verse each mention was found in.
+pub fn group_by_verse<T>(
+    verse_count: usize,
+    mentions: impl IntoIterator<Item = (usize, T)>,
+) -> Vec<Vec<T>> {
+    let mut verses = (0..verse_count).map(|_| vec![]).collect::<Vec<Vec<T>>>();
+
+    for (index, mention) in mentions {
+        if let Some(verse) = verses.get_mut(index) {
+            verse.push(mention);
+        }
+    }
+
+    verses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include_takes_a_list_of_annotations() {
+        assert_eq!(
+            " Places, ".parse::<Include>(),
+            Ok(Include {
+                places: true,
+                ..Include::default()
+            })
+        );
+        assert_eq!(
+            "people,places".parse::<Include>(),
+            Ok(Include {
+                places: true,
+                people: true,
+                alignment: false
+            })
+        );
+        assert!("alignment".parse::<Include>().unwrap().alignment);
+        assert!("".parse::<Include>().unwrap().is_empty());
+        assert!("places,maps".parse::<Include>().is_err());
+    }
+
+    #[test]
+    fn group_by_verse_keeps_verses_without_mentions() {
+        let mentions = [
+            (0, "Bethlehem"),
+            (0, "Jerusalem"),
+            (2, "Nazareth"),
+            (5, "Rome"),
+        ];
+
+        assert_eq!(
+            group_by_verse(3, mentions),
+            [vec!["Bethlehem", "Jerusalem"], vec![], vec!["Nazareth"]]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::{
+    digest::{digest, SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use crate::{
+    audit::{self, AuditAction},
+    auth::{mask_key, Admin, RequireRole},
+    error::BibleApiError,
+    internal_error,
+    state::AppState,
+};
+
+/// The KEY_BYTES is how many random bytes a key is made from. They are sent
+/// as 32 characters of URL safe base64.
+const KEY_BYTES: usize = 24;
+
+/// The DEFAULT_TIER is the tier of a key made without one.
+pub const DEFAULT_TIER: &str = "free";
+
+#[derive(Debug, Deserialize)]
+pub struct CreateKeyRequest {
+    tier: Option<String>,
+    #[serde(default)]
+    label: String,
+}
+
+/// The CreatedKey is a key that was just made. This is the only time the key
+/// is sent, so it has to be kept by whoever asked for it. The key_id is the
+/// hash of the key (see hash_key), which names it to revoke it without
+/// sending the key itself.
+#[derive(Debug, Serialize)]
+pub struct CreatedKey {
+    pub api_key: String,
+    pub key_id: String,
+    pub tier: String,
+    pub label: String,
+    pub created_at: String,
+}
+
+/// The create_key handler serves POST /admin/keys. It makes a new API key of
+/// a tier (free by default), with a label saying who or what it is for
+/// (ex: {"tier": "pro", "label": "reading plan app"}). The key can be used
+/// in X-Api-Key straight away. Only its hash is stored (see hash_key).
+pub async fn create_key(
+    RequireRole { actor, .. }: RequireRole<Admin>,
+    State(state): State<AppState>,
+    Json(request): Json<CreateKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedKey>), BibleApiError> {
+    let tier = request
+        .tier
+        .map(|tier| tier.trim().to_lowercase())
+        .unwrap_or_else(|| DEFAULT_TIER.to_owned());
+
+    let known = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM rate_limit_tiers WHERE tier = $1) as "known!""#,
+        tier
+    )
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal_error)?;
+    if !known {
+        return Err((StatusCode::BAD_REQUEST, format!("Unknown tier: {}", tier)).into());
+    }
+
+    let api_key = generate_key()?;
+    let key_id = hash_key(&api_key);
+    let row = sqlx::query!(
+        r#"
+            INSERT INTO api_keys (key_hash, tier, label) VALUES ($1, $2, $3)
+            RETURNING tier, label,
+                      to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as "created_at!"
+        "#,
+        key_id,
+        tier,
+        request.label.trim(),
+    )
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal_error)?;
+    let created = CreatedKey {
+        api_key,
+        key_id,
+        tier: row.tier,
+        label: row.label,
+        created_at: row.created_at,
+    };
+
+    refresh_limits(&state).await;
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::CreateKey,
+        &mask_key(&created.key_id),
+        &format!("tier {}, {}", created.tier, created.label),
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// The revoke_key handler serves DELETE /admin/keys/:key_id, with the
+/// key_id given when the key was made, so the key itself is never in a URL
+/// (and so in a log). The key stops being accepted straight away, but is
+/// kept along with the saved searches made with it.
+pub async fn revoke_key(
+    RequireRole { actor, .. }: RequireRole<Admin>,
+    State(state): State<AppState>,
+    Path(key_id): Path<String>,
+) -> Result<StatusCode, BibleApiError> {
+    let revoked = sqlx::query!(
+        "UPDATE api_keys SET revoked_at = now() WHERE key_hash = $1 AND revoked_at IS NULL",
+        key_id.to_lowercase()
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(internal_error)?;
+    if revoked.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            "No Matching API Key Found".to_string(),
+        )
+            .into());
+    }
+
+    refresh_limits(&state).await;
+    audit::record(
+        &state.pool,
+        &actor,
+        AuditAction::RevokeKey,
+        &mask_key(&key_id),
+        "",
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The hash_key function returns the SHA-256 of an API key, in hex. Only the
+/// hash is kept in the database, so the keys can not be read back from it.
+pub fn hash_key(api_key: &str) -> String {
+    digest(&SHA256, api_key.as_bytes())
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hash, byte| {
+            let _ = write!(hash, "{:02x}", byte);
+            hash
+        })
+}
+
+// Apply a change to the keys now, rather than at the next refresh. If this
+// fails the change still applies then.
+async fn refresh_limits(state: &AppState) {
+    if let Err(err) = state.rate_limiter.refresh(&state.pool).await {
+        tracing::warn!("could not reload the API keys: {}", err);
+    }
+}
+
+/// The generate_key function makes a new random API key, of KEY_BYTES.
+pub fn generate_key() -> Result<String, BibleApiError> {
+    let mut bytes = [0u8; KEY_BYTES];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| {
+        BibleApiError::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "could not generate a key".to_string(),
+        ))
+    })?;
+
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_key_makes_a_new_url_safe_key_each_time() {
+        let key = generate_key().unwrap();
+
+        assert_eq!(key.len(), 32);
+        assert!(key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_ne!(key, generate_key().unwrap());
+    }
+
+    #[test]
+    fn hash_key_is_the_sha256_of_the_key() {
+        assert_eq!(
+            hash_key("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(hash_key("abc"), hash_key("abd"));
+    }
+}
@@ -0,0 +1,379 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use futures::stream;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+use crate::{
+    db::get_default_translation,
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error, parse,
+    search::{get_reference_with, is_whole_chapter_with, search_with, BibleSearch},
+    state::AppState,
+    validation::{check_reference, check_text, MAX_TRANSLATION_LEN},
+    verse_id::VerseIds,
+    versification::Versification,
+};
+
+/// The AUDIO_START_HEADER and AUDIO_END_HEADER give where the passage starts
+/// and ends in the chapter's file, in seconds, when the timings are loaded.
+pub const AUDIO_START_HEADER: &str = "x-audio-start";
+pub const AUDIO_END_HEADER: &str = "x-audio-end";
+
+// The request headers passed on to the file, so it can be read a range at a
+// time, and the response headers passed back
+const FORWARDED_REQUEST_HEADERS: [HeaderName; 2] = [header::RANGE, header::IF_RANGE];
+const FORWARDED_RESPONSE_HEADERS: [HeaderName; 6] = [
+    header::CONTENT_TYPE,
+    header::CONTENT_LENGTH,
+    header::CONTENT_RANGE,
+    header::ACCEPT_RANGES,
+    header::ETAG,
+    header::LAST_MODIFIED,
+];
+
+#[derive(Debug, Deserialize)]
+pub struct AudioParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    query: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    translation: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    redirect: Option<bool>,
+}
+
+/// The VerseTiming is where a verse starts and ends in its chapter's file,
+/// in seconds.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct VerseTiming {
+    #[serde(flatten)]
+    pub ids: VerseIds,
+    pub verse: i32,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// The AudioTimings are the file a passage is read in and where its verses
+/// are. The start and end are only given when the passage is not the whole
+/// chapter and the timings of its verses are loaded.
+#[derive(Debug, Serialize)]
+pub struct AudioTimings {
+    pub reference: String,
+    pub href: String,
+    pub media_type: String,
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    pub verses: Vec<VerseTiming>,
+}
+
+// The chapter's file and the timings of the verses searched for
+struct PassageAudio {
+    url: String,
+    media_type: String,
+    timings: Vec<VerseTiming>,
+    is_whole_chapter: bool,
+}
+
+impl PassageAudio {
+    // Where the passage starts and ends in the file
+    fn get_span(&self) -> Option<(f64, f64)> {
+        if self.is_whole_chapter {
+            return None;
+        }
+
+        let start = self
+            .timings
+            .iter()
+            .map(|timing| timing.start)
+            .reduce(f64::min)?;
+        let end = self
+            .timings
+            .iter()
+            .map(|timing| timing.end)
+            .reduce(f64::max)?;
+        Some((start, end))
+    }
+}
+
+/// The audio handler serves /audio (ex: /audio?query=Psalm 23) with the
+/// recording of the chapter the passage is in, in the translation asked for
+/// or the default one. A passage has to be within one chapter. The file is
+/// passed through with any Range asked for, so players can seek in it, and
+/// where the passage starts and ends is given in the x-audio-start and
+/// x-audio-end headers. With redirect=true the client is sent to the file
+/// instead, with the passage as a media fragment (ex: #t=12.5,40).
+pub async fn audio(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<AudioParams>,
+) -> Result<Response, Response> {
+    let (_, audio) = find_audio(&state, params.query, params.translation).await?;
+    let span = audio.get_span();
+
+    if params.redirect.unwrap_or(false) {
+        let location = match span {
+            Some((start, end)) => format!("{}#t={},{}", audio.url, start, end),
+            None => audio.url,
+        };
+        return Ok(Redirect::temporary(&location).into_response());
+    }
+
+    let mut request = state.http.get(&audio.url);
+    for name in FORWARDED_REQUEST_HEADERS {
+        if let Some(value) = headers.get(&name) {
+            request = request.header(name.as_str(), value.as_bytes());
+        }
+    }
+    let file = request
+        .send()
+        .await
+        .map_err(|err| bad_gateway(err.to_string()))?;
+
+    let status = StatusCode::from_u16(file.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    if !status.is_success() && status != StatusCode::RANGE_NOT_SATISFIABLE {
+        return Err(bad_gateway(format!("the audio file returned {}", status)));
+    }
+
+    let mut response = Response::builder().status(status);
+    for name in FORWARDED_RESPONSE_HEADERS {
+        if let Some(value) = file.headers().get(name.as_str()) {
+            response = response.header(&name, value.as_bytes());
+        }
+    }
+    if !file.headers().contains_key(header::CONTENT_TYPE.as_str()) {
+        response = response.header(header::CONTENT_TYPE, &audio.media_type);
+    }
+    if let Some((start, end)) = span {
+        response = response
+            .header(AUDIO_START_HEADER, start.to_string())
+            .header(AUDIO_END_HEADER, end.to_string());
+    }
+
+    // The file is passed on a chunk at a time as it arrives
+    let chunks = stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        match file.chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(file))),
+            Ok(None) => None,
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+
+    response
+        .body(Body::from_stream(chunks))
+        .map_err(|err| internal_error(err).into_response())
+}
+
+/// The audio_timings handler serves /audio/timings (ex: /audio/timings?query=
+/// Psalm 23:1-3) with where the verses of the passage are in the chapter's
+/// recording, for players that follow along with the text.
+pub async fn audio_timings(
+    State(state): State<AppState>,
+    Query(params): Query<AudioParams>,
+) -> Result<Json<AudioTimings>, Response> {
+    let (reference, audio) = find_audio(&state, params.query, params.translation).await?;
+    let span = audio.get_span();
+
+    Ok(Json(AudioTimings {
+        href: get_href(&reference),
+        reference,
+        media_type: audio.media_type,
+        start: span.map(|(start, _)| start),
+        end: span.map(|(_, end)| end),
+        verses: audio.timings,
+    }))
+}
+
+/// The get_href function returns the path of the audio of a passage (ex:
+/// /audio?query=Psalms+23%3A1-3).
+pub fn get_href(reference: &str) -> String {
+    match reqwest::Url::parse_with_params("http://localhost/audio", [("query", reference)]) {
+        Ok(url) => format!("{}?{}", url.path(), url.query().unwrap_or_default()),
+        Err(_) => String::from("/audio"),
+    }
+}
+
+// A recording is of one chapter, so a passage that runs into the next one
+// can not be played from it
+fn check_one_chapter(bible_search: &BibleSearch) -> Result<(), BibleApiError> {
+    match bible_search.chapters.len() {
+        0 | 1 => Ok(()),
+        _ => Err(BibleApiError::from((
+            StatusCode::BAD_REQUEST,
+            "audio is only available for a passage within one chapter".to_string(),
+        ))),
+    }
+}
+
+fn bad_gateway(err: String) -> Response {
+    tracing::warn!("could not fetch the audio: {}", err);
+    BibleApiError::from((
+        StatusCode::BAD_GATEWAY,
+        "The audio could not be fetched".to_string(),
+    ))
+    .into_response()
+}
+
+// Resolve the query and find the recording of its chapter, with the
+// reference of the passage
+async fn find_audio(
+    state: &AppState,
+    query: Option<String>,
+    translation: Option<String>,
+) -> Result<(String, PassageAudio), Response> {
+    let query = query.ok_or_else(|| {
+        BibleApiError::from((
+            StatusCode::BAD_REQUEST,
+            "missing query parameter".to_string(),
+        ))
+        .into_response()
+    })?;
+    check_reference("query", &query).map_err(IntoResponse::into_response)?;
+
+    let translation = translation
+        .map(|translation| translation.trim().to_lowercase())
+        .unwrap_or_else(|| get_default_translation().to_owned());
+    check_text("translation", &translation, MAX_TRANSLATION_LEN)
+        .map_err(IntoResponse::into_response)?;
+
+    let versification = state.versifications.get(&translation);
+    let bible_search = search_with(&query, &versification)
+        .map_err(|err| parse::unresolved(&query, err.into()).into_response())?;
+    check_one_chapter(&bible_search).map_err(IntoResponse::into_response)?;
+    let reference = get_reference_with(&bible_search, &versification);
+
+    let audio = get_passage_audio(&state.pool, &translation, &versification, &bible_search)
+        .await
+        .map_err(|err| internal_error(err).into_response())?
+        .ok_or_else(|| {
+            BibleApiError::from((
+                StatusCode::NOT_FOUND,
+                format!("No Audio Found: {}", reference),
+            ))
+            .into_response()
+        })?;
+
+    Ok((reference, audio))
+}
+
+async fn get_passage_audio(
+    pool: &PgPool,
+    translation: &str,
+    versification: &Versification,
+    bible_search: &BibleSearch,
+) -> Result<Option<PassageAudio>, sqlx::Error> {
+    let Some(first) = bible_search.chapters.first() else {
+        return Ok(None);
+    };
+    let chapter = i32::from(first.chapter);
+    let mut verses = first
+        .verses
+        .iter()
+        .map(|verse| i32::from(*verse))
+        .collect::<Vec<i32>>();
+    verses.sort_unstable();
+
+    let Some(file) = sqlx::query!(
+        r#"
+            SELECT url, media_type
+            FROM chapter_audio
+            WHERE translation = $1 AND title = $2 AND chapter_num = $3
+        "#,
+        translation,
+        bible_search.title,
+        chapter,
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let timings = sqlx::query!(
+        r#"
+            SELECT verse_num, start_seconds, end_seconds
+            FROM verse_timings
+            WHERE translation = $1 AND title = $2 AND chapter_num = $3
+                AND verse_num = ANY($4)
+          ORDER BY verse_num
+        "#,
+        translation,
+        bible_search.title,
+        chapter,
+        &verses[..],
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| VerseTiming {
+        ids: VerseIds::new(&bible_search.title, chapter, row.verse_num),
+        verse: row.verse_num,
+        start: row.start_seconds,
+        end: row.end_seconds,
+    })
+    .collect();
+
+    Ok(Some(PassageAudio {
+        url: file.url,
+        media_type: file.media_type,
+        timings,
+        is_whole_chapter: is_whole_chapter_with(bible_search, versification),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(verse: i32, start: f64, end: f64) -> VerseTiming {
+        VerseTiming {
+            ids: VerseIds::new("Psalms", 23, verse),
+            verse,
+            start,
+            end,
+        }
+    }
+
+    fn audio(timings: Vec<VerseTiming>, is_whole_chapter: bool) -> PassageAudio {
+        PassageAudio {
+            url: String::from("https://example.com/psa/023.mp3"),
+            media_type: String::from("audio/mpeg"),
+            timings,
+            is_whole_chapter,
+        }
+    }
+
+    #[test]
+    fn get_span_covers_the_verses_of_part_of_a_chapter() {
+        let timings = vec![timing(2, 6.5, 12.0), timing(3, 12.0, 19.25)];
+
+        assert_eq!(audio(timings, false).get_span(), Some((6.5, 19.25)));
+        assert_eq!(audio(vec![timing(1, 0.0, 6.5)], true).get_span(), None);
+        assert_eq!(audio(vec![], false).get_span(), None);
+    }
+
+    #[test]
+    fn check_one_chapter_refuses_a_passage_across_chapters() {
+        let versification = crate::versification::Versification::default();
+
+        let within = search_with("Psalm 23:1-3", &versification).unwrap();
+        assert!(check_one_chapter(&within).is_ok());
+
+        let across = search_with("Psalm 23-24", &versification).unwrap();
+        assert_eq!(
+            check_one_chapter(&across).unwrap_err().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn get_href_encodes_the_reference() {
+        assert_eq!(get_href("Psalms 23:1-3"), "/audio?query=Psalms+23%3A1-3");
+    }
+}
@@ -0,0 +1,173 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use std::str::FromStr;
+
+use crate::{
+    auth::{Admin, RequireRole},
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error,
+};
+
+/// The DEFAULT_LIMIT and MAX_LIMIT bound how many entries are returned at once.
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+/// The AuditAction is an administrative action that is recorded in the log.
+/// - Import (import) loaded a new version of a translation
+/// - Rollback (rollback) went back to the previous version of a translation
+/// - Purge (purge) purged a translation or book from the CDN
+/// - Invalidate (invalidate) cleared a translation or book from the caches
+/// - Reindex (reindex) started rebuilding the search vectors of a translation
+/// - CreateKey (create-key) made a new API key
+/// - RevokeKey (revoke-key) revoked an API key
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AuditAction {
+    Import,
+    Rollback,
+    Purge,
+    Invalidate,
+    Reindex,
+    CreateKey,
+    RevokeKey,
+}
+
+impl AuditAction {
+    /// The as_str function returns the action as it is stored.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Import => "import",
+            AuditAction::Rollback => "rollback",
+            AuditAction::Purge => "purge",
+            AuditAction::Invalidate => "invalidate",
+            AuditAction::Reindex => "reindex",
+            AuditAction::CreateKey => "create-key",
+            AuditAction::RevokeKey => "revoke-key",
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        match action.trim().to_lowercase().as_str() {
+            "import" => Ok(AuditAction::Import),
+            "rollback" => Ok(AuditAction::Rollback),
+            "purge" => Ok(AuditAction::Purge),
+            "invalidate" => Ok(AuditAction::Invalidate),
+            "reindex" => Ok(AuditAction::Reindex),
+            "create-key" => Ok(AuditAction::CreateKey),
+            "revoke-key" => Ok(AuditAction::RevokeKey),
+            other => Err(format!("Unknown action: {}", other)),
+        }
+    }
+}
+
+/// The record function adds an action to the audit log. The actor is who
+/// took it (ex: editor:…c123), the target what it was taken on (ex: kjv) and
+/// the details anything else worth knowing. A failure to record is logged
+/// rather than returned, as the action has already been taken.
+pub async fn record(pool: &PgPool, actor: &str, action: AuditAction, target: &str, details: &str) {
+    let result = sqlx::query!(
+        "INSERT INTO audit_log (actor, action, target, details) VALUES ($1, $2, $3, $4)",
+        actor,
+        action.as_str(),
+        target,
+        details,
+    )
+    .execute(pool)
+    .await;
+
+    if let Err(err) = result {
+        tracing::error!(
+            "could not record {} of {} by {} in the audit log: {}",
+            action.as_str(),
+            target,
+            actor,
+            err
+        );
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    action: Option<AuditAction>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    actor: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    before: Option<i64>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    limit: Option<i64>,
+}
+
+/// The AuditEntry is an action recorded in the audit log.
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub at: String,
+    pub actor: String,
+    pub action: String,
+    pub target: String,
+    pub details: String,
+}
+
+/// The audit handler serves GET /admin/audit with the audit log, newest
+/// first. It is narrowed with action and actor, and paged by passing the id
+/// of the last entry seen as before.
+pub async fn audit(
+    _: RequireRole<Admin>,
+    State(pool): State<PgPool>,
+    Query(params): Query<AuditParams>,
+) -> Result<Json<Vec<AuditEntry>>, BibleApiError> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let entries = sqlx::query_as!(
+        AuditEntry,
+        r#"
+            SELECT id, to_char(at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as "at!",
+                   actor, action, target, details
+            FROM audit_log
+            WHERE ($1::text IS NULL OR action = $1)
+              AND ($2::text IS NULL OR actor = $2)
+              AND ($3::bigint IS NULL OR id < $3)
+          ORDER BY id DESC
+          LIMIT $4
+        "#,
+        params.action.map(AuditAction::as_str),
+        params.actor,
+        params.before,
+        limit,
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audit_action_parses_each_action_name() {
+        for action in [
+            AuditAction::Import,
+            AuditAction::Rollback,
+            AuditAction::Purge,
+            AuditAction::Invalidate,
+            AuditAction::Reindex,
+            AuditAction::CreateKey,
+            AuditAction::RevokeKey,
+        ] {
+            assert_eq!(action.as_str().parse::<AuditAction>(), Ok(action));
+        }
+        assert!("delete".parse::<AuditAction>().is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+};
+use std::{collections::HashMap, marker::PhantomData, str::FromStr, sync::Arc};
+
+use crate::error::BibleApiError;
+
+/// The Role enum is what an API key is allowed to do. Each role can do
+/// everything the roles before it can:
+/// - Reader (reader) can look at the state of the service
+/// - Service (service) is for automated jobs, like reindexing after an import
+/// - Editor (editor) can correct content and clear caches
+/// - Admin (admin) can do anything, including rolling back a translation
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Role {
+    Reader,
+    Service,
+    Editor,
+    Admin,
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role.trim().to_lowercase().as_str() {
+            "reader" => Ok(Role::Reader),
+            "service" => Ok(Role::Service),
+            "editor" => Ok(Role::Editor),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("Unknown role: {}", other)),
+        }
+    }
+}
+
+/// The ApiKeys map each key that may call the admin routes to its role.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    keys: Arc<HashMap<String, Role>>,
+}
+
+impl ApiKeys {
+    /// The keys are read from API_KEYS, a comma separated list of role:key
+    /// pairs (ex: editor:abc123,service:def456). The ADMIN_TOKEN, if set, is
+    /// an admin key. Entries that can not be read are skipped with a warning.
+    pub fn from_env() -> Self {
+        let mut keys = HashMap::new();
+
+        if let Ok(admin_token) = std::env::var("ADMIN_TOKEN") {
+            keys.insert(admin_token, Role::Admin);
+        }
+
+        let api_keys = std::env::var("API_KEYS").unwrap_or_default();
+        for entry in api_keys.split(',').filter(|entry| !entry.trim().is_empty()) {
+            match parse_api_key(entry) {
+                Ok((key, role)) => {
+                    keys.insert(key, role);
+                }
+                Err(err) => tracing::warn!("skipping API_KEYS entry: {}", err),
+            }
+        }
+
+        ApiKeys {
+            keys: Arc::new(keys),
+        }
+    }
+
+    /// The get_role function returns the role of a key, or None if the key is
+    /// not known.
+    pub fn get_role(&self, key: &str) -> Option<Role> {
+        self.keys.get(key).copied()
+    }
+}
+
+// Split a role:key pair, keeping the key out of any error message
+fn parse_api_key(entry: &str) -> Result<(String, Role), String> {
+    let (role, key) = entry
+        .trim()
+        .split_once(':')
+        .ok_or(String::from("expected role:key"))?;
+
+    if key.is_empty() {
+        return Err(format!("the {} key is empty", role));
+    }
+
+    Ok((key.to_owned(), role.parse()?))
+}
+
+/// The authorize function checks the bearer token of a request against the
+/// API keys. With no keys configured the protected routes are disabled
+/// entirely, an unknown key is rejected with 401 and a key whose role is not
+/// high enough with 403.
+pub fn authorize(
+    api_keys: &ApiKeys,
+    headers: &HeaderMap,
+    required: Role,
+) -> Result<Role, BibleApiError> {
+    if api_keys.keys.is_empty() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "admin routes are disabled".to_string(),
+        )
+            .into());
+    }
+
+    let role = get_bearer_token(headers)
+        .and_then(|token| api_keys.get_role(token))
+        .ok_or(BibleApiError::from((
+            StatusCode::UNAUTHORIZED,
+            "invalid API key".to_string(),
+        )))?;
+
+    if role < required {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("the {:?} role is required", required).to_lowercase(),
+        )
+            .into());
+    }
+
+    Ok(role)
+}
+
+fn get_bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// The get_actor function names who is behind a key for the audit log, by
+/// its role and the last few characters of the key (ex: editor:…c123). The
+/// rest of the key is never recorded.
+pub fn get_actor(role: Role, key: &str) -> String {
+    format!("{:?}:{}", role, mask_key(key)).to_lowercase()
+}
+
+/// The mask_key function shows only the last few characters of a key (ex:
+/// …c123), so it can be logged.
+pub fn mask_key(key: &str) -> String {
+    let shown = key.chars().count().min(8) / 2;
+    let tail = key
+        .chars()
+        .skip(key.chars().count() - shown)
+        .collect::<String>();
+
+    format!("…{}", tail)
+}
+
+/// The RequiredRole trait ties a marker type to the role a route needs, so
+/// the role can be named in a handler's signature (ex: RequireRole<Editor>).
+pub trait RequiredRole {
+    const ROLE: Role;
+}
+
+pub struct Reader;
+pub struct Service;
+pub struct Editor;
+pub struct Admin;
+
+impl RequiredRole for Reader {
+    const ROLE: Role = Role::Reader;
+}
+
+impl RequiredRole for Service {
+    const ROLE: Role = Role::Service;
+}
+
+impl RequiredRole for Editor {
+    const ROLE: Role = Role::Editor;
+}
+
+impl RequiredRole for Admin {
+    const ROLE: Role = Role::Admin;
+}
+
+/// The RequireRole extractor guards a route. The request is rejected unless
+/// it carries an API key with at least the role R. The actor names the key
+/// for the audit log.
+pub struct RequireRole<R> {
+    pub actor: String,
+    role: PhantomData<R>,
+}
+
+#[async_trait]
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    ApiKeys: FromRef<S>,
+    S: Send + Sync,
+    R: RequiredRole,
+{
+    type Rejection = BibleApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let role = authorize(&ApiKeys::from_ref(state), &parts.headers, R::ROLE)?;
+        let key = get_bearer_token(&parts.headers).unwrap_or_default();
+
+        Ok(RequireRole {
+            actor: get_actor(role, key),
+            role: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn api_keys() -> ApiKeys {
+        ApiKeys {
+            keys: Arc::new(HashMap::from([
+                (String::from("reader-key"), Role::Reader),
+                (String::from("editor-key"), Role::Editor),
+            ])),
+        }
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn parse_api_key_reads_a_role_and_key() {
+        assert_eq!(
+            parse_api_key(" editor:abc:123 "),
+            Ok((String::from("abc:123"), Role::Editor))
+        );
+        assert!(parse_api_key("owner:abc").is_err());
+        assert!(parse_api_key("editor:").is_err());
+        assert!(parse_api_key("abc").is_err());
+    }
+
+    #[test]
+    fn authorize_allows_a_role_at_or_above_the_required_one() {
+        assert_eq!(
+            authorize(&api_keys(), &bearer("editor-key"), Role::Service).ok(),
+            Some(Role::Editor)
+        );
+        assert_eq!(
+            authorize(&api_keys(), &bearer("reader-key"), Role::Reader).ok(),
+            Some(Role::Reader)
+        );
+    }
+
+    #[test]
+    fn authorize_rejects_a_role_below_the_required_one() {
+        let err = authorize(&api_keys(), &bearer("editor-key"), Role::Admin).unwrap_err();
+
+        assert_eq!(err.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(err.to_string(), "the admin role is required");
+    }
+
+    #[test]
+    fn get_actor_shows_only_the_end_of_the_key() {
+        assert_eq!(get_actor(Role::Editor, "abcdefgh123"), "editor:…h123");
+        assert_eq!(get_actor(Role::Admin, "abc"), "admin:…c");
+    }
+
+    #[test]
+    fn authorize_rejects_unknown_keys_and_disabled_routes() {
+        assert_eq!(
+            authorize(&api_keys(), &bearer("guess"), Role::Reader)
+                .unwrap_err()
+                .status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            authorize(&ApiKeys::default(), &bearer("guess"), Role::Reader)
+                .unwrap_err()
+                .status_code(),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
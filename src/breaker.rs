@@ -0,0 +1,509 @@
+use axum::http::{header, HeaderName, StatusCode};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    coalesce::Coalescer,
+    db::{get_default_translation, SearchOptions, SearchResult},
+    error::BibleApiError,
+    offline::OfflineDataset,
+    passage_cache::{PassageCache, PassageKey},
+    search::BibleSearch,
+    store::VerseStore,
+};
+
+/// The FAILURE_THRESHOLD is how many searches in a row have to fail before
+/// the breaker opens and the database is left alone.
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// The OPEN_DURATION is how long the breaker stays open before a single
+/// search is let through to see whether the database is back.
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+
+/// The DEGRADED_HEADER is set to true on responses that were answered from
+/// the offline dataset instead of the database.
+pub const DEGRADED_HEADER: &str = "x-degraded";
+
+/// The DEGRADED_CACHE_CONTROL is the Cache-Control of a degraded response, so
+/// that neither a CDN nor a client keeps it once the database is back.
+pub const DEGRADED_CACHE_CONTROL: &str = "no-store";
+
+/// The get_degraded_headers function returns the headers of a response
+/// answered from the offline dataset, or None for one that was not.
+pub fn get_degraded_headers(degraded: bool) -> Option<[(HeaderName, &'static str); 2]> {
+    degraded.then(|| {
+        [
+            (HeaderName::from_static(DEGRADED_HEADER), "true"),
+            (header::CACHE_CONTROL, DEGRADED_CACHE_CONTROL),
+        ]
+    })
+}
+
+// A search is the same as one in flight when it is for the same verses of
+// the same translation, with the same options. The verses are compared
+// rather than the reference, which depends on the versification it is
+// written in.
+type SearchKey = PassageKey;
+type Fetched = Result<Vec<SearchResult>, BibleApiError>;
+
+/// The BreakerState is whether searches go to the database.
+/// - Closed lets every search through, counting the failures in a row
+/// - Open lets nothing through until the time given
+/// - HalfOpen has let one search through to try the database again, and
+///   gives up waiting on it at the time given
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum BreakerState {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { until: Instant },
+}
+
+impl BreakerState {
+    fn record_success(&mut self) {
+        *self = BreakerState::Closed { failures: 0 };
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        *self = match *self {
+            BreakerState::Closed { failures } if failures + 1 < FAILURE_THRESHOLD => {
+                BreakerState::Closed {
+                    failures: failures + 1,
+                }
+            }
+            _ => {
+                tracing::warn!("the database is failing, opening the circuit breaker");
+                BreakerState::Open {
+                    until: now + OPEN_DURATION,
+                }
+            }
+        };
+    }
+}
+
+/// The CircuitBreaker stands between the searches and the store of the
+/// verses, which is called the database here. When the
+/// database keeps failing it stops sending it searches for a while, and
+/// answers them from the offline dataset instead, if there is one and the
+/// search is of the default translation it holds. Otherwise, searches fail
+/// fast with a 503 rather than waiting on the pool. The
+/// same search asked for again while it is running waits for that one
+/// rather than going to the database too, and one fetched recently is
+/// answered from the passage cache, if there is one.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    store: Arc<dyn VerseStore>,
+    state: Arc<Mutex<BreakerState>>,
+    offline: Option<OfflineDataset>,
+    in_flight: Coalescer<SearchKey, Fetched>,
+    cache: PassageCache,
+}
+
+impl CircuitBreaker {
+    pub fn new(store: Arc<dyn VerseStore>, offline: Option<OfflineDataset>) -> Self {
+        CircuitBreaker {
+            store,
+            state: Arc::new(Mutex::new(BreakerState::Closed { failures: 0 })),
+            offline,
+            in_flight: Coalescer::default(),
+            cache: PassageCache::default(),
+        }
+    }
+
+    /// The with_cache function answers the searches fetched recently from
+    /// the cache given. Only verses from the database are kept in it.
+    pub fn with_cache(self, cache: PassageCache) -> Self {
+        CircuitBreaker { cache, ..self }
+    }
+
+    /// The cache function returns the cache of the passages fetched
+    /// recently.
+    pub fn cache(&self) -> &PassageCache {
+        &self.cache
+    }
+
+    /// The store function returns the store the verses are read from.
+    pub fn store(&self) -> &dyn VerseStore {
+        self.store.as_ref()
+    }
+
+    /// The is_closed function returns true while searches go straight to the
+    /// database.
+    pub fn is_closed(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), BreakerState::Closed { .. })
+    }
+
+    /// The state_name function returns the state of the breaker as it is
+    /// reported by the health check (closed, open or half-open).
+    pub fn state_name(&self) -> &'static str {
+        match *self.state.lock().unwrap() {
+            BreakerState::Closed { .. } => "closed",
+            BreakerState::Open { .. } => "open",
+            BreakerState::HalfOpen { .. } => "half-open",
+        }
+    }
+
+    /// The offline function returns the offline dataset searches fall back
+    /// to, if there is one.
+    pub fn offline(&self) -> Option<&OfflineDataset> {
+        self.offline.as_ref()
+    }
+
+    /// The search function runs a search against the database while the
+    /// breaker allows it, and against the offline dataset when it does not or
+    /// the database fails. The flag returned is true when the verses came
+    /// from the offline dataset, and each of them is flagged as degraded.
+    pub async fn search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> Result<(Vec<SearchResult>, bool), BibleApiError> {
+        let cache_key = PassageKey::new(translation, &bible_search, options);
+        if let Some(verses) = self.cache.get(&cache_key).await {
+            return Ok((verses, false));
+        }
+
+        let fetch = {
+            let (store, translation) = (self.store.clone(), translation.to_owned());
+            let bible_search = bible_search.clone();
+            async move { store.search(&translation, bible_search, options).await }
+        };
+        let fetch = self.in_flight.run(cache_key.clone(), self.recorded(fetch));
+
+        let (mut verses, degraded) = self
+            .run(fetch, |offline| {
+                (translation == get_default_translation())
+                    .then(|| offline.search(&bible_search, options.superscription))
+            })
+            .await?;
+        match degraded {
+            true => mark_degraded(&mut verses),
+            false => self.cache.insert(&cache_key, &verses).await,
+        }
+
+        Ok((verses, degraded))
+    }
+
+    /// The search_many function runs several searches of a translation the
+    /// same way as search, in a single statement, and returns the verses of
+    /// each in the order the searches were given. Only the searches that are
+    /// not in the passage cache are fetched.
+    pub async fn search_many(
+        &self,
+        translation: &str,
+        searches: Vec<(BibleSearch, SearchOptions)>,
+    ) -> Result<(Vec<Vec<SearchResult>>, bool), BibleApiError> {
+        let keys = searches
+            .iter()
+            .map(|(bible_search, options)| PassageKey::new(translation, bible_search, *options))
+            .collect::<Vec<PassageKey>>();
+        let cached = self.cache.get_many(&keys).await;
+        let missing = searches
+            .into_iter()
+            .zip(&cached)
+            .filter(|(_, cached)| cached.is_none())
+            .map(|(search, _)| search)
+            .collect::<Vec<(BibleSearch, SearchOptions)>>();
+        if missing.is_empty() {
+            return Ok((cached.into_iter().flatten().collect(), false));
+        }
+
+        let fetch = self.recorded(self.store.search_many(translation, &missing));
+        let (fetched, degraded) = self
+            .run(fetch, |offline| {
+                (translation == get_default_translation()).then(|| {
+                    missing
+                        .iter()
+                        .map(|(bible_search, options)| {
+                            offline.search(bible_search, options.superscription)
+                        })
+                        .collect()
+                })
+            })
+            .await?;
+
+        // Fill in the searches that were not cached, in order
+        let mut fetched = fetched.into_iter();
+        let mut verses = Vec::with_capacity(keys.len());
+        for (cached, key) in cached.into_iter().zip(&keys) {
+            match cached {
+                Some(cached) => verses.push(cached),
+                None => {
+                    let mut fetched = fetched.next().unwrap_or_default();
+                    match degraded {
+                        true => mark_degraded(&mut fetched),
+                        false => self.cache.insert(key, &fetched).await,
+                    }
+                    verses.push(fetched);
+                }
+            }
+        }
+
+        Ok((verses, degraded))
+    }
+
+    // Record how a fetch against the database went when it finishes. It is
+    // recorded by the fetch itself rather than by each search waiting on it,
+    // so a fetch that fails for many coalesced searches counts once.
+    fn recorded<T>(
+        &self,
+        fetch: impl Future<Output = Result<T, BibleApiError>>,
+    ) -> impl Future<Output = Result<T, BibleApiError>> {
+        let state = self.state.clone();
+        async move {
+            let fetched = fetch.await;
+            match &fetched {
+                Ok(_) => state.lock().unwrap().record_success(),
+                Err(_) => state.lock().unwrap().record_failure(Instant::now()),
+            }
+            fetched
+        }
+    }
+
+    // Run a recorded fetch against the database while the breaker allows
+    // it, and answer from the offline dataset when it does not or the fetch
+    // fails
+    async fn run<T>(
+        &self,
+        fetch: impl Future<Output = Result<T, BibleApiError>>,
+        offline_fetch: impl FnOnce(&OfflineDataset) -> Option<T>,
+    ) -> Result<(T, bool), BibleApiError> {
+        let err = match self.allow(Instant::now()) {
+            true => match fetch.await {
+                Ok(fetched) => return Ok((fetched, false)),
+                Err(err) => err,
+            },
+            false => BibleApiError::from((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "the database is unavailable, try again shortly".to_string(),
+            )),
+        };
+
+        match self.offline.as_ref().and_then(offline_fetch) {
+            Some(fetched) => Ok((fetched, true)),
+            None => Err(err),
+        }
+    }
+
+    // Decide whether a search may go to the database
+    fn allow(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        match *state {
+            BreakerState::Closed { .. } => true,
+            // A trial search that never reported back is given up on
+            BreakerState::Open { until } | BreakerState::HalfOpen { until } if now >= until => {
+                *state = BreakerState::HalfOpen {
+                    until: now + OPEN_DURATION,
+                };
+                true
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => false,
+        }
+    }
+}
+
+fn mark_degraded(verses: &mut [SearchResult]) {
+    for verse in verses {
+        verse.degraded = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::TextFormat, error::BibleApiError};
+    use axum::async_trait;
+    use futures::future::join_all;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio_stream::wrappers::ReceiverStream;
+
+    // A store the breaker is never asked to search
+    struct UnusedStore;
+
+    #[async_trait]
+    impl VerseStore for UnusedStore {
+        fn name(&self) -> &'static str {
+            "unused"
+        }
+
+        async fn ping(&self) -> Result<(), BibleApiError> {
+            unreachable!()
+        }
+
+        async fn is_active(&self, _: &str) -> Result<bool, BibleApiError> {
+            unreachable!()
+        }
+
+        async fn search(&self, _: &str, _: BibleSearch, _: SearchOptions) -> Fetched {
+            unreachable!()
+        }
+
+        async fn search_many(
+            &self,
+            _: &str,
+            _: &[(BibleSearch, SearchOptions)],
+        ) -> Result<Vec<Vec<SearchResult>>, BibleApiError> {
+            unreachable!()
+        }
+
+        fn stream_search(
+            &self,
+            _: &str,
+            _: BibleSearch,
+            _: SearchOptions,
+        ) -> ReceiverStream<Result<SearchResult, sqlx::Error>> {
+            unreachable!()
+        }
+    }
+
+    // A store whose searches fail after a while, counting them
+    #[derive(Default)]
+    struct FailingStore {
+        searches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl VerseStore for FailingStore {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn ping(&self) -> Result<(), BibleApiError> {
+            unreachable!()
+        }
+
+        async fn is_active(&self, _: &str) -> Result<bool, BibleApiError> {
+            unreachable!()
+        }
+
+        async fn search(&self, _: &str, _: BibleSearch, _: SearchOptions) -> Fetched {
+            self.searches.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err(BibleApiError::from((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "the database is down".to_string(),
+            )))
+        }
+
+        async fn search_many(
+            &self,
+            _: &str,
+            _: &[(BibleSearch, SearchOptions)],
+        ) -> Result<Vec<Vec<SearchResult>>, BibleApiError> {
+            unreachable!()
+        }
+
+        fn stream_search(
+            &self,
+            _: &str,
+            _: BibleSearch,
+            _: SearchOptions,
+        ) -> ReceiverStream<Result<SearchResult, sqlx::Error>> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn breaker_opens_after_too_many_failures_in_a_row() {
+        let breaker = CircuitBreaker::new(Arc::new(UnusedStore), None);
+        let now = Instant::now();
+
+        for _ in 1..FAILURE_THRESHOLD {
+            breaker.state.lock().unwrap().record_failure(now);
+        }
+        assert!(breaker.allow(now));
+
+        breaker.state.lock().unwrap().record_failure(now);
+        assert!(!breaker.allow(now));
+        assert!(!breaker.is_closed());
+    }
+
+    #[test]
+    fn breaker_lets_one_search_through_once_the_wait_is_over() {
+        let breaker = CircuitBreaker::new(Arc::new(UnusedStore), None);
+        let now = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.state.lock().unwrap().record_failure(now);
+        }
+
+        let later = now + OPEN_DURATION;
+        assert!(breaker.allow(later));
+        assert!(!breaker.allow(later));
+
+        breaker.state.lock().unwrap().record_success();
+        assert!(breaker.is_closed());
+    }
+
+    #[test]
+    fn breaker_reopens_when_the_trial_search_fails() {
+        let breaker = CircuitBreaker::new(Arc::new(UnusedStore), None);
+        let now = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.state.lock().unwrap().record_failure(now);
+        }
+
+        let later = now + OPEN_DURATION;
+        assert!(breaker.allow(later));
+        breaker.state.lock().unwrap().record_failure(later);
+        assert!(!breaker.allow(later));
+    }
+
+    #[tokio::test]
+    async fn breaker_flags_the_verses_of_the_offline_dataset_as_degraded() {
+        let offline = OfflineDataset::parse(
+            r#"{"title":"John","chapter":11,"verse":35,"text":"Jesus wept.","paragraph_start":false}"#,
+        )
+        .unwrap();
+        let breaker = CircuitBreaker::new(Arc::new(UnusedStore), Some(offline));
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.state.lock().unwrap().record_failure(Instant::now());
+        }
+        let options = SearchOptions {
+            superscription: false,
+            format: TextFormat::Plain,
+        };
+
+        let (verses, degraded) = breaker
+            .search(
+                get_default_translation(),
+                crate::search::search("John 11:35").unwrap(),
+                options,
+            )
+            .await
+            .unwrap();
+        assert!(degraded);
+        assert_eq!(verses.len(), 1);
+        assert!(verses[0].degraded);
+        assert!(get_degraded_headers(false).is_none());
+    }
+
+    #[tokio::test]
+    async fn breaker_counts_a_failed_fetch_once_for_every_search_waiting_on_it() {
+        let store = Arc::new(FailingStore::default());
+        let breaker = CircuitBreaker::new(store.clone(), None);
+        let options = SearchOptions {
+            superscription: false,
+            format: TextFormat::Plain,
+        };
+
+        let searches = (0..FAILURE_THRESHOLD).map(|_| {
+            breaker.search(
+                get_default_translation(),
+                crate::search::search("John 11:35").unwrap(),
+                options,
+            )
+        });
+        let fetched = join_all(searches).await;
+
+        assert!(fetched.iter().all(Result::is_err));
+        assert_eq!(store.searches.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *breaker.state.lock().unwrap(),
+            BreakerState::Closed { failures: 1 }
+        );
+    }
+}
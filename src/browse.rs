@@ -0,0 +1,252 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::{
+    book::get_title,
+    breaker::get_degraded_headers,
+    db::{get_default_translation, SearchOptions, SearchResult, TextFormat},
+    error::BibleApiError,
+    rate_limit::VerseCount,
+    search::{BibleSearch, Chapter},
+    state::AppState,
+    verse::SUPERSCRIPTION_VERSE,
+    versification::Versification,
+};
+
+/// The BookOutline is what /books/:book answers with: the book's title and
+/// how many verses each of its chapters has, so a client can link to them.
+#[derive(Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct BookOutline {
+    pub title: String,
+    pub chapters: Vec<ChapterOutline>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct ChapterOutline {
+    pub chapter: u8,
+    pub verses: u8,
+}
+
+/// The book handler serves /books/:book (ex: /books/jn) with the outline of
+/// the book. The book is matched the same way as in a search.
+pub async fn book(
+    State(state): State<AppState>,
+    Path(book): Path<String>,
+) -> Result<Json<BookOutline>, BibleApiError> {
+    let title = find_title(&book)?;
+    let versification = state.versifications.get(get_default_translation());
+
+    Ok(Json(get_outline(&title, &versification)))
+}
+
+/// The chapter handler serves /books/:book/chapters/:chapter (ex:
+/// /books/john/chapters/3) with every verse of the chapter, and its
+/// superscription if it has one.
+pub async fn chapter(
+    State(state): State<AppState>,
+    Path((book, chapter)): Path<(String, u8)>,
+) -> Result<Response, Response> {
+    let title = find_title(&book).map_err(IntoResponse::into_response)?;
+    let versification = state.versifications.get(get_default_translation());
+    let bible_search =
+        get_chapter_search(&title, chapter, &versification).map_err(IntoResponse::into_response)?;
+
+    let (results, degraded) = fetch(state, bible_search, true).await?;
+
+    Ok(respond(results, degraded))
+}
+
+/// The verse handler serves /books/:book/chapters/:chapter/verses/:verse
+/// (ex: /books/john/chapters/3/verses/16) with the verse. Verse 0 is the
+/// chapter's superscription.
+pub async fn verse(
+    State(state): State<AppState>,
+    Path((book, chapter, verse)): Path<(String, u8, u8)>,
+) -> Result<Response, Response> {
+    let title = find_title(&book).map_err(IntoResponse::into_response)?;
+    let versification = state.versifications.get(get_default_translation());
+    let bible_search = get_verse_search(&title, chapter, verse, &versification)
+        .map_err(IntoResponse::into_response)?;
+
+    let (results, degraded) = fetch(state, bible_search, verse == SUPERSCRIPTION_VERSE).await?;
+
+    // Only a chapter with a superscription has a verse 0
+    if results.is_empty() {
+        return Err(BibleApiError::VerseOutOfRange {
+            title,
+            chapter,
+            verse: verse.to_string(),
+        }
+        .into_response());
+    }
+
+    Ok(respond(results, degraded))
+}
+
+/// The find_title function matches the book in a path like the book of a
+/// query (ex: jn is John).
+pub fn find_title(book: &str) -> Result<String, BibleApiError> {
+    get_title(book).ok_or_else(|| BibleApiError::UnknownBook(book.to_owned()))
+}
+
+/// The get_outline function returns the outline of a book, from the
+/// chapters and verses of a versification.
+pub fn get_outline(title: &str, versification: &Versification) -> BookOutline {
+    let chapter_count = versification.get_chapter_count(title).unwrap_or(0);
+
+    BookOutline {
+        title: title.to_owned(),
+        chapters: (1..=chapter_count)
+            .filter_map(|chapter| {
+                versification
+                    .get_verse_count(title, chapter)
+                    .map(|verses| ChapterOutline { chapter, verses })
+            })
+            .collect(),
+    }
+}
+
+/// The get_chapter_search function returns the search for every verse of a
+/// chapter, or what is missing when the book does not have it.
+pub fn get_chapter_search(
+    title: &str,
+    chapter: u8,
+    versification: &Versification,
+) -> Result<BibleSearch, BibleApiError> {
+    let verse_count = versification
+        .get_verse_count(title, chapter)
+        .ok_or_else(|| BibleApiError::ChapterOutOfRange {
+            title: title.to_owned(),
+            chapter,
+        })?;
+
+    Ok(BibleSearch::from_verses(
+        title,
+        (1..=verse_count).map(|verse| (chapter, verse)),
+    ))
+}
+
+fn get_verse_search(
+    title: &str,
+    chapter: u8,
+    verse: u8,
+    versification: &Versification,
+) -> Result<BibleSearch, BibleApiError> {
+    if !versification.chapter_exists(title, chapter) {
+        return Err(BibleApiError::ChapterOutOfRange {
+            title: title.to_owned(),
+            chapter,
+        });
+    }
+
+    // The superscription is not one of the chapter's numbered verses, it is
+    // fetched along with the chapter, where the chapter has one
+    if verse == SUPERSCRIPTION_VERSE && versification.has_superscription(title, chapter) {
+        return Ok(BibleSearch {
+            title: title.to_owned(),
+            chapters: vec![Chapter {
+                chapter,
+                verses: HashSet::new(),
+            }],
+        });
+    }
+
+    match versification.verse_exists(title, chapter, verse) {
+        true => Ok(BibleSearch::from_verses(title, [(chapter, verse)])),
+        false => Err(BibleApiError::VerseOutOfRange {
+            title: title.to_owned(),
+            chapter,
+            verse: verse.to_string(),
+        }),
+    }
+}
+
+// Fetch the verses of a search from the default translation
+async fn fetch(
+    state: AppState,
+    bible_search: BibleSearch,
+    superscription: bool,
+) -> Result<(Vec<SearchResult>, bool), Response> {
+    let options = SearchOptions {
+        superscription,
+        format: TextFormat::Plain,
+    };
+
+    state
+        .breaker
+        .search(get_default_translation(), bible_search, options)
+        .await
+        .map_err(IntoResponse::into_response)
+}
+
+fn respond(results: Vec<SearchResult>, degraded: bool) -> Response {
+    let verse_count = Extension(VerseCount(results.len()));
+    let degraded = get_degraded_headers(degraded);
+
+    (verse_count, degraded, Json(results)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_outline_counts_the_verses_of_each_chapter() {
+        let outline = get_outline("2 John", &Versification::default());
+
+        assert_eq!(
+            outline,
+            BookOutline {
+                title: "2 John".to_owned(),
+                chapters: vec![ChapterOutline {
+                    chapter: 1,
+                    verses: 13
+                }],
+            }
+        );
+        assert_eq!(
+            get_outline("Genesis", &Versification::default())
+                .chapters
+                .len(),
+            50
+        );
+    }
+
+    #[test]
+    fn get_verse_search_refuses_what_the_book_does_not_have() {
+        let versification = Versification::default();
+
+        assert_eq!(
+            get_chapter_search("John", 3, &versification)
+                .unwrap()
+                .verse_count(),
+            36
+        );
+        assert_eq!(
+            get_verse_search("John", 3, 16, &versification)
+                .unwrap()
+                .get_verses(),
+            vec![(3, 16)]
+        );
+        assert_eq!(
+            get_chapter_search("John", 22, &versification)
+                .unwrap_err()
+                .to_string(),
+            "John has no chapter 22"
+        );
+        assert_eq!(
+            get_verse_search("John", 3, 37, &versification)
+                .unwrap_err()
+                .to_string(),
+            "John 3 has no verse 37"
+        );
+        assert!(find_title("Book of Robert").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use axum::{
+    body::{self, Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::digest::{digest, SHA256};
+use std::time::{Duration, SystemTime};
+
+/// The MAX_ETAG_BYTES is the largest response hashed for an ETag. A larger
+/// one is sent without an ETag rather than being held in memory.
+const MAX_ETAG_BYTES: usize = 8 * 1024 * 1024;
+
+/// The ETAG_BYTES is how many bytes of the SHA-256 of a response make up its
+/// ETag, which is plenty to tell two responses apart.
+const ETAG_BYTES: usize = 16;
+
+/// The RouteClass enum groups routes that share a caching strategy.
+/// - Passage (ex: /search, /books/John, /verses/43003016) is bible text and
+///   never changes
+/// - Votd (ex: /votd) changes daily so it may only be cached briefly
+/// - Private (ex: /me/bookmarks) is per user and must never be cached
+/// - Other is everything else, which is left untouched
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RouteClass {
+    Passage,
+    Votd,
+    Private,
+    Other,
+}
+
+/// The CachePolicy holds the Cache-Control value sent for each route class.
+/// Each value can be overridden with an environment variable.
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    pub passage: String,
+    pub votd: String,
+    pub private: String,
+}
+
+impl CachePolicy {
+    pub fn from_env() -> Self {
+        CachePolicy {
+            passage: env_or(
+                "CACHE_CONTROL_PASSAGE",
+                "public, max-age=31536000, immutable",
+            ),
+            votd: env_or("CACHE_CONTROL_VOTD", "public, max-age=300"),
+            private: env_or("CACHE_CONTROL_PRIVATE", "no-store"),
+        }
+    }
+
+    fn get(&self, route_class: RouteClass) -> Option<&str> {
+        match route_class {
+            RouteClass::Passage => Some(&self.passage),
+            RouteClass::Votd => Some(&self.votd),
+            RouteClass::Private => Some(&self.private),
+            RouteClass::Other => None,
+        }
+    }
+}
+
+fn env_or(key: &str, default: &str) -> String {
+    std::env::var(key).unwrap_or_else(|_| default.to_owned())
+}
+
+/// The get_route_class function maps a request path to its route class.
+pub fn get_route_class(path: &str) -> RouteClass {
+    if path == "/me" || path.starts_with("/me/") {
+        RouteClass::Private
+    } else if path == "/votd" || path.starts_with("/votd/") {
+        RouteClass::Votd
+    } else if path == "/search" || path.starts_with("/books/") || path.starts_with("/verses/") {
+        RouteClass::Passage
+    } else {
+        RouteClass::Other
+    }
+}
+
+/// The set_cache_headers middleware adds the Cache-Control and Expires headers
+/// for the route class of the request. Only successful responses are tagged,
+/// and a Cache-Control header set by the handler itself is left alone.
+pub async fn set_cache_headers(
+    State(policy): State<CachePolicy>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route_class = get_route_class(request.uri().path());
+    let mut response = next.run(request).await;
+
+    if !response.status().is_success() || response.headers().contains_key(header::CACHE_CONTROL) {
+        return response;
+    }
+
+    if let Some(cache_control) = policy.get(route_class) {
+        let expires = get_expires(cache_control, SystemTime::now());
+
+        if let Ok(value) = HeaderValue::from_str(cache_control) {
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+
+        if let Ok(value) = HeaderValue::from_str(&expires) {
+            response.headers_mut().insert(header::EXPIRES, value);
+        }
+    }
+
+    response
+}
+
+/// The set_etag middleware gives the successful GET and HEAD responses of
+/// the passage and votd routes a strong ETag, the hash of their body, unless
+/// the handler gave one itself. A request whose If-None-Match has that ETag
+/// is answered with a 304 and no body, keeping the Cache-Control and
+/// Expires headers, so a client or a CDN can check that what it has is
+/// still good without fetching it again. Responses that are streamed,
+/// larger than MAX_ETAG_BYTES or not to be stored (ex: those answered from
+/// the offline dataset) are sent as they are.
+pub async fn set_etag(request: Request, next: Next) -> Response {
+    let route_class = get_route_class(request.uri().path());
+    let is_read = matches!(*request.method(), Method::GET | Method::HEAD);
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+    let response = next.run(request).await;
+
+    let size = response.body().size_hint().exact();
+    if !is_read
+        || response.status() != StatusCode::OK
+        || !matches!(route_class, RouteClass::Passage | RouteClass::Votd)
+        || size.is_none_or(|size| size as usize > MAX_ETAG_BYTES)
+        || is_no_store(response.headers().get(header::CACHE_CONTROL))
+    {
+        return response;
+    }
+
+    let (mut parts, response_body) = response.into_parts();
+    let response_body = match parts.headers.get(header::ETAG) {
+        Some(_) => response_body,
+        None => {
+            let response_body = match body::to_bytes(response_body, MAX_ETAG_BYTES).await {
+                Ok(response_body) => response_body,
+                Err(err) => {
+                    tracing::warn!("could not hash the response for its ETag: {}", err);
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            };
+            if let Ok(etag) = HeaderValue::from_str(&get_etag(&response_body)) {
+                parts.headers.insert(header::ETAG, etag);
+            }
+            Body::from(response_body)
+        }
+    };
+
+    let not_modified = match (parts.headers.get(header::ETAG), if_none_match) {
+        (Some(etag), Some(if_none_match)) => is_none_match(
+            if_none_match.to_str().unwrap_or_default(),
+            etag.to_str().unwrap_or_default(),
+        ),
+        _ => false,
+    };
+    if !not_modified {
+        return Response::from_parts(parts, response_body);
+    }
+
+    parts.status = StatusCode::NOT_MODIFIED;
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::CONTENT_TYPE);
+    Response::from_parts(parts, Body::empty())
+}
+
+fn is_no_store(cache_control: Option<&HeaderValue>) -> bool {
+    cache_control
+        .and_then(|cache_control| cache_control.to_str().ok())
+        .is_some_and(|cache_control| {
+            cache_control
+                .split(',')
+                .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"))
+        })
+}
+
+/// The get_etag function returns the strong ETag of a response body (ex:
+/// "3q2-7wEAAAAAAAAAAAAAAA"), quoted as the header is.
+fn get_etag(body: &[u8]) -> String {
+    let hash = digest(&SHA256, body);
+
+    format!(
+        "\"{}\"",
+        URL_SAFE_NO_PAD.encode(&hash.as_ref()[..ETAG_BYTES])
+    )
+}
+
+/// The is_none_match function returns true when an If-None-Match header
+/// has the ETag, or is *, meaning what the client has is still good. The
+/// comparison is the weak one If-None-Match calls for, so a W/ in front of
+/// either ETag is ignored.
+fn is_none_match(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// The get_expires function derives the Expires header from the max-age of a
+/// Cache-Control value. Anything without a max-age (ex: no-store) is already
+/// expired, so the epoch is returned.
+fn get_expires(cache_control: &str, now: SystemTime) -> String {
+    match get_max_age(cache_control) {
+        Some(max_age) => httpdate::fmt_http_date(now + Duration::from_secs(max_age)),
+        None => httpdate::fmt_http_date(SystemTime::UNIX_EPOCH),
+    }
+}
+
+fn get_max_age(cache_control: &str) -> Option<u64> {
+    cache_control
+        .split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|seconds| seconds.parse::<u64>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_route_class_recognizes_each_route_class() {
+        assert_eq!(get_route_class("/search"), RouteClass::Passage);
+        assert_eq!(get_route_class("/books/John"), RouteClass::Passage);
+        assert_eq!(get_route_class("/verses/43003016"), RouteClass::Passage);
+        assert_eq!(get_route_class("/votd"), RouteClass::Votd);
+        assert_eq!(get_route_class("/me/bookmarks"), RouteClass::Private);
+        assert_eq!(get_route_class("/menu"), RouteClass::Other);
+    }
+
+    #[test]
+    fn get_etag_is_the_same_only_for_the_same_body() {
+        let etag = get_etag(b"{\"text\":\"Jesus wept.\"}");
+
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert_eq!(etag, get_etag(b"{\"text\":\"Jesus wept.\"}"));
+        assert_ne!(etag, get_etag(b"{\"text\":\"Jesus wept!\"}"));
+    }
+
+    #[test]
+    fn is_none_match_finds_the_etag_in_the_list() {
+        assert!(is_none_match("\"a\"", "\"a\""));
+        assert!(is_none_match("\"b\", W/\"a\"", "\"a\""));
+        assert!(is_none_match("*", "\"a\""));
+        assert!(!is_none_match("\"b\"", "\"a\""));
+        assert!(!is_none_match("", "\"a\""));
+    }
+
+    #[test]
+    fn is_no_store_finds_the_directive() {
+        assert!(is_no_store(Some(&HeaderValue::from_static("no-store"))));
+        assert!(is_no_store(Some(&HeaderValue::from_static(
+            "private, No-Store"
+        ))));
+        assert!(!is_no_store(Some(&HeaderValue::from_static(
+            "public, max-age=300"
+        ))));
+        assert!(!is_no_store(None));
+    }
+
+    #[test]
+    fn get_max_age_reads_the_max_age_directive() {
+        assert_eq!(get_max_age("public, max-age=300"), Some(300));
+        assert_eq!(get_max_age("no-store"), None);
+    }
+
+    #[test]
+    fn get_expires_returns_the_epoch_when_there_is_no_max_age() {
+        assert_eq!(
+            get_expires("no-store", SystemTime::now()),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn get_expires_adds_the_max_age_to_now() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+
+        assert_eq!(
+            get_expires("public, max-age=300", now),
+            "Thu, 01 Jan 1970 00:06:00 GMT"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::sitemap::get_book_slug;
+
+/// The SURROGATE_KEY_HEADER is the response header CDNs read to tag cached
+/// responses so they can later be purged by key instead of by URL.
+pub const SURROGATE_KEY_HEADER: &str = "surrogate-key";
+
+/// The CdnConfig holds what is needed to call the CDN purge API. The API is
+/// Fastly style: a POST to the purge URL with the keys to purge in a
+/// Surrogate-Key header and the API key in a Fastly-Key header.
+#[derive(Debug, Clone)]
+pub struct CdnConfig {
+    pub purge_url: String,
+    pub api_key: String,
+    pub client: reqwest::Client,
+}
+
+impl CdnConfig {
+    /// Returns None unless both CDN_PURGE_URL and CDN_API_KEY are set, as
+    /// purging is optional for deployments without a CDN. The purges are
+    /// sent with the client given.
+    pub fn from_env(client: &reqwest::Client) -> Option<Self> {
+        Some(CdnConfig {
+            purge_url: std::env::var("CDN_PURGE_URL").ok()?,
+            api_key: std::env::var("CDN_API_KEY").ok()?,
+            client: client.clone(),
+        })
+    }
+}
+
+/// The get_translation_key function returns the surrogate key shared by every
+/// response of a translation (ex: translation-kjv).
+pub fn get_translation_key(translation: &str) -> String {
+    format!("translation-{}", translation.to_lowercase())
+}
+
+/// The get_book_key function returns the surrogate key shared by every
+/// response of a book in a translation (ex: kjv-book-1-john).
+pub fn get_book_key(translation: &str, book: &str) -> String {
+    format!(
+        "{}-book-{}",
+        translation.to_lowercase(),
+        get_book_slug(book)
+    )
+}
+
+/// The get_surrogate_keys function builds the space separated Surrogate-Key
+/// header value for a passage response.
+pub fn get_surrogate_keys(translation: &str, book: &str) -> String {
+    format!(
+        "{} {}",
+        get_translation_key(translation),
+        get_book_key(translation, book)
+    )
+}
+
+/// The purge function asks the CDN to drop every cached response tagged with
+/// any of the given keys. Call this after a translation is re-imported.
+pub async fn purge(config: &CdnConfig, keys: &[String]) -> Result<(), String> {
+    let response = config
+        .client
+        .post(&config.purge_url)
+        .header("Fastly-Key", &config.api_key)
+        .header(SURROGATE_KEY_HEADER, keys.join(" "))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "CDN purge failed with status {}",
+            response.status()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_surrogate_keys_tags_translation_and_book() {
+        assert_eq!(
+            get_surrogate_keys("KJV", "Song of Solomon"),
+            "translation-kjv kjv-book-song-of-solomon"
+        );
+    }
+}
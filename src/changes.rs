@@ -0,0 +1,187 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    empty_string_as_none, error::BibleApiError, internal_error, pool_stats, rate_limit::VerseCount,
+    state::AppState, verse_id::VerseIds, versions,
+};
+
+/// The ChangeKind is what happened to a verse between two versions of a
+/// translation.
+/// - Added (added) is a verse only the newer version has
+/// - Changed (changed) is a verse whose text or paragraph break changed
+/// - Removed (removed) is a verse only the older version has
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+impl ChangeKind {
+    fn new(in_since: bool, in_current: bool) -> Self {
+        match (in_since, in_current) {
+            (false, _) => ChangeKind::Added,
+            (true, true) => ChangeKind::Changed,
+            (true, false) => ChangeKind::Removed,
+        }
+    }
+}
+
+/// The VerseChange is a verse that is not the same in the current version
+/// as in the version a client has. The text and paragraph_start are the
+/// verse as it is now, and are left out for a removed verse.
+#[derive(Debug, Serialize)]
+pub struct VerseChange {
+    #[serde(flatten)]
+    pub ids: VerseIds,
+    pub title: String,
+    pub chapter: i32,
+    pub verse: i32,
+    pub change: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paragraph_start: Option<bool>,
+}
+
+/// The TranslationChanges are every verse of a translation that changed
+/// from the since version to the version being served, which a client can
+/// store as its new version.
+#[derive(Debug, Serialize)]
+pub struct TranslationChanges {
+    pub translation: String,
+    pub since: i32,
+    pub version: i32,
+    pub changes: Vec<VerseChange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    since: Option<i32>,
+}
+
+/// The changes handler serves /translations/:translation/changes (ex:
+/// /translations/kjv/changes?since=1) with the verses that changed since a
+/// version of the translation, so an app can bring its copy up to date
+/// without downloading the whole translation again. Since 0 is before the
+/// first version, so every verse comes back as added.
+pub async fn changes(
+    State(state): State<AppState>,
+    Path(translation): Path<String>,
+    Query(params): Query<ChangesParams>,
+) -> Result<Response, BibleApiError> {
+    let translation = translation.to_lowercase();
+    let since = params.since.ok_or(BibleApiError::from((
+        StatusCode::BAD_REQUEST,
+        "missing since parameter".to_string(),
+    )))?;
+
+    let versions = versions::get_versions(&state.pool, &translation).await?;
+    let current = versions
+        .iter()
+        .find(|version| version.state == "active")
+        .ok_or(BibleApiError::from((
+            StatusCode::NOT_FOUND,
+            "No Matching Translation Found".to_string(),
+        )))?
+        .version;
+    if since != 0 && !versions.iter().any(|version| version.version == since) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("{} has no version {}", translation, since),
+        )
+            .into());
+    }
+
+    let changes = get_changes(&state, since, current).await?;
+
+    Ok((
+        Extension(VerseCount(changes.len())),
+        Json(TranslationChanges {
+            translation,
+            since,
+            version: current,
+            changes,
+        }),
+    )
+        .into_response())
+}
+
+// The verses that differ between two versions, in canonical order
+async fn get_changes(
+    state: &AppState,
+    since: i32,
+    current: i32,
+) -> Result<Vec<VerseChange>, BibleApiError> {
+    if since == current {
+        return Ok(vec![]);
+    }
+
+    let mut connection = pool_stats::acquire(&state.pool)
+        .await
+        .map_err(internal_error)?;
+
+    let rows = sqlx::query!(
+        r#"
+            WITH
+                since AS (SELECT * FROM verses WHERE version = $1),
+                current AS (SELECT * FROM verses WHERE version = $2)
+            SELECT
+                COALESCE(c.title, s.title) as "title!",
+                COALESCE(c.chapter_num, s.chapter_num) as "chapter!",
+                COALESCE(c.num, s.num) as "verse!",
+                s.num IS NOT NULL as "in_since!",
+                c.num IS NOT NULL as "in_current!",
+                c.contents as "text?",
+                c.paragraph_start as "paragraph_start?"
+            FROM since s
+                FULL OUTER JOIN current c ON c.title = s.title
+                    AND c.chapter_num = s.chapter_num
+                    AND c.num = s.num
+            WHERE s.contents IS DISTINCT FROM c.contents
+                OR s.formatted_contents IS DISTINCT FROM c.formatted_contents
+                OR s.paragraph_start IS DISTINCT FROM c.paragraph_start
+        "#,
+        since,
+        current
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .map_err(internal_error)?;
+
+    let mut changes = rows
+        .into_iter()
+        .map(|row| VerseChange {
+            ids: VerseIds::new(&row.title, row.chapter, row.verse),
+            change: ChangeKind::new(row.in_since, row.in_current),
+            title: row.title,
+            chapter: row.chapter,
+            verse: row.verse,
+            text: row.text,
+            paragraph_start: row.paragraph_start,
+        })
+        .collect::<Vec<VerseChange>>();
+    changes.sort_by_key(|change| change.ids.verse_id);
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn change_kind_follows_the_versions_with_the_verse() {
+        assert_eq!(ChangeKind::new(false, true), ChangeKind::Added);
+        assert_eq!(ChangeKind::new(true, true), ChangeKind::Changed);
+        assert_eq!(ChangeKind::new(true, false), ChangeKind::Removed);
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(feature = "import")]
+pub mod import;
+
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use std::process;
+
+use crate::{
+    config::Config,
+    integrity::{self, TranslationReport},
+    search_engine::SearchEngine,
+};
+#[cfg(feature = "import")]
+use import::ImportArgs;
+
+/// The Cli is the command line of the server. With no command it serves the
+/// API, as it always has. The settings of the config can be given before or
+/// after the command.
+#[derive(Debug, Parser)]
+#[command(name = "bible-api", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub config: Config,
+}
+
+#[derive(Debug, Default, Subcommand)]
+pub enum Command {
+    /// Serve the API (the default)
+    #[default]
+    Serve,
+    /// Import a translation from a directory of source files
+    #[cfg(feature = "import")]
+    Import(ImportArgs),
+    /// Check the verses of the active translations, printing a JSON report
+    Verify(VerifyArgs),
+    /// Push the verses of the active translations into the search engine
+    IndexSearch(IndexSearchArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// The translation to check (ex: kjv), or every active one when not given
+    #[arg(long)]
+    pub translation: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct IndexSearchArgs {
+    /// The translation to index (ex: kjv), or every active one when not given
+    #[arg(long)]
+    pub translation: Option<String>,
+}
+
+/// The VerifyReport is what the verify command prints: a report per
+/// translation checked, and whether all of them came through clean.
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    pub ok: bool,
+    pub translations: Vec<TranslationReport>,
+}
+
+/// The verify function runs the verify command, printing its report to
+/// stdout and exiting with a non-zero status when any problem is found.
+pub async fn verify(args: VerifyArgs, config: &Config) {
+    let pool = crate::connect(config).await;
+    let translations = get_translations(&pool, args.translation).await;
+
+    let mut reports = vec![];
+    for translation in translations {
+        match integrity::verify(&pool, &translation).await {
+            Ok(Some(report)) => reports.push(report),
+            Ok(None) => fail(&format!("{} has no active version", translation)),
+            Err(err) => fail(&format!("can not verify {}: {}", translation, err)),
+        }
+    }
+
+    let report = VerifyReport {
+        ok: reports.iter().all(|report| report.problems.is_empty()),
+        translations: reports,
+    };
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(err) => fail(&format!("can not write the report: {}", err)),
+    }
+
+    process::exit(i32::from(!report.ok));
+}
+
+/// The index_search function runs the index-search command, pushing every
+/// verse of the translations into the search engine SEARCH_ENGINE_URL names.
+/// Run it again after a translation is imported.
+pub async fn index_search(args: IndexSearchArgs, config: &Config) {
+    let engine = SearchEngine::from_env(&reqwest::Client::new())
+        .unwrap_or_else(|| fail("SEARCH_ENGINE_URL is not set, there is no search engine"));
+    let pool = crate::connect(config).await;
+    let translations = get_translations(&pool, args.translation).await;
+
+    for translation in translations {
+        match engine.index_translation(&pool, &translation).await {
+            Ok(0) => fail(&format!("{} has no active version", translation)),
+            Ok(verses) => println!("sent {} verses of {} to be indexed", verses, translation),
+            Err(err) => fail(&format!("can not index {}: {}", translation, err)),
+        }
+    }
+}
+
+// The translation asked for, or every active one
+async fn get_translations(pool: &PgPool, translation: Option<String>) -> Vec<String> {
+    match translation {
+        Some(translation) => vec![translation.trim().to_lowercase()],
+        None => integrity::get_active_translations(pool)
+            .await
+            .unwrap_or_else(|err| fail(&format!("can not list translations: {}", err))),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
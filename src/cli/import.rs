@@ -0,0 +1,164 @@
+use clap::{Args, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{fs, path::PathBuf, process};
+
+use super::fail;
+use crate::{
+    audit::{self, AuditAction},
+    cdn::CdnConfig,
+    chapter::get_book_number,
+    config::Config,
+    import::{self, ImportedBook},
+    versions,
+};
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// The format of the source files
+    #[arg(long, value_enum, default_value_t = ImportFormat::Usfm)]
+    pub format: ImportFormat,
+    /// The translation the files are imported as (ex: web)
+    #[arg(long)]
+    pub translation: String,
+    /// Read and check the files without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Import even when the check finds problems
+    #[arg(long)]
+    pub force: bool,
+    /// The directory holding one source file per book
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ImportFormat {
+    Usfm,
+}
+
+impl ImportFormat {
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ImportFormat::Usfm => &["usfm", "sfm"],
+        }
+    }
+}
+
+/// The import function runs the import command, exiting with a non-zero
+/// status when a file can not be read or the check finds problems.
+pub async fn import(args: ImportArgs, config: &Config) {
+    let translation = args.translation.trim().to_lowercase();
+    let books = match read_books(&args) {
+        Ok(books) => books,
+        Err(err) => fail(&err),
+    };
+
+    let problems = books
+        .iter()
+        .flat_map(ImportedBook::check)
+        .collect::<Vec<_>>();
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    println!(
+        "read {} books ({} verses) with {} problems",
+        books.len(),
+        books.iter().map(|book| book.verses.len()).sum::<usize>(),
+        problems.len()
+    );
+
+    if args.dry_run {
+        process::exit(i32::from(!problems.is_empty()));
+    }
+    if !problems.is_empty() && !args.force {
+        fail("not importing a translation with problems (use --force to import anyway)");
+    }
+
+    let pool = crate::connect(config).await;
+    let bar = progress_bar(books.len(), "writing");
+    let version = import::import(&pool, &translation, &books, |book| {
+        bar.set_message(book.title.clone());
+        bar.inc(1);
+    })
+    .await;
+    bar.finish_and_clear();
+
+    match version {
+        Ok(version) => {
+            // The import is run by hand, so the actor is the user running it
+            let actor = match std::env::var("USER") {
+                Ok(user) => format!("cli:{}", user),
+                Err(_) => String::from("cli"),
+            };
+            let details = format!("version {}", version);
+            audit::record(&pool, &actor, AuditAction::Import, &translation, &details).await;
+
+            // The new version is served from now on, so the old text has to
+            // go from the shared caches
+            let cache = config.get_passage_cache();
+            versions::clear_cached(
+                &cache,
+                CdnConfig::from_env(&reqwest::Client::new()).as_ref(),
+                &translation,
+            )
+            .await;
+            println!("imported {} as version {}", translation, version)
+        }
+        Err(err) => fail(&format!("import failed: {}", err)),
+    }
+}
+
+// Read every source file in the directory, in canonical order
+fn read_books(args: &ImportArgs) -> Result<Vec<ImportedBook>, String> {
+    let mut paths = fs::read_dir(&args.dir)
+        .map_err(|err| format!("can not read {}: {}", args.dir.display(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    args.format
+                        .extensions()
+                        .contains(&extension.to_lowercase().as_str())
+                })
+        })
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    if paths.is_empty() {
+        return Err(format!("no source files found in {}", args.dir.display()));
+    }
+
+    let bar = progress_bar(paths.len(), "reading");
+    let mut books = vec![];
+    for path in paths {
+        bar.set_message(path.display().to_string());
+
+        let source = fs::read_to_string(&path)
+            .map_err(|err| format!("can not read {}: {}", path.display(), err))?;
+        let book = match args.format {
+            ImportFormat::Usfm => import::parse_usfm(&source),
+        }
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+        books.push(book);
+        bar.inc(1);
+    }
+    bar.finish_and_clear();
+
+    books.sort_by_key(|book| get_book_number(&book.title));
+    if let Some(pair) = books.windows(2).find(|pair| pair[0].title == pair[1].title) {
+        return Err(format!("{} is in more than one file", pair[0].title));
+    }
+
+    Ok(books)
+}
+
+fn progress_bar(length: usize, action: &str) -> ProgressBar {
+    let style = ProgressStyle::with_template("{prefix} [{bar:40}] {pos}/{len} {msg}")
+        .unwrap()
+        .progress_chars("=> ");
+
+    ProgressBar::new(length as u64)
+        .with_style(style)
+        .with_prefix(action.to_owned())
+}
@@ -0,0 +1,115 @@
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+/// The Coalescer runs one fetch at a time for each key. A fetch asked for
+/// while the same key is already being fetched waits for that one and gets
+/// a copy of what it returns, so a burst of the same request only does the
+/// work once. Nothing is kept once the fetch is done, so it is not a cache.
+pub struct Coalescer<K, V: Clone> {
+    in_flight: Arc<Mutex<HashMap<K, Shared<BoxFuture<'static, V>>>>>,
+}
+
+impl<K, V: Clone> Clone for Coalescer<K, V> {
+    fn clone(&self) -> Self {
+        Coalescer {
+            in_flight: self.in_flight.clone(),
+        }
+    }
+}
+
+impl<K, V: Clone> Default for Coalescer<K, V> {
+    fn default() -> Self {
+        Coalescer {
+            in_flight: Arc::default(),
+        }
+    }
+}
+
+impl<K, V> Coalescer<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    /// The run function returns what the fetch for the key returns, running
+    /// the fetch given only when the key is not already being fetched. The
+    /// fetch goes on as long as anyone is waiting on it, even when the
+    /// request that started it has gone.
+    pub async fn run(&self, key: K, fetch: impl Future<Output = V> + Send + 'static) -> V {
+        let shared = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| fetch.boxed().shared())
+            .clone();
+
+        let value = shared.clone().await;
+
+        // Whoever sees the fetch finish first lets the next one start afresh
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .is_some_and(|current| current.ptr_eq(&shared))
+        {
+            in_flight.remove(&key);
+        }
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn in_flight(coalescer: &Coalescer<&str, usize>) -> usize {
+        coalescer.in_flight.lock().unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn run_fetches_a_key_once_while_it_is_in_flight() {
+        let coalescer: Coalescer<&str, usize> = Coalescer::default();
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let released = released.shared();
+
+        let fetch = |fetches: Arc<AtomicUsize>| {
+            let released = released.clone();
+            async move {
+                let _ = released.await;
+                fetches.fetch_add(1, Ordering::SeqCst) + 1
+            }
+        };
+
+        let first = tokio::spawn({
+            let coalescer = coalescer.clone();
+            let fetch = fetch(fetches.clone());
+            async move { coalescer.run("John 3", fetch).await }
+        });
+        let second = tokio::spawn({
+            let coalescer = coalescer.clone();
+            let fetch = fetch(fetches.clone());
+            async move { coalescer.run("John 3", fetch).await }
+        });
+        while in_flight(&coalescer) == 0 {
+            tokio::task::yield_now().await;
+        }
+        tokio::task::yield_now().await;
+        release.send(()).unwrap();
+
+        assert_eq!(first.await.unwrap(), 1);
+        assert_eq!(second.await.unwrap(), 1);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(in_flight(&coalescer), 0);
+
+        // Once it is done the key is fetched again
+        let again = coalescer.run("John 3", fetch(fetches.clone())).await;
+        assert_eq!(again, 2);
+    }
+}
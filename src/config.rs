@@ -0,0 +1,220 @@
+use axum::http::HeaderValue;
+use clap::Args;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing_subscriber::EnvFilter;
+
+use crate::passage_cache::PassageCache;
+
+/// The ANY_ORIGIN allows every origin to call the API from a browser.
+const ANY_ORIGIN: &str = "*";
+
+/// The DEFAULT_LOG_FILTER is the log filter when neither --log-filter nor
+/// RUST_LOG is given.
+const DEFAULT_LOG_FILTER: &str = "info";
+
+/// The Config is how the server and its commands are set up. Each setting
+/// can be given as a flag, or in an environment variable (or .env) when the
+/// flag is left out, and falls back to a default that suits running locally.
+#[derive(Debug, PartialEq, Clone, Args)]
+pub struct Config {
+    /// The database to serve from, Postgres or, with the sqlite feature, a
+    /// SQLite file of the verses alone (ex: sqlite:db/bible.db). With the
+    /// embedded feature, leaving it out (or embedded:) serves the KJV built in
+    #[arg(long, global = true, env = "DATABASE_URL", hide_env_values = true)]
+    pub database_url: Option<String>,
+
+    /// Leave the database as it is instead of migrating it on startup
+    #[arg(long, global = true, env = "SKIP_MIGRATIONS")]
+    pub skip_migrations: bool,
+
+    /// A dump of the default translation, a verse per line the way the
+    /// ndjson format writes them (ex: db/kjv.ndjson), loaded before serving
+    /// when the database has none of the translation yet
+    #[cfg(feature = "import")]
+    #[arg(long, global = true, env = "SEED", value_name = "PATH")]
+    pub seed: Option<std::path::PathBuf>,
+
+    /// The address to listen on
+    #[arg(long, global = true, env = "BIND_ADDRESS", default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    pub bind_address: IpAddr,
+
+    /// The port to listen on
+    #[arg(long, global = true, env = "PORT", default_value_t = 3000)]
+    pub port: u16,
+
+    /// The most connections the database pool opens at once
+    #[arg(long, global = true, env = "DB_MAX_CONNECTIONS", default_value_t = 5)]
+    pub db_max_connections: u32,
+
+    /// The connections the database pool keeps open when idle
+    #[arg(long, global = true, env = "DB_MIN_CONNECTIONS", default_value_t = 0)]
+    pub db_min_connections: u32,
+
+    /// How long a request waits for a database connection, in seconds
+    #[arg(
+        long,
+        global = true,
+        env = "DB_ACQUIRE_TIMEOUT_SECS",
+        default_value_t = 3
+    )]
+    pub db_acquire_timeout_secs: u64,
+
+    /// The origins allowed to call the API from a browser, comma separated
+    /// (ex: https://example.com), or * for any
+    #[arg(
+        long,
+        global = true,
+        env = "CORS_ORIGINS",
+        value_delimiter = ',',
+        default_value = ANY_ORIGIN
+    )]
+    pub cors_origins: Vec<String>,
+
+    /// How many passages are kept in the passage cache in memory, or 0 to
+    /// keep none
+    #[arg(
+        long,
+        global = true,
+        env = "PASSAGE_CACHE_CAPACITY",
+        default_value_t = 1000
+    )]
+    pub passage_cache_capacity: usize,
+
+    /// How long a passage is kept in the passage cache, in seconds
+    #[arg(
+        long,
+        global = true,
+        env = "PASSAGE_CACHE_TTL_SECS",
+        default_value_t = 300
+    )]
+    pub passage_cache_ttl_secs: u64,
+
+    /// The Redis server the passage cache is kept in, shared by every
+    /// replica, instead of in memory (ex: redis://127.0.0.1:6379)
+    #[cfg(feature = "redis")]
+    #[arg(long, global = true, env = "REDIS_URL", hide_env_values = true)]
+    pub redis_url: Option<String>,
+
+    /// Which logs are written (ex: bible_api=debug,tower_http=info)
+    #[arg(long, global = true, env = "RUST_LOG", default_value = DEFAULT_LOG_FILTER)]
+    pub log_filter: String,
+}
+
+impl Config {
+    /// The get_address function returns the address the server listens on.
+    pub fn get_address(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_address, self.port)
+    }
+
+    /// The get_acquire_timeout function returns how long a request waits for
+    /// a database connection.
+    pub fn get_acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.db_acquire_timeout_secs)
+    }
+
+    /// The get_passage_cache function returns the cache of the passages
+    /// fetched recently, with the TTL of the config. It is kept in Redis when
+    /// a Redis server is given, and otherwise in memory, with the capacity
+    /// of the config.
+    pub fn get_passage_cache(&self) -> PassageCache {
+        let ttl = Duration::from_secs(self.passage_cache_ttl_secs);
+
+        #[cfg(feature = "redis")]
+        if let Some(redis_url) = self.redis_url.as_deref() {
+            match crate::redis_cache::RedisCache::new(redis_url) {
+                Ok(redis) => return PassageCache::new(std::sync::Arc::new(redis), ttl),
+                Err(err) => {
+                    tracing::warn!(
+                        "keeping the passage cache in memory, as Redis can not be used: {}",
+                        err
+                    )
+                }
+            }
+        }
+
+        PassageCache::in_memory(self.passage_cache_capacity, ttl)
+    }
+
+    /// The get_log_filter function returns the filter of the logs written,
+    /// or the default filter when it can not be read. Logging is not set up
+    /// yet, so the problem is written to stderr.
+    pub fn get_log_filter(&self) -> EnvFilter {
+        EnvFilter::try_new(&self.log_filter).unwrap_or_else(|err| {
+            eprintln!(
+                "{} is not a valid log filter, using {}: {}",
+                self.log_filter, DEFAULT_LOG_FILTER, err
+            );
+            EnvFilter::new(DEFAULT_LOG_FILTER)
+        })
+    }
+
+    /// The get_cors_layer function returns the CORS policy of the API. Any
+    /// origin is allowed when one of them is *; otherwise only the origins
+    /// given are, and an origin that can not be read is skipped with a
+    /// warning.
+    pub fn get_cors_layer(&self) -> CorsLayer {
+        let origins = self
+            .cors_origins
+            .iter()
+            .map(|origin| origin.trim())
+            .filter(|origin| !origin.is_empty())
+            .collect::<Vec<&str>>();
+        if origins.contains(&ANY_ORIGIN) {
+            return CorsLayer::permissive();
+        }
+
+        let origins = origins
+            .into_iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin) {
+                Ok(origin) => Some(origin),
+                Err(_) => {
+                    tracing::warn!("skipping the CORS origin {}", origin);
+                    None
+                }
+            })
+            .collect::<Vec<HeaderValue>>();
+
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .expose_headers(Any)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    #[test]
+    fn config_reads_flags_before_or_after_the_command() {
+        let cli = Cli::try_parse_from([
+            "bible-api",
+            "--port",
+            "8080",
+            "serve",
+            "--bind-address",
+            "0.0.0.0",
+            "--cors-origins",
+            "https://a.example,https://b.example",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.config.get_address().to_string(), "0.0.0.0:8080");
+        assert_eq!(
+            cli.config.cors_origins,
+            vec!["https://a.example", "https://b.example"]
+        );
+    }
+
+    #[test]
+    fn config_refuses_a_setting_that_can_not_be_read() {
+        assert!(Cli::try_parse_from(["bible-api", "--port", "http"]).is_err());
+        assert!(Cli::try_parse_from(["bible-api", "--bind-address", "localhost"]).is_err());
+    }
+}
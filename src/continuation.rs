@@ -0,0 +1,203 @@
+use axum::http::{HeaderName, StatusCode};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use std::sync::OnceLock;
+
+use crate::{
+    error::BibleApiError,
+    search::{get_reference_with, BibleSearch},
+    versification::Versification,
+};
+
+/// The CONTINUATION_HEADER holds the token for the rest of a passage that
+/// was sent in part. It is sent back as the continuation parameter of
+/// /search, with the same other parameters, for the next chunk.
+pub const CONTINUATION_HEADER: &str = "x-continuation-token";
+
+/// The TOTAL_COUNT_HEADER, PAGE_OFFSET_HEADER and PAGE_LIMIT_HEADER describe
+/// the page of a passage that was sent for a limit and offset: how many
+/// verses the whole passage has, and which of them were sent.
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+pub const PAGE_OFFSET_HEADER: &str = "x-page-offset";
+pub const PAGE_LIMIT_HEADER: &str = "x-page-limit";
+
+/// The DEFAULT_MAX_PASSAGE_VERSES is the verse cap when MAX_PASSAGE_VERSES is
+/// not set. It is more than the longest chapter (Psalms 119) has.
+pub const DEFAULT_MAX_PASSAGE_VERSES: usize = 250;
+
+/// The get_max_verses function returns the most verses sent for a passage at
+/// once, read once from MAX_PASSAGE_VERSES. A passage with more is sent a
+/// chunk at a time.
+pub fn get_max_verses() -> usize {
+    static MAX_VERSES: OnceLock<usize> = OnceLock::new();
+
+    *MAX_VERSES.get_or_init(|| match std::env::var("MAX_PASSAGE_VERSES") {
+        Ok(max_verses) => match max_verses.trim().parse() {
+            Ok(max_verses) if max_verses > 0 => max_verses,
+            _ => {
+                tracing::warn!(
+                    "{} is not a valid value for MAX_PASSAGE_VERSES, using {}",
+                    max_verses,
+                    DEFAULT_MAX_PASSAGE_VERSES
+                );
+                DEFAULT_MAX_PASSAGE_VERSES
+            }
+        },
+        Err(_) => DEFAULT_MAX_PASSAGE_VERSES,
+    })
+}
+
+/// The split_search function splits a search into its first max_verses
+/// verses and the rest, if there are more than max_verses of them. A
+/// passage across chapters is split wherever the cap falls.
+pub fn split_search(
+    bible_search: BibleSearch,
+    max_verses: usize,
+) -> (BibleSearch, Option<BibleSearch>) {
+    if bible_search.verse_count() <= max_verses {
+        return (bible_search, None);
+    }
+
+    let mut verses = bible_search.get_verses();
+    let rest = verses.split_off(max_verses);
+
+    (
+        BibleSearch::from_verses(&bible_search.title, verses),
+        Some(BibleSearch::from_verses(&bible_search.title, rest)),
+    )
+}
+
+/// The Page is the part of a passage a client asked for, by the number of
+/// verses to skip and the most to send, out of the total the passage has.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: usize,
+    pub total: usize,
+}
+
+impl Page {
+    pub fn headers(self) -> [(HeaderName, String); 3] {
+        [
+            (
+                HeaderName::from_static(TOTAL_COUNT_HEADER),
+                self.total.to_string(),
+            ),
+            (
+                HeaderName::from_static(PAGE_OFFSET_HEADER),
+                self.offset.to_string(),
+            ),
+            (
+                HeaderName::from_static(PAGE_LIMIT_HEADER),
+                self.limit.to_string(),
+            ),
+        ]
+    }
+}
+
+/// The page_search function returns the verses of a search from the offset,
+/// up to the limit. The limit is the verse cap when it is not given, and can
+/// be no more than it, so a page is never sent in chunks. An offset past
+/// the end of the passage is a page without verses.
+pub fn page_search(
+    bible_search: BibleSearch,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> (BibleSearch, Page) {
+    let max_verses = get_max_verses();
+    let limit = limit.unwrap_or(max_verses).clamp(1, max_verses);
+    let offset = offset.unwrap_or(0);
+    let verses = bible_search.get_verses();
+    let total = verses.len();
+
+    (
+        BibleSearch::from_verses(
+            &bible_search.title,
+            verses.into_iter().skip(offset).take(limit),
+        ),
+        Page {
+            offset,
+            limit,
+            total,
+        },
+    )
+}
+
+/// The get_token function returns the continuation token for the rest of a
+/// passage. It is the reference of the rest in the versification of its
+/// translation, so it needs nothing kept on the server and is checked like
+/// any other query when it comes back.
+pub fn get_token(rest: &BibleSearch, versification: &Versification) -> String {
+    URL_SAFE_NO_PAD.encode(get_reference_with(rest, versification))
+}
+
+/// The read_token function returns the reference a continuation token is
+/// for.
+pub fn read_token(token: &str) -> Result<String, BibleApiError> {
+    URL_SAFE_NO_PAD
+        .decode(token.trim())
+        .ok()
+        .and_then(|reference| String::from_utf8(reference).ok())
+        .ok_or(BibleApiError::from((
+            StatusCode::BAD_REQUEST,
+            "invalid continuation token".to_string(),
+        )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{get_reference, search};
+
+    #[test]
+    fn split_search_sends_the_first_verses_first() {
+        let (first, rest) = split_search(search("Psalms 119").unwrap(), 100);
+        let rest = rest.unwrap();
+
+        assert_eq!(get_reference(&first), "Psalms 119:1-100");
+        assert_eq!(get_reference(&rest), "Psalms 119:101-176");
+
+        let (second, rest) = split_search(rest, 100);
+        assert_eq!(get_reference(&second), "Psalms 119:101-176");
+        assert_eq!(rest, None);
+    }
+
+    #[test]
+    fn split_search_carries_a_passage_into_the_next_chapter() {
+        let (first, rest) = split_search(search("John 3:30-4:10").unwrap(), 10);
+
+        assert_eq!(get_reference(&first), "John 3:30-4:3");
+        assert_eq!(get_reference(&rest.unwrap()), "John 4:4-10");
+    }
+
+    #[test]
+    fn page_search_sends_the_verses_from_the_offset() {
+        let (page, info) = page_search(search("Psalms 119").unwrap(), Some(170), Some(10));
+
+        assert_eq!(get_reference(&page), "Psalms 119:171-176");
+        assert_eq!(
+            info,
+            Page {
+                offset: 170,
+                limit: 10,
+                total: 176,
+            }
+        );
+
+        let (page, info) = page_search(search("John 3:35-4:2").unwrap(), None, Some(0));
+        assert_eq!(get_reference(&page), "John 3:35");
+        assert_eq!(info.limit, 1);
+
+        let (page, info) = page_search(search("John 3").unwrap(), Some(50), None);
+        assert_eq!(page.verse_count(), 0);
+        assert_eq!(info.limit, get_max_verses());
+    }
+
+    #[test]
+    fn read_token_returns_the_rest_of_the_passage() {
+        let (_, rest) = split_search(search("John 3").unwrap(), 30);
+        let token = get_token(&rest.unwrap(), &Versification::default());
+
+        assert_eq!(read_token(&token).unwrap(), "John 3:31-36");
+        assert!(read_token("not a token!").is_err());
+    }
+}
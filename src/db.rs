@@ -1,58 +1,443 @@
-use axum::{http::StatusCode, Json};
-use serde::Serialize;
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres};
+use std::{str::FromStr, sync::OnceLock};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
-    internal_error,
-    search::{BibleSearch, Chapter},
+    error::BibleApiError, internal_error, pool_stats, search::BibleSearch,
+    verse::SUPERSCRIPTION_VERSE, verse_id::VerseIds,
 };
-#[derive(Serialize)]
+
+/// The DEFAULT_TRANSLATION is the translation searched when none is asked
+/// for and DEFAULT_TRANSLATION is not set in the environment.
+pub const DEFAULT_TRANSLATION: &str = "kjv";
+
+/// The get_default_translation function returns the translation searched
+/// when a request does not ask for one (ex: web). It is read once from
+/// DEFAULT_TRANSLATION, and is kjv when that is not set.
+pub fn get_default_translation() -> &'static str {
+    static TRANSLATION: OnceLock<String> = OnceLock::new();
+
+    TRANSLATION.get_or_init(|| {
+        std::env::var("DEFAULT_TRANSLATION")
+            .map(|translation| translation.trim().to_lowercase())
+            .ok()
+            .filter(|translation| !translation.is_empty())
+            .unwrap_or_else(|| DEFAULT_TRANSLATION.to_owned())
+    })
+}
+
+/// The STREAM_BUFFER is how many rows may be waiting to be written to the
+/// response before the database fetch is paused.
+pub const STREAM_BUFFER: usize = 64;
+
+/// The TextFormat picks which stored version of a verse's text is returned.
+/// The html output format uses Html, every other format Plain.
+/// - Plain (plain) is the text with all markup stripped (the default)
+/// - Html (html) keeps the light markup recorded at import time (ex: the
+///   <i> around words the translators supplied), falling back to the plain
+///   text for verses that have no formatted version
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum TextFormat {
+    #[default]
+    Plain,
+    Html,
+}
+
+impl FromStr for TextFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.trim().to_lowercase().as_str() {
+            "plain" => Ok(TextFormat::Plain),
+            "html" => Ok(TextFormat::Html),
+            other => Err(format!("Unknown format: {}", other)),
+        }
+    }
+}
+
+/// The SearchOptions change what is fetched for a search without changing
+/// which verses it covers.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct SearchOptions {
+    /// Include the chapter's superscription (verse 0), if it has one
+    pub superscription: bool,
+    pub format: TextFormat,
+}
+
+/// The SearchResult is a single verse of a search, from a translation (ex:
+/// kjv). The ids number the verse, its chapter and its book the same way in
+/// every import of every translation (see VerseIds), and are fields of their
+/// own so that every format can write them. The paragraph_start flag marks a
+/// verse that opens a paragraph, so clients can break the text there instead
+/// of after every verse.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 pub struct SearchResult {
+    #[serde(default)]
+    pub verse_id: i32,
+    #[serde(default)]
+    pub chapter_id: i32,
+    #[serde(default)]
+    pub book_id: i32,
+    #[serde(default)]
+    pub translation: String,
     pub title: String,
     pub chapter: i32,
     pub verse: i32,
     pub text: String,
+    pub paragraph_start: bool,
+    /// Set on a verse answered from the offline dataset while the database
+    /// is down
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub degraded: bool,
 }
 
+fn is_false(flag: &bool) -> bool {
+    !flag
+}
+
+impl SearchResult {
+    /// The new function makes a verse of a search, with its ids.
+    pub fn new(
+        translation: &str,
+        title: String,
+        chapter: i32,
+        verse: i32,
+        text: String,
+        paragraph_start: bool,
+    ) -> Self {
+        let VerseIds {
+            verse_id,
+            chapter_id,
+            book_id,
+        } = VerseIds::new(&title, chapter, verse);
+
+        SearchResult {
+            verse_id,
+            chapter_id,
+            book_id,
+            translation: translation.to_owned(),
+            title,
+            chapter,
+            verse,
+            text,
+            paragraph_start,
+            degraded: false,
+        }
+    }
+}
+
+/// The search function fetches the verses of a search from the active
+/// version of a translation (ex: web). A translation that is not loaded has
+/// no verses.
 pub async fn search(
     pool: Pool<Postgres>,
+    translation: &str,
+    bible_search: BibleSearch,
+    options: SearchOptions,
+) -> Result<Vec<SearchResult>, BibleApiError> {
+    stream_search(pool, translation, bible_search, options)
+        .try_collect()
+        .await
+        .map_err(internal_error)
+}
+
+/// The stream_search function fetches the rows of a search from a
+/// translation one at a time instead of buffering them all, so large
+/// passages can be written to the response as they arrive.
+pub fn stream_search(
+    pool: Pool<Postgres>,
+    translation: &str,
     bible_search: BibleSearch,
-) -> Result<Json<Vec<SearchResult>>, (StatusCode, String)> {
-    let title = bible_search.title;
-    let chapter = bible_search.chapter.chapter as i32;
-    let verses = get_verses(&bible_search.chapter);
-
-    sqlx::query_as!(
-        SearchResult,
-        "
-                SELECT
-                    b.title as title,
-                    c.num as chapter,
+    options: SearchOptions,
+) -> ReceiverStream<Result<SearchResult, sqlx::Error>> {
+    let translation = translation.to_owned();
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+
+    tokio::spawn(async move {
+        let (chapters, verses): (Vec<i32>, Vec<i32>) =
+            get_verses(&bible_search, options.superscription)
+                .into_iter()
+                .unzip();
+        let ids = chapters
+            .iter()
+            .zip(&verses)
+            .map(|(chapter, verse)| VerseIds::new(&bible_search.title, *chapter, *verse).verse_id)
+            .collect::<Vec<i32>>();
+
+        let mut connection = match pool_stats::acquire(&pool).await {
+            Ok(connection) => connection,
+            Err(err) => {
+                let _ = sender.send(Err(err)).await;
+                return;
+            }
+        };
+
+        // A verse bridged to the one before it (ex: 5 of \v 4-5) is read
+        // from the row of the bridge, which is sent once
+        let mut rows = sqlx::query_as!(
+            SearchResult,
+            r#"
+                SELECT DISTINCT ON (w.chapter, v.num)
+                    w.verse_id - w.verse + v.num as "verse_id!",
+                    w.verse_id / 1000 as "chapter_id!",
+                    w.verse_id / 1000000 as "book_id!",
+                    $6::text as "translation!",
+                    v.title as title,
+                    v.chapter_num as chapter,
                     v.num as verse,
-                    v.contents as text
-                FROM books b
-                    INNER JOIN chapters c ON c.title = b.title
-                    INNER JOIN verses v ON v.title = c.title
-                        AND v.chapter_num = c.num
-                WHERE b.title = $1
-                    AND c.num = $2
-                    AND v.num = ANY($3)
-              ORDER BY v.num
-      ",
-        title,
-        chapter,
-        &verses[..],
+                    CASE WHEN $5
+                        THEN COALESCE(v.formatted_contents, v.contents)
+                        ELSE v.contents
+                    END as "text!",
+                    v.paragraph_start as paragraph_start,
+                    false as "degraded!"
+                FROM unnest($2::int[], $3::int[], $4::int[])
+                        AS w(chapter, verse, verse_id)
+                    INNER JOIN verses v ON v.title = $1
+                        AND v.chapter_num = w.chapter
+                        AND w.verse BETWEEN v.num AND COALESCE(v.bridged_to, v.num)
+                WHERE v.version = (
+                    SELECT t.version FROM translation_versions t
+                    WHERE t.translation = $6 AND t.state = 'active'
+                )
+              ORDER BY w.chapter, v.num
+      "#,
+            bible_search.title,
+            &chapters[..],
+            &verses[..],
+            &ids[..],
+            options.format == TextFormat::Html,
+            translation,
+        )
+        .fetch(&mut *connection);
+
+        // Stop fetching as soon as the receiving side has gone away
+        while let Some(row) = rows.next().await {
+            if sender.send(row).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    ReceiverStream::new(receiver)
+}
+
+/// The search_many function fetches the verses of several searches from a
+/// translation in one statement, saving a round trip per search, and splits
+/// the rows back into the verses of each search, in the order the searches
+/// were given.
+pub async fn search_many(
+    pool: Pool<Postgres>,
+    translation: &str,
+    searches: &[(BibleSearch, SearchOptions)],
+) -> Result<Vec<Vec<SearchResult>>, BibleApiError> {
+    let wanted = get_wanted_verses(searches);
+    let mut connection = pool_stats::acquire(&pool).await.map_err(internal_error)?;
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT DISTINCT ON (w.search, v.chapter_num, v.num)
+                w.search as "search!",
+                v.title as title,
+                v.chapter_num as chapter,
+                v.num as verse,
+                CASE WHEN w.html
+                    THEN COALESCE(v.formatted_contents, v.contents)
+                    ELSE v.contents
+                END as "text!",
+                v.paragraph_start as paragraph_start
+            FROM unnest($1::int[], $2::text[], $3::int[], $4::int[], $5::bool[])
+                    AS w(search, title, chapter, verse, html)
+                INNER JOIN verses v ON v.title = w.title
+                    AND v.chapter_num = w.chapter
+                    AND w.verse BETWEEN v.num AND COALESCE(v.bridged_to, v.num)
+            WHERE v.version = (
+                SELECT t.version FROM translation_versions t
+                WHERE t.translation = $6 AND t.state = 'active'
+            )
+          ORDER BY w.search, v.chapter_num, v.num
+        "#,
+        &wanted.searches[..],
+        &wanted.titles[..],
+        &wanted.chapters[..],
+        &wanted.verses[..],
+        &wanted.html[..],
+        translation,
     )
-    .fetch_all(&pool)
+    .fetch_all(&mut *connection)
     .await
-    .map(Json)
-    .map_err(internal_error)
+    .map_err(internal_error)?;
+
+    let mut results = vec![vec![]; searches.len()];
+    for row in rows {
+        let result = SearchResult::new(
+            translation,
+            row.title,
+            row.chapter,
+            row.verse,
+            row.text,
+            row.paragraph_start,
+        );
+        if let Some(search) = usize::try_from(row.search)
+            .ok()
+            .and_then(|search| results.get_mut(search))
+        {
+            search.push(result);
+        }
+    }
+
+    Ok(results)
+}
+
+// The WantedVerses are the verses of several searches as the columns of a
+// table, a row per verse, each marked with the index of its search
+#[derive(Debug, Default, PartialEq)]
+struct WantedVerses {
+    searches: Vec<i32>,
+    titles: Vec<String>,
+    chapters: Vec<i32>,
+    verses: Vec<i32>,
+    html: Vec<bool>,
 }
 
-fn get_verses(chapter: &Chapter) -> Vec<i32> {
-    chapter
-        .verses
+fn get_wanted_verses(searches: &[(BibleSearch, SearchOptions)]) -> WantedVerses {
+    let mut wanted = WantedVerses::default();
+
+    for (index, (bible_search, options)) in searches.iter().enumerate() {
+        for (chapter, verse) in get_verses(bible_search, options.superscription) {
+            wanted.searches.push(index as i32);
+            wanted.titles.push(bible_search.title.clone());
+            wanted.chapters.push(chapter);
+            wanted.verses.push(verse);
+            wanted.html.push(options.format == TextFormat::Html);
+        }
+    }
+
+    wanted
+}
+
+/// The get_verses function returns the chapter and verse of every verse of
+/// a search, and of the superscription of each of its chapters when it is
+/// asked for.
+pub fn get_verses(bible_search: &BibleSearch, superscription: bool) -> Vec<(i32, i32)> {
+    bible_search
+        .chapters
         .iter()
-        .map(|v| i32::from(*v))
-        .collect::<Vec<i32>>()
+        .flat_map(|chapter| {
+            let superscription = superscription.then_some(SUPERSCRIPTION_VERSE);
+
+            chapter
+                .verses
+                .iter()
+                .copied()
+                .chain(superscription)
+                .map(|verse| (i32::from(chapter.chapter), i32::from(verse)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Chapter;
+
+    // A translation of Jude that bridges verses 2-3, and 4 through the end
+    #[cfg(feature = "import")]
+    async fn import_bridges(pool: &Pool<Postgres>) {
+        let book = crate::import::parse_usfm(
+            "\\id JUD\n\\c 1\n\\p\n\\v 1 Jude\n\\v 2-3 Mercy\n\\v 4-25 The rest",
+        )
+        .unwrap();
+        crate::import::import(pool, "bridges", &[book], |_| {})
+            .await
+            .unwrap();
+    }
+
+    #[cfg(feature = "import")]
+    #[sqlx::test]
+    async fn search_reads_a_bridged_verse_from_the_row_of_its_bridge(pool: Pool<Postgres>) {
+        import_bridges(&pool).await;
+        let plain = SearchOptions {
+            superscription: false,
+            format: TextFormat::Plain,
+        };
+
+        let verses = search(
+            pool.clone(),
+            "bridges",
+            crate::search::search("Jude 1:3").unwrap(),
+            plain,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            verses
+                .iter()
+                .map(|verse| (verse.verse, verse.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(2, "Mercy")]
+        );
+
+        // A search over the whole bridge still gets it once
+        let verses = search_many(
+            pool,
+            "bridges",
+            &[(crate::search::search("Jude 1:2-5").unwrap(), plain)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            verses[0]
+                .iter()
+                .map(|verse| verse.verse)
+                .collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+    }
+
+    #[test]
+    fn text_format_parses_each_format_name() {
+        assert_eq!("plain".parse::<TextFormat>(), Ok(TextFormat::Plain));
+        assert_eq!(" HTML ".parse::<TextFormat>(), Ok(TextFormat::Html));
+        assert!("usfm".parse::<TextFormat>().is_err());
+    }
+
+    #[test]
+    fn get_wanted_verses_marks_each_verse_with_its_search() {
+        let search = |title: &str, chapter: u8, verses: &[u8]| BibleSearch {
+            title: title.to_owned(),
+            chapters: vec![Chapter {
+                chapter,
+                verses: verses.iter().copied().collect(),
+            }],
+        };
+        let plain = SearchOptions {
+            superscription: false,
+            format: TextFormat::Plain,
+        };
+        let with_superscription = SearchOptions {
+            superscription: true,
+            format: TextFormat::Html,
+        };
+
+        let wanted = get_wanted_verses(&[
+            (search("John", 3, &[16]), plain),
+            (search("Psalms", 3, &[1]), with_superscription),
+        ]);
+
+        assert_eq!(
+            wanted,
+            WantedVerses {
+                searches: vec![0, 1, 1],
+                titles: vec!["John".into(), "Psalms".into(), "Psalms".into()],
+                chapters: vec![3, 3, 3],
+                verses: vec![16, 1, 0],
+                html: vec![false, true, true],
+            }
+        );
+    }
 }
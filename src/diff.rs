@@ -0,0 +1,304 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    db::{SearchOptions, SearchResult, TextFormat},
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error, parse,
+    rate_limit::VerseCount,
+    search::{get_reference_with, is_whole_chapter_with, search_with},
+    state::AppState,
+    validation::check_reference,
+    verse_id::VerseIds,
+    versions,
+};
+
+/// The DiffOp is what a span of a diff does to turn the first rendering into
+/// the second.
+/// - Equal (equal) is in both
+/// - Delete (delete) is only in the first
+/// - Insert (insert) is only in the second
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// The DiffSpan is a run of words that share an op, joined by spaces.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DiffSpan {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// The VerseDiff is the diff of a verse of the passage.
+#[derive(Debug, Serialize)]
+pub struct VerseDiff {
+    #[serde(flatten)]
+    pub ids: VerseIds,
+    pub chapter: i32,
+    pub verse: i32,
+    pub spans: Vec<DiffSpan>,
+}
+
+/// The Diff compares the passage in two translations, verse by verse.
+#[derive(Debug, Serialize)]
+pub struct Diff {
+    pub reference: String,
+    pub a: String,
+    pub b: String,
+    pub verses: Vec<VerseDiff>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    query: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    a: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    b: Option<String>,
+}
+
+/// The diff handler serves /diff (ex: /diff?query=John 3:16&a=kjv&b=web)
+/// with a word level diff of the passage from translation a to translation
+/// b. Words are compared exactly, so a change of case or punctuation is a
+/// change of the word. A verse only one translation has is a single span.
+pub async fn diff(
+    State(state): State<AppState>,
+    Query(params): Query<DiffParams>,
+) -> Result<Response, Response> {
+    let missing = |param: &str| {
+        BibleApiError::from((
+            StatusCode::BAD_REQUEST,
+            format!("missing {} parameter", param),
+        ))
+        .into_response()
+    };
+    let query = params.query.ok_or_else(|| missing("query"))?;
+    let a = params.a.ok_or_else(|| missing("a"))?.to_lowercase();
+    let b = params.b.ok_or_else(|| missing("b"))?.to_lowercase();
+    check_reference("query", &query).map_err(IntoResponse::into_response)?;
+
+    let active = versions::get_active_versions(&state.pool)
+        .await
+        .map_err(|err| internal_error(err).into_response())?;
+    for translation in [&a, &b] {
+        if !active
+            .iter()
+            .any(|version| &version.translation == translation)
+        {
+            return Err(BibleApiError::from((
+                StatusCode::NOT_FOUND,
+                format!("No Matching Translation Found: {}", translation),
+            ))
+            .into_response());
+        }
+    }
+
+    // The passage is read the way the first translation numbers its verses
+    let versification = state.versifications.get(&a);
+    let bible_search = search_with(&query, &versification)
+        .map_err(|err| parse::unresolved(&query, err.into()).into_response())?;
+    let options = SearchOptions {
+        superscription: is_whole_chapter_with(&bible_search, &versification),
+        format: TextFormat::Plain,
+    };
+
+    let reference = get_reference_with(&bible_search, &versification);
+    let store = state.breaker.store();
+    let (a_verses, b_verses) = tokio::try_join!(
+        store.search(&a, bible_search.clone(), options),
+        store.search(&b, bible_search, options),
+    )
+    .map_err(IntoResponse::into_response)?;
+
+    let verse_count = a_verses.len() + b_verses.len();
+    let diff = Diff {
+        reference,
+        a,
+        b,
+        verses: diff_verses(a_verses, b_verses),
+    };
+
+    Ok((Extension(VerseCount(verse_count)), Json(diff)).into_response())
+}
+
+// Pair the verses of the two translations by their number and diff each pair
+fn diff_verses(a: Vec<SearchResult>, b: Vec<SearchResult>) -> Vec<VerseDiff> {
+    let title = a.iter().chain(&b).next().map(|result| result.title.clone());
+    let mut pairs: BTreeMap<(i32, i32), (Option<String>, Option<String>)> = BTreeMap::new();
+
+    for result in a {
+        pairs.entry((result.chapter, result.verse)).or_default().0 = Some(result.text);
+    }
+    for result in b {
+        pairs.entry((result.chapter, result.verse)).or_default().1 = Some(result.text);
+    }
+
+    pairs
+        .into_iter()
+        .map(|((chapter, verse), (a, b))| VerseDiff {
+            ids: VerseIds::new(title.as_deref().unwrap_or_default(), chapter, verse),
+            chapter,
+            verse,
+            spans: diff_words(
+                a.as_deref().unwrap_or_default(),
+                b.as_deref().unwrap_or_default(),
+            ),
+        })
+        .collect()
+}
+
+/// The diff_words function diffs two texts a word at a time, using their
+/// longest common subsequence of words. Where words were replaced the
+/// deleted span comes before the inserted one.
+pub fn diff_words(a: &str, b: &str) -> Vec<DiffSpan> {
+    let a = a.split_whitespace().collect::<Vec<&str>>();
+    let b = b.split_whitespace().collect::<Vec<&str>>();
+
+    // lengths[i][j] is the length of the longest common subsequence of the
+    // words of a from i and the words of b from j
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = match a[i] == b[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let mut words = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            words.push((DiffOp::Equal, a[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < a.len() && (j == b.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            words.push((DiffOp::Delete, a[i]));
+            i += 1;
+        } else {
+            words.push((DiffOp::Insert, b[j]));
+            j += 1;
+        }
+    }
+
+    merge_words(words)
+}
+
+// Join the runs of words that share an op into spans, keeping the deleted
+// words of a change ahead of the inserted ones
+fn merge_words(words: Vec<(DiffOp, &str)>) -> Vec<DiffSpan> {
+    let mut spans: Vec<DiffSpan> = vec![];
+    let mut deleted: Vec<&str> = vec![];
+    let mut inserted: Vec<&str> = vec![];
+    let mut equal: Vec<&str> = vec![];
+
+    let flush = |spans: &mut Vec<DiffSpan>, op: DiffOp, words: &mut Vec<&str>| {
+        if !words.is_empty() {
+            spans.push(DiffSpan {
+                op,
+                text: words.join(" "),
+            });
+            words.clear();
+        }
+    };
+
+    for (op, word) in words {
+        match op {
+            DiffOp::Equal => {
+                flush(&mut spans, DiffOp::Delete, &mut deleted);
+                flush(&mut spans, DiffOp::Insert, &mut inserted);
+                equal.push(word);
+            }
+            DiffOp::Delete => {
+                flush(&mut spans, DiffOp::Equal, &mut equal);
+                deleted.push(word);
+            }
+            DiffOp::Insert => {
+                flush(&mut spans, DiffOp::Equal, &mut equal);
+                inserted.push(word);
+            }
+        }
+    }
+    flush(&mut spans, DiffOp::Equal, &mut equal);
+    flush(&mut spans, DiffOp::Delete, &mut deleted);
+    flush(&mut spans, DiffOp::Insert, &mut inserted);
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(op: DiffOp, text: &str) -> DiffSpan {
+        DiffSpan {
+            op,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn diff_words_finds_the_changed_words() {
+        assert_eq!(
+            diff_words(
+                "For God so loved the world, that he gave",
+                "For God so loved the world that he gave"
+            ),
+            [
+                span(DiffOp::Equal, "For God so loved the"),
+                span(DiffOp::Delete, "world,"),
+                span(DiffOp::Insert, "world"),
+                span(DiffOp::Equal, "that he gave"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_words_handles_added_and_missing_text() {
+        assert_eq!(
+            diff_words("Jesus wept.", "Jesus wept loudly."),
+            [
+                span(DiffOp::Equal, "Jesus"),
+                span(DiffOp::Delete, "wept."),
+                span(DiffOp::Insert, "wept loudly."),
+            ]
+        );
+        assert_eq!(
+            diff_words("", "In the beginning"),
+            [span(DiffOp::Insert, "In the beginning")]
+        );
+        assert!(diff_words("", "").is_empty());
+    }
+
+    #[test]
+    fn diff_verses_keeps_verses_only_one_translation_has() {
+        let verse = |verse: i32, text: &str| {
+            SearchResult::new(
+                "kjv",
+                String::from("Mark"),
+                9,
+                verse,
+                text.to_owned(),
+                false,
+            )
+        };
+
+        let verses = diff_verses(vec![verse(43, "a"), verse(44, "b")], vec![verse(43, "a")]);
+
+        assert_eq!(verses.len(), 2);
+        assert_eq!(verses[1].ids.verse_id, 41_009_044);
+        assert_eq!(verses[1].spans, [span(DiffOp::Delete, "b")]);
+    }
+}
@@ -0,0 +1,157 @@
+use axum::async_trait;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    db::{get_default_translation, SearchOptions, SearchResult},
+    error::BibleApiError,
+    offline::OfflineDataset,
+    search::BibleSearch,
+    store::VerseStore,
+};
+
+/// The TEXT is the KJV, a verse per line in the same shape as the ndjson
+/// format, built into the binary with the embedded feature.
+const TEXT: &str = include_str!("../db/kjv.ndjson");
+
+/// The EmbeddedStore reads the verses of the text built in from memory, so
+/// the service runs without a database. The text is served as the default
+/// translation, so DEFAULT_TRANSLATION is best left unset. It has no other
+/// translation and no formatted text.
+pub struct EmbeddedStore {
+    dataset: OfflineDataset,
+}
+
+impl EmbeddedStore {
+    /// The load function reads the text built in into memory.
+    pub fn load() -> Self {
+        EmbeddedStore {
+            dataset: OfflineDataset::parse(TEXT).expect("the text built in is valid"),
+        }
+    }
+
+    // The verses of a search, which only the default translation has
+    fn get_verses(
+        &self,
+        translation: &str,
+        bible_search: &BibleSearch,
+        options: SearchOptions,
+    ) -> Vec<SearchResult> {
+        match translation == get_default_translation() {
+            true => self.dataset.search(bible_search, options.superscription),
+            false => vec![],
+        }
+    }
+}
+
+#[async_trait]
+impl VerseStore for EmbeddedStore {
+    fn name(&self) -> &'static str {
+        "embedded"
+    }
+
+    // The text is in memory, so there is nothing to wait on
+    async fn ping(&self) -> Result<(), BibleApiError> {
+        Ok(())
+    }
+
+    async fn is_active(&self, translation: &str) -> Result<bool, BibleApiError> {
+        Ok(translation == get_default_translation())
+    }
+
+    async fn search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> Result<Vec<SearchResult>, BibleApiError> {
+        Ok(self.get_verses(translation, &bible_search, options))
+    }
+
+    async fn search_many(
+        &self,
+        translation: &str,
+        searches: &[(BibleSearch, SearchOptions)],
+    ) -> Result<Vec<Vec<SearchResult>>, BibleApiError> {
+        Ok(searches
+            .iter()
+            .map(|(bible_search, options)| self.get_verses(translation, bible_search, *options))
+            .collect())
+    }
+
+    // The verses are all at hand, so they are queued at once
+    fn stream_search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> ReceiverStream<Result<SearchResult, sqlx::Error>> {
+        let verses = self.get_verses(translation, &bible_search, options);
+        let (sender, receiver) = mpsc::channel(verses.len().max(1));
+        for verse in verses {
+            let _ = sender.try_send(Ok(verse));
+        }
+
+        ReceiverStream::new(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::TextFormat, search::search};
+    use futures::StreamExt;
+
+    const PLAIN: SearchOptions = SearchOptions {
+        superscription: false,
+        format: TextFormat::Plain,
+    };
+
+    #[tokio::test]
+    async fn the_text_built_in_is_the_whole_kjv() {
+        let store = EmbeddedStore::load();
+        assert_eq!(store.dataset.verse_count(), 31102);
+
+        let verses = store
+            .search(
+                get_default_translation(),
+                search("John 3:16").unwrap(),
+                PLAIN,
+            )
+            .await
+            .unwrap();
+        assert!(verses[0].text.starts_with("For God so loved the world"));
+    }
+
+    #[tokio::test]
+    async fn stream_search_sends_every_verse_of_the_search() {
+        let store = EmbeddedStore::load();
+        let verses = store
+            .stream_search(
+                get_default_translation(),
+                search("Psalm 119").unwrap(),
+                PLAIN,
+            )
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(verses.len(), 176);
+        assert!(verses.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn other_translations_have_no_verses() {
+        let store = EmbeddedStore::load();
+
+        assert!(!store.is_active("not-a-translation").await.unwrap());
+        let verses = store
+            .search_many(
+                "not-a-translation",
+                &[(search("John 3:16").unwrap(), PLAIN)],
+            )
+            .await
+            .unwrap();
+        assert_eq!(verses.len(), 1);
+        assert!(verses[0].is_empty());
+    }
+}
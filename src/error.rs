@@ -0,0 +1,187 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bible_ref::ReferenceError;
+use serde::Serialize;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The BibleApiError is what can go wrong answering a request. Each kind is
+/// answered with its own status code, and a JSON body with the message. The
+/// kinds of a reference that can not be resolved are those of the
+/// ReferenceError it comes from.
+/// - EmptyQuery (400) is a query with no reference in it (ex: ;)
+/// - ParseError (422) is a reference that was read, but can not be searched
+///   for (ex: John 3:5-2)
+/// - UnknownBook (404) is a reference whose book could not be found
+/// - ChapterOutOfRange (422) is a strict search for a chapter the book does
+///   not have
+/// - VerseOutOfRange (422) is a strict search for a verse the chapter does
+///   not have
+/// - Db (500) is a database fetch that failed
+/// - Request is any other request that can not be answered, with its status
+///   code (ex: 400 for a missing query parameter)
+/// - Internal (500) is anything else that failed on the server
+///
+/// What went wrong on the server is logged, and only a generic message is
+/// sent, so the client is not shown the inner workings (ex: a SQL error).
+#[derive(Debug, Clone, Error)]
+pub enum BibleApiError {
+    #[error("No Results Found")]
+    EmptyQuery,
+    #[error("{0}")]
+    ParseError(String),
+    #[error("No Matching Book Found: {0}")]
+    UnknownBook(String),
+    #[error("{title} has no chapter {chapter}")]
+    ChapterOutOfRange { title: String, chapter: u8 },
+    #[error("{title} {chapter} has no verse {verse}")]
+    VerseOutOfRange {
+        title: String,
+        chapter: u8,
+        verse: String,
+    },
+    #[error("The database could not be read")]
+    Db(Arc<sqlx::Error>),
+    #[error("{message}")]
+    Request { status: StatusCode, message: String },
+    #[error("Internal Server Error")]
+    Internal,
+}
+
+impl From<sqlx::Error> for BibleApiError {
+    fn from(err: sqlx::Error) -> Self {
+        BibleApiError::Db(Arc::new(err))
+    }
+}
+
+// A server error made as a status code and message keeps the message to
+// the log
+impl From<(StatusCode, String)> for BibleApiError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        match status {
+            StatusCode::INTERNAL_SERVER_ERROR => {
+                tracing::error!("{}", message);
+                BibleApiError::Internal
+            }
+            status => BibleApiError::Request { status, message },
+        }
+    }
+}
+
+/// The ErrorBody is the JSON a BibleApiError is sent as.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+impl From<ReferenceError> for BibleApiError {
+    fn from(err: ReferenceError) -> Self {
+        match err {
+            ReferenceError::EmptyQuery => BibleApiError::EmptyQuery,
+            ReferenceError::ParseError(reason) => BibleApiError::ParseError(reason),
+            ReferenceError::UnknownBook(query) => BibleApiError::UnknownBook(query),
+            ReferenceError::ChapterOutOfRange { title, chapter } => {
+                BibleApiError::ChapterOutOfRange { title, chapter }
+            }
+            ReferenceError::VerseOutOfRange {
+                title,
+                chapter,
+                verse,
+            } => BibleApiError::VerseOutOfRange {
+                title,
+                chapter,
+                verse,
+            },
+        }
+    }
+}
+
+impl BibleApiError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            BibleApiError::EmptyQuery => StatusCode::BAD_REQUEST,
+            BibleApiError::UnknownBook(_) => StatusCode::NOT_FOUND,
+            BibleApiError::ParseError(_)
+            | BibleApiError::ChapterOutOfRange { .. }
+            | BibleApiError::VerseOutOfRange { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            BibleApiError::Db(_) | BibleApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            BibleApiError::Request { status, .. } => *status,
+        }
+    }
+
+    /// The kind function names the kind of error for the metrics (ex:
+    /// unknown_book).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BibleApiError::EmptyQuery => "empty_query",
+            BibleApiError::ParseError(_) => "parse_error",
+            BibleApiError::UnknownBook(_) => "unknown_book",
+            BibleApiError::ChapterOutOfRange { .. } => "chapter_out_of_range",
+            BibleApiError::VerseOutOfRange { .. } => "verse_out_of_range",
+            BibleApiError::Db(_) => "db",
+            BibleApiError::Request { .. } => "request",
+            BibleApiError::Internal => "internal",
+        }
+    }
+}
+
+impl IntoResponse for BibleApiError {
+    fn into_response(self) -> Response {
+        if let BibleApiError::Db(err) = &self {
+            tracing::warn!("a database fetch failed: {}", err);
+        }
+
+        (
+            self.status_code(),
+            Json(ErrorBody {
+                error: self.to_string(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_code_follows_the_kind_of_error() {
+        let out_of_range = BibleApiError::VerseOutOfRange {
+            title: String::from("John"),
+            chapter: 3,
+            verse: String::from("99"),
+        };
+
+        assert_eq!(out_of_range.to_string(), "John 3 has no verse 99");
+        assert_eq!(out_of_range.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(out_of_range.kind(), "verse_out_of_range");
+        assert_eq!(
+            BibleApiError::UnknownBook(String::from("Robert 1")).status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            BibleApiError::from(sqlx::Error::PoolTimedOut).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    fn server_errors_do_not_give_away_what_went_wrong() {
+        let db = BibleApiError::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(db.to_string(), "The database could not be read");
+
+        let internal = BibleApiError::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            String::from("relation \"verses\" does not exist"),
+        ));
+        assert_eq!(internal.to_string(), "Internal Server Error");
+
+        let request = BibleApiError::from((StatusCode::CONFLICT, String::from("in use")));
+        assert_eq!(request.status_code(), StatusCode::CONFLICT);
+        assert_eq!(request.to_string(), "in use");
+    }
+}
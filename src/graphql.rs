@@ -0,0 +1,215 @@
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Error, Object, Request, Result, Schema, SimpleObject,
+};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, OnceLock,
+};
+
+use crate::{
+    browse::{self, BookOutline},
+    chapter::Testament,
+    db::{get_default_translation, SearchOptions, SearchResult, TextFormat},
+    error::BibleApiError,
+    passages::{fetch_passages, Passage},
+    rate_limit::VerseCount,
+    search::search_passages,
+    state::AppState,
+    text_search::{self, TextSearch, TextSearchHit, TextSearchParams},
+    validation::{check_reference, check_text, MAX_TEXT_QUERY_LEN, MAX_TRANSLATION_LEN},
+};
+
+/// The MAX_DEPTH is how deeply a GraphQL query can nest its fields. The
+/// schema is shallow, so a deeper query is not one a client needs.
+const MAX_DEPTH: usize = 8;
+
+pub type BibleSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+// The verses sent for a request, added up by the resolvers for the rate
+// limit
+#[derive(Debug, Default)]
+struct VerseTally(AtomicUsize);
+
+impl VerseTally {
+    fn add(&self, verses: usize) {
+        self.0.fetch_add(verses, Ordering::Relaxed);
+    }
+}
+
+/// The TextSearchPage is a page of the verses that matched a text search,
+/// how many matched in all, and which engine answered.
+#[derive(Debug, SimpleObject)]
+pub struct TextSearchPage {
+    pub engine: String,
+    pub total: u64,
+    pub hits: Vec<TextSearchHit>,
+}
+
+/// The get_schema function returns the schema /graphql answers with, built
+/// once.
+pub fn get_schema() -> &'static BibleSchema {
+    static SCHEMA: OnceLock<BibleSchema> = OnceLock::new();
+
+    SCHEMA.get_or_init(|| {
+        Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .limit_depth(MAX_DEPTH)
+            .finish()
+    })
+}
+
+/// The graphql handler serves POST /graphql with the answer to a GraphQL
+/// query, so a client can ask for just the fields it needs, and for several
+/// books, chapters and passages at once. The verses of every field count
+/// against the rate limit.
+pub async fn graphql(State(state): State<AppState>, Json(request): Json<Request>) -> Response {
+    let tally = Arc::new(VerseTally::default());
+    let response = get_schema()
+        .execute(request.data(state).data(tally.clone()))
+        .await;
+
+    let verse_count = Extension(VerseCount(tally.0.load(Ordering::Relaxed)));
+    (verse_count, Json(response)).into_response()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// The book (ex: jn), with the number of verses in each of its chapters.
+    async fn book(&self, ctx: &Context<'_>, name: String) -> Result<BookOutline> {
+        let state = ctx.data::<AppState>()?;
+        let title = browse::find_title(&name)?;
+        let versification = state.versifications.get(get_default_translation());
+
+        Ok(browse::get_outline(&title, &versification))
+    }
+
+    /// Every verse of a chapter of a book, and its superscription if it has
+    /// one.
+    async fn chapter(
+        &self,
+        ctx: &Context<'_>,
+        book: String,
+        chapter: u8,
+        translation: Option<String>,
+    ) -> Result<Vec<SearchResult>> {
+        let state = ctx.data::<AppState>()?;
+        let translation = get_translation(state, translation).await?;
+        let title = browse::find_title(&book)?;
+        let versification = state.versifications.get(&translation);
+        let bible_search = browse::get_chapter_search(&title, chapter, &versification)?;
+        let options = SearchOptions {
+            superscription: true,
+            format: TextFormat::Plain,
+        };
+
+        let (results, _) = state
+            .breaker
+            .search(&translation, bible_search, options)
+            .await?;
+        ctx.data::<Arc<VerseTally>>()?.add(results.len());
+
+        Ok(results)
+    }
+
+    /// The passages of a reference (ex: John 3:16; Romans 8:28), each with
+    /// its verses, resolved the same way as /search.
+    async fn passage(
+        &self,
+        ctx: &Context<'_>,
+        reference: String,
+        translation: Option<String>,
+    ) -> Result<Vec<Passage>> {
+        let state = ctx.data::<AppState>()?;
+        check_reference("reference", &reference).map_err(|err| Error::new(err.reason))?;
+        let translation = get_translation(state, translation).await?;
+        let versification = state.versifications.get(&translation);
+        let searches = search_passages(&reference, &versification, false)?;
+
+        let (passages, _) = fetch_passages(
+            state.breaker.clone(),
+            &translation,
+            searches,
+            &versification,
+            TextFormat::Plain,
+            None,
+        )
+        .await?;
+        ctx.data::<Arc<VerseTally>>()?
+            .add(passages.iter().map(|passage| passage.verses.len()).sum());
+
+        Ok(passages)
+    }
+
+    /// The verses with the words searched for, best first, optionally
+    /// narrowed to a book or a testament.
+    async fn text_search(
+        &self,
+        ctx: &Context<'_>,
+        q: String,
+        book: Option<String>,
+        testament: Option<Testament>,
+        limit: Option<u8>,
+        offset: Option<u32>,
+    ) -> Result<TextSearchPage> {
+        let state = ctx.data::<AppState>()?;
+        check_text("q", &q, MAX_TEXT_QUERY_LEN).map_err(|err| Error::new(err.reason))?;
+        let text_search = TextSearch::from_params(TextSearchParams {
+            q: Some(q),
+            book,
+            testament,
+            limit,
+            offset,
+            within: None,
+        })?;
+
+        let (results, _) = text_search::run(state, &text_search)
+            .await
+            .map_err(BibleApiError::from)?;
+        ctx.data::<Arc<VerseTally>>()?.add(results.hits.len());
+
+        Ok(TextSearchPage {
+            engine: results.engine.to_owned(),
+            total: results.total,
+            hits: results.hits,
+        })
+    }
+}
+
+// The translation asked for, which has to be the default or one that is
+// being served
+async fn get_translation(state: &AppState, translation: Option<String>) -> Result<String> {
+    let translation = translation
+        .map(|translation| translation.trim().to_lowercase())
+        .unwrap_or_else(|| get_default_translation().to_owned());
+    check_text("translation", &translation, MAX_TRANSLATION_LEN)
+        .map_err(|err| Error::new(err.reason))?;
+
+    if translation != get_default_translation()
+        && !state.breaker.store().is_active(&translation).await?
+    {
+        return Err(Error::new("No Matching Translation Found"));
+    }
+
+    Ok(translation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_schema_lists_the_queries() {
+        let sdl = get_schema().sdl();
+
+        for query in ["book(", "chapter(", "passage(", "textSearch("] {
+            assert!(sdl.contains(query), "{} is missing from the schema", query);
+        }
+        assert!(sdl.contains("verseId: Int!"));
+    }
+}
@@ -0,0 +1,331 @@
+use axum::http::StatusCode;
+use prost::Message;
+use std::{
+    convert::Infallible,
+    future::Future,
+    net::SocketAddr,
+    task::{Context, Poll},
+};
+use tonic::{
+    body::BoxBody,
+    codec::ProstCodec,
+    codegen::{http, BoxFuture, Service},
+    server::{Grpc, NamedService, UnaryService},
+    transport::Server,
+    Status,
+};
+
+use crate::{
+    db::{get_default_translation, TextFormat},
+    error::BibleApiError,
+    render::protobuf::{Passage, Verse},
+    search::search_passages,
+    state::AppState,
+    text_search::{self, TextSearch, TextSearchHit, TextSearchParams},
+    validation::{check_reference, check_text, MAX_TEXT_QUERY_LEN, MAX_TRANSLATION_LEN},
+};
+
+/// The DEFAULT_GRPC_PORT is the port the gRPC service listens on when
+/// GRPC_PORT is not set.
+const DEFAULT_GRPC_PORT: u16 = 50051;
+
+const GET_PASSAGE_PATH: &str = "/bible.BibleService/GetPassage";
+const SEARCH_TEXT_PATH: &str = "/bible.BibleService/SearchText";
+
+// The messages are defined in proto/bible.proto, and have to be kept in step
+// with it by hand, the same as the messages of the protobuf format.
+
+/// The GetPassageRequest message is a reference to look up (see
+/// proto/bible.proto).
+#[derive(Clone, PartialEq, Message)]
+pub struct GetPassageRequest {
+    #[prost(string, tag = "1")]
+    pub reference: String,
+    #[prost(string, tag = "2")]
+    pub translation: String,
+}
+
+/// The GetPassageResponse message is each passage of a reference.
+#[derive(Clone, PartialEq, Message)]
+pub struct GetPassageResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub passages: Vec<Passage>,
+}
+
+/// The SearchTextRequest message is a text search, optionally narrowed to a
+/// book or a testament.
+#[derive(Clone, PartialEq, Message)]
+pub struct SearchTextRequest {
+    #[prost(string, tag = "1")]
+    pub q: String,
+    #[prost(string, tag = "2")]
+    pub book: String,
+    #[prost(string, tag = "3")]
+    pub testament: String,
+    #[prost(uint32, tag = "4")]
+    pub limit: u32,
+    #[prost(uint32, tag = "5")]
+    pub offset: u32,
+}
+
+/// The SearchTextResponse message is a page of the verses that matched a
+/// text search.
+#[derive(Clone, PartialEq, Message)]
+pub struct SearchTextResponse {
+    #[prost(string, tag = "1")]
+    pub engine: String,
+    #[prost(uint64, tag = "2")]
+    pub total: u64,
+    #[prost(message, repeated, tag = "3")]
+    pub hits: Vec<Verse>,
+}
+
+/// The BibleServiceServer serves the BibleService of proto/bible.proto,
+/// answering from the same state as the HTTP API.
+#[derive(Clone)]
+pub struct BibleServiceServer {
+    state: AppState,
+}
+
+impl BibleServiceServer {
+    pub fn new(state: AppState) -> Self {
+        BibleServiceServer { state }
+    }
+}
+
+impl NamedService for BibleServiceServer {
+    const NAME: &'static str = "bible.BibleService";
+}
+
+impl Service<http::Request<BoxBody>> for BibleServiceServer {
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        let state = self.state.clone();
+
+        match request.uri().path() {
+            GET_PASSAGE_PATH => Box::pin(async move {
+                let mut grpc = Grpc::new(ProstCodec::default());
+                Ok(grpc.unary(Unary(state, get_passage), request).await)
+            }),
+            SEARCH_TEXT_PATH => Box::pin(async move {
+                let mut grpc = Grpc::new(ProstCodec::default());
+                Ok(grpc.unary(Unary(state, search_text), request).await)
+            }),
+            _ => Box::pin(async { Ok(Status::unimplemented("").into_http()) }),
+        }
+    }
+}
+
+// An RPC answered by a function of the state and the request message
+struct Unary<F>(AppState, F);
+
+impl<F, Fut, Req, Res> UnaryService<Req> for Unary<F>
+where
+    F: Fn(AppState, Req) -> Fut,
+    Fut: Future<Output = Result<Res, Status>> + Send + 'static,
+{
+    type Response = Res;
+    type Future = BoxFuture<tonic::Response<Res>, Status>;
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        let response = (self.1)(self.0.clone(), request.into_inner());
+        Box::pin(async move { response.await.map(tonic::Response::new) })
+    }
+}
+
+/// The spawn_server function starts the gRPC service on GRPC_PORT (50051 by
+/// default), beside the HTTP API. It is meant for services on the same
+/// network, so it is neither rate limited nor behind an API key.
+pub fn spawn_server(state: AppState) {
+    let port = std::env::var("GRPC_PORT")
+        .ok()
+        .and_then(|port| port.trim().parse().ok())
+        .unwrap_or(DEFAULT_GRPC_PORT);
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+
+    tokio::spawn(async move {
+        tracing::debug!("gRPC listening on {}", addr);
+
+        let served = Server::builder()
+            .add_service(BibleServiceServer::new(state))
+            .serve_with_shutdown(addr, crate::server::shutdown_signal())
+            .await;
+        if let Err(err) = served {
+            tracing::error!("the gRPC service stopped: {}", err);
+        }
+    });
+}
+
+// Answer GetPassage the same way as /search answers several passages
+async fn get_passage(
+    state: AppState,
+    request: GetPassageRequest,
+) -> Result<GetPassageResponse, Status> {
+    check_reference("reference", &request.reference)
+        .map_err(|err| Status::invalid_argument(err.reason))?;
+    let translation = get_translation(&state, &request.translation).await?;
+    let versification = state.versifications.get(&translation);
+    let searches = search_passages(&request.reference, &versification, false)
+        .map_err(|err| get_status(BibleApiError::from(err)))?;
+
+    let (passages, _) = crate::passages::fetch_passages(
+        state.breaker.clone(),
+        &translation,
+        searches,
+        &versification,
+        TextFormat::Plain,
+        None,
+    )
+    .await
+    .map_err(get_status)?;
+
+    Ok(GetPassageResponse {
+        passages: passages
+            .into_iter()
+            .map(|passage| Passage {
+                reference: passage.reference,
+                ..Passage::from(passage.verses)
+            })
+            .collect(),
+    })
+}
+
+// Answer SearchText the same way as /search/text
+async fn search_text(
+    state: AppState,
+    request: SearchTextRequest,
+) -> Result<SearchTextResponse, Status> {
+    check_text("q", &request.q, MAX_TEXT_QUERY_LEN)
+        .map_err(|err| Status::invalid_argument(err.reason))?;
+    let testament = match request.testament.trim() {
+        "" => None,
+        testament => Some(testament.parse().map_err(Status::invalid_argument)?),
+    };
+
+    let text_search = TextSearch::from_params(TextSearchParams {
+        q: Some(request.q).filter(|q| !q.trim().is_empty()),
+        book: Some(request.book).filter(|book| !book.trim().is_empty()),
+        testament,
+        limit: (request.limit > 0).then(|| u8::try_from(request.limit).unwrap_or(u8::MAX)),
+        offset: Some(request.offset),
+        within: None,
+    })
+    .map_err(get_status)?;
+
+    let (results, _) = text_search::run(&state, &text_search)
+        .await
+        .map_err(|err| get_status(err.into()))?;
+
+    Ok(SearchTextResponse {
+        engine: results.engine.to_owned(),
+        total: results.total,
+        hits: results
+            .hits
+            .into_iter()
+            .map(|hit| get_hit(hit, &text_search.translation))
+            .collect(),
+    })
+}
+
+// The translation asked for, which has to be the default or one that is
+// being served
+async fn get_translation(state: &AppState, translation: &str) -> Result<String, Status> {
+    let translation = match translation.trim() {
+        "" => get_default_translation().to_owned(),
+        translation => translation.to_lowercase(),
+    };
+    check_text("translation", &translation, MAX_TRANSLATION_LEN)
+        .map_err(|err| Status::invalid_argument(err.reason))?;
+
+    let active = translation == get_default_translation()
+        || state
+            .breaker
+            .store()
+            .is_active(&translation)
+            .await
+            .map_err(get_status)?;
+    match active {
+        true => Ok(translation),
+        false => Err(Status::not_found("No Matching Translation Found")),
+    }
+}
+
+fn get_hit(hit: TextSearchHit, translation: &str) -> Verse {
+    Verse {
+        title: hit.title,
+        chapter: hit.chapter,
+        verse: hit.verse,
+        text: hit.text,
+        paragraph_start: false,
+        verse_id: hit.ids.verse_id,
+        chapter_id: hit.ids.chapter_id,
+        book_id: hit.ids.book_id,
+        translation: translation.to_owned(),
+    }
+}
+
+// The gRPC status of an error the HTTP API would answer with the status code
+fn get_status(err: BibleApiError) -> Status {
+    let message = err.to_string();
+    match err.status_code() {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+            Status::invalid_argument(message)
+        }
+        StatusCode::NOT_FOUND => Status::not_found(message),
+        StatusCode::SERVICE_UNAVAILABLE => Status::unavailable(message),
+        _ => Status::internal(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    #[test]
+    fn get_status_follows_the_status_code() {
+        let status = |status_code| {
+            get_status(BibleApiError::from((status_code, String::from("oops")))).code()
+        };
+
+        assert_eq!(status(StatusCode::BAD_REQUEST), Code::InvalidArgument);
+        assert_eq!(
+            status(StatusCode::UNPROCESSABLE_ENTITY),
+            Code::InvalidArgument
+        );
+        assert_eq!(status(StatusCode::NOT_FOUND), Code::NotFound);
+        assert_eq!(status(StatusCode::SERVICE_UNAVAILABLE), Code::Unavailable);
+        assert_eq!(status(StatusCode::INTERNAL_SERVER_ERROR), Code::Internal);
+    }
+
+    #[test]
+    fn get_hit_keeps_the_ids_of_the_verse() {
+        let hit = TextSearchHit::new(String::from("John"), 11, 35, String::from("Jesus wept."));
+        let verse = get_hit(hit, "kjv");
+
+        assert_eq!(verse.verse_id, 43_011_035);
+        assert_eq!(verse.book_id, 43);
+        assert_eq!(verse.translation, "kjv");
+        assert_eq!(
+            SearchTextResponse::decode(
+                SearchTextResponse {
+                    engine: String::from("postgres"),
+                    total: 1,
+                    hits: vec![verse.clone()],
+                }
+                .encode_to_vec()
+                .as_slice()
+            )
+            .unwrap()
+            .hits,
+            vec![verse]
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use std::time::{Duration, Instant};
+
+use crate::{
+    pool_stats::{self, PoolSummary},
+    state::AppState,
+    store::Backend,
+    versions::{self, TranslationVersion},
+};
+
+/// The SLOW_DATABASE is the round trip over which the database is reported
+/// as degraded rather than ok.
+const SLOW_DATABASE: Duration = Duration::from_millis(250);
+
+/// The SATURATED_POOL is the share of the pool in use over which the pool is
+/// reported as degraded rather than ok.
+const SATURATED_POOL: f64 = 0.9;
+
+/// The READY_TIMEOUT is how long the readiness probe waits on the database
+/// before the server is reported as not ready.
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The REQUIRED_TABLES are the tables the migrations create. The server
+/// is not ready until every one of them is there.
+const REQUIRED_TABLES: [&str; 23] = [
+    "api_keys",
+    "audit_log",
+    "books",
+    "chapter_audio",
+    "chapters",
+    "lectionary_days",
+    "lectionary_readings",
+    "office_readings",
+    "people",
+    "person_appearances",
+    "person_relationships",
+    "places",
+    "rate_limit_tiers",
+    "saved_searches",
+    "search_trends",
+    "source_tokens",
+    "timeline_events",
+    "translation_versions",
+    "translations",
+    "verse_timings",
+    "verse_views",
+    "verses",
+    "word_alignments",
+];
+
+/// The HealthStatus is the overall health of the server.
+/// - Ok is every dependency answering in good time
+/// - Degraded is still answering searches, but slowly, from a busy pool or
+///   from the offline dataset
+/// - Down is not able to answer searches at all
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+/// The DatabaseHealth is how a round trip to the database went.
+#[derive(Debug, Serialize)]
+pub struct DatabaseHealth {
+    pub ok: bool,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The CacheHealth is how responses are cached: the Cache-Control sent with
+/// passages, and whether the CDN can be purged.
+#[derive(Debug, Serialize)]
+pub struct CacheHealth {
+    pub passage_cache_control: String,
+    pub cdn_purge: bool,
+}
+
+/// The DatasetHealth is what the server has to search: the circuit breaker
+/// in front of the database, the offline dataset behind it, and the version
+/// of every translation being served.
+#[derive(Debug, Serialize)]
+pub struct DatasetHealth {
+    pub breaker: &'static str,
+    pub offline_verses: Option<usize>,
+    pub translations: Vec<TranslationVersion>,
+}
+
+/// The Readiness is the body of GET /readyz. The database is whether the
+/// store of the verses answered, and the missing tables are those of the
+/// scripts in db/ that have not been run yet, which only Postgres has.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: bool,
+    pub missing_tables: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The HealthReport is the body of GET /health.
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub database: DatabaseHealth,
+    pub pool: PoolSummary,
+    pub cache: CacheHealth,
+    pub dataset: DatasetHealth,
+}
+
+/// The health handler serves GET /health with the state of every dependency,
+/// so monitoring can alert on a degraded server and not only on one that is
+/// down. A server that is down answers with a 503.
+pub async fn health(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let database = check_database(&state.pool).await;
+    let pool = pool_stats::get_summary(&state.pool);
+
+    // The versions can only be listed while the database answers
+    let translations = match database.ok {
+        true => versions::get_active_versions(&state.pool)
+            .await
+            .unwrap_or_default(),
+        false => vec![],
+    };
+
+    let dataset = DatasetHealth {
+        breaker: state.breaker.state_name(),
+        offline_verses: state.breaker.offline().map(|offline| offline.verse_count()),
+        translations,
+    };
+    let status = get_status(&database, &pool, &dataset);
+
+    let report = HealthReport {
+        status,
+        database,
+        pool,
+        cache: CacheHealth {
+            passage_cache_control: state.cache_policy.passage.clone(),
+            cdn_purge: state.cdn.is_some(),
+        },
+        dataset,
+    };
+
+    let code = match status {
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    };
+    (code, Json(report))
+}
+
+/// The healthz handler serves GET /healthz, the liveness probe. It answers
+/// as long as the process can serve requests, and looks at nothing else, so
+/// a database outage does not get the server restarted.
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// The readyz handler serves GET /readyz, the readiness probe, whatever the
+/// store of the verses. The server is ready when the store answers a ping
+/// within READY_TIMEOUT and, on Postgres, has every table the server reads
+/// from; otherwise it answers with a 503 saying what is missing, so no
+/// traffic is sent its way.
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let missing_tables = tokio::time::timeout(READY_TIMEOUT, check_store(&state)).await;
+
+    let readiness = match missing_tables {
+        Ok(Ok(missing_tables)) => Readiness {
+            ready: missing_tables.is_empty(),
+            database: true,
+            missing_tables,
+            error: None,
+        },
+        Ok(Err(err)) => not_ready(err),
+        Err(_) => not_ready(format!(
+            "the store did not answer within {:?}",
+            READY_TIMEOUT
+        )),
+    };
+
+    let code = match readiness.ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (code, Json(readiness))
+}
+
+fn not_ready(error: String) -> Readiness {
+    Readiness {
+        ready: false,
+        database: false,
+        missing_tables: vec![],
+        error: Some(error),
+    }
+}
+
+// The store answers, then the required tables Postgres does not have
+async fn check_store(state: &AppState) -> Result<Vec<String>, String> {
+    let store = state.breaker.store();
+    store.ping().await.map_err(|err| err.to_string())?;
+
+    match store.name() == Backend::Postgres.name() {
+        true => get_missing_tables(&state.pool)
+            .await
+            .map_err(|err| err.to_string()),
+        false => Ok(vec![]),
+    }
+}
+
+// The required tables that are not in the database
+async fn get_missing_tables(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+    let tables = REQUIRED_TABLES.map(String::from);
+
+    sqlx::query_scalar!(
+        r#"
+            SELECT name as "name!" FROM unnest($1::text[]) name
+            WHERE to_regclass('public.' || name) IS NULL
+        "#,
+        &tables[..]
+    )
+    .fetch_all(pool)
+    .await
+}
+
+async fn check_database(pool: &PgPool) -> DatabaseHealth {
+    let started = Instant::now();
+    let result = sqlx::query_scalar!("SELECT 1 as \"one!\"")
+        .fetch_one(pool)
+        .await;
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    DatabaseHealth {
+        ok: result.is_ok(),
+        latency_ms: (latency_ms * 100.0).round() / 100.0,
+        error: result.err().map(|err| err.to_string()),
+    }
+}
+
+// Searches still work without the database while there is an offline
+// dataset to fall back on
+fn get_status(
+    database: &DatabaseHealth,
+    pool: &PoolSummary,
+    dataset: &DatasetHealth,
+) -> HealthStatus {
+    if !database.ok {
+        return match dataset.offline_verses {
+            Some(_) => HealthStatus::Degraded,
+            None => HealthStatus::Down,
+        };
+    }
+
+    let slow = database.latency_ms > SLOW_DATABASE.as_secs_f64() * 1000.0;
+    if slow || pool.saturation >= SATURATED_POOL || dataset.breaker != "closed" {
+        return HealthStatus::Degraded;
+    }
+
+    HealthStatus::Ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(ok: bool, latency_ms: f64) -> DatabaseHealth {
+        DatabaseHealth {
+            ok,
+            latency_ms,
+            error: None,
+        }
+    }
+
+    fn pool(saturation: f64) -> PoolSummary {
+        PoolSummary {
+            size: 5,
+            idle: 0,
+            max_connections: 5,
+            saturation,
+            verse_fetch_timeouts: 0,
+        }
+    }
+
+    fn dataset(breaker: &'static str, offline_verses: Option<usize>) -> DatasetHealth {
+        DatasetHealth {
+            breaker,
+            offline_verses,
+            translations: vec![],
+        }
+    }
+
+    #[test]
+    fn get_status_is_ok_when_everything_answers_in_good_time() {
+        assert_eq!(
+            get_status(&database(true, 2.0), &pool(0.2), &dataset("closed", None)),
+            HealthStatus::Ok
+        );
+    }
+
+    #[test]
+    fn get_status_is_degraded_when_a_dependency_struggles() {
+        let cases = [
+            get_status(&database(true, 900.0), &pool(0.2), &dataset("closed", None)),
+            get_status(&database(true, 2.0), &pool(1.0), &dataset("closed", None)),
+            get_status(
+                &database(true, 2.0),
+                &pool(0.2),
+                &dataset("half-open", None),
+            ),
+            get_status(
+                &database(false, 2.0),
+                &pool(0.2),
+                &dataset("open", Some(10)),
+            ),
+        ];
+
+        assert!(cases.iter().all(|status| *status == HealthStatus::Degraded));
+    }
+
+    #[test]
+    fn required_tables_are_each_listed_once() {
+        let mut tables = REQUIRED_TABLES.to_vec();
+        tables.sort_unstable();
+        tables.dedup();
+
+        assert_eq!(tables.len(), REQUIRED_TABLES.len());
+    }
+
+    #[test]
+    fn get_status_is_down_without_a_database_or_a_fallback() {
+        assert_eq!(
+            get_status(&database(false, 2.0), &pool(0.0), &dataset("open", None)),
+            HealthStatus::Down
+        );
+    }
+}
@@ -0,0 +1,441 @@
+use axum::{
+    body::{self, Body, Bytes, HttpBody},
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use lru::LruCache;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    api_keys::hash_key, error::BibleApiError, privacy::get_client_address,
+    rate_limit::API_KEY_HEADER,
+};
+
+/// The IDEMPOTENCY_KEY_HEADER carries the key a client picks for a write, and
+/// sends again with each retry of it.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// The REPLAYED_HEADER marks a response that was kept from the first time a
+/// write was made, instead of making it again.
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// The KEY_TTL is how long the response to a write is kept for its retries.
+const KEY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The SWEEP_INTERVAL is how often the responses older than KEY_TTL are
+/// forgotten.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The MAX_KEYS is the most keys that are kept at once. Past it, the key
+/// least recently used is forgotten first, so a client sending a new key
+/// with every write can not grow the store without bound.
+const MAX_KEYS: NonZeroUsize = match NonZeroUsize::new(10_000) {
+    Some(max_keys) => max_keys,
+    None => unreachable!(),
+};
+
+/// The MAX_KEY_LEN is the most characters an idempotency key can take.
+const MAX_KEY_LEN: usize = 255;
+
+/// The MAX_RESPONSE_BYTES is the largest response that is kept. A write
+/// with a larger response, or one that is streamed, is not protected from
+/// retries.
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+// The response kept for the retries of a write
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+#[derive(Debug)]
+enum EntryState {
+    InProgress,
+    Done(StoredResponse),
+}
+
+#[derive(Debug)]
+struct Entry {
+    fingerprint: u64,
+    created: Instant,
+    state: EntryState,
+}
+
+// The Lookup is what to do with a write, given what was seen of its key
+#[derive(Debug)]
+enum Lookup {
+    Started,
+    Replay(StoredResponse),
+    InProgress,
+    Mismatch,
+}
+
+/// The IdempotencyStore keeps the response to each write made with an
+/// idempotency key, so a retry of the write (ex: after a mobile client lost
+/// the response) is answered with it instead of being made twice. Up to
+/// MAX_KEYS keys are kept for KEY_TTL, in memory, and are only shared by the
+/// client that sent them. It is cheap to clone and shared by every request.
+#[derive(Clone)]
+pub struct IdempotencyStore {
+    entries: Arc<Mutex<LruCache<String, Entry>>>,
+    max_body_bytes: usize,
+}
+
+impl IdempotencyStore {
+    /// The new function makes an empty store that reads request bodies of
+    /// up to max_body_bytes.
+    pub fn new(max_body_bytes: usize) -> Self {
+        IdempotencyStore::with_capacity(max_body_bytes, MAX_KEYS)
+    }
+
+    fn with_capacity(max_body_bytes: usize, capacity: NonZeroUsize) -> Self {
+        IdempotencyStore {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+            max_body_bytes,
+        }
+    }
+
+    /// The spawn_sweep function starts a background task that forgets the
+    /// responses older than KEY_TTL every SWEEP_INTERVAL.
+    pub fn spawn_sweep(&self) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                ticker.tick().await;
+
+                store.sweep(Instant::now());
+            }
+        });
+    }
+
+    // Forget the responses older than KEY_TTL
+    fn sweep(&self, now: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        let expired = entries
+            .iter()
+            .filter(|(_, entry)| now.duration_since(entry.created) >= KEY_TTL)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in expired {
+            entries.pop(&key);
+        }
+    }
+
+    // Start a write under a key, unless the key has already been used
+    fn lookup(&self, key: &str, fingerprint: u64, now: Instant) -> Lookup {
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.get(key) {
+            Some(entry) if now.duration_since(entry.created) < KEY_TTL => {
+                match (&entry.state, entry.fingerprint == fingerprint) {
+                    (_, false) => Lookup::Mismatch,
+                    (EntryState::InProgress, true) => Lookup::InProgress,
+                    (EntryState::Done(stored), true) => Lookup::Replay(stored.clone()),
+                }
+            }
+            _ => {
+                entries.put(
+                    key.to_owned(),
+                    Entry {
+                        fingerprint,
+                        created: now,
+                        state: EntryState::InProgress,
+                    },
+                );
+                Lookup::Started
+            }
+        }
+    }
+
+    fn finish(&self, key: &str, stored: StoredResponse) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(key) {
+            entry.state = EntryState::Done(stored);
+        }
+    }
+
+    fn forget(&self, key: &str) {
+        self.entries.lock().unwrap().pop(key);
+    }
+}
+
+// The Pending guard forgets a write that never finished (ex: the client
+// went away), so that a retry of it is made instead of waiting forever
+struct Pending<'a> {
+    store: &'a IdempotencyStore,
+    key: &'a str,
+    finished: bool,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.store.forget(self.key);
+        }
+    }
+}
+
+/// The idempotent middleware makes the writes (POST, PUT, PATCH and DELETE)
+/// sent with an Idempotency-Key header safe to retry. The first request
+/// with a key is made and its response kept; a retry with the same key gets
+/// that response again, flagged with the Idempotent-Replayed header. A key
+/// sent again while its first request is still being made gets a 409, and
+/// a key used for a different request (ex: another path or body) gets a
+/// 422. Server errors are not kept, so the write can be tried again.
+pub async fn idempotent(
+    State(store): State<IdempotencyStore>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let is_write = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let idempotency_key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(idempotency_key) if is_write => idempotency_key,
+        _ => return next.run(request).await,
+    };
+    let idempotency_key = match idempotency_key.to_str() {
+        Ok(key) if !key.trim().is_empty() && key.len() <= MAX_KEY_LEN => key.trim().to_owned(),
+        _ => {
+            return BibleApiError::from((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "{} has to be between 1 and {} visible characters",
+                    IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN
+                ),
+            ))
+            .into_response()
+        }
+    };
+
+    // A key only means something to the client that sent it. The API key is
+    // kept as its hash, like everywhere else.
+    let client = match request.headers().get(API_KEY_HEADER) {
+        Some(api_key) => format!(
+            "key:{}",
+            hash_key(&String::from_utf8_lossy(api_key.as_bytes()))
+        ),
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| format!("ip:{}", get_client_address(address.ip())))
+            .unwrap_or_else(|| String::from("anonymous")),
+    };
+    let key = format!("{} {}", client, idempotency_key);
+
+    let (parts, request_body) = request.into_parts();
+    let request_body = match body::to_bytes(request_body, store.max_body_bytes).await {
+        Ok(request_body) => request_body,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+    let fingerprint = get_fingerprint(&parts.method, &parts.uri.to_string(), &request_body);
+
+    match store.lookup(&key, fingerprint, Instant::now()) {
+        Lookup::Started => {}
+        Lookup::Replay(stored) => {
+            let mut response = (stored.status, stored.headers, stored.body).into_response();
+            response.headers_mut().insert(
+                HeaderName::from_static(REPLAYED_HEADER),
+                HeaderValue::from_static("true"),
+            );
+            return response;
+        }
+        Lookup::InProgress => {
+            return (
+                [(header::RETRY_AFTER, "1")],
+                BibleApiError::from((
+                    StatusCode::CONFLICT,
+                    "a request with this idempotency key is still being made".to_string(),
+                )),
+            )
+                .into_response()
+        }
+        Lookup::Mismatch => {
+            return BibleApiError::from((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "this idempotency key was used for a different request".to_string(),
+            ))
+            .into_response()
+        }
+    }
+
+    let mut pending = Pending {
+        store: &store,
+        key: &key,
+        finished: false,
+    };
+    let response = next
+        .run(Request::from_parts(parts, Body::from(request_body)))
+        .await;
+    let size = response.body().size_hint().exact();
+    if response.status().is_server_error()
+        || size.is_none_or(|size| size as usize > MAX_RESPONSE_BYTES)
+    {
+        return response;
+    }
+
+    let (parts, response_body) = response.into_parts();
+    let response_body = match body::to_bytes(response_body, MAX_RESPONSE_BYTES).await {
+        Ok(response_body) => response_body,
+        Err(err) => {
+            tracing::warn!("could not keep the response to {}: {}", key, err);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    store.finish(
+        &key,
+        StoredResponse {
+            status: parts.status,
+            headers: parts.headers.clone(),
+            body: response_body.clone(),
+        },
+    );
+    pending.finished = true;
+
+    Response::from_parts(parts, Body::from(response_body))
+}
+
+// The fingerprint tells a retry of a request from a different request made
+// with the same key
+fn get_fingerprint(method: &Method, uri: &str, body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    method.as_str().hash(&mut hasher);
+    uri.hash(&mut hasher);
+    body.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored() -> StoredResponse {
+        StoredResponse {
+            status: StatusCode::CREATED,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(b"saved"),
+        }
+    }
+
+    #[test]
+    fn lookup_replays_a_finished_write() {
+        let store = IdempotencyStore::new(1024);
+        let fingerprint = get_fingerprint(&Method::PUT, "/saved/john", b"{}");
+        let now = Instant::now();
+
+        assert!(matches!(
+            store.lookup("key:a 1", fingerprint, now),
+            Lookup::Started
+        ));
+        assert!(matches!(
+            store.lookup("key:a 1", fingerprint, now),
+            Lookup::InProgress
+        ));
+
+        store.finish("key:a 1", stored());
+        match store.lookup("key:a 1", fingerprint, now) {
+            Lookup::Replay(replay) => assert_eq!(replay.body, stored().body),
+            other => panic!("expected a replay, got {:?}", other),
+        }
+
+        // Another client can use the same key
+        assert!(matches!(
+            store.lookup("key:b 1", fingerprint, now),
+            Lookup::Started
+        ));
+    }
+
+    #[test]
+    fn lookup_refuses_a_key_used_for_another_request() {
+        let store = IdempotencyStore::new(1024);
+        let now = Instant::now();
+
+        store.lookup(
+            "key:a 1",
+            get_fingerprint(&Method::PUT, "/saved/john", b"{}"),
+            now,
+        );
+        assert!(matches!(
+            store.lookup(
+                "key:a 1",
+                get_fingerprint(&Method::PUT, "/saved/mark", b"{}"),
+                now
+            ),
+            Lookup::Mismatch
+        ));
+    }
+
+    #[test]
+    fn lookup_starts_again_once_a_key_expires() {
+        let store = IdempotencyStore::new(1024);
+        let fingerprint = get_fingerprint(&Method::POST, "/identify", b"{}");
+        let now = Instant::now();
+
+        store.lookup("key:a 1", fingerprint, now);
+        store.finish("key:a 1", stored());
+        assert!(matches!(
+            store.lookup("key:a 1", fingerprint, now + KEY_TTL),
+            Lookup::Started
+        ));
+    }
+
+    #[test]
+    fn lookup_forgets_the_least_recently_used_key_once_full() {
+        let store = IdempotencyStore::with_capacity(1024, NonZeroUsize::new(2).unwrap());
+        let now = Instant::now();
+
+        store.lookup("key:a 1", 1, now);
+        store.lookup("key:a 2", 1, now);
+        store.lookup("key:a 1", 1, now);
+        store.lookup("key:a 3", 1, now);
+
+        assert!(matches!(
+            store.lookup("key:a 1", 1, now),
+            Lookup::InProgress
+        ));
+        assert!(matches!(store.lookup("key:a 2", 1, now), Lookup::Started));
+    }
+
+    #[test]
+    fn sweep_forgets_the_expired_keys() {
+        let store = IdempotencyStore::new(1024);
+        let now = Instant::now();
+
+        store.lookup("key:a 1", 1, now);
+        store.lookup("key:a 2", 1, now + KEY_TTL);
+        store.sweep(now + KEY_TTL);
+
+        let entries = store.entries.lock().unwrap();
+        assert!(!entries.contains("key:a 1"));
+        assert!(entries.contains("key:a 2"));
+    }
+
+    #[test]
+    fn pending_forgets_a_write_that_did_not_finish() {
+        let store = IdempotencyStore::new(1024);
+        let now = Instant::now();
+
+        store.lookup("key:a 1", 1, now);
+        drop(Pending {
+            store: &store,
+            key: "key:a 1",
+            finished: false,
+        });
+        assert!(matches!(store.lookup("key:a 1", 1, now), Lookup::Started));
+    }
+}
@@ -0,0 +1,461 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use std::collections::HashSet;
+
+use crate::{
+    chapter::get_book_number,
+    db::get_default_translation,
+    error::BibleApiError,
+    internal_error,
+    search::{get_reference_with, BibleSearch, Chapter},
+    validation::check_text,
+    verse_id::VerseIds,
+    versification::{Versification, Versifications},
+    versions,
+};
+
+/// The MAX_QUOTATION_LEN is the most characters a quotation can take.
+pub const MAX_QUOTATION_LEN: usize = 2048;
+
+/// The MIN_QUOTATION_WORDS is the fewest words a quotation can have.
+pub const MIN_QUOTATION_WORDS: usize = 2;
+
+/// The MAX_CANDIDATES is the most verses given for a quotation that is not
+/// found word for word.
+pub const MAX_CANDIDATES: i64 = 10;
+
+/// The MAX_CHAPTERS is the most chapters a quotation is looked for in, so a
+/// quotation of common words does not read the whole translation.
+pub const MAX_CHAPTERS: i64 = 50;
+
+// The quotation is looked up by runs of this many words, taken from its first
+// ANCHOR_WORDS words, so one of them is inside a verse it quotes
+const WINDOW_WORDS: usize = 3;
+const ANCHOR_WORDS: usize = 16;
+
+#[derive(Debug, Deserialize)]
+pub struct IdentifyRequest {
+    text: String,
+    #[serde(default)]
+    translation: Option<String>,
+    #[serde(default)]
+    fuzzy: Option<bool>,
+}
+
+/// The Identification is a passage the quotation was found in. The verses
+/// are the ones it runs from the start to the end of, so a quotation that
+/// only starts part way through a verse still has that verse. The score is
+/// 1 for a quotation found word for word, and otherwise how alike the
+/// quotation and the most alike part of the verse are, from 0 to 1.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Identification {
+    pub reference: String,
+    pub title: String,
+    pub chapter: i32,
+    pub start_verse: i32,
+    pub end_verse: i32,
+    pub verses: Vec<VerseIds>,
+    pub score: f32,
+}
+
+/// The Identified are the passages of the translation the quotation is
+/// found in, in the order of the books, or when it is not found word for
+/// word the verses most like it, best first.
+#[derive(Debug, Serialize)]
+pub struct Identified {
+    pub translation: String,
+    pub matches: Vec<Identification>,
+}
+
+// A verse of a chapter, with its text normalized
+struct NormalizedVerse {
+    verse: i32,
+    text: String,
+}
+
+/// The identify handler serves POST /identify (ex: {"text": "For God so
+/// loved the world,"}) with the references of the verses a quotation is
+/// from, in the translation asked for or the default one. The quotation and
+/// the verses are compared without case or punctuation, and a quotation can
+/// run across the verses of a chapter. A quotation that is only part of a
+/// verse or is misremembered is matched by the trigrams of its words,
+/// unless fuzzy is false.
+pub async fn identify(
+    State(pool): State<PgPool>,
+    State(versifications): State<Versifications>,
+    Json(request): Json<IdentifyRequest>,
+) -> Result<Json<Identified>, Response> {
+    let translation = request
+        .translation
+        .filter(|translation| !translation.trim().is_empty())
+        .map(|translation| translation.to_lowercase())
+        .unwrap_or_else(|| get_default_translation().to_owned());
+
+    let text = normalize(&request.text);
+    check_text("text", &text, MAX_QUOTATION_LEN).map_err(IntoResponse::into_response)?;
+    if text.split(' ').count() < MIN_QUOTATION_WORDS {
+        return Err(BibleApiError::from((
+            StatusCode::BAD_REQUEST,
+            format!("the quotation needs at least {} words", MIN_QUOTATION_WORDS),
+        ))
+        .into_response());
+    }
+
+    let active = versions::get_active_versions(&pool)
+        .await
+        .map_err(|err| internal_error(err).into_response())?;
+    if !active
+        .iter()
+        .any(|version| version.translation == translation)
+    {
+        return Err(BibleApiError::from((
+            StatusCode::NOT_FOUND,
+            format!("No Matching Translation Found: {}", translation),
+        ))
+        .into_response());
+    }
+
+    let versification = versifications.get(&translation);
+    let chapters = get_chapters(&pool, &translation, &text)
+        .await
+        .map_err(|err| internal_error(err).into_response())?;
+    let mut matches = chapters
+        .into_iter()
+        .flat_map(|(title, chapter, verses)| {
+            find_in_chapter(&text, &title, chapter, &verses, &versification)
+        })
+        .collect::<Vec<Identification>>();
+    if matches.is_empty() && request.fuzzy.unwrap_or(true) {
+        matches = get_candidates(&pool, &translation, &text, &versification)
+            .await
+            .map_err(|err| internal_error(err).into_response())?;
+    }
+
+    Ok(Json(Identified {
+        translation,
+        matches,
+    }))
+}
+
+/// The normalize function puts text in the form quotations are compared in:
+/// lower case, without punctuation, and with one space between words. It is
+/// the same as the normalized_contents of the verses (see
+/// db/identify-pg.sql).
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// The runs of words a verse the quotation is from has one of
+fn get_windows(text: &str) -> Vec<String> {
+    let words = text.split(' ').take(ANCHOR_WORDS).collect::<Vec<&str>>();
+    if words.len() <= WINDOW_WORDS {
+        return vec![format!("%{}%", words.join(" "))];
+    }
+
+    let mut windows = words
+        .windows(WINDOW_WORDS)
+        .map(|window| format!("%{}%", window.join(" ")))
+        .collect::<Vec<String>>();
+    windows.dedup();
+    windows
+}
+
+// Find the chapters that may have the quotation, with their verses, in the
+// order of the books. When there are too many, the ones with the most runs
+// of its words are kept
+async fn get_chapters(
+    pool: &PgPool,
+    translation: &str,
+    text: &str,
+) -> Result<Vec<(String, i32, Vec<NormalizedVerse>)>, sqlx::Error> {
+    let mut chapters = sqlx::query!(
+        r#"
+            SELECT v.title, v.chapter_num
+            FROM unnest($1::text[]) AS w(pattern)
+                INNER JOIN verses v ON v.normalized_contents LIKE w.pattern
+            WHERE v.version = (
+                SELECT t.version FROM translation_versions t
+                WHERE t.translation = $2 AND t.state = 'active'
+            )
+          GROUP BY v.title, v.chapter_num
+          ORDER BY count(DISTINCT w.pattern) DESC
+            LIMIT $3
+        "#,
+        &get_windows(text),
+        translation,
+        MAX_CHAPTERS,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| (row.title, row.chapter_num))
+    .collect::<Vec<(String, i32)>>();
+    chapters.sort_by_key(|(title, chapter)| (get_book_number(title), *chapter));
+
+    let titles = chapters
+        .iter()
+        .map(|(title, _)| title.clone())
+        .collect::<Vec<String>>();
+    let chapter_nums = chapters
+        .iter()
+        .map(|(_, chapter)| *chapter)
+        .collect::<Vec<i32>>();
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT c.index as "index!", v.num, v.normalized_contents as "text!"
+            FROM unnest($1::text[], $2::int[]) WITH ORDINALITY AS c(title, chapter, index)
+                INNER JOIN verses v ON v.title = c.title AND v.chapter_num = c.chapter
+            WHERE v.version = (
+                SELECT t.version FROM translation_versions t
+                WHERE t.translation = $3 AND t.state = 'active'
+            )
+          ORDER BY c.index, v.num
+        "#,
+        &titles,
+        &chapter_nums,
+        translation,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut found = chapters
+        .into_iter()
+        .map(|(title, chapter)| (title, chapter, vec![]))
+        .collect::<Vec<(String, i32, Vec<NormalizedVerse>)>>();
+    for row in rows {
+        // The ordinality counts from 1
+        if let Some((_, _, verses)) = found.get_mut(row.index as usize - 1) {
+            verses.push(NormalizedVerse {
+                verse: row.num,
+                text: row.text,
+            });
+        }
+    }
+
+    Ok(found)
+}
+
+// Find the verses most like the quotation, by the share of its trigrams the
+// most alike run of a verse's words has (see pg_trgm's word_similarity).
+// Verses under pg_trgm's word_similarity_threshold are left out
+async fn get_candidates(
+    pool: &PgPool,
+    translation: &str,
+    text: &str,
+    versification: &Versification,
+) -> Result<Vec<Identification>, sqlx::Error> {
+    let candidates = sqlx::query!(
+        r#"
+            SELECT title, chapter_num, num,
+                   word_similarity($1, normalized_contents) as "score!"
+            FROM verses
+            WHERE $1 <% normalized_contents
+                AND version = (
+                    SELECT t.version FROM translation_versions t
+                    WHERE t.translation = $2 AND t.state = 'active'
+                )
+          ORDER BY 4 DESC, chapter_num, num
+            LIMIT $3
+        "#,
+        text,
+        translation,
+        MAX_CANDIDATES,
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        identify_verses(
+            &row.title,
+            row.chapter_num,
+            (row.num, row.num),
+            row.score,
+            versification,
+        )
+    })
+    .collect();
+
+    Ok(candidates)
+}
+
+// Find every place in the chapter the quotation starts, keeping to whole
+// words, and the verses it runs across from there
+fn find_in_chapter(
+    text: &str,
+    title: &str,
+    chapter: i32,
+    verses: &[NormalizedVerse],
+    versification: &Versification,
+) -> Vec<Identification> {
+    // The chapter's text with a space around every verse, and where each
+    // verse starts in it
+    let mut chapter_text = String::from(" ");
+    let mut starts = vec![];
+    for verse in verses.iter().filter(|verse| !verse.text.is_empty()) {
+        starts.push((chapter_text.len(), verse.verse));
+        chapter_text.push_str(&verse.text);
+        chapter_text.push(' ');
+    }
+    let verse_at = |offset: usize| {
+        starts
+            .iter()
+            .take_while(|(start, _)| *start <= offset)
+            .last()
+            .map(|(_, verse)| *verse)
+    };
+
+    let needle = format!(" {} ", text);
+    let mut identifications: Vec<Identification> = vec![];
+    let mut from = 0;
+    while let Some(found) = chapter_text[from..].find(&needle) {
+        let start = from + found + 1;
+        let end = start + text.len() - 1;
+        from = start;
+
+        let (Some(start_verse), Some(end_verse)) = (verse_at(start), verse_at(end)) else {
+            continue;
+        };
+        // A quotation found twice in the same verses is only given once
+        if identifications
+            .last()
+            .is_some_and(|last| last.start_verse == start_verse && last.end_verse == end_verse)
+        {
+            continue;
+        }
+        identifications.push(identify_verses(
+            title,
+            chapter,
+            (start_verse, end_verse),
+            1.0,
+            versification,
+        ));
+    }
+
+    identifications
+}
+
+fn identify_verses(
+    title: &str,
+    chapter: i32,
+    (start_verse, end_verse): (i32, i32),
+    score: f32,
+    versification: &Versification,
+) -> Identification {
+    let bible_search = BibleSearch {
+        title: title.to_owned(),
+        chapters: vec![Chapter {
+            chapter: u8::try_from(chapter).unwrap_or_default(),
+            verses: (start_verse..=end_verse)
+                .filter_map(|verse| u8::try_from(verse).ok())
+                .collect::<HashSet<u8>>(),
+        }],
+    };
+
+    Identification {
+        reference: get_reference_with(&bible_search, versification),
+        title: title.to_owned(),
+        chapter,
+        start_verse,
+        end_verse,
+        verses: (start_verse..=end_verse)
+            .map(|verse| VerseIds::new(title, chapter, verse))
+            .collect(),
+        score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verse(verse: i32, text: &str) -> NormalizedVerse {
+        NormalizedVerse {
+            verse,
+            text: normalize(text),
+        }
+    }
+
+    fn john_3() -> Vec<NormalizedVerse> {
+        vec![
+            verse(16, "For God so loved the world, that he gave his only begotten Son, that whosoever believeth in him should not perish, but have everlasting life."),
+            verse(17, "For God sent not his Son into the world to condemn the world; but that the world through him might be saved."),
+        ]
+    }
+
+    #[test]
+    fn normalize_drops_case_punctuation_and_extra_spaces() {
+        assert_eq!(
+            normalize("  For God so loved the world,\n that he gave his only begotten Son; "),
+            "for god so loved the world that he gave his only begotten son"
+        );
+        assert_eq!(normalize("the LORD'S house"), "the lords house");
+        assert_eq!(normalize("¶ Jesus wept."), "jesus wept");
+    }
+
+    #[test]
+    fn get_windows_takes_runs_of_the_first_words() {
+        assert_eq!(get_windows("jesus wept"), ["%jesus wept%"]);
+        assert_eq!(
+            get_windows("for god so loved"),
+            ["%for god so%", "%god so loved%"]
+        );
+        assert_eq!(
+            get_windows(&["word"; 40].join(" ")).len(),
+            1,
+            "repeated runs are only looked for once"
+        );
+    }
+
+    #[test]
+    fn find_in_chapter_finds_a_quotation_within_a_verse() {
+        let found = find_in_chapter(
+            &normalize("God so loved the WORLD that he gave"),
+            "John",
+            3,
+            &john_3(),
+            &Versification::default(),
+        );
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].reference, "John 3:16");
+        assert_eq!(found[0].verses[0].verse_id, 43_003_016);
+        assert_eq!(found[0].score, 1.0);
+    }
+
+    #[test]
+    fn find_in_chapter_finds_a_quotation_across_verses() {
+        let found = find_in_chapter(
+            &normalize("have everlasting life. For God sent not his Son"),
+            "John",
+            3,
+            &john_3(),
+            &Versification::default(),
+        );
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].reference, "John 3:16-17");
+        assert_eq!((found[0].start_verse, found[0].end_verse), (16, 17));
+    }
+
+    #[test]
+    fn find_in_chapter_keeps_to_whole_words() {
+        let versification = Versification::default();
+        let find = |text: &str| find_in_chapter(text, "John", 3, &john_3(), &versification);
+
+        assert!(find("od so loved").is_empty());
+        assert!(find("the world to save").is_empty());
+    }
+}
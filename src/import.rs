@@ -0,0 +1,643 @@
+use regex::Regex;
+use sqlx::postgres::PgPool;
+use std::{cmp::Ordering, path::Path, sync::OnceLock};
+
+use crate::{
+    audit::{self, AuditAction},
+    chapter::{get_book_by_usfm_code, get_book_number},
+    db::{get_default_translation, SearchResult},
+    integrity,
+    verse::SUPERSCRIPTION_VERSE,
+    versions,
+};
+
+/// The ImportedVerse is a verse read from a source file. The formatted text
+/// is only kept when it has markup the plain text does not. A verse bridged
+/// with the ones after it (ex: \v 4-5) holds the text of all of them, and
+/// bridged_to is the last verse of the bridge.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportedVerse {
+    pub chapter: i32,
+    pub verse: i32,
+    pub bridged_to: Option<i32>,
+    pub text: String,
+    pub formatted_text: Option<String>,
+    pub paragraph_start: bool,
+}
+
+/// The ImportedBook is every verse read from one source file.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportedBook {
+    pub title: String,
+    pub verses: Vec<ImportedVerse>,
+}
+
+impl ImportedBook {
+    /// The check function looks for missing, duplicate, unexpected and empty
+    /// verses in the book. The verses a bridge covers are not missing.
+    pub fn check(&self) -> Vec<integrity::Problem> {
+        let verses = self.verses.iter().flat_map(|verse| {
+            integrity::get_covered_verses(verse.verse, verse.bridged_to)
+                .map(|number| (verse.chapter, number, verse.text.as_str()))
+        });
+
+        integrity::check_book(&self.title, verses)
+    }
+}
+
+// The verse being read, with its plain and formatted text so far
+#[derive(Debug)]
+struct CurrentVerse {
+    verse: i32,
+    bridged_to: Option<i32>,
+    paragraph_start: bool,
+    text: String,
+    formatted_text: String,
+}
+
+// What the text between two markers belongs to
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Verse,
+    Skip,
+}
+
+/// The UsfmParser reads a USFM file one marker at a time. Headings, titles
+/// and notes are skipped, paragraph markers flag the verse that follows them,
+/// a psalm title (\d) becomes the superscription verse, and words supplied by
+/// the translators (\add) are kept in <i> in the formatted text. A verse
+/// bridge (\v 4-5) is read as one verse, and chapters have to come in order,
+/// as a verse is placed by the last \c before it.
+#[derive(Debug)]
+struct UsfmParser {
+    title: Option<&'static str>,
+    chapter: Option<i32>,
+    verses: Vec<ImportedVerse>,
+    current: Option<CurrentVerse>,
+    mode: Mode,
+    note_depth: usize,
+    in_word: bool,
+    paragraph_start: bool,
+}
+
+fn get_marker_regex() -> &'static Regex {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    MARKER.get_or_init(|| Regex::new(r"\\(\+?[a-z]+[0-9]*\*?)").unwrap())
+}
+
+/// The parse_usfm function reads the verses of one book from USFM source.
+/// It fails when the book code is missing or unknown, a verse is found
+/// outside a chapter, or a chapter comes after a later one.
+pub fn parse_usfm(source: &str) -> Result<ImportedBook, String> {
+    let mut parser = UsfmParser {
+        title: None,
+        chapter: None,
+        verses: vec![],
+        current: None,
+        mode: Mode::Skip,
+        note_depth: 0,
+        in_word: false,
+        paragraph_start: false,
+    };
+
+    let mut last = 0;
+    for marker in get_marker_regex().captures_iter(source) {
+        let whole = marker.get(0).unwrap();
+        parser.text(&source[last..whole.start()]);
+
+        let rest = &source[whole.end()..];
+        last = whole.end() + parser.marker(&marker[1], rest)?;
+    }
+    parser.text(&source[last..]);
+    parser.finish_verse();
+
+    Ok(ImportedBook {
+        title: parser.title.ok_or("missing \\id marker")?.to_owned(),
+        verses: parser.verses,
+    })
+}
+
+impl UsfmParser {
+    // Handle a marker, returning how much of the text after it was its
+    // argument (ex: the number after \c)
+    fn marker(&mut self, name: &str, rest: &str) -> Result<usize, String> {
+        let name = name.trim_start_matches('+');
+        let (argument, length) = read_argument(rest);
+
+        match name {
+            "id" => {
+                let title = get_book_by_usfm_code(argument)
+                    .ok_or(format!("unknown book code: {}", argument))?;
+                self.title = Some(title);
+                self.mode = Mode::Skip;
+                return Ok(length);
+            }
+            "c" => {
+                self.finish_verse();
+                let chapter = argument
+                    .parse()
+                    .ok()
+                    .filter(|chapter| *chapter > 0)
+                    .ok_or(format!("invalid chapter: {}", argument))?;
+                if let Some(previous) = self.chapter.filter(|previous| *previous >= chapter) {
+                    return Err(format!(
+                        "chapter {} comes after chapter {}",
+                        chapter, previous
+                    ));
+                }
+                self.chapter = Some(chapter);
+                self.mode = Mode::Skip;
+                return Ok(length);
+            }
+            "v" => {
+                self.finish_verse();
+                let (verse, bridged_to) = parse_verse_range(argument)?;
+                self.start_verse(verse, bridged_to)?;
+                return Ok(length);
+            }
+            "d" => {
+                self.finish_verse();
+                self.start_verse(i32::from(SUPERSCRIPTION_VERSE), None)?;
+            }
+            // The alternate and published numbers of chapters and verses
+            // (ex: \vp 1a\vp*) are skipped the same as notes
+            "f" | "fe" | "x" | "ca" | "va" | "vp" => self.note_depth += 1,
+            "f*" | "fe*" | "x*" | "ca*" | "va*" | "vp*" => {
+                self.note_depth = self.note_depth.saturating_sub(1)
+            }
+            "w" => self.in_word = true,
+            "w*" => self.in_word = false,
+            "add" => self.formatted("<i>"),
+            "add*" => self.formatted("</i>"),
+            "p" | "m" | "pi" | "pi1" | "pi2" | "nb" | "b" => {
+                self.paragraph_start = true;
+                if self.current.is_none() {
+                    self.mode = Mode::Skip;
+                }
+            }
+            name if is_heading(name) => {
+                self.finish_verse();
+                self.mode = Mode::Skip;
+            }
+            // Character styles and poetry lines only change how the text
+            // looks, so the text itself is kept
+            _ => {}
+        }
+
+        // The space after an opening marker only ends the marker
+        let ends_marker = !name.ends_with('*') && rest.starts_with([' ', '\t', '\r', '\n']);
+        Ok(usize::from(ends_marker))
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.mode == Mode::Skip || self.note_depth > 0 {
+            return;
+        }
+
+        // A word's attributes follow a bar (ex: \w gracious|strong="H2587"\w*)
+        let text = match self.in_word {
+            true => text.split('|').next().unwrap_or_default(),
+            false => text,
+        };
+
+        if let Some(current) = self.current.as_mut() {
+            current.text.push_str(text);
+            current.formatted_text.push_str(text);
+        }
+    }
+
+    fn formatted(&mut self, markup: &str) {
+        if let Some(current) = self.current.as_mut() {
+            current.formatted_text.push_str(markup);
+        }
+    }
+
+    fn start_verse(&mut self, verse: i32, bridged_to: Option<i32>) -> Result<(), String> {
+        if self.chapter.is_none() {
+            return Err(format!("verse {} is not in a chapter", verse));
+        }
+
+        // A paragraph marker belongs to the verse after it
+        self.current = Some(CurrentVerse {
+            verse,
+            bridged_to,
+            paragraph_start: std::mem::take(&mut self.paragraph_start),
+            text: String::new(),
+            formatted_text: String::new(),
+        });
+        self.mode = Mode::Verse;
+        Ok(())
+    }
+
+    fn finish_verse(&mut self) {
+        let (Some(chapter), Some(current)) = (self.chapter, self.current.take()) else {
+            return;
+        };
+
+        let text = collapse_whitespace(&current.text);
+        let formatted_text = collapse_whitespace(&current.formatted_text);
+
+        self.verses.push(ImportedVerse {
+            chapter,
+            verse: current.verse,
+            bridged_to: current.bridged_to,
+            formatted_text: (formatted_text != text).then_some(formatted_text),
+            text,
+            paragraph_start: current.paragraph_start,
+        });
+    }
+}
+
+// Read the word after a marker, returning it and the length read
+fn read_argument(rest: &str) -> (&str, usize) {
+    let trimmed = rest.trim_start();
+    let skipped = rest.len() - trimmed.len();
+    let argument = trimmed.split_whitespace().next().unwrap_or_default();
+
+    (argument, skipped + argument.len())
+}
+
+// Read a verse number, or a bridge of verses (ex: 4-5), returning the first
+// verse and the last one of the bridge
+fn parse_verse_range(argument: &str) -> Result<(i32, Option<i32>), String> {
+    let invalid = || format!("invalid verse: {}", argument);
+    let parse = |verse: &str| verse.parse::<i32>().map_err(|_| invalid());
+
+    match argument.split_once('-') {
+        None => Ok((parse(argument)?, None)),
+        Some((first, last)) => {
+            let (first, last) = (parse(first)?, parse(last)?);
+            match last.cmp(&first) {
+                Ordering::Less => Err(invalid()),
+                Ordering::Equal => Ok((first, None)),
+                Ordering::Greater => Ok((first, Some(last))),
+            }
+        }
+    }
+}
+
+// Identification, titles, headings and introductions are not verse text
+fn is_heading(name: &str) -> bool {
+    let name = name.trim_end_matches(char::is_numeric);
+
+    matches!(
+        name,
+        "ide"
+            | "h"
+            | "toc"
+            | "rem"
+            | "sts"
+            | "usfm"
+            | "mt"
+            | "mte"
+            | "ms"
+            | "mr"
+            | "s"
+            | "sr"
+            | "r"
+            | "sp"
+            | "cl"
+            | "cp"
+            | "cd"
+            | "imt"
+            | "is"
+            | "ip"
+            | "io"
+            | "iot"
+            | "ie"
+    )
+}
+
+/// The parse_dump function reads the books of a dump of a translation, a
+/// verse per line in the same shape as the ndjson format (db/kjv.ndjson is
+/// the KJV), in canonical order. It fails on a line that can not be read or
+/// is of a book it does not know.
+pub fn parse_dump(source: &str) -> Result<Vec<ImportedBook>, String> {
+    let mut books: Vec<ImportedBook> = vec![];
+
+    for (index, line) in source.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let verse: SearchResult =
+            serde_json::from_str(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+        if get_book_number(&verse.title).is_none() {
+            return Err(format!("line {}: unknown book {}", index + 1, verse.title));
+        }
+
+        let imported = ImportedVerse {
+            chapter: verse.chapter,
+            verse: verse.verse,
+            bridged_to: None,
+            text: verse.text,
+            formatted_text: None,
+            paragraph_start: verse.paragraph_start,
+        };
+        match books.iter_mut().find(|book| book.title == verse.title) {
+            Some(book) => book.verses.push(imported),
+            None => books.push(ImportedBook {
+                title: verse.title,
+                verses: vec![imported],
+            }),
+        }
+    }
+
+    books.sort_by_key(|book| get_book_number(&book.title));
+    for book in &mut books {
+        book.verses
+            .sort_by_key(|verse| (verse.chapter, verse.verse));
+    }
+
+    Ok(books)
+}
+
+/// The seed function loads the dump at the path (see parse_dump) as the
+/// default translation, unless it already has an active version, so it can
+/// be left on for every start. The problems the check finds are logged, but
+/// do not stop the dump from being loaded. It returns the version loaded, if
+/// one was.
+pub async fn seed(pool: &PgPool, path: &Path) -> Result<Option<i32>, String> {
+    let translation = get_default_translation();
+    if versions::is_active(pool, translation)
+        .await
+        .map_err(|err| err.to_string())?
+    {
+        return Ok(None);
+    }
+
+    let source = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|err| err.to_string())?;
+    let books = parse_dump(&source)?;
+    let problems = books.iter().flat_map(ImportedBook::check).count();
+    if problems > 0 {
+        tracing::warn!("the seed of {} has {} problems", translation, problems);
+    }
+
+    let version = import(pool, translation, &books, |_| {})
+        .await
+        .map_err(|err| err.to_string())?;
+    let details = format!("version {}", version);
+    audit::record(pool, "seed", AuditAction::Import, translation, &details).await;
+
+    Ok(Some(version))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// The import function loads the books of a translation as a new version of
+/// it, and makes that version the active one. The active version it replaces
+/// is kept as the previous version, so the import can be rolled back. The
+/// progress function is called after each book is written.
+pub async fn import(
+    pool: &PgPool,
+    translation: &str,
+    books: &[ImportedBook],
+    progress: impl Fn(&ImportedBook),
+) -> Result<i32, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    // Versions are numbered across every translation, as the verses of all
+    // of them share one table
+    let version = sqlx::query_scalar!(
+        r#"SELECT COALESCE(MAX(version), 0) + 1 as "version!" FROM translation_versions"#
+    )
+    .fetch_one(&mut transaction)
+    .await?;
+
+    // A translation imported for the first time is named by its code until
+    // it is given a name
+    sqlx::query!(
+        "INSERT INTO translations (translation, name) VALUES ($1::text, upper($1::text)) ON CONFLICT DO NOTHING",
+        translation
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO translation_versions (translation, version, state) VALUES ($1, $2, 'pending')",
+        translation,
+        version
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    for book in books {
+        let mut chapters = vec![];
+        let mut verses = vec![];
+        let mut bridges = vec![];
+        let mut texts = vec![];
+        let mut formatted_texts = vec![];
+        let mut paragraph_starts = vec![];
+
+        for verse in &book.verses {
+            chapters.push(verse.chapter);
+            verses.push(verse.verse);
+            bridges.push(verse.bridged_to);
+            texts.push(verse.text.clone());
+            formatted_texts.push(verse.formatted_text.clone());
+            paragraph_starts.push(verse.paragraph_start);
+        }
+
+        sqlx::query!(
+            "
+                INSERT INTO books (title) VALUES ($1) ON CONFLICT DO NOTHING
+            ",
+            book.title
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+                INSERT INTO chapters (title, num)
+                SELECT DISTINCT $1, chapter FROM UNNEST($2::int[]) as chapter
+                ON CONFLICT DO NOTHING
+            ",
+            book.title,
+            &chapters[..]
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        sqlx::query!(
+            "
+                INSERT INTO verses (
+                    title, chapter_num, num, contents, formatted_contents,
+                    paragraph_start, search_vector, version, bridged_to
+                )
+                SELECT $1, chapter_num, num, contents, formatted_contents,
+                    paragraph_start, to_tsvector('english', contents), $7, bridged_to
+                FROM UNNEST($2::int[], $3::int[], $4::text[], $5::text[], $6::bool[], $8::int[])
+                    as v(chapter_num, num, contents, formatted_contents, paragraph_start, bridged_to)
+            ",
+            book.title,
+            &chapters[..],
+            &verses[..],
+            &texts[..],
+            &formatted_texts[..] as &[Option<String>],
+            &paragraph_starts[..],
+            version,
+            &bridges[..] as &[Option<i32>]
+        )
+        .execute(&mut transaction)
+        .await?;
+
+        progress(book);
+    }
+
+    // The active version has to step aside first, as only one can be active
+    sqlx::query!(
+        "
+            UPDATE translation_versions SET state = 'previous'
+            WHERE translation = $1 AND state = 'active'
+        ",
+        translation
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        "
+            UPDATE translation_versions SET state = 'active'
+            WHERE translation = $1 AND version = $2
+        ",
+        translation,
+        version
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PSALM: &str = r#"\id PSA Psalms
+\h Psalms
+\mt1 The Psalms
+\c 3
+\d A Psalm of David, when he fled from Absalom his son.
+\q1
+\v 1 \w Lord|strong="H3068"\w*, how are they increased that trouble me!\f + \fr 3:1 \ft a note\f*
+\q2 many are they that rise up against me.
+\s1 A heading
+\p
+\v 2 Many \add there be\add* which say of my soul,
+"#;
+
+    #[test]
+    fn parse_usfm_reads_the_verses_of_a_book() {
+        let book = parse_usfm(PSALM).unwrap();
+
+        assert_eq!(book.title, "Psalms");
+        assert_eq!(
+            book.verses,
+            vec![
+                ImportedVerse {
+                    chapter: 3,
+                    verse: 0,
+                    bridged_to: None,
+                    text: String::from("A Psalm of David, when he fled from Absalom his son."),
+                    formatted_text: None,
+                    paragraph_start: false,
+                },
+                ImportedVerse {
+                    chapter: 3,
+                    verse: 1,
+                    bridged_to: None,
+                    text: String::from(
+                        "Lord, how are they increased that trouble me! many are they that rise up against me."
+                    ),
+                    formatted_text: None,
+                    paragraph_start: false,
+                },
+                ImportedVerse {
+                    chapter: 3,
+                    verse: 2,
+                    bridged_to: None,
+                    text: String::from("Many there be which say of my soul,"),
+                    formatted_text: Some(String::from("Many <i>there be</i> which say of my soul,")),
+                    paragraph_start: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_usfm_rejects_unknown_books_and_stray_verses() {
+        assert!(parse_usfm("\\id TOB\n\\c 1\n\\v 1 text").is_err());
+        assert!(parse_usfm("\\id GEN\n\\v 1 text").is_err());
+        assert!(parse_usfm("\\c 1\n\\v 1 text").is_err());
+    }
+
+    #[test]
+    fn parse_usfm_reads_verse_bridges_and_chapter_boundaries() {
+        let book = parse_usfm(
+            "\\id JUD\n\\c 1\n\\p\n\\v 1 Jude\n\\v 2-3 \\vp 2-3\\vp* Mercy\n\\v 4-25 The rest",
+        )
+        .unwrap();
+
+        assert_eq!(
+            book.verses
+                .iter()
+                .map(|verse| (verse.verse, verse.bridged_to, verse.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, None, "Jude"),
+                (2, Some(3), "Mercy"),
+                (4, Some(25), "The rest")
+            ]
+        );
+        assert_eq!(book.check(), vec![]);
+
+        assert_eq!(parse_verse_range("7-7"), Ok((7, None)));
+        assert!(parse_verse_range("5-4").is_err());
+        assert!(parse_usfm("\\id GEN\n\\c 2\n\\v 1 a\n\\c 1\n\\v 1 b").is_err());
+        assert!(parse_usfm("\\id GEN\n\\c 0\n\\v 1 a").is_err());
+    }
+
+    #[test]
+    fn parse_dump_reads_the_books_in_canonical_order() {
+        let dump = r#"{"title":"John","chapter":1,"verse":2,"text":"The same was in the beginning with God.","paragraph_start":false}
+{"title":"Genesis","chapter":1,"verse":1,"text":"In the beginning God created the heaven and the earth.","paragraph_start":true}
+
+{"title":"John","chapter":1,"verse":1,"text":"In the beginning was the Word","paragraph_start":true}
+"#;
+        let books = parse_dump(dump).unwrap();
+
+        assert_eq!(
+            books
+                .iter()
+                .map(|book| (book.title.as_str(), book.verses.len()))
+                .collect::<Vec<_>>(),
+            vec![("Genesis", 1), ("John", 2)]
+        );
+        assert_eq!(books[1].verses[0].verse, 1);
+        assert!(books[1].verses[0].paragraph_start);
+    }
+
+    #[test]
+    fn parse_dump_rejects_lines_it_can_not_read() {
+        assert!(parse_dump(
+            r#"{"title":"Tobit","chapter":1,"verse":1,"text":"","paragraph_start":false}"#
+        )
+        .unwrap_err()
+        .contains("line 1"));
+        assert!(parse_dump("John 3:16").is_err());
+    }
+
+    #[test]
+    fn imported_book_check_finds_missing_verses() {
+        let book = parse_usfm("\\id JUD\n\\c 1\n\\v 1 Jude, the servant").unwrap();
+
+        assert_eq!(book.check().len(), 24);
+    }
+}
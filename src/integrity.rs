@@ -0,0 +1,279 @@
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    ops::RangeInclusive,
+};
+
+use crate::{
+    chapter::{get_chapter_count_by_book, BOOKS},
+    verse::{get_verse_count_by_book_and_chapter, SUPERSCRIPTION_VERSE},
+};
+
+/// The ProblemKind is what is wrong with a verse of a translation.
+/// - Missing is a verse the book should have but does not
+/// - Duplicate is a verse that appears more than once
+/// - Unexpected is a verse or chapter the book should not have
+/// - Empty is a verse with no text
+/// - Encoding is a verse whose text was garbled on the way in (ex: it has
+///   control characters, U+FFFD or UTF-8 read as Latin-1)
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemKind {
+    Missing,
+    Duplicate,
+    Unexpected,
+    Empty,
+    Encoding,
+}
+
+/// The Problem is one thing wrong with a translation, at the verse it was
+/// found.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    pub title: String,
+    pub chapter: i32,
+    pub verse: i32,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ProblemKind::Missing => "missing verse",
+            ProblemKind::Duplicate => "duplicate verse",
+            ProblemKind::Unexpected => "unexpected verse",
+            ProblemKind::Empty => "empty verse",
+            ProblemKind::Encoding => "badly encoded verse",
+        };
+
+        write!(
+            f,
+            "{}: {} {}:{}",
+            kind, self.title, self.chapter, self.verse
+        )
+    }
+}
+
+/// The check_book function compares the verses of a book, given as
+/// (chapter, verse, text), with the verses the book is known to have. Every
+/// problem found is returned, in the order the verses were given followed by
+/// the missing verses in canonical order.
+pub fn check_book<'a>(
+    title: &str,
+    verses: impl IntoIterator<Item = (i32, i32, &'a str)>,
+) -> Vec<Problem> {
+    let problem = |kind, chapter, verse| Problem {
+        kind,
+        title: title.to_owned(),
+        chapter,
+        verse,
+    };
+
+    let mut problems = vec![];
+    let mut seen = HashSet::new();
+
+    for (chapter, verse, text) in verses {
+        if !seen.insert((chapter, verse)) {
+            problems.push(problem(ProblemKind::Duplicate, chapter, verse));
+        } else if !is_known_verse(title, chapter, verse) {
+            problems.push(problem(ProblemKind::Unexpected, chapter, verse));
+        }
+
+        if text.trim().is_empty() {
+            problems.push(problem(ProblemKind::Empty, chapter, verse));
+        } else if is_badly_encoded(text) {
+            problems.push(problem(ProblemKind::Encoding, chapter, verse));
+        }
+    }
+
+    let chapter_count = get_chapter_count_by_book(title).unwrap_or(0);
+    for chapter in 1..=chapter_count {
+        let verse_count = get_verse_count_by_book_and_chapter(title, chapter).unwrap_or(0);
+
+        for verse in 1..=verse_count {
+            let key = (i32::from(chapter), i32::from(verse));
+            if !seen.contains(&key) {
+                problems.push(problem(ProblemKind::Missing, key.0, key.1));
+            }
+        }
+    }
+
+    problems
+}
+
+/// The get_covered_verses function returns the verses a verse's text
+/// covers: the verse itself, through the last verse of its bridge when it is
+/// bridged (ex: 4 to 5 for \v 4-5).
+pub fn get_covered_verses(verse: i32, bridged_to: Option<i32>) -> RangeInclusive<i32> {
+    verse..=bridged_to.unwrap_or(verse).max(verse)
+}
+
+/// The MOJIBAKE constant lists what UTF-8 punctuation and letters look like
+/// once they have been read as Latin-1, Windows-1252 or code page 437 (ex: ’
+/// becomes â€™ or ΓÇÖ).
+const MOJIBAKE: [&str; 5] = ["â€", "Ã", "Â", "ï»¿", "ΓÇ"];
+
+/// The is_badly_encoded function checks a verse's text for the marks of a
+/// broken import: control characters, the replacement character, and UTF-8
+/// that was decoded as a single byte encoding.
+pub fn is_badly_encoded(text: &str) -> bool {
+    text.chars()
+        .any(|c| c == char::REPLACEMENT_CHARACTER || c.is_control())
+        || MOJIBAKE.iter().any(|pattern| text.contains(pattern))
+}
+
+/// The TranslationReport is what verify found wrong with the active version
+/// of a translation.
+#[derive(Debug, Serialize)]
+pub struct TranslationReport {
+    pub translation: String,
+    pub version: i32,
+    pub verses: usize,
+    pub problems: Vec<Problem>,
+}
+
+/// The verify function checks every book of the active version of a
+/// translation, or returns None when the translation has no active version.
+/// Books that are not in the canon are reported as unexpected verses, and
+/// the verses a bridge covers are checked as if each had the bridge's text.
+pub async fn verify(
+    pool: &PgPool,
+    translation: &str,
+) -> Result<Option<TranslationReport>, sqlx::Error> {
+    let Some(version) = sqlx::query_scalar!(
+        "
+            SELECT version FROM translation_versions
+            WHERE translation = $1 AND state = 'active'
+        ",
+        translation
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let rows = sqlx::query!(
+        "
+            SELECT title, chapter_num, num, contents, bridged_to FROM verses
+            WHERE version = $1
+          ORDER BY title, chapter_num, num
+        ",
+        version
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut books: BTreeMap<&str, Vec<(i32, i32, &str)>> = BTreeMap::new();
+    for row in &rows {
+        books.entry(row.title.as_str()).or_default().extend(
+            get_covered_verses(row.num, row.bridged_to)
+                .map(|verse| (row.chapter_num, verse, row.contents.as_str())),
+        );
+    }
+
+    let mut problems = vec![];
+    for title in BOOKS {
+        problems.extend(check_book(title, books.remove(title).unwrap_or_default()));
+    }
+    for (title, verses) in books {
+        problems.extend(check_book(title, verses));
+    }
+
+    Ok(Some(TranslationReport {
+        translation: translation.to_owned(),
+        version,
+        verses: rows.len(),
+        problems,
+    }))
+}
+
+/// The get_active_translations function lists the translations that have an
+/// active version, in name order.
+pub async fn get_active_translations(pool: &PgPool) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "
+            SELECT translation FROM translation_versions
+            WHERE state = 'active'
+          ORDER BY translation
+        "
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// A superscription is known wherever the chapter is
+fn is_known_verse(title: &str, chapter: i32, verse: i32) -> bool {
+    let (Ok(chapter), Ok(verse)) = (u8::try_from(chapter), u8::try_from(verse)) else {
+        return false;
+    };
+
+    match get_verse_count_by_book_and_chapter(title, chapter) {
+        Some(verse_count) => verse == SUPERSCRIPTION_VERSE || verse <= verse_count,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_book_finds_nothing_wrong_with_a_whole_book() {
+        let verses = (1..=25).map(|verse| (1, verse, "text"));
+
+        assert_eq!(check_book("Jude", verses), vec![]);
+    }
+
+    #[test]
+    fn check_book_finds_every_kind_of_problem() {
+        let mut verses = (1..=24).map(|verse| (1, verse, "text")).collect::<Vec<_>>();
+        verses[2].2 = " ";
+        verses.push((1, 4, "text"));
+        verses.push((2, 1, "text"));
+
+        let kinds = check_book("Jude", verses)
+            .into_iter()
+            .map(|problem| (problem.kind, problem.chapter, problem.verse))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (ProblemKind::Empty, 1, 3),
+                (ProblemKind::Duplicate, 1, 4),
+                (ProblemKind::Unexpected, 2, 1),
+                (ProblemKind::Missing, 1, 25),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_covered_verses_runs_through_the_bridge() {
+        assert_eq!(get_covered_verses(4, None), 4..=4);
+        assert_eq!(get_covered_verses(4, Some(6)), 4..=6);
+        assert_eq!(get_covered_verses(4, Some(2)), 4..=4);
+    }
+
+    #[test]
+    fn is_badly_encoded_finds_garbled_text() {
+        assert!(!is_badly_encoded("And God said, “Let there be light.”"));
+        assert!(is_badly_encoded("And God said, â€œLet there be light."));
+        assert!(is_badly_encoded("And SolomonΓÇÖs son was Rehoboam"));
+        assert!(is_badly_encoded("Let there be light\u{FFFD}"));
+        assert!(is_badly_encoded("Let there be\u{0}light"));
+    }
+
+    #[test]
+    fn problem_displays_the_kind_and_reference() {
+        let problem = Problem {
+            kind: ProblemKind::Missing,
+            title: String::from("Jude"),
+            chapter: 1,
+            verse: 25,
+        };
+
+        assert_eq!(problem.to_string(), "missing verse: Jude 1:25");
+    }
+}
@@ -0,0 +1,197 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error,
+    readings::{Reading, Readings},
+    state::AppState,
+};
+
+/// The Lectionary is a cycle of readings for the days of the church year.
+/// - Rcl (rcl) is the Revised Common Lectionary (the default)
+/// - Catholic (catholic) is the Roman Catholic lectionary
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lectionary {
+    #[default]
+    Rcl,
+    Catholic,
+}
+
+impl Lectionary {
+    /// The as_str function returns the lectionary as it is stored.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lectionary::Rcl => "rcl",
+            Lectionary::Catholic => "catholic",
+        }
+    }
+}
+
+impl FromStr for Lectionary {
+    type Err = String;
+
+    fn from_str(lectionary: &str) -> Result<Self, Self::Err> {
+        match lectionary.trim().to_lowercase().as_str() {
+            "rcl" => Ok(Lectionary::Rcl),
+            "catholic" => Ok(Lectionary::Catholic),
+            other => Err(format!(
+                "Unknown lectionary: {} (ex: rcl or catholic)",
+                other
+            )),
+        }
+    }
+}
+
+/// The LectionaryDate is a day of the calendar (ex: 2025-12-25).
+#[derive(Debug, PartialEq, Clone)]
+pub struct LectionaryDate(String);
+
+impl LectionaryDate {
+    /// The as_str function returns the date as it was given (ex: 2025-12-25).
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The day function returns the day of the month (ex: 25).
+    pub fn day(&self) -> u8 {
+        self.0[8..].parse().unwrap_or(1)
+    }
+}
+
+impl FromStr for LectionaryDate {
+    type Err = String;
+
+    fn from_str(date: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid date: {} (ex: 2025-12-25)", date);
+        let date = date.trim();
+        let parts = date.split('-').collect::<Vec<&str>>();
+
+        let [year, month, day] = parts[..] else {
+            return Err(invalid());
+        };
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(invalid());
+        }
+
+        let year = year.parse::<u16>().map_err(|_| invalid())?;
+        let month = month.parse::<u8>().map_err(|_| invalid())?;
+        let day = day.parse::<u8>().map_err(|_| invalid())?;
+        if year == 0 || day == 0 || day > get_days_in_month(year, month).ok_or_else(invalid)? {
+            return Err(invalid());
+        }
+
+        Ok(LectionaryDate(date.to_owned()))
+    }
+}
+
+fn get_days_in_month(year: u16, month: u8) -> Option<u8> {
+    let is_leap_year =
+        year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+        4 | 6 | 9 | 11 => Some(30),
+        2 if is_leap_year => Some(29),
+        2 => Some(28),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LectionaryParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    date: Option<LectionaryDate>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    lectionary: Option<Lectionary>,
+}
+
+/// The LectionaryDay is the readings a lectionary appoints for a day.
+#[derive(Debug, Serialize)]
+pub struct LectionaryDay {
+    pub date: String,
+    pub lectionary: Lectionary,
+    pub name: String,
+    pub readings: Vec<Reading>,
+}
+
+/// The lectionary handler serves /lectionary (ex: /lectionary?date=2025-12-25
+/// &lectionary=catholic) with the readings of the day, each written out in
+/// full. The date defaults to today and the lectionary to the RCL.
+pub async fn lectionary(
+    State(state): State<AppState>,
+    Query(params): Query<LectionaryParams>,
+) -> Result<Response, BibleApiError> {
+    let lectionary = params.lectionary.unwrap_or_default();
+    let date = params.date.map(|LectionaryDate(date)| date);
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT d.day::text as "date!", d.name, r.label, r.reference
+            FROM lectionary_days d
+            JOIN lectionary_readings r ON r.lectionary = d.lectionary AND r.day = d.day
+            WHERE d.lectionary = $1 AND d.day = COALESCE($2::text::date, CURRENT_DATE)
+          ORDER BY r.position
+        "#,
+        lectionary.as_str(),
+        date,
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let Some(first) = rows.first() else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!(
+                "No readings for {} in the {} lectionary",
+                date.as_deref().unwrap_or("today"),
+                lectionary.as_str()
+            ),
+        )
+            .into());
+    };
+    let (date, name) = (first.date.clone(), first.name.clone());
+
+    let passages = rows.into_iter().map(|row| (row.label, row.reference));
+    let readings = Readings::expand(&state, passages).await?;
+
+    Ok(readings.into_response(|readings| LectionaryDay {
+        date,
+        lectionary,
+        name,
+        readings,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lectionary_date_takes_only_real_days() {
+        assert_eq!(
+            "2025-12-25".parse::<LectionaryDate>(),
+            Ok(LectionaryDate(String::from("2025-12-25")))
+        );
+        assert!("2024-02-29".parse::<LectionaryDate>().is_ok());
+        assert!("2025-02-29".parse::<LectionaryDate>().is_err());
+        assert!("2025-13-01".parse::<LectionaryDate>().is_err());
+        assert!("25-12-25".parse::<LectionaryDate>().is_err());
+        assert!("christmas".parse::<LectionaryDate>().is_err());
+    }
+
+    #[test]
+    fn lectionary_parses_each_name() {
+        assert_eq!("RCL".parse::<Lectionary>(), Ok(Lectionary::Rcl));
+        assert_eq!(" catholic".parse::<Lectionary>(), Ok(Lectionary::Catholic));
+        assert!("sarum".parse::<Lectionary>().is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// The SearchKind is what a search looks up.
+/// - Reference (reference) is a passage (ex: John 3:16-18), as /search takes
+/// - Keyword (keyword) is the words in verses (ex: love one another), as
+///   /search/text takes
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Reference,
+    Keyword,
+}
+
+impl SearchKind {
+    /// The as_str function returns the kind as it is stored.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchKind::Reference => "reference",
+            SearchKind::Keyword => "keyword",
+        }
+    }
+}
+
+impl FromStr for SearchKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind.trim().to_lowercase().as_str() {
+            "reference" => Ok(SearchKind::Reference),
+            "keyword" => Ok(SearchKind::Keyword),
+            other => Err(format!(
+                "Unknown kind: {} (ex: reference or keyword)",
+                other
+            )),
+        }
+    }
+}
+
+/// The get_href function returns the path that runs a search (ex:
+/// /search?query=John+3%3A16).
+pub fn get_href(kind: SearchKind, query: &str) -> String {
+    let (path, param) = match kind {
+        SearchKind::Reference => ("/search", "query"),
+        SearchKind::Keyword => ("/search/text", "q"),
+    };
+
+    match reqwest::Url::parse_with_params(&format!("http://localhost{}", path), [(param, query)]) {
+        Ok(url) => format!("{}?{}", url.path(), url.query().unwrap_or_default()),
+        Err(_) => path.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_href_encodes_the_query() {
+        assert_eq!(
+            get_href(SearchKind::Reference, "John 3:16"),
+            "/search?query=John+3%3A16"
+        );
+        assert_eq!(
+            get_href(SearchKind::Keyword, "love & mercy"),
+            "/search/text?q=love+%26+mercy"
+        );
+    }
+}
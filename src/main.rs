@@ -1,59 +1,442 @@
 extern crate dotenv;
+mod admin;
+mod alignment;
+mod annotate;
+mod api_keys;
+mod audio;
+mod audit;
+mod auth;
 mod book;
-mod chapter;
+mod breaker;
+mod browse;
+mod cache_control;
+mod cdn;
+mod changes;
+mod cli;
+mod coalesce;
+mod config;
+mod continuation;
 mod db;
-mod params;
-mod search;
-mod verse;
+mod diff;
+#[cfg(feature = "embedded")]
+mod embedded_store;
+mod error;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod health;
+mod idempotency;
+mod identify;
+#[cfg(feature = "import")]
+mod import;
+mod integrity;
+mod lectionary;
+mod links;
+mod migrations;
+mod ndjson;
+mod oauth;
+mod office;
+mod offline;
+#[cfg(feature = "tantivy")]
+mod offline_index;
+mod parse;
+mod passage_cache;
+mod passages;
+mod people;
+mod places;
+mod pool_stats;
+mod popularity;
+mod privacy;
+mod prometheus;
+mod random;
+mod rate_limit;
+mod readings;
+#[cfg(feature = "redis")]
+mod redis_cache;
+mod reindex;
+mod render;
+mod saved;
+mod search_engine;
+mod server;
+mod signing;
+mod sitemap;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+mod state;
+mod stats;
+mod store;
+mod text_search;
+mod timeline;
+mod topics;
+mod trending;
+mod validation;
+mod verse_id;
+mod versification;
+mod versions;
+mod warm_up;
 
-use axum::{extract::Query, extract::State, http::StatusCode, routing::get, Json, Router};
-use db::SearchResult;
+use auth::ApiKeys;
+use axum::{
+    extract::DefaultBodyLimit,
+    extract::Query,
+    extract::State,
+    http::{HeaderMap, HeaderName, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
+};
+// The reference parsing lives in the bible-ref crate, and is used from
+// here as if it were the server's own modules. Only the search module is
+// taken, as the search handler is named search too.
+use bible_ref::{
+    chapter, params, reference,
+    search::{self},
+    spoken, verse, ReferenceError,
+};
+use breaker::{get_degraded_headers, CircuitBreaker};
+use cache_control::CachePolicy;
+use cdn::{get_surrogate_keys, CdnConfig, SURROGATE_KEY_HEADER};
+use clap::Parser;
+use cli::{Cli, Command};
+use config::Config;
+use continuation::CONTINUATION_HEADER;
+use error::BibleApiError;
+use idempotency::IdempotencyStore;
+use oauth::OAuth;
+use offline::OfflineDataset;
+use popularity::Popularity;
+use rate_limit::{RateLimiter, VerseCount};
+use reindex::ReindexJob;
+use search_engine::SearchEngine;
 use serde::{de, Deserialize, Deserializer};
+use server::ServerConfig;
+use signing::{Signer, SIGNATURE_HEADER, SIGNATURE_KEY_HEADER};
 use sqlx::postgres::{PgPool, PgPoolOptions};
-use std::{fmt, str::FromStr, time::Duration};
+use state::AppState;
+use stats::UsageStats;
+use std::{fmt, str::FromStr, sync::Arc};
+use store::{Backend, PostgresStore, VerseStore};
 use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use trending::Trending;
+use versification::{Versification, Versifications};
 
 #[tokio::main]
 async fn main() {
-    dotenv::dotenv().expect("Failed to load .env file");
+    // A .env is optional, every setting has a default or can be given as a
+    // flag
+    let _ = dotenv::dotenv();
+    let cli = Cli::parse();
+    let config = cli.config;
 
     tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .expect("Failed to load .env file (tracing)"),
-        )
-        .with(tracing_subscriber::fmt::layer())
+        .with(config.get_log_filter())
+        // Logs go to stderr, leaving stdout to what the commands print
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
+    book::init_ambiguity_policy();
+    book::init_canon();
 
-    let db_connection_str = std::env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    match cli.command.unwrap_or_default() {
+        Command::Serve => serve(config).await,
+        #[cfg(feature = "import")]
+        Command::Import(args) => cli::import::import(args, &config).await,
+        Command::Verify(args) => cli::verify(args, &config).await,
+        Command::IndexSearch(args) => cli::index_search(args, &config).await,
+    }
+}
+
+/// The connect function sets up the connection pool to the database of the
+/// config, with its pool limits, and migrates the database unless that is
+/// turned off.
+async fn connect(config: &Config) -> PgPool {
+    let db_connection_str = config
+        .database_url
+        .as_deref()
+        .expect("DATABASE_URL not set (or pass --database-url)");
+    pool_stats::set_max_connections(config.db_max_connections);
 
-    // setup connection pool
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .acquire_timeout(Duration::from_secs(3))
-        .connect(&db_connection_str)
+        .max_connections(config.db_max_connections)
+        .min_connections(config.db_min_connections)
+        .acquire_timeout(config.get_acquire_timeout())
+        .after_connect(pool_stats::on_connect)
+        .connect(db_connection_str)
         .await
         .expect("can't connect to database");
 
-    // build our application with some routes
+    if !config.skip_migrations {
+        migrations::run(&pool)
+            .await
+            .expect("can't migrate the database");
+    }
+
+    pool
+}
+
+/// The open_store function opens the store of the verses of a backend other
+/// than Postgres, which the binary needs the feature of the same name for.
+/// Nothing else is kept outside of Postgres, so the pool returned is never
+/// connected, and only the routes that read verses are served.
+#[cfg_attr(not(feature = "sqlite"), allow(unused_variables))]
+async fn open_store(backend: Backend, config: &Config) -> (PgPool, Arc<dyn VerseStore>) {
+    let store: Option<Arc<dyn VerseStore>> = match backend {
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => {
+            let database_url = config.database_url.as_deref().unwrap_or_default();
+            let store = sqlite_store::SqliteStore::connect(database_url)
+                .await
+                .expect("can't open the SQLite database");
+            Some(Arc::new(store))
+        }
+        #[cfg(feature = "embedded")]
+        Backend::Embedded => Some(Arc::new(embedded_store::EmbeddedStore::load())),
+        _ => None,
+    };
+    let store =
+        store.unwrap_or_else(|| panic!("the {} database needs the {0} feature", backend.name()));
+    let pool = PgPoolOptions::new()
+        .connect_lazy("postgres://localhost")
+        .expect("can't set up the unused Postgres pool");
+
+    (pool, store)
+}
+
+async fn serve(config: Config) {
+    // record the metrics scraped from /metrics, from the first connection on
+    prometheus::install();
+
+    // a SQLite database or the text built in has the verses of the default
+    // translation and nothing else, so everything that needs Postgres is
+    // left out
+    let backend = Backend::from_url(config.database_url.as_deref());
+    let postgres = backend == Backend::Postgres;
+    let (pool, store) = match backend {
+        Backend::Postgres => {
+            let pool = connect(&config).await;
+            let store: Arc<dyn VerseStore> = Arc::new(PostgresStore::new(pool.clone()));
+            (pool, store)
+        }
+        backend => open_store(backend, &config).await,
+    };
+    tracing::debug!("reading the verses from {}", store.name());
+
+    // load the text into a database that has none of it yet
+    #[cfg(feature = "import")]
+    if let Some(seed) = config.seed.as_deref().filter(|_| postgres) {
+        match import::seed(&pool, seed).await {
+            Ok(Some(version)) => tracing::info!(
+                "seeded the database from {} as version {}",
+                seed.display(),
+                version
+            ),
+            Ok(None) => tracing::debug!("the database is already seeded"),
+            Err(err) => panic!("can't seed the database from {}: {}", seed.display(), err),
+        }
+    }
+
+    // only Postgres has the API keys to check
+    let rate_limiter = RateLimiter::from_env(postgres).unwrap_or_else(|err| panic!("{}", err));
+    // forget the clients whose rate limit window has run out, on every backend
+    rate_limiter.spawn_sweep();
+    let popularity = Popularity::default();
+    let trending = Trending::default();
+    let versifications = Versifications::default();
+    if postgres {
+        // log pool usage periodically so connection problems can be diagnosed later
+        pool_stats::spawn_summary_logger(pool.clone());
+
+        // keep the rate limit tiers of every API key up to date
+        rate_limiter.spawn_refresh(pool.clone());
+
+        // count the verses looked up, for the popularity ranking
+        popularity.spawn_flush(pool.clone());
+
+        // count the references searched for, for the trending searches
+        trending.spawn_rollup(pool.clone());
+
+        // check searches against how each translation numbers its verses,
+        // from the first one on, and keep that up to date
+        versifications.load(&pool).await;
+        versifications.spawn_refresh(pool.clone());
+
+        // tell the webhooks of saved searches when new verses match them
+        saved::spawn_notifier(pool.clone());
+    }
+
+    // answer searches from a copy of the text while the database is down
+    let offline = OfflineDataset::from_env();
+
+    // keep the passages read most often, so they are answered without a
+    // trip to the database
+    let passage_cache = config.get_passage_cache();
+
+    // one client for every call out, so its connections are reused
+    let http = reqwest::Client::new();
+
+    let state = AppState {
+        pool: pool.clone(),
+        cdn: CdnConfig::from_env(&http),
+        api_keys: ApiKeys::from_env(),
+        rate_limiter: rate_limiter.clone(),
+        reindex: ReindexJob::default(),
+        popularity: popularity.clone(),
+        trending: trending.clone(),
+        stats: UsageStats::from_env(),
+        versifications,
+        #[cfg(feature = "tantivy")]
+        offline_index: offline
+            .as_ref()
+            .and_then(offline_index::OfflineIndex::from_env),
+        breaker: CircuitBreaker::new(store, offline).with_cache(passage_cache),
+        cache_policy: CachePolicy::from_env(),
+        search_engine: SearchEngine::from_env(&http),
+        signer: Signer::from_env(),
+        // only Postgres has the accounts to sign in to
+        oauth: OAuth::from_env(&http).filter(|_| postgres),
+        http,
+    };
+
+    // fetch the most read passages so the first readers are not kept waiting
+    warm_up::spawn_warm_up(state.clone(), warm_up::get_passages());
+
+    // answer the services that only speak gRPC, on a port of their own
+    #[cfg(feature = "grpc")]
+    grpc::spawn_server(state.clone());
+
+    // how connections are served, tuned for the traffic the deployment gets
+    let server_config = ServerConfig::from_env();
+
+    // answer the retries of a write with the response it was first given
+    let idempotency_store = IdempotencyStore::new(server_config.max_body_bytes);
+    idempotency_store.spawn_sweep();
+
+    // build our application with some routes, first the ones that only read
+    // verses or need no database (ex: the sitemaps), which are all the other
+    // backends can serve
     let app = Router::new()
-        .route("/", get(hello))
         .route("/search", get(search))
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
-        .with_state(pool);
+        .route("/parse", get(parse::parse))
+        .route("/books/aliases", get(book::aliases))
+        .route("/books/:book", get(browse::book))
+        .route("/books/:book/chapters/:chapter", get(browse::chapter))
+        .route(
+            "/books/:book/chapters/:chapter/verses/:verse",
+            get(browse::verse),
+        )
+        .route("/random", get(random::random))
+        .route("/sitemap.xml", get(sitemap::sitemap_index))
+        .route("/sitemaps/:shard", get(sitemap::sitemap_shard))
+        .route("/topics/:topic/random", get(topics::random))
+        .route("/verses/:id", get(verse_id::verse));
+    let app = match postgres {
+        false => app,
+        true => app
+            .route("/", get(hello))
+            .route("/search/text", get(text_search::text_search))
+            .route("/audio", get(audio::audio))
+            .route("/audio/timings", get(audio::audio_timings))
+            .route("/auth/:provider", get(oauth::sign_in))
+            .route(
+                "/auth/:provider/callback",
+                get(oauth::callback).post(oauth::callback_form),
+            )
+            .route("/diff", get(diff::diff))
+            .route("/identify", post(identify::identify))
+            .route("/lectionary", get(lectionary::lectionary))
+            .route("/office", get(office::office))
+            .route("/people/:name", get(people::person))
+            .route("/places", get(places::places))
+            .route("/popular", get(popularity::popular))
+            .route("/saved", get(saved::saved_searches))
+            .route(
+                "/saved/:name",
+                put(saved::save_search)
+                    .get(saved::run_saved_search)
+                    .delete(saved::delete_saved_search),
+            )
+            .route("/timeline", get(timeline::timeline))
+            .route("/translations/:translation/changes", get(changes::changes))
+            .route("/translations", get(versions::translations))
+            .route("/trending", get(trending::trending)),
+    };
+    #[cfg(feature = "graphql")]
+    let app = match postgres {
+        false => app,
+        true => app.route("/graphql", post(graphql::graphql)),
+    };
+    let app = app
+        // retries of the writes above are answered without being made again
+        .route_layer(middleware::from_fn_with_state(
+            idempotency_store,
+            idempotency::idempotent,
+        ))
+        // only the routes above are rate limited, the admin routes have keys
+        .route_layer(middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit::limit,
+        ))
+        // monitoring checks the health without a key or a rate limit
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .route("/metrics", get(prometheus::metrics))
+        .route("/signing-key", get(signing::signing_key));
+    let app = match postgres {
+        false => app,
+        true => app
+            .route("/health", get(health::health))
+            .route("/admin/purge", post(admin::purge))
+            .route("/admin/cache/invalidate", post(admin::invalidate))
+            .route(
+                "/admin/translations/:translation/versions",
+                get(admin::translation_versions),
+            )
+            .route(
+                "/admin/translations/:translation/rollback",
+                post(admin::rollback),
+            )
+            .route(
+                "/admin/reindex",
+                get(admin::reindex_status).post(admin::reindex),
+            )
+            .route("/admin/stats", get(admin::stats))
+            .route("/admin/audit", get(audit::audit))
+            .route("/admin/keys", post(api_keys::create_key))
+            .route("/admin/keys/:key_id", delete(api_keys::revoke_key)),
+    };
+    let app = app
+        .layer(middleware::from_fn_with_state(
+            state.cache_policy.clone(),
+            cache_control::set_cache_headers,
+        ))
+        // the cache headers have to be set before a 304 can keep them
+        .layer(middleware::from_fn(cache_control::set_etag))
+        .layer(middleware::from_fn(validation::limit_uri))
+        .layer(middleware::from_fn(prometheus::track))
+        .layer(DefaultBodyLimit::max(server_config.max_body_bytes))
+        .layer(config.get_cors_layer())
+        .layer(TraceLayer::new_for_http().make_span_with(privacy::make_request_span))
+        .with_state(state);
 
     // run it with hyper
-    let listener = TcpListener::bind("127.0.0.1:3000").await.unwrap();
+    let listener = TcpListener::bind(config.get_address()).await.unwrap();
     tracing::debug!("listening on {}", listener.local_addr().unwrap());
     println!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    server::serve(listener, app, server_config).await;
+
+    // save the counts not written yet, then close the connections cleanly
+    if postgres {
+        popularity.save(&pool).await;
+        trending.save(&pool).await;
+        pool.close().await;
+    }
+    tracing::info!("stopped");
 }
 
-async fn hello(State(pool): State<PgPool>) -> Result<String, (StatusCode, String)> {
+async fn hello(State(pool): State<PgPool>) -> Result<String, BibleApiError> {
     sqlx::query_scalar("select 'hello world from pg'")
         .fetch_one(&pool)
         .await
@@ -64,6 +447,26 @@ async fn hello(State(pool): State<PgPool>) -> Result<String, (StatusCode, String
 struct Params {
     #[serde(default, deserialize_with = "empty_string_as_none")]
     query: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    translation: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    superscriptions: Option<bool>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    format: Option<render::Format>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    include: Option<annotate::Include>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    signed: Option<bool>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    continuation: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    strict: Option<bool>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    limit: Option<usize>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    offset: Option<usize>,
+    #[serde(flatten)]
+    render_options: render::RenderOptions,
 }
 
 /// Serde deserialization decorator to map empty Strings to None,
@@ -81,27 +484,296 @@ where
 }
 
 async fn search(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
     Query(params): Query<Params>,
-) -> Result<Json<Vec<SearchResult>>, (StatusCode, String)> {
-    let query = params.query.ok_or((
-        StatusCode::BAD_REQUEST,
-        "missing query parameter".to_string(),
-    ))?;
-
-    match search::search(&query) {
-        Ok(bible_search) => match db::search(pool, bible_search).await {
-            Ok(results) => Ok(results),
-            Err(err) => Err(err),
-        },
-        Err(err) => Err((StatusCode::NOT_FOUND, err)),
+) -> Result<Response, Response> {
+    // The token of a passage sent in part stands in for its query
+    let query = match params.continuation {
+        Some(token) => continuation::read_token(&token).map_err(IntoResponse::into_response)?,
+        None => params.query.ok_or(
+            BibleApiError::from((
+                StatusCode::BAD_REQUEST,
+                "missing query parameter".to_string(),
+            ))
+            .into_response(),
+        )?,
+    };
+    validation::check_reference("query", &query).map_err(IntoResponse::into_response)?;
+
+    let signer = match params.signed.unwrap_or(false) {
+        true => Some(
+            state.signer.ok_or(
+                BibleApiError::from((StatusCode::NOT_FOUND, "Signing Is Not Enabled".to_string()))
+                    .into_response(),
+            )?,
+        ),
+        false => None,
+    };
+
+    // The default translation is always served, any other has to be loaded
+    let translation = params
+        .translation
+        .map(|translation| translation.trim().to_lowercase())
+        .unwrap_or_else(|| db::get_default_translation().to_owned());
+    validation::check_text("translation", &translation, validation::MAX_TRANSLATION_LEN)
+        .map_err(IntoResponse::into_response)?;
+    if translation != db::get_default_translation()
+        && !state
+            .breaker
+            .store()
+            .is_active(&translation)
+            .await
+            .map_err(IntoResponse::into_response)?
+    {
+        return Err(BibleApiError::from((
+            StatusCode::NOT_FOUND,
+            "No Matching Translation Found".to_string(),
+        ))
+        .into_response());
     }
+
+    let versification = state.versifications.get(&translation);
+    let strict = params.strict.unwrap_or(false);
+    match search::search_passages(&query, &versification, strict) {
+        Ok(searches) if searches.len() > 1 => {
+            for bible_search in &searches {
+                state.popularity.record(bible_search);
+                state.trending.record(bible_search, &versification);
+            }
+
+            // Annotations and signatures are for the verses of one passage
+            if params.include.is_some_and(|include| !include.is_empty()) || signer.is_some() {
+                return Err(BibleApiError::from((
+                    StatusCode::BAD_REQUEST,
+                    "include and signed are only available for a single passage".to_string(),
+                ))
+                .into_response());
+            }
+
+            // A page is of the verses of one passage
+            if params.limit.is_some() || params.offset.is_some() {
+                return Err(BibleApiError::from((
+                    StatusCode::BAD_REQUEST,
+                    "limit and offset are only available for a single passage".to_string(),
+                ))
+                .into_response());
+            }
+
+            let format = render::Format::negotiate(params.format, &headers);
+            state.stats.record_query(format, &translation);
+
+            passages::passages_response(
+                state.breaker,
+                &translation,
+                searches,
+                &versification,
+                format,
+                params.render_options,
+                params.superscriptions,
+            )
+            .await
+        }
+        Ok(mut searches) => {
+            let bible_search = searches.remove(0);
+            state.popularity.record(&bible_search);
+            state.trending.record(&bible_search, &versification);
+
+            let format = render::Format::negotiate(params.format, &headers);
+            state.stats.record_query(format, &translation);
+
+            // Only the page asked for is fetched, described in headers
+            let (bible_search, page) = match (params.offset, params.limit) {
+                (None, None) => (bible_search, None),
+                (offset, limit) => {
+                    let (bible_search, page) =
+                        continuation::page_search(bible_search, offset, limit);
+                    (bible_search, Some(page))
+                }
+            };
+
+            let response = search_response(
+                state.pool,
+                state.breaker,
+                &translation,
+                bible_search,
+                format,
+                params.render_options,
+                PassageOptions {
+                    superscriptions: params.superscriptions,
+                    include: params.include.unwrap_or_default(),
+                    signer,
+                    versification,
+                },
+            )
+            .await?;
+
+            Ok((page.map(continuation::Page::headers), response).into_response())
+        }
+        Err(err) => {
+            if matches!(
+                err,
+                ReferenceError::UnknownBook(_) | ReferenceError::EmptyQuery
+            ) {
+                state.stats.record_not_found();
+            }
+            Err(parse::unresolved(&query, err.into()).into_response())
+        }
+    }
+}
+
+/// The PassageOptions are what a client can add to the verses of a search,
+/// and the versification they are read with.
+/// - superscriptions turns the superscription of a whole chapter on or off
+/// - include is the annotations to add, only with the json format
+/// - signer signs the verses, only with the json format
+/// - versification is how the translation numbers its verses, which decides
+///   what a whole chapter is and how the reference is written (the built in
+///   one by default)
+#[derive(Debug, Default)]
+struct PassageOptions {
+    superscriptions: Option<bool>,
+    include: annotate::Include,
+    signer: Option<Signer>,
+    versification: Arc<Versification>,
+}
+
+/// The search_response function fetches the verses of a search from a
+/// translation and writes them in the format the client asked for, tagged
+/// with their surrogate keys and the number of verses sent. Verses answered
+/// from the offline dataset are flagged as degraded, in the body and a
+/// header, are not to be cached, and can not be annotated.
+/// With a signer the verses are signed, and the signature and the id of the
+/// key are sent in headers. A passage of more verses than the verse cap is
+/// sent a chunk at a time, with the continuation token for the rest in a
+/// header.
+async fn search_response(
+    pool: PgPool,
+    breaker: CircuitBreaker,
+    translation: &str,
+    bible_search: search::BibleSearch,
+    format: render::Format,
+    render_options: render::RenderOptions,
+    passage_options: PassageOptions,
+) -> Result<Response, Response> {
+    let PassageOptions {
+        superscriptions,
+        include,
+        signer,
+        versification,
+    } = passage_options;
+
+    // Annotations are only written as JSON
+    if !include.is_empty() && format != render::Format::Json {
+        return Err(BibleApiError::from((
+            StatusCode::BAD_REQUEST,
+            "include is only available with the json format".to_string(),
+        ))
+        .into_response());
+    }
+
+    // Signed verses are only written as JSON, so they can be checked as
+    // they were sent
+    if signer.is_some() && format != render::Format::Json {
+        return Err(BibleApiError::from((
+            StatusCode::BAD_REQUEST,
+            "signed is only available with the json format".to_string(),
+        ))
+        .into_response());
+    }
+
+    // Superscriptions come with whole chapters unless turned off
+    let options = db::SearchOptions {
+        superscription: superscriptions.unwrap_or(true)
+            && search::is_whole_chapter_with(&bible_search, &versification),
+        format: format.text_format(),
+    };
+
+    let (bible_search, rest) =
+        continuation::split_search(bible_search, continuation::get_max_verses());
+    let continuation = rest.map(|rest| {
+        [(
+            HeaderName::from_static(CONTINUATION_HEADER),
+            continuation::get_token(&rest, &versification),
+        )]
+    });
+
+    let reference = search::get_reference_with(&bible_search, &versification);
+    let surrogate_keys = [(
+        HeaderName::from_static(SURROGATE_KEY_HEADER),
+        get_surrogate_keys(translation, &bible_search.title),
+    )];
+
+    // Stream the rows as they arrive when the client can take NDJSON.
+    // The rows are not counted as they go, so the verses requested are
+    // charged against the client's rate limit instead. While the breaker is
+    // not closed the rows are fetched the same way as every other format.
+    if format == render::Format::Ndjson && breaker.is_closed() {
+        let verse_count = Extension(VerseCount(bible_search.verse_count()));
+        let rows = breaker
+            .store()
+            .stream_search(translation, bible_search, options);
+        return Ok((
+            surrogate_keys,
+            continuation,
+            verse_count,
+            ndjson::ndjson_response(rows),
+        )
+            .into_response());
+    }
+
+    let (results, degraded) = breaker
+        .search(translation, bible_search, options)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    let verse_count = Extension(VerseCount(results.len()));
+    let signature = signer.map(|signer| {
+        [
+            (
+                HeaderName::from_static(SIGNATURE_HEADER),
+                signer.sign(translation, &results),
+            ),
+            (
+                HeaderName::from_static(SIGNATURE_KEY_HEADER),
+                signer.key_id().to_owned(),
+            ),
+        ]
+    });
+    // The annotations are in the database, so none can be added to verses
+    // from the offline dataset
+    if degraded && !include.is_empty() {
+        return Err(BibleApiError::from((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "include is not available while the database is down".to_string(),
+        ))
+        .into_response());
+    }
+    let body = match include.is_empty() {
+        true => render::render(format, results, &reference, &render_options),
+        false => annotate::annotate(&pool, results, include)
+            .await
+            .map(|annotated| Json(annotated).into_response()),
+    }
+    .map_err(IntoResponse::into_response)?;
+    let degraded = get_degraded_headers(degraded);
+
+    Ok((
+        surrogate_keys,
+        continuation,
+        verse_count,
+        degraded,
+        signature,
+        body,
+    )
+        .into_response())
 }
 
 /// Utility function for mapping any error into a `500 Internal Server Error` response.
-fn internal_error<E>(err: E) -> (StatusCode, String)
+/// The error is logged, and the client is only told that something failed.
+fn internal_error<E>(err: E) -> BibleApiError
 where
     E: std::error::Error,
 {
-    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+    tracing::error!("{}", err);
+    BibleApiError::Internal
 }
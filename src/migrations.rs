@@ -0,0 +1,37 @@
+use sqlx::{
+    migrate::{MigrateError, Migrator},
+    postgres::PgPool,
+};
+
+/// The MIGRATOR holds the migrations in migrations/, built into the binary.
+/// They set up the schema and the reference data the API serves, but not
+/// the text, which is imported (see --seed).
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// The run function applies the migrations the database has not had yet, in
+/// order, each in a transaction. They are recorded in _sqlx_migrations, so
+/// running them again does nothing.
+pub async fn run(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrator_holds_the_migrations_in_order() {
+        let versions = MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .collect::<Vec<i64>>();
+
+        assert_eq!(versions, vec![1, 2, 3, 4, 5, 6]);
+        assert!(MIGRATOR
+            .iter()
+            .next()
+            .unwrap()
+            .sql
+            .contains("CREATE TABLE IF NOT EXISTS public.verses"));
+    }
+}
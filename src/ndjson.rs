@@ -0,0 +1,37 @@
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+
+/// The NDJSON_CONTENT_TYPE is the media type for newline delimited JSON,
+/// where every line of the body is a complete JSON document.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// The ndjson_response function turns a stream of rows into a chunked
+/// response body with one JSON object per line.
+pub fn ndjson_response<S, T, E>(rows: S) -> Response
+where
+    S: Stream<Item = Result<T, E>> + Send + 'static,
+    T: Serialize,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let lines = rows.map(|row| {
+        row.map(|row| {
+            let mut line = serde_json::to_vec(&row).unwrap_or_default();
+            line.push(b'\n');
+            line
+        })
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, NDJSON_CONTENT_TYPE),
+            (header::VARY, header::ACCEPT.as_str()),
+        ],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
@@ -0,0 +1,199 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error,
+    lectionary::LectionaryDate,
+    readings::{Reading, Readings},
+    state::AppState,
+};
+
+/// The PSALTER is the 30 day cycle of psalms read at morning and evening
+/// prayer, as in the Book of Common Prayer. Each entry is a range of whole
+/// psalms (ex: 1-5) or a part of one (ex: 119:1-32). The 31st day of a month
+/// repeats the 30th.
+const PSALTER: [[&str; 2]; 30] = [
+    ["1-5", "6-8"],
+    ["9-11", "12-14"],
+    ["15-17", "18"],
+    ["19-21", "22-23"],
+    ["24-26", "27-29"],
+    ["30-31", "32-34"],
+    ["35-36", "37"],
+    ["38-40", "41-43"],
+    ["44-46", "47-49"],
+    ["50-52", "53-55"],
+    ["56-58", "59-61"],
+    ["62-64", "65-67"],
+    ["68", "69-70"],
+    ["71-72", "73-74"],
+    ["75-77", "78"],
+    ["79-81", "82-85"],
+    ["86-88", "89"],
+    ["90-92", "93-94"],
+    ["95-97", "98-101"],
+    ["102-103", "104"],
+    ["105", "106"],
+    ["107", "108-109"],
+    ["110-113", "114-115"],
+    ["116-118", "119:1-32"],
+    ["119:33-72", "119:73-104"],
+    ["119:105-144", "119:145-176"],
+    ["120-125", "126-131"],
+    ["132-135", "136-138"],
+    ["139-141", "142-143"],
+    ["144-146", "147-150"],
+];
+
+/// The OfficeHour is the service the readings are appointed for.
+/// - Morning (morning) is morning prayer (the default)
+/// - Evening (evening) is evening prayer
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OfficeHour {
+    #[default]
+    Morning,
+    Evening,
+}
+
+impl OfficeHour {
+    /// The as_str function returns the hour as it is stored.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OfficeHour::Morning => "morning",
+            OfficeHour::Evening => "evening",
+        }
+    }
+}
+
+impl FromStr for OfficeHour {
+    type Err = String;
+
+    fn from_str(hour: &str) -> Result<Self, Self::Err> {
+        match hour.trim().to_lowercase().as_str() {
+            "morning" => Ok(OfficeHour::Morning),
+            "evening" => Ok(OfficeHour::Evening),
+            other => Err(format!("Unknown hour: {} (ex: morning or evening)", other)),
+        }
+    }
+}
+
+/// The get_psalms function returns the references of the psalms the Psalter
+/// appoints for a day of the month and hour, a reading per psalm.
+pub fn get_psalms(day: u8, hour: OfficeHour) -> Vec<String> {
+    let day = usize::from(day.clamp(1, 30));
+    let entry = PSALTER[day - 1][hour as usize];
+
+    if entry.contains(':') {
+        return vec![format!("Psalms {}", entry)];
+    }
+
+    let (start, end) = entry.split_once('-').unwrap_or((entry, entry));
+    let start = start.parse::<u8>().unwrap_or_default();
+    let end = end.parse::<u8>().unwrap_or_default();
+
+    (start..=end)
+        .map(|psalm| format!("Psalms {}", psalm))
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OfficeParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    date: Option<LectionaryDate>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    hour: Option<OfficeHour>,
+}
+
+/// The OfficeDay is the psalms and lessons appointed for an hour of a day.
+#[derive(Debug, Serialize)]
+pub struct OfficeDay {
+    pub date: String,
+    pub hour: OfficeHour,
+    pub readings: Vec<Reading>,
+}
+
+/// The office handler serves /office (ex: /office?date=2025-12-25&hour=evening)
+/// with the psalms of the Psalter cycle followed by the lessons of the day,
+/// each written out in full. The date defaults to today and the hour to the
+/// morning. A day without lessons still has its psalms.
+pub async fn office(
+    State(state): State<AppState>,
+    Query(params): Query<OfficeParams>,
+) -> Result<Response, BibleApiError> {
+    let hour = params.hour.unwrap_or_default();
+    let date = match params.date {
+        Some(date) => date,
+        None => sqlx::query_scalar!(r#"SELECT CURRENT_DATE::text as "date!""#)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(internal_error)?
+            .parse()
+            .map_err(|err| BibleApiError::from((StatusCode::INTERNAL_SERVER_ERROR, err)))?,
+    };
+
+    let lessons = sqlx::query!(
+        r#"
+            SELECT label, reference
+            FROM office_readings
+            WHERE day = $1::text::date AND hour = $2
+          ORDER BY position
+        "#,
+        date.as_str(),
+        hour.as_str(),
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let psalms = get_psalms(date.day(), hour)
+        .into_iter()
+        .map(|reference| (String::from("Psalm"), reference));
+    let lessons = lessons
+        .into_iter()
+        .map(|lesson| (lesson.label, lesson.reference));
+    let readings = Readings::expand(&state, psalms.chain(lessons)).await?;
+
+    Ok(readings.into_response(|readings| OfficeDay {
+        date: date.as_str().to_owned(),
+        hour,
+        readings,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_psalms_expands_the_psalter_ranges() {
+        assert_eq!(
+            get_psalms(1, OfficeHour::Morning),
+            ["Psalms 1", "Psalms 2", "Psalms 3", "Psalms 4", "Psalms 5"]
+        );
+        assert_eq!(get_psalms(3, OfficeHour::Evening), ["Psalms 18"]);
+        assert_eq!(get_psalms(24, OfficeHour::Evening), ["Psalms 119:1-32"]);
+    }
+
+    #[test]
+    fn get_psalms_repeats_the_thirtieth_day_on_the_thirty_first() {
+        assert_eq!(
+            get_psalms(31, OfficeHour::Evening),
+            get_psalms(30, OfficeHour::Evening)
+        );
+        assert_eq!(get_psalms(31, OfficeHour::Evening).len(), 4);
+    }
+
+    #[test]
+    fn office_hour_parses_each_hour() {
+        assert_eq!("Evening".parse::<OfficeHour>(), Ok(OfficeHour::Evening));
+        assert!("compline".parse::<OfficeHour>().is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use std::{collections::HashMap, fs, sync::Arc};
+
+use crate::{
+    db::{get_default_translation, SearchResult},
+    search::BibleSearch,
+    verse::SUPERSCRIPTION_VERSE,
+};
+
+// The verses are kept by book and chapter, in verse order
+type Chapters = HashMap<(String, i32), Vec<SearchResult>>;
+
+/// The OfflineDataset is a copy of the default translation held in memory, so
+/// references can still be looked up while the database is down. It is read
+/// at startup from the file OFFLINE_DATASET names, which holds a verse per
+/// line in the same shape as the ndjson format (db/kjv.ndjson is the KJV).
+/// Only the plain text of the verses is kept.
+#[derive(Clone, Default)]
+pub struct OfflineDataset {
+    chapters: Arc<Chapters>,
+}
+
+impl OfflineDataset {
+    /// The from_env function loads the dataset OFFLINE_DATASET names, or
+    /// returns None when it is not set. A dataset that can not be read is
+    /// logged and left out, so the server still starts.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("OFFLINE_DATASET").ok()?;
+
+        let dataset = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| OfflineDataset::parse(&source));
+
+        match dataset {
+            Ok(dataset) => {
+                tracing::info!("loaded the offline dataset from {}", path);
+                Some(dataset)
+            }
+            Err(err) => {
+                tracing::warn!("could not load the offline dataset {}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    /// The parse function reads a dataset of a verse per line.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut chapters: Chapters = HashMap::new();
+
+        for (index, line) in source.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let verse: SearchResult =
+                serde_json::from_str(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+            // The dataset may be older than the ids, so they are numbered again
+            let verse = SearchResult::new(
+                get_default_translation(),
+                verse.title,
+                verse.chapter,
+                verse.verse,
+                verse.text,
+                verse.paragraph_start,
+            );
+            chapters
+                .entry((verse.title.clone(), verse.chapter))
+                .or_default()
+                .push(verse);
+        }
+
+        for verses in chapters.values_mut() {
+            verses.sort_by_key(|verse| verse.verse);
+        }
+
+        Ok(OfflineDataset {
+            chapters: Arc::new(chapters),
+        })
+    }
+
+    /// The verse_count function returns how many verses the dataset holds.
+    pub fn verse_count(&self) -> usize {
+        self.chapters.values().map(Vec::len).sum()
+    }
+
+    /// The verses function returns every verse of the dataset, a chapter at
+    /// a time.
+    #[cfg_attr(not(feature = "tantivy"), allow(dead_code))]
+    pub fn verses(&self) -> impl Iterator<Item = &SearchResult> {
+        self.chapters.values().flatten()
+    }
+
+    /// The search function returns the verses of a search, the way
+    /// db::search would, including the superscription when asked for.
+    pub fn search(&self, bible_search: &BibleSearch, superscription: bool) -> Vec<SearchResult> {
+        bible_search
+            .chapters
+            .iter()
+            .flat_map(|chapter| {
+                let key = (bible_search.title.clone(), i32::from(chapter.chapter));
+
+                self.chapters
+                    .get(&key)
+                    .into_iter()
+                    .flatten()
+                    .filter(move |verse| {
+                        let wanted = u8::try_from(verse.verse)
+                            .is_ok_and(|number| chapter.verses.contains(&number));
+                        let is_superscription = verse.verse == i32::from(SUPERSCRIPTION_VERSE);
+
+                        wanted || (superscription && is_superscription)
+                    })
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::search;
+
+    const DATASET: &str = r#"
+{"title":"Psalms","chapter":3,"verse":1,"text":"LORD, how are they increased","paragraph_start":true}
+{"title":"Psalms","chapter":3,"verse":0,"text":"A Psalm of David","paragraph_start":false}
+{"title":"Psalms","chapter":3,"verse":2,"text":"Many there be","paragraph_start":false}
+"#;
+
+    #[test]
+    fn search_returns_the_verses_of_a_search_in_order() {
+        let dataset = OfflineDataset::parse(DATASET).unwrap();
+        let verses = |superscription| {
+            dataset
+                .search(&search("Psalms 3:1-2").unwrap(), superscription)
+                .into_iter()
+                .map(|verse| verse.verse)
+                .collect::<Vec<i32>>()
+        };
+
+        assert_eq!(verses(false), vec![1, 2]);
+        assert_eq!(verses(true), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn parse_reports_the_line_that_is_not_a_verse() {
+        let err = OfflineDataset::parse("{\"title\":\"Jude\"}").err().unwrap();
+
+        assert!(err.starts_with("line 1:"));
+    }
+}
@@ -0,0 +1,304 @@
+use std::path::Path;
+use tantivy::{
+    collector::{Count, FacetCollector, TopDocs},
+    query::{BooleanQuery, ConstScoreQuery, Occur, Query, QueryParser, TermQuery},
+    schema::{
+        Facet, FacetOptions, Field, IndexRecordOption, NumericOptions, Schema, TextFieldIndexing,
+        TextOptions, Value, STORED, STRING,
+    },
+    Index, IndexReader, TantivyDocument, Term,
+};
+
+use crate::{
+    chapter::{get_testament, Testament},
+    offline::OfflineDataset,
+    text_search::{get_facets, TextSearch, TextSearchHit, TextSearchResults},
+};
+
+/// The WRITER_MEMORY is the memory given to the index writer while the index
+/// is built.
+const WRITER_MEMORY: usize = 50_000_000;
+
+/// The OfflineIndex is a Tantivy index over the offline dataset, so text
+/// searches still work without Postgres or a search engine. It is built in
+/// memory at startup, or, when OFFLINE_INDEX_DIR is set, built into that
+/// directory once and opened from it after (so it can be shipped prebuilt).
+#[derive(Clone)]
+pub struct OfflineIndex {
+    index: Index,
+    reader: IndexReader,
+    fields: Fields,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    title: Field,
+    testament: Field,
+    // The title as a facet (ex: /John), so the matches can be counted by book
+    book: Field,
+    chapter: Field,
+    verse: Field,
+    text: Field,
+}
+
+impl OfflineIndex {
+    /// The from_env function indexes the offline dataset, in the directory
+    /// OFFLINE_INDEX_DIR names when it is set. An index that can not be built
+    /// is logged and left out, so the server still starts.
+    pub fn from_env(dataset: &OfflineDataset) -> Option<Self> {
+        let index = match std::env::var("OFFLINE_INDEX_DIR") {
+            Ok(dir) => OfflineIndex::open_or_build(dataset, Path::new(&dir)),
+            Err(_) => OfflineIndex::build(dataset),
+        };
+
+        match index {
+            Ok(index) => {
+                tracing::info!("indexed the offline dataset");
+                Some(index)
+            }
+            Err(err) => {
+                tracing::warn!("could not index the offline dataset: {}", err);
+                None
+            }
+        }
+    }
+
+    /// The build function indexes the offline dataset in memory.
+    pub fn build(dataset: &OfflineDataset) -> tantivy::Result<Self> {
+        let index = Index::create_in_ram(get_schema());
+        OfflineIndex::fill(&index, dataset)?;
+        OfflineIndex::open(index)
+    }
+
+    // A directory that already has an index is taken to be the prebuilt index
+    // of the dataset
+    fn open_or_build(dataset: &OfflineDataset, dir: &Path) -> tantivy::Result<Self> {
+        if let Ok(index) = Index::open_in_dir(dir) {
+            return OfflineIndex::open(index);
+        }
+
+        std::fs::create_dir_all(dir)?;
+        let index = Index::create_in_dir(dir, get_schema())?;
+        OfflineIndex::fill(&index, dataset)?;
+        OfflineIndex::open(index)
+    }
+
+    fn fill(index: &Index, dataset: &OfflineDataset) -> tantivy::Result<()> {
+        let fields = get_fields(&index.schema())?;
+        let mut writer = index.writer::<TantivyDocument>(WRITER_MEMORY)?;
+
+        for verse in dataset.verses() {
+            // A book outside the canon is indexed under other
+            let testament = get_testament(&verse.title).map_or("other", Testament::as_str);
+
+            let mut document = TantivyDocument::default();
+            document.add_text(fields.title, &verse.title);
+            document.add_text(fields.testament, testament);
+            document.add_facet(fields.book, Facet::from_path([&verse.title]));
+            document.add_u64(fields.chapter, u64::try_from(verse.chapter).unwrap_or(0));
+            document.add_u64(fields.verse, u64::try_from(verse.verse).unwrap_or(0));
+            document.add_text(fields.text, &verse.text);
+            writer.add_document(document)?;
+        }
+
+        writer.commit()?;
+        Ok(())
+    }
+
+    fn open(index: Index) -> tantivy::Result<Self> {
+        let fields = get_fields(&index.schema())?;
+        let reader = index.reader()?;
+
+        Ok(OfflineIndex {
+            index,
+            reader,
+            fields,
+        })
+    }
+
+    /// The search function runs a text search against the index, the way
+    /// the search engine would.
+    pub fn search(&self, text_search: &TextSearch) -> tantivy::Result<TextSearchResults> {
+        let fields = self.fields;
+        let searcher = self.reader.searcher();
+
+        // Every word has to match, as in the Postgres full text search. Words
+        // the query parser can not make sense of are searched as they are.
+        let mut parser = QueryParser::for_index(&self.index, vec![fields.text]);
+        parser.set_conjunction_by_default();
+        let (text_query, _) = parser.parse_query_lenient(&text_search.query);
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, text_query)];
+        // The earlier searches narrow the verses without changing their scores
+        for within in &text_search.within {
+            let (within_query, _) = parser.parse_query_lenient(within);
+            clauses.push((
+                Occur::Must,
+                Box::new(ConstScoreQuery::new(within_query, 0.0)),
+            ));
+        }
+        if let Some(book) = &text_search.book {
+            clauses.push((Occur::Must, term_query(fields.title, book)));
+        }
+        if let Some(testament) = text_search.testament {
+            clauses.push((
+                Occur::Must,
+                term_query(fields.testament, testament.as_str()),
+            ));
+        }
+        let query = BooleanQuery::new(clauses);
+
+        let mut facet_collector = FacetCollector::for_field("book");
+        facet_collector.add_facet(Facet::root());
+        let top_docs = TopDocs::with_limit(usize::from(text_search.limit))
+            .and_offset(usize::try_from(text_search.offset).unwrap_or(usize::MAX));
+
+        let (top_docs, total, facet_counts) =
+            searcher.search(&query, &(top_docs, Count, facet_collector))?;
+
+        let mut hits = vec![];
+        for (_, address) in top_docs {
+            let document: TantivyDocument = searcher.doc(address)?;
+            let text = |field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_owned()
+            };
+            let number = |field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_u64())
+                    .and_then(|number| i32::try_from(number).ok())
+                    .unwrap_or_default()
+            };
+
+            hits.push(TextSearchHit::new(
+                text(fields.title),
+                number(fields.chapter),
+                number(fields.verse),
+                text(fields.text),
+            ));
+        }
+
+        let book_counts = facet_counts
+            .get("/")
+            .filter_map(|(facet, count)| Some((facet.to_path().last()?.to_string(), count)));
+
+        Ok(TextSearchResults {
+            engine: "tantivy",
+            total: u64::try_from(total).unwrap_or(u64::MAX),
+            hits,
+            facets: get_facets(book_counts),
+        })
+    }
+}
+
+// The text is stemmed the way the Postgres full text search stems it. The
+// title and testament are only filtered on, so they are kept whole.
+fn get_schema() -> Schema {
+    let mut schema = Schema::builder();
+    let stemmed = TextFieldIndexing::default()
+        .set_tokenizer("en_stem")
+        .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+
+    schema.add_text_field("title", STRING | STORED);
+    schema.add_text_field("testament", STRING);
+    schema.add_facet_field("book", FacetOptions::default());
+    schema.add_u64_field("chapter", NumericOptions::default().set_stored());
+    schema.add_u64_field("verse", NumericOptions::default().set_stored());
+    schema.add_text_field(
+        "text",
+        TextOptions::default()
+            .set_indexing_options(stemmed)
+            .set_stored(),
+    );
+
+    schema.build()
+}
+
+fn get_fields(schema: &Schema) -> tantivy::Result<Fields> {
+    Ok(Fields {
+        title: schema.get_field("title")?,
+        testament: schema.get_field("testament")?,
+        book: schema.get_field("book")?,
+        chapter: schema.get_field("chapter")?,
+        verse: schema.get_field("verse")?,
+        text: schema.get_field("text")?,
+    })
+}
+
+fn term_query(field: Field, value: &str) -> Box<dyn Query> {
+    Box::new(TermQuery::new(
+        Term::from_field_text(field, value),
+        IndexRecordOption::Basic,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATASET: &str = r#"
+{"title":"Genesis","chapter":1,"verse":1,"text":"In the beginning God created the heaven and the earth.","paragraph_start":true}
+{"title":"John","chapter":1,"verse":1,"text":"In the beginning was the Word, and the Word was with God.","paragraph_start":true}
+{"title":"John","chapter":3,"verse":16,"text":"For God so loved the world","paragraph_start":true}
+{"title":"1 John","chapter":4,"verse":8,"text":"He that loveth not knoweth not God; for God is love.","paragraph_start":false}
+"#;
+
+    fn text_search(query: &str, book: Option<&str>, testament: Option<Testament>) -> TextSearch {
+        TextSearch {
+            query: query.to_owned(),
+            translation: String::from("kjv"),
+            book: book.map(String::from),
+            testament,
+            within: vec![],
+            limit: 10,
+            offset: 0,
+        }
+    }
+
+    fn index() -> OfflineIndex {
+        OfflineIndex::build(&OfflineDataset::parse(DATASET).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn search_matches_the_stems_of_the_words() {
+        let results = index().search(&text_search("loving", None, None)).unwrap();
+
+        assert_eq!(results.total, 2);
+        assert_eq!(results.facets.book.get("1 John"), Some(&1));
+        assert_eq!(results.facets.testament.get("new"), Some(&2));
+    }
+
+    #[test]
+    fn search_filters_by_book_and_testament() {
+        let index = index();
+        let in_john = index
+            .search(&text_search("beginning", Some("John"), None))
+            .unwrap();
+        let in_old = index
+            .search(&text_search("beginning", None, Some(Testament::Old)))
+            .unwrap();
+
+        assert_eq!(in_john.hits.len(), 1);
+        assert_eq!(in_john.hits[0].title, "John");
+        assert_eq!(in_old.hits.len(), 1);
+        assert_eq!(in_old.hits[0].title, "Genesis");
+        assert_eq!(in_old.hits[0].chapter, 1);
+    }
+
+    #[test]
+    fn search_keeps_to_the_verses_of_earlier_searches() {
+        let mut within_love = text_search("god", None, None);
+        within_love.within = vec![String::from("love")];
+
+        let results = index().search(&within_love).unwrap();
+
+        assert_eq!(results.total, 2);
+        assert!(results
+            .hits
+            .iter()
+            .all(|hit| hit.title != "John" || hit.verse == 16));
+    }
+}
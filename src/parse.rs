@@ -0,0 +1,578 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::{
+    book::{get_params, get_title_candidates},
+    chapter::get_chapter_count_by_book,
+    db::get_default_translation,
+    error::BibleApiError,
+    params::{
+        get_passages, get_reference_params, normalize_osis, normalize_osis_with, BookParams,
+        SearchType,
+    },
+    reference::{
+        normalize_whitespace, parse as parse_reference, parse_passages, split_book, PassageSpan,
+    },
+    search::{search_passages, BibleSearch},
+    spoken::normalize_spoken,
+    validation::{check_reference, check_text, MAX_TRANSLATION_LEN},
+    versification::{Versification, Versifications},
+    Params,
+};
+
+/// The ParsedReference is what the parser made of a query: the raw AST, the
+/// detected search type, the chapter and verses a search would fetch, and a
+/// human readable note for every correction made along the way. When the
+/// book is ambiguous the other readings are listed, best first. The chapter
+/// and verses are where the search starts, and chapters has every chapter
+/// it reaches into (ex: John 3 and 4 for John 3:16-4:2).
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ParsedReference {
+    pub query: String,
+    pub title: String,
+    pub search_type: SearchType,
+    pub passages: Vec<PassageSpan>,
+    pub chapter: u8,
+    pub verses: Vec<u8>,
+    pub chapters: Vec<ParsedChapter>,
+    pub normalizations: Vec<String>,
+    pub confidence: f32,
+    pub alternatives: Vec<Alternative>,
+}
+
+/// The ParsedChapter is a chapter a search would fetch, with its verses in
+/// order.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ParsedChapter {
+    pub chapter: u8,
+    pub verses: Vec<u8>,
+}
+
+/// The Alternative is another way the query could have been read, as a
+/// reference that can be searched for directly (ex: Jude 1:3 for Ju 3).
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Alternative {
+    pub reference: String,
+    pub title: String,
+    pub confidence: f32,
+}
+
+/// The ParseError is the body returned when a query can not be resolved. It
+/// still lists the readings that come closest, so clients can suggest them.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ParseError {
+    pub error: String,
+    pub alternatives: Vec<Alternative>,
+}
+
+/// The parse handler serves GET /parse?query=... and resolves the query
+/// exactly like /search does, against the versification of the translation
+/// and with strict=true refusing what would be fallen back from, without
+/// touching the database. Only the first passage of the query is described.
+pub async fn parse(
+    State(versifications): State<Versifications>,
+    Query(params): Query<Params>,
+) -> Result<Json<ParsedReference>, Response> {
+    let query = params.query.ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ParseError {
+                error: "missing query parameter".to_string(),
+                alternatives: Vec::new(),
+            }),
+        )
+            .into_response()
+    })?;
+    check_reference("query", &query).map_err(IntoResponse::into_response)?;
+
+    let translation = params
+        .translation
+        .map(|translation| translation.trim().to_lowercase())
+        .unwrap_or_else(|| get_default_translation().to_owned());
+    check_text("translation", &translation, MAX_TRANSLATION_LEN)
+        .map_err(IntoResponse::into_response)?;
+
+    let versification = versifications.get(&translation);
+    resolve(&query, &versification, params.strict.unwrap_or(false))
+        .map(Json)
+        .map_err(|err| unresolved(&query, err).into_response())
+}
+
+/// The unresolved function builds the response for a query that could not
+/// be resolved, with the status code of its error. A query whose book could
+/// not be found also lists the closest alternative readings. Queries that
+/// could not be read are counted by kind in parse_failures_total.
+pub fn unresolved(query: &str, error: BibleApiError) -> (StatusCode, Json<ParseError>) {
+    if !matches!(error, BibleApiError::Db(_)) {
+        metrics::increment_counter!("parse_failures_total", "kind" => error.kind());
+    }
+
+    let alternatives = match error {
+        BibleApiError::UnknownBook(_) => get_alternatives(query),
+        _ => Vec::new(),
+    };
+
+    (
+        error.status_code(),
+        Json(ParseError {
+            error: error.to_string(),
+            alternatives,
+        }),
+    )
+}
+
+/// The resolve function runs the query through the parser and the search
+/// resolution of a translation's versification, strictly or not, then
+/// explains how the result differs from what was asked for.
+pub fn resolve(
+    query: &str,
+    versification: &Versification,
+    strict: bool,
+) -> Result<ParsedReference, BibleApiError> {
+    let bible_search = search_passages(query, versification, strict)?.remove(0);
+
+    // The first passage of the query is what the search type is detected
+    // from
+    let whitespace_normalized = normalize_whitespace(query);
+    let normalized = normalize_spoken(&normalize_osis_with(&whitespace_normalized, versification)?);
+    let head = get_passages(&normalized)
+        .first()
+        .copied()
+        .unwrap_or_default();
+    let reference = parse_reference(head)?;
+    let mut passage_params = get_reference_params(&reference);
+    let book_params = passage_params.remove(0);
+
+    let mut normalizations = Vec::new();
+
+    if normalized != whitespace_normalized {
+        normalizations.push(format!(
+            "\"{}\" was read as \"{}\"",
+            whitespace_normalized, normalized
+        ));
+    }
+
+    let (book_text, _) = split_book(head);
+    if book_text.trim() != bible_search.title {
+        normalizations.push(format!(
+            "\"{}\" was read as {}",
+            book_text.trim(),
+            bible_search.title
+        ));
+    }
+
+    normalizations.extend(get_search_normalizations(&book_params, &bible_search));
+
+    let chapters = bible_search
+        .chapters
+        .iter()
+        .map(|chapter| {
+            let mut verses: Vec<u8> = chapter.verses.iter().copied().collect();
+            verses.sort_unstable();
+
+            ParsedChapter {
+                chapter: chapter.chapter,
+                verses,
+            }
+        })
+        .collect::<Vec<ParsedChapter>>();
+
+    // The passages after the first only add the verses the chapters have
+    normalizations.extend(
+        passage_params
+            .iter()
+            .filter_map(|params| get_sub_passage_normalization(params, &chapters)),
+    );
+
+    // Split the chosen reading from the other ways the book could be read
+    let (chosen, alternatives): (Vec<Alternative>, Vec<Alternative>) = get_alternatives(query)
+        .into_iter()
+        .partition(|alternative| alternative.title == bible_search.title);
+    let confidence = chosen.first().map_or(1.0, |chosen| chosen.confidence);
+
+    Ok(ParsedReference {
+        query: query.to_owned(),
+        title: bible_search.title,
+        search_type: book_params.search_type,
+        passages: reference.passages,
+        chapter: chapters.first().map_or(0, |chapter| chapter.chapter),
+        verses: chapters
+            .first()
+            .map(|chapter| chapter.verses.clone())
+            .unwrap_or_default(),
+        chapters,
+        normalizations,
+        confidence,
+        alternatives,
+    })
+}
+
+/// The get_alternatives function lists every book the query could refer to as
+/// a searchable reference, with confidences that add up to 1.
+pub fn get_alternatives(query: &str) -> Vec<Alternative> {
+    let whitespace_normalized = normalize_whitespace(query);
+    let normalized =
+        normalize_spoken(&normalize_osis(&whitespace_normalized).unwrap_or(whitespace_normalized));
+    let head = get_passages(&normalized)
+        .first()
+        .copied()
+        .unwrap_or_default();
+    let params = get_params(head);
+    let candidates = get_title_candidates(head);
+    let total: f32 = candidates.iter().map(|candidate| candidate.score).sum();
+
+    candidates
+        .into_iter()
+        .map(|candidate| Alternative {
+            reference: get_alternative_reference(&candidate.title, params.as_deref()),
+            confidence: round_confidence(candidate.score / total),
+            title: candidate.title,
+        })
+        .collect()
+}
+
+// Two decimal places is plenty for ranking and keeps the JSON readable
+fn round_confidence(confidence: f32) -> f32 {
+    (confidence * 100.0).round() / 100.0
+}
+
+// A book with a single chapter has no chapter to choose, so a bare number
+// after it is read as a verse (ex: Jude 3 -> Jude 1:3).
+fn get_alternative_reference(title: &str, params: Option<&str>) -> String {
+    let params = params.unwrap_or_default().trim();
+    let single_chapter = get_chapter_count_by_book(title) == Some(1);
+
+    match parse_passages(params).unwrap_or_default().first() {
+        Some(passage) if single_chapter && passage.start.verse.is_none() => {
+            format!("{} 1:{}", title, params)
+        }
+        _ if params.is_empty() => title.to_owned(),
+        _ => format!("{} {}", title, params),
+    }
+}
+
+// A passage after the first is left out where the chapters do not have its
+// start, and stops early where they do not have its end
+fn get_sub_passage_normalization(
+    params: &BookParams,
+    chapters: &[ParsedChapter],
+) -> Option<String> {
+    let title = &params.title;
+    let chapter = params.chapter?;
+    let has = |chapter: u8, verse: Option<u8>| {
+        chapters.iter().any(|parsed| {
+            parsed.chapter == chapter
+                && verse
+                    .into_iter()
+                    .all(|verse| parsed.verses.contains(&verse))
+        })
+    };
+
+    if !has(chapter, params.verse_start) {
+        return Some(format!(
+            "{} does not exist, so it is left out",
+            get_point(title, chapter, params.verse_start)
+        ));
+    }
+
+    let chapter_end = params.chapter_end.unwrap_or(chapter);
+    match has(chapter_end, params.verse_end) {
+        true => None,
+        false => Some(format!(
+            "{} does not exist, so the range stops before it",
+            get_point(title, chapter_end, params.verse_end)
+        )),
+    }
+}
+
+// A chapter (ex: John 3) or a verse of it (ex: John 3:16)
+fn get_point(title: &str, chapter: u8, verse: Option<u8>) -> String {
+    match verse {
+        Some(verse) => format!("{} {}:{}", title, chapter, verse),
+        None => format!("{} {}", title, chapter),
+    }
+}
+
+// Compare what the params asked for with what the search resolved to
+fn get_search_normalizations(params: &BookParams, bible_search: &BibleSearch) -> Vec<String> {
+    let title = &bible_search.title;
+
+    // The passages after the first can add chapters before the one it asked
+    // for (ex: John 3 for John 4:1, 3:16)
+    let first = params
+        .chapter
+        .and_then(|requested| {
+            bible_search
+                .chapters
+                .iter()
+                .find(|chapter| chapter.chapter == requested)
+        })
+        .or(bible_search.chapters.first());
+    let (Some(first), Some(last)) = (first, bible_search.chapters.last()) else {
+        return vec![];
+    };
+    let chapter = first.chapter;
+    let verses = &first.verses;
+
+    if params.search_type == SearchType::Book {
+        return vec![format!(
+            "no chapter was given, so {} {} is used",
+            title, chapter
+        )];
+    }
+
+    if let Some(requested) = params.chapter {
+        if requested != chapter {
+            return vec![format!(
+                "{} has no chapter {}, so {} {} is used",
+                title, requested, title, chapter
+            )];
+        }
+    }
+
+    // A range that runs into a later chapter stops at the end of the book
+    if let Some(chapter_end) = params.chapter_end {
+        if last.chapter < chapter_end {
+            return vec![format!(
+                "{} has no chapter {}, so the range stops at {} {}",
+                title, chapter_end, title, last.chapter
+            )];
+        }
+    }
+
+    let verse_start = match params.verse_start {
+        Some(verse_start) => verse_start,
+        None => return vec![],
+    };
+
+    if params.search_type == SearchType::Verse && verses.contains(&verse_start) {
+        return vec![];
+    }
+
+    if !verses.contains(&verse_start) && verses.len() > 1 {
+        return vec![format!(
+            "{} {} has no verse {}, so the whole chapter is used",
+            title, chapter, verse_start
+        )];
+    }
+
+    // A range that runs into a later chapter ends in the last chapter
+    let (chapter, verses) = match params.chapter_end {
+        Some(_) => (last.chapter, &last.verses),
+        None => (chapter, verses),
+    };
+
+    let verse_end = params.verse_end.unwrap_or(verse_start);
+    let last = verses.iter().max().copied().unwrap_or(verse_end);
+    if last < verse_end {
+        return vec![format!(
+            "{} {} ends at verse {}, so the range stops there",
+            title, chapter, last
+        )];
+    }
+
+    vec![]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_reports_nothing_for_an_exact_verse() {
+        let parsed = resolve("John 3:16", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.search_type, SearchType::Verse);
+        assert_eq!(parsed.chapter, 3);
+        assert_eq!(parsed.verses, vec![16]);
+        assert!(parsed.normalizations.is_empty());
+    }
+
+    #[test]
+    fn resolve_reports_the_book_title_that_was_read() {
+        let parsed = resolve("joh 3:16", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.title, "John");
+        assert_eq!(parsed.normalizations, vec!["\"joh\" was read as John"]);
+    }
+
+    #[test]
+    fn resolve_reports_a_missing_chapter() {
+        let parsed = resolve("1 John 223:3", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.chapter, 1);
+        assert_eq!(
+            parsed.normalizations,
+            vec!["1 John has no chapter 223, so 1 John 1 is used"]
+        );
+    }
+
+    #[test]
+    fn resolve_reports_a_clamped_range() {
+        let parsed = resolve("1 John 1:8-20", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.verses, vec![8, 9, 10]);
+        assert_eq!(
+            parsed.normalizations,
+            vec!["1 John 1 ends at verse 10, so the range stops there"]
+        );
+    }
+
+    #[test]
+    fn resolve_reports_ignored_sub_queries() {
+        let parsed = resolve("1 John 1:2, 3, 15", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.verses, vec![2, 3]);
+        assert_eq!(
+            parsed.normalizations,
+            vec!["1 John 1:15 does not exist, so it is left out"]
+        );
+
+        let parsed = resolve("1 John 1:2, 100, 11", &Versification::default(), false).unwrap();
+        assert_eq!(
+            parsed.normalizations,
+            vec![
+                "1 John 1:100 does not exist, so it is left out",
+                "1 John 1:11 does not exist, so it is left out",
+            ]
+        );
+
+        let parsed = resolve("1 John 1:2, 9-12, 2:1", &Versification::default(), false).unwrap();
+        assert_eq!(parsed.chapters.len(), 2);
+        assert_eq!(
+            parsed.normalizations,
+            vec!["1 John 1:12 does not exist, so the range stops before it"]
+        );
+    }
+
+    #[test]
+    fn resolve_reads_every_passage_of_the_list() {
+        let parsed = resolve("John 4:1, 3:16", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.passages.len(), 2);
+        assert_eq!(
+            parsed.chapters,
+            vec![
+                ParsedChapter {
+                    chapter: 3,
+                    verses: vec![16],
+                },
+                ParsedChapter {
+                    chapter: 4,
+                    verses: vec![1],
+                },
+            ]
+        );
+        assert!(parsed.normalizations.is_empty());
+    }
+
+    #[test]
+    fn resolve_lists_every_chapter_of_a_range_across_chapters() {
+        let parsed = resolve("Jude 1:24-2:1", &Versification::default(), false).unwrap();
+        assert_eq!(parsed.chapters.len(), 1);
+        assert_eq!(
+            parsed.normalizations,
+            vec!["Jude has no chapter 2, so the range stops at Jude 1"]
+        );
+
+        let parsed = resolve("John 3:35-4:2", &Versification::default(), false).unwrap();
+        assert_eq!(
+            parsed.chapters,
+            vec![
+                ParsedChapter {
+                    chapter: 3,
+                    verses: vec![35, 36],
+                },
+                ParsedChapter {
+                    chapter: 4,
+                    verses: vec![1, 2],
+                },
+            ]
+        );
+        assert!(parsed.normalizations.is_empty());
+    }
+
+    #[test]
+    fn resolve_reports_a_chapter_range_past_the_end_of_the_book() {
+        let parsed = resolve("1 John 4-7", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.search_type, SearchType::ChapterRange);
+        assert_eq!(parsed.chapters.len(), 2);
+        assert_eq!(
+            parsed.normalizations,
+            vec!["1 John has no chapter 7, so the range stops at 1 John 5"]
+        );
+    }
+
+    #[test]
+    fn resolve_is_fully_confident_when_only_one_book_matches() {
+        let parsed = resolve("John 3:16", &Versification::default(), false).unwrap();
+
+        assert_eq!(parsed.confidence, 1.0);
+        assert!(parsed.alternatives.is_empty());
+    }
+
+    #[test]
+    fn get_alternatives_ranks_every_reading_of_an_ambiguous_book() {
+        assert_eq!(
+            get_alternatives("Ju 3"),
+            vec![
+                Alternative {
+                    reference: String::from("Jude 1:3"),
+                    title: String::from("Jude"),
+                    confidence: 0.6,
+                },
+                Alternative {
+                    reference: String::from("Judges 3"),
+                    title: String::from("Judges"),
+                    confidence: 0.4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_reports_a_spoken_reference() {
+        let parsed = resolve(
+            "John chapter three verse sixteen",
+            &Versification::default(),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.chapter, 3);
+        assert_eq!(parsed.verses, vec![16]);
+        assert_eq!(
+            parsed.normalizations,
+            vec!["\"John chapter three verse sixteen\" was read as \"John 3:16\""]
+        );
+    }
+
+    #[test]
+    fn resolve_returns_an_error_for_an_unknown_book() {
+        assert!(matches!(
+            resolve("Book of Robert 1", &Versification::default(), false),
+            Err(BibleApiError::UnknownBook(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_reads_the_query_like_search_does() {
+        let kjv = Versification::from_counts([(String::from("Leviticus"), 6, 30)]);
+
+        let parsed = resolve("Leviticus 6:30", &kjv, false).unwrap();
+        assert_eq!(parsed.verses, vec![30]);
+        assert!(parsed.normalizations.is_empty());
+
+        assert!(resolve("1 John 4:99", &Versification::default(), false).is_ok());
+        assert!(matches!(
+            resolve("1 John 4:99", &Versification::default(), true),
+            Err(BibleApiError::VerseOutOfRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,446 @@
+use axum::async_trait;
+use futures::future::join_all;
+use lru::LruCache;
+use std::{
+    fmt,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    db::{SearchOptions, SearchResult, TextFormat},
+    search::BibleSearch,
+};
+
+/// The CacheBackend is where cached values are kept, each under a key (ex:
+/// passage:kjv:John:3=16:plain) until its TTL runs out. Keys are namespaced
+/// by what they hold, so one backend can be shared by every cache of the
+/// service. An error from the backend is never a failed request, only a
+/// miss.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// The name function returns the name of the backend (ex: redis).
+    fn name(&self) -> &'static str;
+
+    /// The get function returns the value under the key, if it has one.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// The set function keeps the value under the key for the TTL.
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), String>;
+
+    /// The remove_matching function drops every key the pattern matches,
+    /// where * stands for any run of characters, and returns how many were
+    /// dropped.
+    async fn remove_matching(&self, pattern: &str) -> Result<usize, String>;
+}
+
+type Entries = LruCache<String, (Instant, Vec<u8>)>;
+
+/// The MemoryCache keeps values in the memory of this replica. The least
+/// recently used value is dropped to make room for a new one.
+pub struct MemoryCache {
+    entries: Mutex<Entries>,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        MemoryCache {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(lookup(
+            &mut self.entries.lock().unwrap(),
+            key,
+            Instant::now(),
+        ))
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), String> {
+        let expires = Instant::now() + ttl;
+        self.entries
+            .lock()
+            .unwrap()
+            .put(key.to_owned(), (expires, value));
+
+        Ok(())
+    }
+
+    async fn remove_matching(&self, pattern: &str) -> Result<usize, String> {
+        let mut entries = self.entries.lock().unwrap();
+
+        let dropped = entries
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| is_match(pattern, key))
+            .cloned()
+            .collect::<Vec<String>>();
+        for key in &dropped {
+            entries.pop(key);
+        }
+
+        Ok(dropped.len())
+    }
+}
+
+// The value under the key, unless it has none or it has expired, in which
+// case it is dropped
+fn lookup(entries: &mut Entries, key: &str, now: Instant) -> Option<Vec<u8>> {
+    match entries.get(key) {
+        Some((expires, value)) if now < *expires => Some(value.clone()),
+        Some(_) => {
+            entries.pop(key);
+            None
+        }
+        None => None,
+    }
+}
+
+// Whether the key matches the pattern, where * stands for any run of
+// characters, the same as the patterns Redis matches keys with
+fn is_match(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = key.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<&str>>();
+
+    match parts.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(start) => rest = &rest[start + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+    }
+}
+
+/// The PassageKey is a search of a translation, fetched with some options,
+/// in a form that is the same however the reference was written (ex:
+/// John 3:16-17 and jn 3.17, 16). It is kept under passage:, then the
+/// translation, the book, the verses of each chapter and the options (ex:
+/// passage:kjv:John:3=16,17:plain).
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct PassageKey {
+    translation: String,
+    title: String,
+    chapters: Vec<(u8, Vec<u8>)>,
+    options: SearchOptions,
+}
+
+impl PassageKey {
+    pub fn new(translation: &str, bible_search: &BibleSearch, options: SearchOptions) -> Self {
+        let chapters = bible_search
+            .chapters
+            .iter()
+            .map(|chapter| {
+                let mut verses = chapter.verses.iter().copied().collect::<Vec<u8>>();
+                verses.sort_unstable();
+                (chapter.chapter, verses)
+            })
+            .collect();
+
+        PassageKey {
+            translation: translation.to_owned(),
+            title: bible_search.title.clone(),
+            chapters,
+            options,
+        }
+    }
+
+    /// The get_pattern function returns the pattern matching the keys of a
+    /// translation, of a book, or of both, and every key when neither is
+    /// given.
+    pub fn get_pattern(translation: Option<&str>, title: Option<&str>) -> String {
+        format!(
+            "passage:{}:{}:*",
+            translation.unwrap_or("*"),
+            title.unwrap_or("*")
+        )
+    }
+}
+
+impl fmt::Display for PassageKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chapters = self
+            .chapters
+            .iter()
+            .map(|(chapter, verses)| {
+                let verses = verses
+                    .iter()
+                    .map(u8::to_string)
+                    .collect::<Vec<String>>()
+                    .join(",");
+                format!("{}={}", chapter, verses)
+            })
+            .collect::<Vec<String>>()
+            .join(";");
+        let format = match self.options.format {
+            TextFormat::Plain => "plain",
+            TextFormat::Html => "html",
+        };
+
+        write!(
+            f,
+            "passage:{}:{}:{}:{}",
+            self.translation, self.title, chapters, format
+        )?;
+        if self.options.superscription {
+            write!(f, ":superscription")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The PassageCache keeps the verses of the passages fetched recently, so
+/// the popular ones are answered without going to the database. A passage
+/// is fetched again once it is older than the TTL, so a correction shows up
+/// within it even when the cache is not cleared. The verses are kept in
+/// memory, or in a backend shared by every replica (ex: Redis). Its hits,
+/// misses and the errors of its backend are counted in
+/// passage_cache_hits_total, passage_cache_misses_total and
+/// passage_cache_errors_total. A cache without a backend keeps nothing.
+#[derive(Clone, Default)]
+pub struct PassageCache {
+    backend: Option<Arc<dyn CacheBackend>>,
+    ttl: Duration,
+}
+
+impl PassageCache {
+    pub fn new(backend: Arc<dyn CacheBackend>, ttl: Duration) -> Self {
+        PassageCache {
+            backend: (!ttl.is_zero()).then_some(backend),
+            ttl,
+        }
+    }
+
+    /// The in_memory function returns a cache of as many passages as the
+    /// capacity, kept in memory. A cache with no room keeps nothing.
+    pub fn in_memory(capacity: usize, ttl: Duration) -> Self {
+        match NonZeroUsize::new(capacity) {
+            Some(capacity) => PassageCache::new(Arc::new(MemoryCache::new(capacity)), ttl),
+            None => PassageCache::default(),
+        }
+    }
+
+    /// The backend_name function returns the name of the backend the
+    /// passages are kept in, if they are kept.
+    pub fn backend_name(&self) -> Option<&'static str> {
+        self.backend.as_ref().map(|backend| backend.name())
+    }
+
+    /// The get function returns the verses of the passage, if they were
+    /// fetched within the TTL.
+    pub async fn get(&self, key: &PassageKey) -> Option<Vec<SearchResult>> {
+        let backend = self.backend.as_ref()?;
+
+        let verses = match backend.get(&key.to_string()).await {
+            Ok(value) => value.and_then(|value| serde_json::from_slice(&value).ok()),
+            Err(err) => {
+                record_error(backend.as_ref(), &err);
+                None
+            }
+        };
+        match verses {
+            Some(_) => metrics::increment_counter!("passage_cache_hits_total"),
+            None => metrics::increment_counter!("passage_cache_misses_total"),
+        }
+
+        verses
+    }
+
+    /// The get_many function returns the verses of each passage that is
+    /// cached, in the order the keys were given.
+    pub async fn get_many(&self, keys: &[PassageKey]) -> Vec<Option<Vec<SearchResult>>> {
+        join_all(keys.iter().map(|key| self.get(key))).await
+    }
+
+    /// The insert function keeps the verses fetched for the passage.
+    pub async fn insert(&self, key: &PassageKey, verses: &[SearchResult]) {
+        let Some(backend) = self.backend.as_ref() else {
+            return;
+        };
+        let Ok(value) = serde_json::to_vec(verses) else {
+            return;
+        };
+
+        if let Err(err) = backend.set(&key.to_string(), value, self.ttl).await {
+            record_error(backend.as_ref(), &err);
+        }
+    }
+
+    /// The invalidate function drops the passages of a translation, of a
+    /// book (ex: John), or of both, and every passage when neither is given.
+    /// It returns how many were dropped.
+    pub async fn invalidate(
+        &self,
+        translation: Option<&str>,
+        title: Option<&str>,
+    ) -> Result<usize, String> {
+        match self.backend.as_ref() {
+            Some(backend) => {
+                let pattern = PassageKey::get_pattern(translation, title);
+                backend.remove_matching(&pattern).await
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+fn record_error(backend: &dyn CacheBackend, err: &str) {
+    metrics::increment_counter!("passage_cache_errors_total");
+    tracing::debug!("the {} passage cache failed: {}", backend.name(), err);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::search;
+
+    const OPTIONS: SearchOptions = SearchOptions {
+        superscription: false,
+        format: TextFormat::Plain,
+    };
+
+    fn get_key(translation: &str, query: &str) -> PassageKey {
+        PassageKey::new(translation, &search(query).unwrap(), OPTIONS)
+    }
+
+    fn get_verses(title: &str) -> Vec<SearchResult> {
+        vec![SearchResult::new(
+            "kjv",
+            title.to_owned(),
+            1,
+            1,
+            String::from("In the beginning"),
+            true,
+        )]
+    }
+
+    #[test]
+    fn passage_key_is_the_same_however_the_reference_is_written() {
+        assert_eq!(
+            get_key("kjv", "John 3:16-17"),
+            get_key("kjv", "jn 3:17, 16")
+        );
+        assert_eq!(
+            get_key("kjv", "John 3:16-17").to_string(),
+            "passage:kjv:John:3=16,17:plain"
+        );
+        assert_ne!(get_key("kjv", "John 3:16"), get_key("web", "John 3:16"));
+        assert_ne!(
+            get_key("kjv", "John 3:16").to_string(),
+            PassageKey::new(
+                "kjv",
+                &search("John 3:16").unwrap(),
+                SearchOptions {
+                    format: TextFormat::Html,
+                    ..OPTIONS
+                }
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn is_match_follows_the_stars_of_the_pattern() {
+        let key = "passage:kjv:John:3=16:plain";
+
+        assert!(is_match("passage:*:*:*", key));
+        assert!(is_match("passage:kjv:John:*", key));
+        assert!(is_match("passage:*:John:*", key));
+        assert!(is_match(key, key));
+        assert!(!is_match("passage:*:1 John:*", key));
+        assert!(!is_match("passage:web:*:*", key));
+        assert!(!is_match("passage:kjv", key));
+    }
+
+    #[tokio::test]
+    async fn cache_drops_the_least_recently_used_passage_when_full() {
+        let cache = PassageCache::in_memory(2, Duration::from_secs(60));
+        cache
+            .insert(&get_key("kjv", "Genesis 1:1"), &get_verses("Genesis"))
+            .await;
+        cache
+            .insert(&get_key("kjv", "John 1:1"), &get_verses("John"))
+            .await;
+
+        assert!(cache.get(&get_key("kjv", "Genesis 1:1")).await.is_some());
+        cache
+            .insert(&get_key("kjv", "Mark 1:1"), &get_verses("Mark"))
+            .await;
+
+        let cached = cache
+            .get_many(&[
+                get_key("kjv", "Genesis 1:1"),
+                get_key("kjv", "John 1:1"),
+                get_key("kjv", "Mark 1:1"),
+            ])
+            .await;
+        assert_eq!(
+            cached.iter().map(Option::is_some).collect::<Vec<bool>>(),
+            vec![true, false, true]
+        );
+        assert_eq!(cached[2].as_ref().unwrap()[0].title, "Mark");
+    }
+
+    #[tokio::test]
+    async fn cache_fetches_a_passage_again_once_it_is_older_than_the_ttl() {
+        let ttl = Duration::from_secs(60);
+        let memory = MemoryCache::new(NonZeroUsize::new(2).unwrap());
+        memory
+            .set("passage:kjv:Genesis:1=1:plain", vec![], ttl)
+            .await
+            .unwrap();
+
+        let mut entries = memory.entries.lock().unwrap();
+        let now = Instant::now();
+        assert!(lookup(&mut entries, "passage:kjv:Genesis:1=1:plain", now).is_some());
+        assert!(lookup(&mut entries, "passage:kjv:Genesis:1=1:plain", now + ttl).is_none());
+        assert!(entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cache_invalidates_a_translation_or_a_book() {
+        let cache = PassageCache::in_memory(4, Duration::from_secs(60));
+        cache
+            .insert(&get_key("kjv", "Genesis 1:1"), &get_verses("Genesis"))
+            .await;
+        cache
+            .insert(&get_key("kjv", "John 1:1"), &get_verses("John"))
+            .await;
+        cache
+            .insert(&get_key("web", "John 1:1"), &get_verses("John"))
+            .await;
+
+        assert_eq!(cache.invalidate(Some("kjv"), Some("John")).await, Ok(1));
+        assert_eq!(cache.invalidate(Some("web"), None).await, Ok(1));
+        assert!(cache.get(&get_key("kjv", "Genesis 1:1")).await.is_some());
+        assert_eq!(cache.invalidate(None, None).await, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn cache_with_no_room_keeps_nothing() {
+        let cache = PassageCache::in_memory(0, Duration::from_secs(60));
+        cache
+            .insert(&get_key("kjv", "Genesis 1:1"), &get_verses("Genesis"))
+            .await;
+
+        assert_eq!(cache.backend_name(), None);
+        assert!(cache.get(&get_key("kjv", "Genesis 1:1")).await.is_none());
+    }
+}
@@ -0,0 +1,170 @@
+use axum::{
+    http::{HeaderName, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Serialize;
+
+use crate::{
+    breaker::{get_degraded_headers, CircuitBreaker},
+    cdn::{get_book_key, get_translation_key, SURROGATE_KEY_HEADER},
+    continuation::get_max_verses,
+    db::{SearchOptions, SearchResult, TextFormat},
+    error::BibleApiError,
+    rate_limit::VerseCount,
+    render,
+    search::{get_reference_with, is_whole_chapter_with, BibleSearch},
+    versification::Versification,
+};
+
+/// The Passage is one of the passages of a query that asks for several (ex:
+/// John 3:16; Romans 8:28), with its verses.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct Passage {
+    pub reference: String,
+    pub verses: Vec<SearchResult>,
+}
+
+/// The passages_response function fetches the verses of several passages of
+/// a translation in one statement. As JSON they are sent as a list of the
+/// passages, each with its verses; every other format writes the verses of
+/// all of them in order, under the references joined with semicolons. The
+/// passages are not sent in chunks, so together they can have no more
+/// verses than the verse cap.
+pub async fn passages_response(
+    breaker: CircuitBreaker,
+    translation: &str,
+    searches: Vec<BibleSearch>,
+    versification: &Versification,
+    format: render::Format,
+    render_options: render::RenderOptions,
+    superscriptions: Option<bool>,
+) -> Result<Response, Response> {
+    let surrogate_keys = [(
+        HeaderName::from_static(SURROGATE_KEY_HEADER),
+        get_surrogate_keys(translation, &searches),
+    )];
+
+    let (passages, degraded) = fetch_passages(
+        breaker,
+        translation,
+        searches,
+        versification,
+        format.text_format(),
+        superscriptions,
+    )
+    .await
+    .map_err(IntoResponse::into_response)?;
+    let verse_count = Extension(VerseCount(
+        passages.iter().map(|passage| passage.verses.len()).sum(),
+    ));
+    let degraded = get_degraded_headers(degraded);
+
+    let body = match format {
+        render::Format::Json => Json(passages).into_response(),
+        _ => {
+            let references = passages
+                .iter()
+                .map(|passage| passage.reference.as_str())
+                .collect::<Vec<&str>>()
+                .join("; ");
+            render::render(
+                format,
+                passages
+                    .into_iter()
+                    .flat_map(|passage| passage.verses)
+                    .collect(),
+                &references,
+                &render_options,
+            )
+            .map_err(IntoResponse::into_response)?
+        }
+    };
+
+    Ok((surrogate_keys, verse_count, degraded, body).into_response())
+}
+
+/// The fetch_passages function fetches the verses of several passages of a
+/// translation in one statement, each under its reference. Together they can
+/// have no more verses than the verse cap. Superscriptions come with whole
+/// chapters of the versification unless turned off. The flag returned is
+/// true when the offline copy answered.
+pub async fn fetch_passages(
+    breaker: CircuitBreaker,
+    translation: &str,
+    searches: Vec<BibleSearch>,
+    versification: &Versification,
+    format: TextFormat,
+    superscriptions: Option<bool>,
+) -> Result<(Vec<Passage>, bool), BibleApiError> {
+    let verse_count = searches.iter().map(BibleSearch::verse_count).sum::<usize>();
+    let max_verses = get_max_verses();
+    if verse_count > max_verses {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "the passages have {} verses, more than {}, so ask for them one at a time",
+                verse_count, max_verses
+            ),
+        )
+            .into());
+    }
+
+    let references = searches
+        .iter()
+        .map(|bible_search| get_reference_with(bible_search, versification))
+        .collect::<Vec<String>>();
+    let searches = searches
+        .into_iter()
+        .map(|bible_search| {
+            let options = SearchOptions {
+                superscription: superscriptions.unwrap_or(true)
+                    && is_whole_chapter_with(&bible_search, versification),
+                format,
+            };
+            (bible_search, options)
+        })
+        .collect();
+
+    let (verses, degraded) = breaker.search_many(translation, searches).await?;
+    let passages = references
+        .into_iter()
+        .zip(verses)
+        .map(|(reference, verses)| Passage { reference, verses })
+        .collect();
+
+    Ok((passages, degraded))
+}
+
+// The translation's key and the key of each book the passages are in
+fn get_surrogate_keys(translation: &str, searches: &[BibleSearch]) -> String {
+    let mut keys = vec![get_translation_key(translation)];
+    for bible_search in searches {
+        let key = get_book_key(translation, &bible_search.title);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    keys.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::search;
+
+    #[test]
+    fn get_surrogate_keys_lists_each_book_once() {
+        let searches = ["John 3:16", "Romans 8:28", "John 4:2"]
+            .into_iter()
+            .map(|query| search(query).unwrap())
+            .collect::<Vec<BibleSearch>>();
+
+        assert_eq!(
+            get_surrogate_keys("kjv", &searches),
+            "translation-kjv kjv-book-john kjv-book-romans"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+};
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+
+use crate::{
+    annotate::group_by_verse,
+    db::SearchResult,
+    error::BibleApiError,
+    internal_error,
+    readings::{Reading, Readings},
+    state::AppState,
+};
+
+/// The Relationship is what a relative is to a person.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Relationship {
+    Father,
+    Mother,
+    Spouse,
+    Sibling,
+    Child,
+}
+
+/// The get_relationship function reads a relationship as it is stored. Only
+/// parents are stored, so when the person is the relative of the stored row
+/// a father or mother is read as a child.
+pub fn get_relationship(relationship: &str, is_relative: bool) -> Option<Relationship> {
+    match (relationship, is_relative) {
+        ("father" | "mother", true) => Some(Relationship::Child),
+        ("father", false) => Some(Relationship::Father),
+        ("mother", false) => Some(Relationship::Mother),
+        ("spouse", _) => Some(Relationship::Spouse),
+        ("sibling", _) => Some(Relationship::Sibling),
+        _ => None,
+    }
+}
+
+/// The Relative is someone in the family of a person.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Relative {
+    pub relationship: Relationship,
+    pub name: String,
+}
+
+/// The Person is someone named in the text, with their family and the
+/// passages they appear in, written out in full.
+#[derive(Debug, Serialize)]
+pub struct Person {
+    pub name: String,
+    pub description: String,
+    pub relatives: Vec<Relative>,
+    pub appearances: Vec<Reading>,
+}
+
+/// The PersonLink is a person named in a verse, with where to find them.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct PersonLink {
+    pub name: String,
+    pub href: String,
+}
+
+impl PersonLink {
+    /// The new function links to the person of the name given.
+    pub fn new(name: String) -> Self {
+        let href = format!("/people/{}", name.replace(' ', "%20"));
+        PersonLink { name, href }
+    }
+}
+
+/// The person handler serves /people/:name (ex: /people/ruth) with the person
+/// of the name, in any case, their family and their appearances.
+pub async fn person(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Response, BibleApiError> {
+    let person = sqlx::query!(
+        "SELECT name, description FROM people WHERE lower(name) = lower($1)",
+        name.trim(),
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or(BibleApiError::from((
+        StatusCode::NOT_FOUND,
+        format!("No person named {}", name),
+    )))?;
+
+    let relatives = sqlx::query!(
+        r#"
+            SELECT relationship as "relationship!", relative as "name!", false as "is_relative!"
+            FROM person_relationships
+            WHERE name = $1
+            UNION ALL
+            SELECT relationship, name, true
+            FROM person_relationships
+            WHERE relative = $1
+          ORDER BY 2
+        "#,
+        person.name,
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal_error)?
+    .into_iter()
+    .filter_map(|row| {
+        Some(Relative {
+            relationship: get_relationship(&row.relationship, row.is_relative)?,
+            name: row.name,
+        })
+    })
+    .collect();
+
+    let appearances = sqlx::query!(
+        "SELECT label, reference FROM person_appearances WHERE name = $1 ORDER BY position",
+        person.name,
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal_error)?;
+
+    let passages = appearances
+        .into_iter()
+        .map(|row| (row.label, row.reference));
+    let readings = Readings::expand(&state, passages).await?;
+
+    Ok(readings.into_response(|appearances| Person {
+        name: person.name,
+        description: person.description,
+        relatives,
+        appearances,
+    }))
+}
+
+/// The get_mentions function links the people named in each verse, in the
+/// order of the verses. A person is named when their name is a whole word of
+/// the text, in the same case.
+pub async fn get_mentions(
+    pool: &PgPool,
+    results: &[SearchResult],
+) -> Result<Vec<Vec<PersonLink>>, sqlx::Error> {
+    let texts = results
+        .iter()
+        .map(|result| result.text.clone())
+        .collect::<Vec<String>>();
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT v.index as "index!", p.name
+            FROM unnest($1::text[]) WITH ORDINALITY AS v(text, index)
+            JOIN people p ON v.text ~ ('\m' || p.name || '\M')
+          ORDER BY v.index, p.name
+        "#,
+        &texts,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // The ordinality counts from 1
+    let mentions = rows
+        .into_iter()
+        .map(|row| (row.index as usize - 1, PersonLink::new(row.name)));
+
+    Ok(group_by_verse(results.len(), mentions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_relationship_reads_parents_as_children_from_the_other_side() {
+        assert_eq!(
+            get_relationship("father", false),
+            Some(Relationship::Father)
+        );
+        assert_eq!(get_relationship("mother", true), Some(Relationship::Child));
+        assert_eq!(
+            get_relationship("sibling", true),
+            Some(Relationship::Sibling)
+        );
+        assert_eq!(get_relationship("cousin", false), None);
+    }
+
+    #[test]
+    fn person_link_escapes_spaces() {
+        assert_eq!(
+            PersonLink::new(String::from("Mary Magdalene")).href,
+            "/people/Mary%20Magdalene"
+        );
+    }
+}
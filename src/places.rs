@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+
+use crate::{
+    annotate::group_by_verse, db::SearchResult, empty_string_as_none, error::BibleApiError,
+    internal_error,
+};
+
+/// The Place is a location named in the text, with where it was. The
+/// coordinates are approximate, and the modern name is only given when the
+/// site is known by another name today.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Place {
+    pub name: String,
+    pub modern_name: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub references: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlaceParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    query: Option<String>,
+}
+
+/// The places handler serves /places (ex: /places?query=Capernaum) with the
+/// places whose name, or modern name, contains the query in any case. Every
+/// place is returned when there is no query.
+pub async fn places(
+    State(pool): State<PgPool>,
+    Query(params): Query<PlaceParams>,
+) -> Result<Json<Vec<Place>>, BibleApiError> {
+    let query = params.query.map(|query| query.trim().to_lowercase());
+
+    let places = sqlx::query_as!(
+        Place,
+        r#"
+            SELECT name, modern_name, latitude, longitude, verse_references as "references"
+            FROM places
+            WHERE $1::text IS NULL
+               OR strpos(lower(name), $1) > 0
+               OR strpos(lower(modern_name), $1) > 0
+          ORDER BY name
+        "#,
+        query,
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(places))
+}
+
+/// The get_mentions function finds the places named in each verse, in the
+/// order of the verses. A place is named when its name is a whole word of
+/// the text, in the same case.
+pub async fn get_mentions(
+    pool: &PgPool,
+    results: &[SearchResult],
+) -> Result<Vec<Vec<Place>>, sqlx::Error> {
+    let texts = results
+        .iter()
+        .map(|result| result.text.clone())
+        .collect::<Vec<String>>();
+
+    let rows = sqlx::query!(
+        r#"
+            SELECT v.index as "index!", p.name, p.modern_name, p.latitude, p.longitude,
+                   p.verse_references as "references"
+            FROM unnest($1::text[]) WITH ORDINALITY AS v(text, index)
+            JOIN places p ON v.text ~ ('\m' || p.name || '\M')
+          ORDER BY v.index, p.name
+        "#,
+        &texts,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mentions = rows.into_iter().map(|row| {
+        let place = Place {
+            name: row.name,
+            modern_name: row.modern_name,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            references: row.references,
+        };
+        // The ordinality counts from 1
+        (row.index as usize - 1, place)
+    });
+
+    Ok(group_by_verse(results.len(), mentions))
+}
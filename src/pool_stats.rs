@@ -0,0 +1,213 @@
+use futures::future::BoxFuture;
+use serde::Serialize;
+use sqlx::{
+    pool::{PoolConnection, PoolConnectionMetadata},
+    postgres::{PgConnection, PgPool},
+    Postgres,
+};
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// The DEFAULT_MAX_CONNECTIONS is the most connections the pool opens at
+/// once, until the pool is set up with its own limit.
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+
+/// The DEFAULT_SUMMARY_INTERVAL is how often the pool summary is logged when
+/// POOL_STATS_INTERVAL_SECS is not set.
+const DEFAULT_SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The PoolStats keep running totals of how the connection pool behaves so
+/// that timeouts and connection churn can be looked at after an incident.
+/// The acquires, their waits and their timeouts are only those of the verse
+/// fetches (see acquire), while connection churn counts every connection.
+struct PoolStats {
+    acquires: AtomicU64,
+    timeouts: AtomicU64,
+    acquire_micros_total: AtomicU64,
+    acquire_micros_max: AtomicU64,
+    connections_opened: AtomicU64,
+    max_connections: AtomicU32,
+}
+
+static POOL_STATS: PoolStats = PoolStats {
+    acquires: AtomicU64::new(0),
+    timeouts: AtomicU64::new(0),
+    acquire_micros_total: AtomicU64::new(0),
+    acquire_micros_max: AtomicU64::new(0),
+    connections_opened: AtomicU64::new(0),
+    max_connections: AtomicU32::new(DEFAULT_MAX_CONNECTIONS),
+};
+
+/// The set_max_connections function records the most connections the pool
+/// was set up to open, which the pool itself does not say.
+pub fn set_max_connections(max_connections: u32) {
+    POOL_STATS
+        .max_connections
+        .store(max_connections, Ordering::Relaxed);
+}
+
+/// The get_max_connections function returns the most connections the pool
+/// opens at once.
+pub fn get_max_connections() -> u32 {
+    POOL_STATS.max_connections.load(Ordering::Relaxed)
+}
+
+/// The acquire function checks a connection out of the pool while recording
+/// how long the pool made us wait and whether it gave up. Only the verse
+/// fetches (the searches and the changes between versions) take their
+/// connection through it, as they are what most requests wait on, so the
+/// db_verse_fetch_acquire metrics are theirs. Every other query takes its
+/// connection from the pool directly and is not counted.
+pub async fn acquire(pool: &PgPool) -> Result<PoolConnection<Postgres>, sqlx::Error> {
+    let started = Instant::now();
+    let connection = pool.acquire().await;
+    let elapsed = started.elapsed();
+
+    metrics::histogram!("db_verse_fetch_acquire_seconds", elapsed.as_secs_f64());
+
+    match connection {
+        Ok(connection) => {
+            record_acquire(elapsed);
+            Ok(connection)
+        }
+        Err(sqlx::Error::PoolTimedOut) => {
+            POOL_STATS.timeouts.fetch_add(1, Ordering::Relaxed);
+            metrics::increment_counter!("db_verse_fetch_acquire_timeouts_total");
+            tracing::warn!(
+                "timed out after {:?} waiting for a database connection (size {}, idle {})",
+                elapsed,
+                pool.size(),
+                pool.num_idle()
+            );
+            Err(sqlx::Error::PoolTimedOut)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn record_acquire(elapsed: Duration) {
+    let micros = elapsed.as_micros() as u64;
+
+    POOL_STATS.acquires.fetch_add(1, Ordering::Relaxed);
+    POOL_STATS
+        .acquire_micros_total
+        .fetch_add(micros, Ordering::Relaxed);
+    POOL_STATS
+        .acquire_micros_max
+        .fetch_max(micros, Ordering::Relaxed);
+}
+
+/// The on_connect function is registered as the pool's after_connect hook to
+/// count every new connection, which is how churn is measured.
+pub fn on_connect(
+    _connection: &mut PgConnection,
+    _metadata: PoolConnectionMetadata,
+) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+    POOL_STATS
+        .connections_opened
+        .fetch_add(1, Ordering::Relaxed);
+    metrics::increment_counter!("db_pool_connections_opened_total");
+
+    Box::pin(async { Ok(()) })
+}
+
+/// The PoolSummary is how busy the pool is right now, for the health check.
+/// Saturation is the share of the most connections the pool may open that
+/// are checked out, and the timeouts are those of the verse fetches.
+#[derive(Debug, Serialize)]
+pub struct PoolSummary {
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
+    pub saturation: f64,
+    pub verse_fetch_timeouts: u64,
+}
+
+/// The get_summary function returns how busy the pool is right now.
+pub fn get_summary(pool: &PgPool) -> PoolSummary {
+    let size = pool.size();
+    let idle = pool.num_idle();
+    let max_connections = get_max_connections();
+    let in_use = size.saturating_sub(idle as u32);
+
+    PoolSummary {
+        size,
+        idle,
+        max_connections,
+        saturation: f64::from(in_use) / f64::from(max_connections.max(1)),
+        verse_fetch_timeouts: POOL_STATS.timeouts.load(Ordering::Relaxed),
+    }
+}
+
+/// The spawn_summary_logger function starts a background task that logs a
+/// summary of the pool every interval (POOL_STATS_INTERVAL_SECS). An
+/// interval of 0 is not one, so the default is used instead.
+pub fn spawn_summary_logger(pool: PgPool) {
+    let secs = std::env::var("POOL_STATS_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok());
+    let interval = match secs {
+        Some(0) => {
+            tracing::warn!(
+                "POOL_STATS_INTERVAL_SECS can't be 0, using {:?}",
+                DEFAULT_SUMMARY_INTERVAL
+            );
+            DEFAULT_SUMMARY_INTERVAL
+        }
+        Some(secs) => Duration::from_secs(secs),
+        None => DEFAULT_SUMMARY_INTERVAL,
+    };
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+            log_summary(&pool);
+        }
+    });
+}
+
+/// The record_gauges function sets the gauges of how busy the pool is right
+/// now: its connections, how many are idle, and its saturation.
+pub fn record_gauges(pool: &PgPool) {
+    let summary = get_summary(pool);
+
+    metrics::gauge!("db_pool_connections", f64::from(summary.size));
+    metrics::gauge!("db_pool_idle_connections", summary.idle as f64);
+    metrics::gauge!(
+        "db_pool_max_connections",
+        f64::from(summary.max_connections)
+    );
+    metrics::gauge!("db_pool_saturation", summary.saturation);
+}
+
+fn log_summary(pool: &PgPool) {
+    let size = pool.size() as u64;
+    let idle = pool.num_idle() as u64;
+    let acquires = POOL_STATS.acquires.load(Ordering::Relaxed);
+    let timeouts = POOL_STATS.timeouts.load(Ordering::Relaxed);
+    let opened = POOL_STATS.connections_opened.load(Ordering::Relaxed);
+    let max_micros = POOL_STATS.acquire_micros_max.swap(0, Ordering::Relaxed);
+    let average_micros = POOL_STATS
+        .acquire_micros_total
+        .load(Ordering::Relaxed)
+        .checked_div(acquires)
+        .unwrap_or(0);
+
+    record_gauges(pool);
+
+    tracing::info!(
+        "db pool: size {}, idle {}, verse fetch acquires {}, avg acquire {}us, max acquire {}us, timeouts {}, opened {}, closed {}",
+        size,
+        idle,
+        acquires,
+        average_micros,
+        max_micros,
+        timeouts,
+        opened,
+        opened.saturating_sub(size)
+    );
+}
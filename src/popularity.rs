@@ -0,0 +1,257 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    db::get_default_translation, empty_string_as_none, error::BibleApiError, internal_error,
+    search::BibleSearch, verse_id::VerseIds,
+};
+
+/// The FLUSH_INTERVAL is how often the views counted in memory are added to
+/// the daily counts in the database.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The DEFAULT_PERIOD is the number of days ranked when no period is given.
+const DEFAULT_PERIOD: Period = Period(30);
+
+/// The MAX_PERIOD is the most days that can be ranked at once.
+const MAX_PERIOD: u16 = 365;
+
+/// The DEFAULT_LIMIT and MAX_LIMIT bound how many verses are ranked.
+const DEFAULT_LIMIT: u8 = 10;
+const MAX_LIMIT: u8 = 100;
+
+// A verse is counted by its title, chapter and verse number
+type VerseKey = (String, i32, i32);
+
+/// The Popularity counts how often each verse is looked up. Views are counted
+/// in memory and flushed to the database every FLUSH_INTERVAL, so a search
+/// never waits on a write. Nothing but the verse is recorded.
+#[derive(Clone, Default)]
+pub struct Popularity {
+    views: Arc<Mutex<HashMap<VerseKey, i64>>>,
+}
+
+impl Popularity {
+    /// The record function counts a view of every verse of a search.
+    pub fn record(&self, bible_search: &BibleSearch) {
+        let mut views = self.views.lock().unwrap();
+
+        for (chapter, verse) in bible_search.get_verses() {
+            let key = (
+                bible_search.title.clone(),
+                i32::from(chapter),
+                i32::from(verse),
+            );
+            *views.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    /// The spawn_flush function starts a background task that adds the views
+    /// counted so far to today's counts every FLUSH_INTERVAL. Views that can
+    /// not be written are kept for the next flush.
+    pub fn spawn_flush(&self, pool: PgPool) {
+        let popularity = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+                popularity.save(&pool).await;
+            }
+        });
+    }
+
+    /// The save function adds the views counted so far to today's counts, as
+    /// the flush does, and is called once more on shutdown so they are not
+    /// lost.
+    pub async fn save(&self, pool: &PgPool) {
+        let views = std::mem::take(&mut *self.views.lock().unwrap());
+        if views.is_empty() {
+            return;
+        }
+
+        if let Err(err) = flush(pool, &views).await {
+            tracing::warn!("could not save verse views: {}", err);
+            self.restore(views);
+        }
+    }
+
+    fn restore(&self, unsaved: HashMap<VerseKey, i64>) {
+        let mut views = self.views.lock().unwrap();
+
+        for (key, count) in unsaved {
+            *views.entry(key).or_insert(0) += count;
+        }
+    }
+}
+
+async fn flush(pool: &PgPool, views: &HashMap<VerseKey, i64>) -> Result<(), sqlx::Error> {
+    let mut titles = vec![];
+    let mut chapters = vec![];
+    let mut verses = vec![];
+    let mut counts = vec![];
+
+    for ((title, chapter, verse), count) in views {
+        titles.push(title.clone());
+        chapters.push(*chapter);
+        verses.push(*verse);
+        counts.push(*count);
+    }
+
+    sqlx::query!(
+        "
+            INSERT INTO verse_views (day, title, chapter_num, num, views)
+            SELECT CURRENT_DATE, * FROM UNNEST($1::text[], $2::int[], $3::int[], $4::bigint[])
+            ON CONFLICT (day, title, chapter_num, num)
+            DO UPDATE SET views = verse_views.views + EXCLUDED.views
+        ",
+        &titles[..],
+        &chapters[..],
+        &verses[..],
+        &counts[..]
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+}
+
+/// The Period is a number of days, counting back from today (ex: 30d).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Period(u16);
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(period: &str) -> Result<Self, Self::Err> {
+        let days = period
+            .trim()
+            .strip_suffix('d')
+            .and_then(|days| days.parse::<u16>().ok())
+            .ok_or(format!("Invalid period: {} (ex: 30d)", period))?;
+
+        if days == 0 || days > MAX_PERIOD {
+            return Err(format!("The period must be 1d to {}d", MAX_PERIOD));
+        }
+
+        Ok(Period(days))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PopularParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    period: Option<Period>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    limit: Option<u8>,
+}
+
+/// The PopularVerse is a verse with the number of times it was looked up.
+#[derive(Debug, Serialize)]
+pub struct PopularVerse {
+    #[serde(flatten)]
+    pub ids: VerseIds,
+    pub title: String,
+    pub chapter: i32,
+    pub verse: i32,
+    pub text: String,
+    pub views: i64,
+}
+
+/// The popular handler serves /popular (ex: /popular?period=7d&limit=5) with
+/// the most looked up verses of the period, most viewed first. The period
+/// defaults to 30 days.
+pub async fn popular(
+    State(pool): State<PgPool>,
+    Query(params): Query<PopularParams>,
+) -> Result<Json<Vec<PopularVerse>>, BibleApiError> {
+    let Period(days) = params.period.unwrap_or(DEFAULT_PERIOD);
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let verses = sqlx::query!(
+        r#"
+            SELECT
+                p.title as title,
+                p.chapter_num as chapter,
+                p.num as verse,
+                v.contents as text,
+                SUM(p.views)::bigint as "views!"
+            FROM verse_views p
+                INNER JOIN verses v ON v.title = p.title
+                    AND v.chapter_num = p.chapter_num
+                    AND v.num = p.num
+            WHERE p.day > CURRENT_DATE - $1::int
+                AND v.version = (
+                    SELECT t.version FROM translation_versions t
+                    WHERE t.translation = $3 AND t.state = 'active'
+                )
+          GROUP BY p.title, p.chapter_num, p.num, v.contents
+          ORDER BY 5 DESC, p.title, p.chapter_num, p.num
+            LIMIT $2
+        "#,
+        i32::from(days),
+        i64::from(limit),
+        get_default_translation(),
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?
+    .into_iter()
+    .map(|row| PopularVerse {
+        ids: VerseIds::new(&row.title, row.chapter, row.verse),
+        title: row.title,
+        chapter: row.chapter,
+        verse: row.verse,
+        text: row.text,
+        views: row.views,
+    })
+    .collect();
+
+    Ok(Json(verses))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Chapter;
+    use std::collections::HashSet;
+
+    #[test]
+    fn period_parses_a_number_of_days() {
+        assert_eq!("30d".parse::<Period>(), Ok(Period(30)));
+        assert_eq!(" 1d ".parse::<Period>(), Ok(Period(1)));
+        assert!("30".parse::<Period>().is_err());
+        assert!("0d".parse::<Period>().is_err());
+        assert!("366d".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn record_counts_every_verse_of_a_search() {
+        let popularity = Popularity::default();
+        let bible_search = BibleSearch {
+            title: String::from("John"),
+            chapters: vec![Chapter {
+                chapter: 3,
+                verses: HashSet::from([16, 17]),
+            }],
+        };
+
+        popularity.record(&bible_search);
+        popularity.record(&bible_search);
+        popularity.restore(HashMap::from([((String::from("John"), 3, 16), 3)]));
+
+        let views = popularity.views.lock().unwrap();
+        assert_eq!(views.get(&(String::from("John"), 3, 16)), Some(&5));
+        assert_eq!(views.get(&(String::from("John"), 3, 17)), Some(&2));
+    }
+}
@@ -0,0 +1,85 @@
+use axum::{extract::Request, http::uri::Uri};
+use std::{collections::hash_map::RandomState, hash::BuildHasher, net::IpAddr, sync::OnceLock};
+use tracing::Span;
+
+/// The is_private function returns whether the privacy mode is on. It is
+/// read once from PRIVACY_MODE, and is off unless set to true. With it on,
+/// the query string of a request is left out of the request logs, and a
+/// client without an API key is rate limited by a hash of its address
+/// instead of the address itself. The hash is keyed when the server starts
+/// and never stored, so it cannot be turned back into the address. Nothing
+/// else records what a client asked for: the popularity, trending and usage
+/// stats only count the references and verses that were looked up.
+pub fn is_private() -> bool {
+    static PRIVATE: OnceLock<bool> = OnceLock::new();
+
+    *PRIVATE.get_or_init(|| {
+        std::env::var("PRIVACY_MODE")
+            .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1"))
+            .unwrap_or(false)
+    })
+}
+
+/// The make_request_span function starts the span a request is logged
+/// under, with its method, URI and HTTP version. In privacy mode the URI is
+/// only the path.
+pub fn make_request_span(request: &Request) -> Span {
+    // The span is where TraceLayer puts its own, so the same filters apply
+    tracing::debug_span!(
+        target: "tower_http::trace::make_span",
+        "request",
+        method = %request.method(),
+        uri = %get_logged_uri(request.uri(), is_private()),
+        version = ?request.version(),
+    )
+}
+
+// The URI as it is logged, without the query string when private
+fn get_logged_uri(uri: &Uri, private: bool) -> String {
+    match private {
+        true => uri.path().to_owned(),
+        false => uri.to_string(),
+    }
+}
+
+/// The get_client_address function returns how a client is known by its
+/// address (ex: for rate limiting). In privacy mode it is a hash of the
+/// address, the same for the same address until the server restarts.
+pub fn get_client_address(address: IpAddr) -> String {
+    match is_private() {
+        true => hash_address(address),
+        false => address.to_string(),
+    }
+}
+
+fn hash_address(address: IpAddr) -> String {
+    static KEYS: OnceLock<RandomState> = OnceLock::new();
+
+    format!(
+        "{:016x}",
+        KEYS.get_or_init(RandomState::new).hash_one(address)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_logged_uri_drops_the_query_when_private() {
+        let uri = "/search?query=John%203:16".parse::<Uri>().unwrap();
+
+        assert_eq!(get_logged_uri(&uri, true), "/search");
+        assert_eq!(get_logged_uri(&uri, false), "/search?query=John%203:16");
+    }
+
+    #[test]
+    fn hash_address_is_stable_and_hides_the_address() {
+        let address = "203.0.113.7".parse::<IpAddr>().unwrap();
+        let other = "203.0.113.8".parse::<IpAddr>().unwrap();
+
+        assert_eq!(hash_address(address), hash_address(address));
+        assert_ne!(hash_address(address), hash_address(other));
+        assert_eq!(hash_address(address).len(), 16);
+    }
+}
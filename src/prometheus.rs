@@ -0,0 +1,92 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::postgres::PgPool;
+use std::{sync::OnceLock, time::Instant};
+
+use crate::{error::BibleApiError, pool_stats};
+
+/// The LATENCY_BUCKETS are the upper bounds, in seconds, of the buckets
+/// request and pool latencies are counted in.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+/// The UNMATCHED_ROUTE is the route of a request that matched none, so a
+/// scan of made up paths does not add a series for each of them.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// The install function starts recording the metrics of the service, to be
+/// scraped from /metrics. Until it is called, metrics are not kept.
+pub fn install() {
+    let recorder = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix(String::from("_seconds")), &LATENCY_BUCKETS)
+        .and_then(PrometheusBuilder::install_recorder);
+
+    match recorder {
+        Ok(handle) => {
+            let _ = HANDLE.set(handle);
+        }
+        Err(err) => tracing::warn!("could not start recording metrics: {}", err),
+    }
+}
+
+/// The track middleware counts every request by its method, route and
+/// status code in http_requests_total, and how long it took by its method
+/// and route in http_request_duration_seconds. The route is the pattern the
+/// request matched (ex: /books/:book), not its path.
+pub async fn track(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE.to_owned(), |path| path.as_str().to_owned());
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::histogram!(
+        "http_request_duration_seconds",
+        started.elapsed().as_secs_f64(),
+        "method" => method.clone(),
+        "route" => route.clone()
+    );
+    metrics::increment_counter!(
+        "http_requests_total",
+        "method" => method,
+        "route" => route,
+        "status" => status
+    );
+
+    response
+}
+
+/// The metrics handler serves /metrics with every metric in the Prometheus
+/// text format, with the pool's gauges as they are right now.
+pub async fn metrics(State(pool): State<PgPool>) -> Response {
+    let handle = match HANDLE.get() {
+        Some(handle) => handle,
+        None => {
+            return BibleApiError::from((
+                StatusCode::NOT_FOUND,
+                "metrics are not being recorded".to_string(),
+            ))
+            .into_response();
+        }
+    };
+
+    pool_stats::record_gauges(&pool);
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    )
+        .into_response()
+}
@@ -0,0 +1,179 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    book,
+    chapter::{self, get_books_in_testament, Testament},
+    db::get_default_translation,
+    empty_string_as_none,
+    error::BibleApiError,
+    render::{Format, RenderOptions},
+    search::BibleSearch,
+    state::AppState,
+    versification::Versification,
+    PassageOptions,
+};
+
+/// The MAX_PICKS is how many times a verse is picked again when the one
+/// picked is left out of the translation (ex: Matthew 17:21 in some).
+const MAX_PICKS: usize = 8;
+
+#[derive(Debug, Deserialize)]
+pub struct RandomParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    book: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    testament: Option<Testament>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    format: Option<Format>,
+    #[serde(flatten)]
+    render_options: RenderOptions,
+}
+
+/// The random handler serves /random (ex: /random?testament=nt or
+/// /random?book=ps) with a verse picked at random, the same way /search
+/// sends a verse. Every verse is as likely to be picked, so a long chapter
+/// comes up more than a short one. The verse is picked from the verse
+/// counts the versification already has, then fetched by its reference, so
+/// the database never scans for it. A different verse can come back every
+/// time, so the response is never cached.
+pub async fn random(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<RandomParams>,
+) -> Result<Response, Response> {
+    let books =
+        get_books(params.book.as_deref(), params.testament).map_err(IntoResponse::into_response)?;
+    let versification = state.versifications.get(get_default_translation());
+    let bible_search = pick_verse(&books, &versification, &mut rand::thread_rng()).ok_or(
+        BibleApiError::from((
+            StatusCode::NOT_FOUND,
+            "No Verses Found To Pick From".to_string(),
+        ))
+        .into_response(),
+    )?;
+
+    let format = Format::negotiate(params.format, &headers);
+    let mut response = crate::search_response(
+        state.pool,
+        state.breaker,
+        get_default_translation(),
+        bible_search,
+        format,
+        params.render_options,
+        PassageOptions {
+            versification,
+            ..PassageOptions::default()
+        },
+    )
+    .await?;
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+    Ok(response)
+}
+
+// The books a verse is picked from, which is none at all when the book is
+// not in the testament
+fn get_books(
+    book: Option<&str>,
+    testament: Option<Testament>,
+) -> Result<Vec<&'static str>, BibleApiError> {
+    let books = match testament {
+        Some(testament) => get_books_in_testament(testament),
+        None => chapter::get_books(),
+    };
+
+    match book {
+        Some(book) => {
+            let title =
+                book::get_title(book).ok_or_else(|| BibleApiError::UnknownBook(book.to_owned()))?;
+            Ok(books.into_iter().filter(|book| *book == title).collect())
+        }
+        None => Ok(books),
+    }
+}
+
+// Pick a verse of the books, each with the same chance
+fn pick_verse(
+    books: &[&str],
+    versification: &Versification,
+    rng: &mut impl Rng,
+) -> Option<BibleSearch> {
+    let chapters = books
+        .iter()
+        .flat_map(|book| {
+            (1..=versification.get_chapter_count(book).unwrap_or(0)).filter_map(move |chapter| {
+                versification
+                    .get_verse_count(book, chapter)
+                    .map(|verse_count| (*book, chapter, verse_count))
+            })
+        })
+        .collect::<Vec<(&str, u8, u8)>>();
+    let total = chapters
+        .iter()
+        .map(|(_, _, verse_count)| u32::from(*verse_count))
+        .sum::<u32>();
+    if total == 0 {
+        return None;
+    }
+
+    (0..MAX_PICKS).find_map(|_| {
+        let mut pick = rng.gen_range(0..total);
+        let (book, chapter, verse) = chapters.iter().find_map(|(book, chapter, verse_count)| {
+            match pick.checked_sub(u32::from(*verse_count)) {
+                Some(rest) => {
+                    pick = rest;
+                    None
+                }
+                None => Some((*book, *chapter, pick as u8 + 1)),
+            }
+        })?;
+
+        versification
+            .verse_exists(book, chapter, verse)
+            .then(|| BibleSearch::from_verses(book, [(chapter, verse)]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn get_books_narrows_to_the_book_within_the_testament() {
+        assert_eq!(get_books(None, None).unwrap().len(), 66);
+        assert_eq!(get_books(None, Some(Testament::New)).unwrap().len(), 27);
+        assert_eq!(
+            get_books(Some("ps"), Some(Testament::Old)).unwrap(),
+            vec!["Psalms"]
+        );
+        assert!(get_books(Some("ps"), Some(Testament::New))
+            .unwrap()
+            .is_empty());
+        assert!(get_books(Some("Robert"), None).is_err());
+    }
+
+    #[test]
+    fn pick_verse_picks_a_verse_of_the_books() {
+        let versification = Versification::default();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..100 {
+            let bible_search = pick_verse(&["Jude", "3 John"], &versification, &mut rng).unwrap();
+            let verses = bible_search.get_verses();
+
+            assert_eq!(verses.len(), 1);
+            let (chapter, verse) = verses[0];
+            assert!(versification.verse_exists(&bible_search.title, chapter, verse));
+        }
+        assert_eq!(pick_verse(&[], &versification, &mut rng), None);
+    }
+}
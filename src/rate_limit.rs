@@ -0,0 +1,428 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sqlx::postgres::PgPool;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use crate::{api_keys::hash_key, error::BibleApiError, privacy::get_client_address};
+
+/// The API_KEY_HEADER carries the API key a request is rate limited by.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The WINDOW is how long a tier's budgets last before they are refilled.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// The REFRESH_INTERVAL is how often the tiers and keys are reloaded from
+/// the database, so changes to them apply without a restart.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The ANONYMOUS_TIER is the tier of requests made without an API key.
+const ANONYMOUS_TIER: &str = "free";
+
+/// The DEFAULT_TIER is used for anonymous requests until the free tier has
+/// been loaded from the database, or when it is missing from it.
+const DEFAULT_TIER: Tier = Tier {
+    requests: 60,
+    verses: 2000,
+};
+
+/// The Tier holds the budgets a client gets every WINDOW: the number of
+/// requests it can make and the number of verses it can be sent.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Tier {
+    pub requests: u32,
+    pub verses: u32,
+}
+
+/// The VerseCount is attached to a response by the handler that built it, so
+/// the verses sent can be charged to the client's verse budget.
+#[derive(Debug, Clone, Copy)]
+pub struct VerseCount(pub usize);
+
+#[derive(Default)]
+struct Limits {
+    tiers: HashMap<String, Tier>,
+    // The tier of each key, by the hash of the key
+    keys: HashMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Usage {
+    window_start: Instant,
+    requests: u32,
+    verses: u32,
+}
+
+// The Decision is what is known about a client once its request is counted
+#[derive(Debug, PartialEq)]
+struct Decision {
+    allowed: bool,
+    tier: Tier,
+    usage: Usage,
+    reset: Duration,
+}
+
+/// The RateLimiter tracks what every client has used of its tier's budgets in
+/// the current window. It is cheap to clone and shared by every request.
+/// When a key is required, requests without one are refused rather than
+/// given the anonymous tier. Without a key store (ex: on the SQLite backend)
+/// there are no keys to check, so a key is given the anonymous tier.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    limits: Arc<RwLock<Limits>>,
+    usage: Arc<Mutex<HashMap<String, Usage>>>,
+    require_key: bool,
+    has_key_store: bool,
+}
+
+impl RateLimiter {
+    /// The from_env function reads REQUIRE_API_KEY, which is off unless set
+    /// to true, so the API can be used without a key locally. A key can only
+    /// be required when there is a key store to check it against.
+    pub fn from_env(has_key_store: bool) -> Result<Self, String> {
+        let require_key = std::env::var("REQUIRE_API_KEY")
+            .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1"))
+            .unwrap_or(false);
+
+        RateLimiter::new(require_key, has_key_store)
+    }
+
+    fn new(require_key: bool, has_key_store: bool) -> Result<Self, String> {
+        if require_key && !has_key_store {
+            return Err(String::from(
+                "REQUIRE_API_KEY is set, but the backend has no API keys to check",
+            ));
+        }
+
+        Ok(RateLimiter {
+            require_key,
+            has_key_store,
+            ..RateLimiter::default()
+        })
+    }
+
+    /// The spawn_refresh function starts a background task that loads the
+    /// tiers and keys from the database now and every REFRESH_INTERVAL after.
+    pub fn spawn_refresh(&self, pool: PgPool) {
+        let limiter = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                if let Err(err) = limiter.refresh(&pool).await {
+                    tracing::warn!("could not load rate limit tiers: {}", err);
+                }
+            }
+        });
+    }
+
+    /// The spawn_sweep function starts a background task that forgets the
+    /// clients whose window has run out every WINDOW, so the usage kept does
+    /// not grow with every client ever seen. Every backend needs it, with or
+    /// without a key store.
+    pub fn spawn_sweep(&self) {
+        let limiter = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(WINDOW);
+
+            loop {
+                ticker.tick().await;
+
+                limiter.sweep(Instant::now());
+            }
+        });
+    }
+
+    // Forget the clients whose window has run out
+    fn sweep(&self, now: Instant) {
+        self.usage
+            .lock()
+            .unwrap()
+            .retain(|_, usage| now.duration_since(usage.window_start) < WINDOW);
+    }
+
+    /// The refresh function loads the tiers and keys from the database, so
+    /// a key that was just made or revoked applies without waiting for the
+    /// next REFRESH_INTERVAL.
+    pub async fn refresh(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let limits = load_limits(pool).await?;
+        *self.limits.write().unwrap() = limits;
+
+        Ok(())
+    }
+
+    // Find the tier of an API key by its hash, or the anonymous tier when
+    // there is none or no key store. None is returned for a key that is not
+    // known.
+    fn get_tier(&self, key_hash: Option<&str>) -> Option<Tier> {
+        let limits = self.limits.read().unwrap();
+
+        let tier = match key_hash {
+            Some(key_hash) if self.has_key_store => limits.keys.get(key_hash)?,
+            _ => ANONYMOUS_TIER,
+        };
+
+        Some(limits.tiers.get(tier).copied().unwrap_or(DEFAULT_TIER))
+    }
+
+    fn check(&self, client: &str, tier: Tier, now: Instant) -> Decision {
+        let mut usage = self.usage.lock().unwrap();
+        let usage = usage.entry(client.to_owned()).or_insert(Usage {
+            window_start: now,
+            requests: 0,
+            verses: 0,
+        });
+
+        check_usage(usage, tier, now)
+    }
+
+    fn record_verses(&self, client: &str, verses: usize) {
+        if let Some(usage) = self.usage.lock().unwrap().get_mut(client) {
+            usage.verses = usage.verses.saturating_add(verses as u32);
+        }
+    }
+}
+
+// Count a request against the budgets, starting a new window if the last
+// one has run out. The verses are charged once the response is built, so a
+// request is refused once either budget is already used up.
+fn check_usage(usage: &mut Usage, tier: Tier, now: Instant) -> Decision {
+    if now.duration_since(usage.window_start) >= WINDOW {
+        *usage = Usage {
+            window_start: now,
+            requests: 0,
+            verses: 0,
+        };
+    }
+
+    let allowed = usage.requests < tier.requests && usage.verses < tier.verses;
+    if allowed {
+        usage.requests += 1;
+    }
+
+    Decision {
+        allowed,
+        tier,
+        usage: *usage,
+        reset: WINDOW.saturating_sub(now.duration_since(usage.window_start)),
+    }
+}
+
+async fn load_limits(pool: &PgPool) -> Result<Limits, sqlx::Error> {
+    let tiers =
+        sqlx::query!("SELECT tier, requests_per_minute, verses_per_minute FROM rate_limit_tiers")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|row| {
+                let tier = Tier {
+                    requests: row.requests_per_minute.max(0) as u32,
+                    verses: row.verses_per_minute.max(0) as u32,
+                };
+                (row.tier, tier)
+            })
+            .collect();
+
+    let keys = sqlx::query!("SELECT key_hash, tier FROM api_keys WHERE revoked_at IS NULL")
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.key_hash, row.tier))
+        .collect();
+
+    Ok(Limits { tiers, keys })
+}
+
+/// The limit middleware enforces the request and verse budgets of the
+/// client's tier. A client is its API key, or its address when it has none.
+/// Every response says how much of the budgets is left in X-RateLimit-*
+/// headers, and a client that is over budget gets a 429. A key that is not
+/// known or was revoked gets a 401, as does a request without a key when
+/// one is required.
+pub async fn limit(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    // Only the hash of the key is kept, here as in the database
+    let key_hash = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(hash_key);
+
+    if key_hash.is_none() && limiter.require_key {
+        let error = format!("an API key is required in {}", API_KEY_HEADER);
+        return BibleApiError::from((StatusCode::UNAUTHORIZED, error)).into_response();
+    }
+
+    let tier = match limiter.get_tier(key_hash.as_deref()) {
+        Some(tier) => tier,
+        None => {
+            return BibleApiError::from((StatusCode::UNAUTHORIZED, "unknown API key".to_string()))
+                .into_response()
+        }
+    };
+
+    let client = match key_hash {
+        Some(key_hash) => format!("key:{}", key_hash),
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| format!("ip:{}", get_client_address(address.ip())))
+            .unwrap_or_else(|| String::from("anonymous")),
+    };
+
+    let decision = limiter.check(&client, tier, Instant::now());
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        metrics::increment_counter!("rate_limited_requests_total");
+        let retry_after = [(header::RETRY_AFTER, decision.reset.as_secs().max(1))];
+        (
+            retry_after,
+            BibleApiError::from((
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate limit exceeded".to_string(),
+            )),
+        )
+            .into_response()
+    };
+
+    let verses = response
+        .extensions()
+        .get::<VerseCount>()
+        .map_or(0, |VerseCount(verses)| *verses);
+    if verses > 0 {
+        limiter.record_verses(&client, verses);
+    }
+
+    let verses_used = decision.usage.verses.saturating_add(verses as u32);
+    let headers = [
+        ("x-ratelimit-limit", decision.tier.requests),
+        (
+            "x-ratelimit-remaining",
+            decision
+                .tier
+                .requests
+                .saturating_sub(decision.usage.requests),
+        ),
+        ("x-ratelimit-reset", decision.reset.as_secs() as u32),
+        ("x-ratelimit-verse-limit", decision.tier.verses),
+        (
+            "x-ratelimit-verse-remaining",
+            decision.tier.verses.saturating_sub(verses_used),
+        ),
+    ];
+    for (name, value) in headers {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIER: Tier = Tier {
+        requests: 2,
+        verses: 10,
+    };
+
+    fn usage(window_start: Instant, requests: u32, verses: u32) -> Usage {
+        Usage {
+            window_start,
+            requests,
+            verses,
+        }
+    }
+
+    #[test]
+    fn check_usage_counts_requests_until_the_budget_is_used() {
+        let now = Instant::now();
+        let mut current = usage(now, 0, 0);
+
+        assert!(check_usage(&mut current, TIER, now).allowed);
+        assert!(check_usage(&mut current, TIER, now).allowed);
+
+        let decision = check_usage(&mut current, TIER, now + Duration::from_secs(15));
+        assert!(!decision.allowed);
+        assert_eq!(decision.usage.requests, 2);
+        assert_eq!(decision.reset, Duration::from_secs(45));
+    }
+
+    #[test]
+    fn check_usage_refuses_requests_once_the_verse_budget_is_used() {
+        let now = Instant::now();
+        let mut current = usage(now, 0, 10);
+
+        assert!(!check_usage(&mut current, TIER, now).allowed);
+    }
+
+    #[test]
+    fn check_usage_refills_the_budgets_when_the_window_runs_out() {
+        let now = Instant::now();
+        let mut current = usage(now, 2, 10);
+        let later = now + WINDOW;
+
+        let decision = check_usage(&mut current, TIER, later);
+        assert!(decision.allowed);
+        assert_eq!(decision.usage, usage(later, 1, 0));
+    }
+
+    #[test]
+    fn get_tier_rejects_unknown_keys_and_defaults_anonymous_requests() {
+        let limiter = RateLimiter::new(false, true).unwrap();
+        limiter.limits.write().unwrap().keys =
+            HashMap::from([(hash_key("abc"), String::from("pro"))]);
+
+        assert_eq!(limiter.get_tier(None), Some(DEFAULT_TIER));
+        assert_eq!(limiter.get_tier(Some(&hash_key("abc"))), Some(DEFAULT_TIER));
+        assert_eq!(limiter.get_tier(Some(&hash_key("xyz"))), None);
+    }
+
+    #[test]
+    fn get_tier_gives_any_key_the_anonymous_tier_without_a_key_store() {
+        let limiter = RateLimiter::new(false, false).unwrap();
+        let free = Tier {
+            requests: 10,
+            verses: 100,
+        };
+        limiter.limits.write().unwrap().tiers = HashMap::from([(String::from("free"), free)]);
+
+        assert_eq!(limiter.get_tier(None), Some(free));
+        assert_eq!(limiter.get_tier(Some(&hash_key("xyz"))), Some(free));
+    }
+
+    #[test]
+    fn sweep_forgets_clients_whose_window_has_run_out() {
+        let limiter = RateLimiter::new(false, false).unwrap();
+        let now = Instant::now();
+        limiter.check("old", TIER, now);
+        limiter.check("new", TIER, now + WINDOW / 2);
+
+        limiter.sweep(now + WINDOW);
+
+        let usage = limiter.usage.lock().unwrap();
+        assert!(!usage.contains_key("old"));
+        assert!(usage.contains_key("new"));
+    }
+
+    #[test]
+    fn new_refuses_to_require_a_key_without_a_key_store() {
+        assert!(RateLimiter::new(true, false).is_err());
+        assert!(RateLimiter::new(true, true).is_ok());
+    }
+}
@@ -0,0 +1,197 @@
+use axum::{
+    http::{HeaderName, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Serialize;
+use std::future::Future;
+use tokio::task::{JoinError, JoinSet};
+
+use crate::{
+    breaker::DEGRADED_HEADER,
+    db::{get_default_translation, SearchOptions, SearchResult, TextFormat},
+    error::BibleApiError,
+    internal_error, pool_stats,
+    rate_limit::VerseCount,
+    search::{get_reference_with, is_whole_chapter_with, search_with},
+    state::AppState,
+};
+
+/// The PASSAGES_PER_FETCH is the most passages fetched in one statement, so
+/// a long list of passages is split over a few connections instead of
+/// waiting on one.
+const PASSAGES_PER_FETCH: usize = 16;
+
+/// The Reading is a passage appointed to be read, with its verses.
+#[derive(Debug, Serialize)]
+pub struct Reading {
+    pub label: String,
+    pub reference: String,
+    pub verses: Vec<SearchResult>,
+}
+
+/// The Readings are the passages appointed for a day, written out in full.
+/// The flag is true when any of them came from the offline dataset.
+pub struct Readings {
+    pub readings: Vec<Reading>,
+    pub degraded: bool,
+}
+
+impl Readings {
+    /// The expand function writes out each passage, given as a label and a
+    /// reference (ex: Gospel, Luke 2:1-14). The passages are appointed by
+    /// hand, so one that does not resolve is a mistake in the schedule. The
+    /// passages are fetched PASSAGES_PER_FETCH to a statement, with up to
+    /// one less statement running at a time than the pool has connections,
+    /// leaving one for the other requests being served, and kept in the
+    /// order they were given.
+    pub async fn expand(
+        state: &AppState,
+        passages: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, BibleApiError> {
+        let versification = state.versifications.get(get_default_translation());
+        let mut labels = vec![];
+        let mut searches = vec![];
+
+        for (label, reference) in passages {
+            let bible_search = search_with(&reference, &versification).map_err(|err| {
+                BibleApiError::from((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("{} does not resolve: {}", reference, err),
+                ))
+            })?;
+            let options = SearchOptions {
+                superscription: is_whole_chapter_with(&bible_search, &versification),
+                format: TextFormat::Plain,
+            };
+
+            labels.push((label, get_reference_with(&bible_search, &versification)));
+            searches.push((bible_search, options));
+        }
+
+        let fetches = searches
+            .chunks(PASSAGES_PER_FETCH)
+            .map(|searches| {
+                let breaker = state.breaker.clone();
+                let searches = searches.to_vec();
+                async move {
+                    breaker
+                        .search_many(get_default_translation(), searches)
+                        .await
+                }
+            })
+            .collect::<Vec<_>>();
+        let max_concurrent_fetches = pool_stats::get_max_connections().saturating_sub(1).max(1);
+        let fetched = join_bounded(fetches, max_concurrent_fetches as usize)
+            .await
+            .map_err(internal_error)?;
+
+        let mut passages = vec![];
+        let mut degraded = false;
+        for result in fetched {
+            let (verses, from_offline) = result?;
+            passages.extend(verses);
+            degraded |= from_offline;
+        }
+
+        let readings = labels
+            .into_iter()
+            .zip(passages)
+            .map(|((label, reference), verses)| Reading {
+                label,
+                reference,
+                verses,
+            })
+            .collect();
+
+        Ok(Readings { readings, degraded })
+    }
+
+    /// The into_response function sends the body built from the readings as
+    /// JSON, charging their verses to the client and flagging the response
+    /// when any came from the offline dataset.
+    pub fn into_response<T: Serialize>(self, body: impl FnOnce(Vec<Reading>) -> T) -> Response {
+        let verse_count = self
+            .readings
+            .iter()
+            .map(|reading| reading.verses.len())
+            .sum();
+        let degraded = self
+            .degraded
+            .then_some([(HeaderName::from_static(DEGRADED_HEADER), "true")]);
+
+        (
+            Extension(VerseCount(verse_count)),
+            degraded,
+            Json(body(self.readings)),
+        )
+            .into_response()
+    }
+}
+
+/// The join_bounded function runs the futures as tasks, no more than limit
+/// at a time, and returns their outputs in the order the futures were given.
+/// If a task panics the tasks still running are aborted.
+pub async fn join_bounded<F>(
+    futures: impl IntoIterator<Item = F>,
+    limit: usize,
+) -> Result<Vec<F::Output>, JoinError>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let mut pending = futures.into_iter().enumerate();
+    let mut outputs = vec![];
+    let mut tasks = JoinSet::new();
+
+    loop {
+        while tasks.len() < limit.max(1) {
+            let Some((index, future)) = pending.next() else {
+                break;
+            };
+            tasks.spawn(async move { (index, future.await) });
+        }
+
+        match tasks.join_next().await {
+            Some(joined) => outputs.push(joined?),
+            None => break,
+        }
+    }
+
+    outputs.sort_unstable_by_key(|(index, _)| *index);
+    Ok(outputs.into_iter().map(|(_, output)| output).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn join_bounded_keeps_the_order_and_the_limit() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most_running = Arc::new(AtomicUsize::new(0));
+
+        let futures = (0..10u64).map(|index| {
+            let (running, most_running) = (running.clone(), most_running.clone());
+            async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most_running.fetch_max(now_running, Ordering::SeqCst);
+                // The later futures finish first
+                tokio::time::sleep(Duration::from_millis(20 - index)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                index
+            }
+        });
+
+        let outputs = join_bounded(futures, 3).await.unwrap();
+        assert_eq!(outputs, (0..10).collect::<Vec<u64>>());
+        assert!(most_running.load(Ordering::SeqCst) <= 3);
+    }
+}
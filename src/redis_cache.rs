@@ -0,0 +1,126 @@
+use axum::async_trait;
+use redis::{
+    aio::{ConnectionManager, ConnectionManagerConfig},
+    AsyncCommands, Client,
+};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::sync::OnceCell;
+
+use crate::passage_cache::CacheBackend;
+
+/// The KEY_PREFIX is put before every key, so the cache can share a Redis
+/// server with other services.
+const KEY_PREFIX: &str = "bible-api:";
+
+/// The TIMEOUT is how long connecting to Redis, and each command, is waited
+/// on before it is taken as a miss. Going to the database is better than
+/// waiting on a cache that is not answering.
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The RECONNECT_DELAY is how long Redis is left alone after it could not be
+/// connected to, so requests are not each kept waiting on it while it is
+/// down.
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// The RedisCache keeps values in a Redis server, so every replica of the
+/// service shares them. It connects on first use, and reconnects on its own
+/// when the connection drops. Until it has connected, it tries again at most
+/// once every RECONNECT_DELAY.
+pub struct RedisCache {
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+    retry_at: Mutex<Option<Instant>>,
+}
+
+impl RedisCache {
+    /// The new function returns the cache of the Redis server at the url
+    /// (ex: redis://127.0.0.1:6379), which is not connected to yet.
+    pub fn new(url: &str) -> Result<Self, String> {
+        Ok(RedisCache {
+            client: Client::open(url).map_err(|err| err.to_string())?,
+            connection: OnceCell::new(),
+            retry_at: Mutex::new(None),
+        })
+    }
+
+    async fn connect(&self) -> Result<ConnectionManager, String> {
+        if let Some(connection) = self.connection.get() {
+            return Ok(connection.clone());
+        }
+        if let Some(retry_at) = *self.retry_at.lock().unwrap() {
+            if Instant::now() < retry_at {
+                return Err(String::from("waiting to connect to Redis again"));
+            }
+        }
+
+        let config = ConnectionManagerConfig::new()
+            .set_connection_timeout(TIMEOUT)
+            .set_response_timeout(TIMEOUT)
+            .set_number_of_retries(0);
+        let connected = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new_with_config(self.client.clone(), config))
+            .await
+            .cloned();
+
+        connected.map_err(|err| {
+            *self.retry_at.lock().unwrap() = Some(Instant::now() + RECONNECT_DELAY);
+            tracing::warn!("could not connect to Redis: {}", err);
+            err.to_string()
+        })
+    }
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        let mut connection = self.connect().await?;
+
+        connection
+            .get(get_key(key))
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), String> {
+        let mut connection = self.connect().await?;
+        let milliseconds = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1);
+
+        connection
+            .pset_ex(get_key(key), value, milliseconds)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    async fn remove_matching(&self, pattern: &str) -> Result<usize, String> {
+        let mut connection = self.connect().await?;
+
+        let keys = {
+            let mut scan = connection
+                .scan_match::<_, String>(get_key(pattern))
+                .await
+                .map_err(|err| err.to_string())?;
+            let mut keys = vec![];
+            while let Some(key) = scan.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        connection.del(keys).await.map_err(|err| err.to_string())
+    }
+}
+
+fn get_key(key: &str) -> String {
+    format!("{}{}", KEY_PREFIX, key)
+}
@@ -0,0 +1,161 @@
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+use std::sync::{Arc, Mutex};
+
+use crate::chapter::get_books;
+
+/// The ReindexState is where a reindex is at.
+/// - Running is still working through the books
+/// - Finished has rebuilt every book and the index
+/// - Failed stopped at an error, which is kept on the status
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReindexState {
+    Running,
+    Finished,
+    Failed,
+}
+
+/// The ReindexStatus is the progress of the latest reindex, counted in books.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ReindexStatus {
+    pub translation: String,
+    pub state: ReindexState,
+    pub books_done: usize,
+    pub books_total: usize,
+    pub error: Option<String>,
+}
+
+/// The ReindexJob tracks the full text search reindex. Only one can run at a
+/// time, and the status of the latest one is kept so it can be polled.
+#[derive(Clone, Default)]
+pub struct ReindexJob {
+    status: Arc<Mutex<Option<ReindexStatus>>>,
+}
+
+impl ReindexJob {
+    /// The status function returns the progress of the latest reindex, or
+    /// None if no reindex has been started.
+    pub fn status(&self) -> Option<ReindexStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// The start function rebuilds the search vectors of a translation in the
+    /// background and returns the status it starts with. It fails if another
+    /// reindex is still running.
+    pub fn start(&self, pool: PgPool, translation: &str) -> Result<ReindexStatus, String> {
+        let status = self.begin(translation)?;
+
+        let job = self.clone();
+        tokio::spawn(async move { job.run(pool).await });
+
+        Ok(status)
+    }
+
+    // Mark a reindex as running, unless one already is
+    fn begin(&self, translation: &str) -> Result<ReindexStatus, String> {
+        let mut current = self.status.lock().unwrap();
+
+        if let Some(running) = current
+            .as_ref()
+            .filter(|status| status.state == ReindexState::Running)
+        {
+            return Err(format!(
+                "a reindex of {} is already running ({} of {} books done)",
+                running.translation, running.books_done, running.books_total
+            ));
+        }
+
+        let status = ReindexStatus {
+            translation: translation.to_owned(),
+            state: ReindexState::Running,
+            books_done: 0,
+            books_total: get_books().len(),
+            error: None,
+        };
+        *current = Some(status.clone());
+
+        Ok(status)
+    }
+
+    async fn run(&self, pool: PgPool) {
+        for book in get_books() {
+            if let Err(err) = reindex_book(&pool, book).await {
+                return self.fail(err);
+            }
+
+            self.update(|status| status.books_done += 1);
+        }
+
+        // Rebuild the index in one go now that every vector has changed
+        if let Err(err) = sqlx::query("REINDEX INDEX verses_search_vector_idx")
+            .execute(&pool)
+            .await
+        {
+            return self.fail(err);
+        }
+
+        self.update(|status| status.state = ReindexState::Finished);
+        tracing::info!("full text search reindex finished");
+    }
+
+    fn fail(&self, err: sqlx::Error) {
+        tracing::warn!("full text search reindex failed: {}", err);
+
+        self.update(|status| {
+            status.state = ReindexState::Failed;
+            status.error = Some(err.to_string());
+        });
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ReindexStatus)) {
+        if let Some(status) = self.status.lock().unwrap().as_mut() {
+            change(status);
+        }
+    }
+}
+
+// Each book is updated on its own so progress can be reported between them
+// and no single statement holds locks on the whole table.
+async fn reindex_book(pool: &PgPool, book: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE verses SET search_vector = to_tsvector('english', contents) WHERE title = $1",
+        book
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn begin_starts_a_reindex_of_every_book() {
+        let job = ReindexJob::default();
+
+        assert_eq!(job.status(), None);
+        assert_eq!(
+            job.begin("kjv").unwrap(),
+            ReindexStatus {
+                translation: String::from("kjv"),
+                state: ReindexState::Running,
+                books_done: 0,
+                books_total: 66,
+                error: None,
+            }
+        );
+    }
+
+    #[test]
+    fn begin_refuses_to_start_while_a_reindex_is_running() {
+        let job = ReindexJob::default();
+        job.begin("kjv").unwrap();
+
+        assert!(job.begin("kjv").is_err());
+
+        job.update(|status| status.state = ReindexState::Finished);
+        assert!(job.begin("kjv").is_ok());
+    }
+}
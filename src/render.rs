@@ -0,0 +1,451 @@
+mod compact;
+mod html;
+mod latex;
+mod markdown;
+mod osis;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+mod text;
+mod usfm;
+
+use axum::{
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::str::FromStr;
+
+use crate::{
+    db::{get_default_translation, SearchResult, TextFormat},
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error,
+    ndjson::NDJSON_CONTENT_TYPE,
+    verse::SUPERSCRIPTION_VERSE,
+};
+
+/// The Format is how the verses of a search are written to the response. It
+/// is picked with the format parameter, or else from the Accept header.
+/// - Json (json) is an array of verse objects (the default)
+/// - Ndjson (ndjson) is one verse object per line, streamed as it is read
+/// - Text (text) is plain text with a block per paragraph
+/// - Html (html) is an HTML fragment using the formatted text of the verses
+/// - Markdown (markdown) is Markdown with the verse numbers in bold
+/// - Osis (osis) is an OSIS XML document
+/// - Latex (latex) is LaTeX with the verse numbers as superscripts
+/// - Compact (compact) is a single line of plain text, for chat and SMS
+/// - Csv (csv) is a row per verse, with a header row
+/// - MsgPack (msgpack) is the JSON array encoded as MessagePack
+/// - Protobuf (protobuf) is a Passage message (see proto/bible.proto)
+///
+/// Csv, MsgPack and Protobuf are only built with the cargo features of the
+/// same name (all on by default).
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    Ndjson,
+    Text,
+    Html,
+    Markdown,
+    Osis,
+    Usfm,
+    Latex,
+    Compact,
+    #[cfg(feature = "csv")]
+    Csv,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.trim().to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "text" | "plain" => Ok(Format::Text),
+            "html" => Ok(Format::Html),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "osis" => Ok(Format::Osis),
+            "usfm" => Ok(Format::Usfm),
+            "latex" | "tex" => Ok(Format::Latex),
+            "compact" => Ok(Format::Compact),
+            #[cfg(feature = "csv")]
+            "csv" => Ok(Format::Csv),
+            #[cfg(feature = "msgpack")]
+            "msgpack" => Ok(Format::MsgPack),
+            #[cfg(feature = "protobuf")]
+            "protobuf" => Ok(Format::Protobuf),
+            other => Err(format!("Unknown format: {}", other)),
+        }
+    }
+}
+
+impl Format {
+    /// The name function returns the name the format parameter takes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Text => "text",
+            Format::Html => "html",
+            Format::Markdown => "markdown",
+            Format::Osis => "osis",
+            Format::Usfm => "usfm",
+            Format::Latex => "latex",
+            Format::Compact => "compact",
+            #[cfg(feature = "csv")]
+            Format::Csv => "csv",
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => "msgpack",
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => "protobuf",
+        }
+    }
+
+    /// The media_type function returns the media type a client names in its
+    /// Accept header to ask for the format.
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Ndjson => NDJSON_CONTENT_TYPE,
+            Format::Text => "text/plain",
+            Format::Html => "text/html",
+            Format::Markdown => "text/markdown",
+            Format::Osis => "application/osis+xml",
+            Format::Usfm => "text/x-usfm",
+            Format::Latex => "text/x-tex",
+            // Compact is only picked with the format parameter, a client
+            // asking for text/plain gets the text format
+            Format::Compact => "text/plain",
+            #[cfg(feature = "csv")]
+            Format::Csv => "text/csv",
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => "application/msgpack",
+            #[cfg(feature = "protobuf")]
+            Format::Protobuf => "application/x-protobuf",
+        }
+    }
+
+    /// The from_media_type function finds the format of a media type, if
+    /// there is one. Wildcards are answered with JSON.
+    pub fn from_media_type(media_type: &str) -> Option<Format> {
+        match media_type.trim().to_lowercase().as_str() {
+            "*/*" | "application/*" => Some(Format::Json),
+            #[cfg(feature = "msgpack")]
+            "application/x-msgpack" => Some(Format::MsgPack),
+            media_type => FORMATS
+                .iter()
+                .copied()
+                .find(|format| format.media_type() == media_type),
+        }
+    }
+
+    /// The negotiate function picks the format of a response. The format
+    /// parameter wins, then the media type the Accept header ranks highest,
+    /// and JSON when neither names a known format.
+    pub fn negotiate(format: Option<Format>, headers: &HeaderMap) -> Format {
+        format
+            .or_else(|| get_accepted_formats(headers).into_iter().next())
+            .unwrap_or_default()
+    }
+
+    /// The text_format function returns which stored text of the verses the
+    /// format is written from. Only HTML keeps the formatting markup.
+    pub fn text_format(self) -> TextFormat {
+        match self {
+            Format::Html => TextFormat::Html,
+            _ => TextFormat::Plain,
+        }
+    }
+
+    // The text formats are sent as UTF-8
+    fn content_type(self) -> String {
+        match self.media_type() {
+            media_type if media_type.starts_with("text/") => {
+                format!("{}; charset=utf-8", media_type)
+            }
+            media_type => media_type.to_owned(),
+        }
+    }
+}
+
+/// The FORMATS constant lists every format that was built, in the order
+/// Format declares them.
+pub const FORMATS: &[Format] = &[
+    Format::Json,
+    Format::Ndjson,
+    Format::Text,
+    Format::Html,
+    Format::Markdown,
+    Format::Osis,
+    Format::Usfm,
+    Format::Latex,
+    Format::Compact,
+    #[cfg(feature = "csv")]
+    Format::Csv,
+    #[cfg(feature = "msgpack")]
+    Format::MsgPack,
+    #[cfg(feature = "protobuf")]
+    Format::Protobuf,
+];
+
+// Read the formats named in the Accept header, ranked by their quality. The
+// sort is stable, so media types of equal quality keep the client's order.
+fn get_accepted_formats(headers: &HeaderMap) -> Vec<Format> {
+    let mut accepted = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_range| {
+            let mut parts = media_range.split(';');
+            let format = Format::from_media_type(parts.next()?)?;
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|quality| quality.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((format, quality))
+        })
+        .filter(|(_, quality)| *quality > 0.0)
+        .collect::<Vec<(Format, f32)>>();
+
+    accepted.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    accepted.into_iter().map(|(format, _)| format).collect()
+}
+
+/// The RenderOptions shape the formats that take options. Each is read from
+/// the query parameter of the same name, and left out for the default.
+/// - latex_environment is the environment a LaTeX passage is wrapped in
+/// - width is the number of columns plain text is wrapped to
+/// - poetry_indent is the spaces plain text poetry lines are indented by
+/// - reference is where plain text, Markdown and HTML put the reference
+///   (prefix, suffix, none). Markdown and HTML lead with it as a heading
+///   unless told otherwise, plain text leaves it out.
+/// - max_len is the most characters a compact line can take
+#[derive(Debug, PartialEq, Clone, Default, Deserialize)]
+pub struct RenderOptions {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub latex_environment: Option<latex::LatexEnvironment>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub width: Option<text::Width>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub poetry_indent: Option<text::Indent>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub reference: Option<text::ReferencePlacement>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub max_len: Option<compact::MaxLen>,
+}
+
+impl RenderOptions {
+    fn text_options(&self) -> text::TextOptions {
+        text::TextOptions {
+            width: self.width,
+            poetry_indent: self.poetry_indent,
+            reference: self.reference.unwrap_or_default(),
+        }
+    }
+
+    // The passages written for reading lead with their reference
+    fn heading(&self) -> text::ReferencePlacement {
+        self.reference.unwrap_or(text::ReferencePlacement::Prefix)
+    }
+}
+
+/// The render function writes the verses of a search in a format, setting
+/// the Content-Type of the response to match. The format can come from the
+/// Accept header, so caches are told the response varies with it. The
+/// reference is the passage the verses are from (ex: John 3:16-18).
+pub fn render(
+    format: Format,
+    results: Vec<SearchResult>,
+    reference: &str,
+    options: &RenderOptions,
+) -> Result<Response, BibleApiError> {
+    let body = match format {
+        Format::Json => serde_json::to_vec(&results).map_err(internal_error)?,
+        Format::Ndjson => results
+            .iter()
+            .map(|result| serde_json::to_string(result).map(|line| line + "\n"))
+            .collect::<Result<String, _>>()
+            .map_err(internal_error)?
+            .into_bytes(),
+        Format::Text => text::render(&results, reference, &options.text_options()).into_bytes(),
+        Format::Html => html::render(&results, reference, options.heading()).into_bytes(),
+        Format::Markdown => markdown::render(&results, reference, options.heading()).into_bytes(),
+        Format::Osis => osis::render(&results).into_bytes(),
+        Format::Usfm => usfm::render(&results).into_bytes(),
+        Format::Latex => {
+            let environment = options.latex_environment.clone().unwrap_or_default();
+            latex::render(&results, &environment).into_bytes()
+        }
+        Format::Compact => compact::render(&results, reference, options.max_len).into_bytes(),
+        #[cfg(feature = "csv")]
+        Format::Csv => render_csv(&results)?,
+        #[cfg(feature = "msgpack")]
+        Format::MsgPack => rmp_serde::to_vec_named(&results).map_err(internal_error)?,
+        #[cfg(feature = "protobuf")]
+        Format::Protobuf => protobuf::render(results, reference),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type()),
+            (header::VARY, header::ACCEPT.to_string()),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(feature = "csv")]
+fn render_csv(results: &[SearchResult]) -> Result<Vec<u8>, BibleApiError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for result in results {
+        writer.serialize(result).map_err(internal_error)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|err| internal_error(err.into_error()))
+}
+
+// Split the verses into paragraphs. A new one starts at every verse that
+// opens a paragraph, and wherever the chapter changes.
+fn paragraphs(results: &[SearchResult]) -> Vec<&[SearchResult]> {
+    let mut paragraphs = vec![];
+    let mut start = 0;
+
+    for (index, pair) in results.windows(2).enumerate() {
+        if pair[1].paragraph_start || pair[1].chapter != pair[0].chapter {
+            paragraphs.push(&results[start..=index]);
+            start = index + 1;
+        }
+    }
+
+    if start < results.len() {
+        paragraphs.push(&results[start..]);
+    }
+
+    paragraphs
+}
+
+fn is_superscription(result: &SearchResult) -> bool {
+    result.verse == i32::from(SUPERSCRIPTION_VERSE)
+}
+
+// The translation the verses are from, as it is written out (ex: KJV)
+fn get_translation_name(results: &[SearchResult]) -> String {
+    results
+        .first()
+        .map_or(get_default_translation(), |result| &result.translation)
+        .to_uppercase()
+}
+
+// Escape the characters that are not allowed in XML text or attributes
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    pub fn verse(verse: i32, text: &str, paragraph_start: bool) -> SearchResult {
+        SearchResult::new(
+            "kjv",
+            String::from("John"),
+            3,
+            verse,
+            text.to_owned(),
+            paragraph_start,
+        )
+    }
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn format_parses_each_format_name() {
+        for &format in FORMATS {
+            assert_eq!(format.name().parse::<Format>(), Ok(format));
+        }
+        assert_eq!(" MD ".parse::<Format>(), Ok(Format::Markdown));
+        assert!("docx".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn negotiate_prefers_the_format_parameter() {
+        assert_eq!(
+            Format::negotiate(Some(Format::Text), &accept("text/html")),
+            Format::Text
+        );
+    }
+
+    #[test]
+    fn negotiate_picks_the_highest_ranked_media_type() {
+        assert_eq!(
+            Format::negotiate(
+                None,
+                &accept("application/json;q=0.5, application/x-ndjson;q=0.9")
+            ),
+            Format::Ndjson
+        );
+        assert_eq!(
+            Format::negotiate(None, &accept("text/markdown, text/plain")),
+            Format::Markdown
+        );
+        assert_eq!(
+            Format::negotiate(None, &accept("application/osis+xml;q=0")),
+            Format::Json
+        );
+    }
+
+    #[test]
+    fn negotiate_defaults_to_json() {
+        assert_eq!(Format::negotiate(None, &HeaderMap::new()), Format::Json);
+        assert_eq!(Format::negotiate(None, &accept("image/png")), Format::Json);
+        assert_eq!(Format::negotiate(None, &accept("*/*")), Format::Json);
+    }
+
+    #[test]
+    fn paragraphs_split_at_paragraph_starts_and_chapters() {
+        let mut results = vec![
+            verse(1, "a", true),
+            verse(2, "b", false),
+            verse(3, "c", true),
+            verse(1, "d", false),
+        ];
+        results[3].chapter = 4;
+
+        let lengths = paragraphs(&results)
+            .iter()
+            .map(|paragraph| paragraph.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(lengths, vec![2, 1, 1]);
+        assert!(paragraphs(&[]).is_empty());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn render_csv_writes_a_header_and_a_row_per_verse() {
+        let csv = render_csv(&[verse(16, "For God, so loved", true)]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "verse_id,chapter_id,book_id,translation,title,chapter,verse,text,paragraph_start\n\
+             43003016,43003,43,kjv,John,3,16,\"For God, so loved\",true\n"
+        );
+    }
+}
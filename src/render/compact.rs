@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+use super::{get_translation_name, is_superscription};
+use crate::{chapter::get_short_book, db::SearchResult};
+
+/// The MIN_LEN and MAX_LEN bound the length a compact line can be cut to.
+const MIN_LEN: usize = 40;
+const MAX_LEN: usize = 4000;
+
+/// The ELLIPSIS ends a compact line whose text was cut short.
+const ELLIPSIS: char = '…';
+
+/// The MaxLen is the most characters a compact line can take (ex: 400).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MaxLen(usize);
+
+impl FromStr for MaxLen {
+    type Err = String;
+
+    fn from_str(max_len: &str) -> Result<Self, Self::Err> {
+        match max_len.trim().parse::<usize>() {
+            Ok(max_len) if (MIN_LEN..=MAX_LEN).contains(&max_len) => Ok(MaxLen(max_len)),
+            _ => Err(format!("The max_len must be {} to {}", MIN_LEN, MAX_LEN)),
+        }
+    }
+}
+
+/// The render function writes the verses as a single line, led by the
+/// reference with the book abbreviated and followed by the translation (ex:
+/// Jn 3:16 — For God so loved… (KJV)). When the line is longer than the
+/// max_len, counted in characters, the text is cut at the last word that
+/// fits and an ellipsis marks the cut, keeping the reference and translation.
+pub fn render(results: &[SearchResult], reference: &str, max_len: Option<MaxLen>) -> String {
+    let reference = shorten(reference, results);
+    let prefix = format!("{} — ", reference);
+    let suffix = format!(" ({})", get_translation_name(results));
+    let text = results
+        .iter()
+        .filter(|result| !is_superscription(result))
+        .map(|result| result.text.trim())
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let line = format!("{}{}{}", prefix, text, suffix);
+    let Some(MaxLen(max_len)) = max_len else {
+        return line;
+    };
+    if line.chars().count() <= max_len {
+        return line;
+    }
+
+    // The ellipsis takes a character of its own
+    let reserved = prefix.chars().count() + suffix.chars().count() + 1;
+    match max_len.checked_sub(reserved).filter(|budget| *budget > 0) {
+        Some(budget) => format!("{}{}{}{}", prefix, cut(&text, budget), ELLIPSIS, suffix),
+        // A reference too long for the line is cut like any other text
+        None => line.chars().take(max_len - 1).chain([ELLIPSIS]).collect(),
+    }
+}
+
+// Write the reference with its book abbreviated (ex: John 3:16 is Jn 3:16)
+fn shorten(reference: &str, results: &[SearchResult]) -> String {
+    let Some(title) = results.first().map(|result| result.title.as_str()) else {
+        return reference.to_owned();
+    };
+
+    match (reference.strip_prefix(title), get_short_book(title)) {
+        (Some(rest), Some(short)) => format!("{}{}", short, rest),
+        _ => reference.to_owned(),
+    }
+}
+
+// Cut the text to the words that fit in the budget of characters, dropping
+// any punctuation left dangling at the cut. A first word too long to fit is
+// cut where the budget runs out.
+fn cut(text: &str, budget: usize) -> &str {
+    let mut end = 0;
+
+    for (index, _) in text.match_indices(' ') {
+        if text[..index].chars().count() > budget {
+            break;
+        }
+        end = index;
+    }
+    if text.chars().count() <= budget {
+        end = text.len();
+    }
+    if end == 0 {
+        end = text
+            .char_indices()
+            .nth(budget)
+            .map_or(text.len(), |(index, _)| index);
+    }
+
+    text[..end].trim_end_matches([' ', ',', ';', ':', '.'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::verse;
+
+    const JOHN_3_16: &str = "For God so loved the world, that he gave his only begotten Son, \
+        that whosoever believeth in him should not perish, but have everlasting life.";
+
+    #[test]
+    fn render_writes_the_passage_on_one_line() {
+        let results = [
+            verse(16, JOHN_3_16, true),
+            verse(17, "For God sent.", false),
+        ];
+
+        assert_eq!(
+            render(&results, "John 3:16-17", None),
+            format!("Jn 3:16-17 — {} For God sent. (KJV)", JOHN_3_16)
+        );
+    }
+
+    #[test]
+    fn render_names_the_translation_of_the_verses() {
+        let mut result = verse(16, JOHN_3_16, true);
+        result.translation = String::from("web");
+
+        assert!(render(&[result], "John 3:16", None).ends_with(" (WEB)"));
+    }
+
+    #[test]
+    fn render_cuts_the_text_at_a_word_to_fit() {
+        let line = render(&[verse(16, JOHN_3_16, true)], "John 3:16", Some(MaxLen(60)));
+
+        assert_eq!(
+            line,
+            "Jn 3:16 — For God so loved the world, that he gave… (KJV)"
+        );
+        assert!(line.chars().count() <= 60);
+    }
+
+    #[test]
+    fn render_keeps_to_the_max_len_when_the_reference_is_long() {
+        let reference = format!("John 3:{}", "1, ".repeat(20));
+        let line = render(&[verse(16, JOHN_3_16, true)], &reference, Some(MaxLen(40)));
+
+        assert_eq!(line.chars().count(), 40);
+        assert!(line.starts_with("Jn 3:1, 1,") && line.ends_with(ELLIPSIS));
+    }
+
+    #[test]
+    fn max_len_is_checked_when_parsed() {
+        assert_eq!("400".parse::<MaxLen>(), Ok(MaxLen(400)));
+        assert!("10".parse::<MaxLen>().is_err());
+        assert!("lots".parse::<MaxLen>().is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use super::{escape, is_superscription, paragraphs, text::ReferencePlacement};
+use crate::db::SearchResult;
+
+/// The render function writes the verses as an HTML fragment that can be
+/// embedded in a page, with a <p> per paragraph and the verse numbers in
+/// <sup>. The text is expected to be HTML already (see TextFormat::Html).
+/// The reference is a heading before the passage, or a citation after it.
+pub fn render(results: &[SearchResult], reference: &str, placement: ReferencePlacement) -> String {
+    let paragraphs = paragraphs(results)
+        .into_iter()
+        .map(|paragraph| {
+            let verses = paragraph
+                .iter()
+                .map(render_verse)
+                .collect::<Vec<String>>()
+                .join(" ");
+            format!("<p>{}</p>\n", verses)
+        })
+        .collect::<String>();
+
+    match placement {
+        ReferencePlacement::Prefix => format!(
+            "<div class=\"passage\">\n<h2 class=\"reference\">{}</h2>\n{}</div>\n",
+            escape(reference),
+            paragraphs
+        ),
+        ReferencePlacement::Suffix => format!(
+            "<div class=\"passage\">\n{}<cite class=\"reference\">{}</cite>\n</div>\n",
+            paragraphs,
+            escape(reference)
+        ),
+        ReferencePlacement::None => format!("<div class=\"passage\">\n{}</div>\n", paragraphs),
+    }
+}
+
+fn render_verse(result: &SearchResult) -> String {
+    if is_superscription(result) {
+        format!(
+            "<span class=\"superscription\" data-verse-id=\"{}\">{}</span>",
+            result.verse_id, result.text
+        )
+    } else {
+        format!(
+            "<sup class=\"verse\" data-verse-id=\"{}\">{}</sup> {}",
+            result.verse_id, result.verse, result.text
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::verse;
+
+    #[test]
+    fn render_wraps_each_paragraph_and_keeps_the_markup() {
+        let results = [
+            verse(1, "Jesus <i>wept</i>.", true),
+            verse(2, "Then said the Jews.", true),
+        ];
+
+        assert_eq!(
+            render(&results, "John 3:1-2", ReferencePlacement::None),
+            "<div class=\"passage\">\n\
+             <p><sup class=\"verse\" data-verse-id=\"43003001\">1</sup> Jesus <i>wept</i>.</p>\n\
+             <p><sup class=\"verse\" data-verse-id=\"43003002\">2</sup> Then said the Jews.</p>\n\
+             </div>\n"
+        );
+    }
+
+    #[test]
+    fn render_leads_with_the_reference() {
+        let results = [verse(16, "For God so loved the world.", true)];
+
+        assert_eq!(
+            render(&results, "John 3:16", ReferencePlacement::Prefix),
+            "<div class=\"passage\">\n\
+             <h2 class=\"reference\">John 3:16</h2>\n\
+             <p><sup class=\"verse\" data-verse-id=\"43003016\">16</sup> For God so loved the world.</p>\n\
+             </div>\n"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::str::FromStr;
+
+use super::{is_superscription, paragraphs};
+use crate::db::SearchResult;
+
+/// The LatexEnvironment is the environment a passage is wrapped in when it
+/// is written as LaTeX (ex: quote, quotation or verse), or none to leave the
+/// passage bare. Only letters and a trailing star make up a name, so the
+/// parameter can not be used to inject commands into the document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LatexEnvironment(Option<String>);
+
+impl Default for LatexEnvironment {
+    fn default() -> Self {
+        LatexEnvironment(Some(String::from("quote")))
+    }
+}
+
+impl FromStr for LatexEnvironment {
+    type Err = String;
+
+    fn from_str(environment: &str) -> Result<Self, Self::Err> {
+        let environment = environment.trim();
+        let name = environment.strip_suffix('*').unwrap_or(environment);
+
+        if environment.eq_ignore_ascii_case("none") {
+            return Ok(LatexEnvironment(None));
+        }
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!(
+                "Invalid LaTeX environment: {} (ex: quote, verse or none)",
+                environment
+            ));
+        }
+
+        Ok(LatexEnvironment(Some(environment.to_owned())))
+    }
+}
+
+/// The render function writes the verses as LaTeX, one paragraph per block,
+/// with each verse number as a superscript and the whole passage wrapped in
+/// the environment given.
+pub fn render(results: &[SearchResult], environment: &LatexEnvironment) -> String {
+    let body = paragraphs(results)
+        .into_iter()
+        .map(|paragraph| {
+            paragraph
+                .iter()
+                .map(render_verse)
+                .collect::<Vec<String>>()
+                .join(" ")
+                + "\n"
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    match &environment.0 {
+        Some(name) => format!("\\begin{{{name}}}\n{body}\\end{{{name}}}\n"),
+        None => body,
+    }
+}
+
+fn render_verse(result: &SearchResult) -> String {
+    if is_superscription(result) {
+        format!("\\textit{{{}}}", escape(&result.text))
+    } else {
+        format!(
+            "\\textsuperscript{{{}}}{}",
+            result.verse,
+            escape(&result.text)
+        )
+    }
+}
+
+// Escape the characters LaTeX treats as markup
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::verse;
+
+    #[test]
+    fn render_wraps_the_passage_in_the_environment() {
+        let results = [
+            verse(0, "A Psalm of David.", false),
+            verse(1, "Lord, how are they increased.", true),
+            verse(2, "Many there be.", false),
+        ];
+
+        assert_eq!(
+            render(&results, &LatexEnvironment::default()),
+            "\\begin{quote}\n\\textit{A Psalm of David.}\n\n\\textsuperscript{1}Lord, how are \
+             they increased. \\textsuperscript{2}Many there be.\n\\end{quote}\n"
+        );
+        assert_eq!(
+            render(&results[1..2], &"none".parse().unwrap()),
+            "\\textsuperscript{1}Lord, how are they increased.\n"
+        );
+    }
+
+    #[test]
+    fn render_escapes_the_markup_characters() {
+        assert_eq!(
+            render(&[verse(1, "50% & {more}", true)], &"verse".parse().unwrap()),
+            "\\begin{verse}\n\\textsuperscript{1}50\\% \\& \\{more\\}\n\\end{verse}\n"
+        );
+    }
+
+    #[test]
+    fn latex_environment_only_takes_a_name() {
+        assert!("quotation*".parse::<LatexEnvironment>().is_ok());
+        assert!("quote}\\input{x".parse::<LatexEnvironment>().is_err());
+        assert!("".parse::<LatexEnvironment>().is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use super::{is_superscription, paragraphs, text::ReferencePlacement};
+use crate::db::SearchResult;
+
+/// The render function writes the verses as Markdown, one paragraph per
+/// block, with each verse number in bold. The reference is a heading before
+/// the passage, or a line after it.
+pub fn render(results: &[SearchResult], reference: &str, placement: ReferencePlacement) -> String {
+    let body = paragraphs(results)
+        .into_iter()
+        .map(|paragraph| {
+            paragraph
+                .iter()
+                .map(render_verse)
+                .collect::<Vec<String>>()
+                .join(" ")
+                + "\n"
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    match placement {
+        ReferencePlacement::Prefix => format!("## {}\n\n{}", reference, body),
+        ReferencePlacement::Suffix => format!("{}\n— {}\n", body, reference),
+        ReferencePlacement::None => body,
+    }
+}
+
+fn render_verse(result: &SearchResult) -> String {
+    if is_superscription(result) {
+        format!("*{}*", result.text)
+    } else {
+        format!("**{}** {}", result.verse, result.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::verse;
+
+    #[test]
+    fn render_bolds_the_verse_numbers() {
+        let results = [
+            verse(0, "A Psalm of David.", false),
+            verse(1, "Lord, how are they increased.", true),
+        ];
+
+        assert_eq!(
+            render(&results, "Psalms 3:0-1", ReferencePlacement::None),
+            "*A Psalm of David.*\n\n**1** Lord, how are they increased.\n"
+        );
+    }
+
+    #[test]
+    fn render_places_the_reference() {
+        let results = [verse(16, "For God so loved the world.", true)];
+
+        assert_eq!(
+            render(&results, "John 3:16", ReferencePlacement::Prefix),
+            "## John 3:16\n\n**16** For God so loved the world.\n"
+        );
+        assert_eq!(
+            render(&results, "John 3:16", ReferencePlacement::Suffix),
+            "**16** For God so loved the world.\n\n— John 3:16\n"
+        );
+    }
+}
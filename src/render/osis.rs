@@ -0,0 +1,72 @@
+use super::{escape, get_translation_name, is_superscription, paragraphs};
+use crate::{chapter::get_osis_book, db::SearchResult};
+
+/// The OSIS_NAMESPACE is the XML namespace of an OSIS document.
+const OSIS_NAMESPACE: &str = "http://www.bibletechnologies.net/2003/OSIS/namespace";
+
+/// The render function writes the verses as an OSIS document. Each verse is
+/// marked with its osisID (ex: John.3.16), and a psalm's superscription is a
+/// canonical title.
+pub fn render(results: &[SearchResult]) -> String {
+    let paragraphs = paragraphs(results)
+        .into_iter()
+        .map(|paragraph| {
+            let verses = paragraph.iter().map(render_verse).collect::<String>();
+            format!("<p>{}</p>", verses)
+        })
+        .collect::<String>();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <osis xmlns=\"{}\"><osisText osisIDWork=\"{}\" xml:lang=\"en\">\
+         <div type=\"passage\">{}</div></osisText></osis>\n",
+        OSIS_NAMESPACE,
+        get_translation_name(results),
+        paragraphs
+    )
+}
+
+fn render_verse(result: &SearchResult) -> String {
+    if is_superscription(result) {
+        return format!(
+            "<title type=\"psalm\" canonical=\"true\">{}</title>",
+            escape(&result.text)
+        );
+    }
+
+    let book = get_osis_book(&result.title).unwrap_or(&result.title);
+    format!(
+        "<verse osisID=\"{}.{}.{}\">{}</verse>",
+        escape(book),
+        result.chapter,
+        result.verse,
+        escape(&result.text)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::verse;
+
+    #[test]
+    fn render_verse_marks_the_osis_id() {
+        let mut result = verse(16, "For God so loved the world", true);
+        result.title = String::from("1 John");
+
+        assert_eq!(
+            render_verse(&result),
+            "<verse osisID=\"1John.3.16\">For God so loved the world</verse>"
+        );
+    }
+
+    #[test]
+    fn render_verse_escapes_the_text() {
+        let result = verse(0, "To the chief Musician & singer", false);
+
+        assert_eq!(
+            render_verse(&result),
+            "<title type=\"psalm\" canonical=\"true\">To the chief Musician &amp; singer</title>"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use prost::Message;
+
+use crate::db::SearchResult;
+
+// The messages are defined in proto/bible.proto, and have to be kept in step
+// with it by hand.
+
+/// The Verse message is one verse of a passage (see proto/bible.proto).
+#[derive(Clone, PartialEq, Message)]
+pub struct Verse {
+    #[prost(string, tag = "1")]
+    pub title: String,
+    #[prost(int32, tag = "2")]
+    pub chapter: i32,
+    #[prost(int32, tag = "3")]
+    pub verse: i32,
+    #[prost(string, tag = "4")]
+    pub text: String,
+    #[prost(bool, tag = "5")]
+    pub paragraph_start: bool,
+    #[prost(int32, tag = "6")]
+    pub verse_id: i32,
+    #[prost(int32, tag = "7")]
+    pub chapter_id: i32,
+    #[prost(int32, tag = "8")]
+    pub book_id: i32,
+    #[prost(string, tag = "9")]
+    pub translation: String,
+}
+
+/// The Passage message is every verse a search matched, in reading order,
+/// under the reference they were matched by.
+#[derive(Clone, PartialEq, Message)]
+pub struct Passage {
+    #[prost(message, repeated, tag = "1")]
+    pub verses: Vec<Verse>,
+    #[prost(string, tag = "2")]
+    pub reference: String,
+}
+
+impl From<SearchResult> for Verse {
+    fn from(result: SearchResult) -> Self {
+        Verse {
+            title: result.title,
+            chapter: result.chapter,
+            verse: result.verse,
+            text: result.text,
+            paragraph_start: result.paragraph_start,
+            verse_id: result.verse_id,
+            chapter_id: result.chapter_id,
+            book_id: result.book_id,
+            translation: result.translation,
+        }
+    }
+}
+
+impl From<Vec<SearchResult>> for Passage {
+    fn from(results: Vec<SearchResult>) -> Self {
+        Passage {
+            verses: results.into_iter().map(Verse::from).collect(),
+            reference: String::new(),
+        }
+    }
+}
+
+/// The render function encodes the verses as a Passage message.
+pub fn render(results: Vec<SearchResult>, reference: &str) -> Vec<u8> {
+    Passage {
+        reference: reference.to_owned(),
+        ..Passage::from(results)
+    }
+    .encode_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passage_round_trips_through_the_wire_format() {
+        let passage = Passage::from(vec![SearchResult::new(
+            "kjv",
+            String::from("John"),
+            11,
+            35,
+            String::from("Jesus wept."),
+            true,
+        )]);
+
+        let decoded = Passage::decode(passage.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, passage);
+        assert_eq!(decoded.verses[0].verse_id, 43_011_035);
+        assert_eq!(decoded.verses[0].title, "John");
+    }
+}
@@ -0,0 +1,266 @@
+use std::str::FromStr;
+
+use super::{is_superscription, paragraphs};
+use crate::db::SearchResult;
+
+/// The POETRY_BOOKS are laid out a verse per line when poetry is indented.
+/// The prose at the start and end of Job is laid out as poetry too.
+const POETRY_BOOKS: [&str; 5] = [
+    "Job",
+    "Psalms",
+    "Proverbs",
+    "Song of Solomon",
+    "Lamentations",
+];
+
+/// The MIN_WIDTH and MAX_WIDTH bound the width the text can be wrapped to.
+const MIN_WIDTH: usize = 20;
+const MAX_WIDTH: usize = 200;
+
+/// The MAX_INDENT is the most spaces poetry lines can be indented by.
+const MAX_INDENT: usize = 16;
+
+/// The Width is the number of columns the text is wrapped to (ex: 80).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Width(usize);
+
+impl FromStr for Width {
+    type Err = String;
+
+    fn from_str(width: &str) -> Result<Self, Self::Err> {
+        match width.trim().parse::<usize>() {
+            Ok(width) if (MIN_WIDTH..=MAX_WIDTH).contains(&width) => Ok(Width(width)),
+            _ => Err(format!("The width must be {} to {}", MIN_WIDTH, MAX_WIDTH)),
+        }
+    }
+}
+
+/// The Indent is the number of spaces poetry lines are indented by (ex: 4).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Indent(usize);
+
+impl FromStr for Indent {
+    type Err = String;
+
+    fn from_str(indent: &str) -> Result<Self, Self::Err> {
+        match indent.trim().parse::<usize>() {
+            Ok(indent) if indent <= MAX_INDENT => Ok(Indent(indent)),
+            _ => Err(format!("The poetry indent must be 0 to {}", MAX_INDENT)),
+        }
+    }
+}
+
+/// The ReferencePlacement is where the reference of the passage is written.
+/// - Prefix is on a line of its own before the passage
+/// - Suffix is on a line of its own after the passage
+/// - None leaves it out (the default)
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ReferencePlacement {
+    Prefix,
+    Suffix,
+    #[default]
+    None,
+}
+
+impl FromStr for ReferencePlacement {
+    type Err = String;
+
+    fn from_str(placement: &str) -> Result<Self, Self::Err> {
+        match placement.trim().to_lowercase().as_str() {
+            "prefix" => Ok(ReferencePlacement::Prefix),
+            "suffix" => Ok(ReferencePlacement::Suffix),
+            "none" => Ok(ReferencePlacement::None),
+            other => Err(format!(
+                "Invalid reference placement: {} (ex: prefix, suffix or none)",
+                other
+            )),
+        }
+    }
+}
+
+/// The TextOptions shape the plain text. Without any, the text is a block
+/// per paragraph with no wrapping.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct TextOptions {
+    pub width: Option<Width>,
+    pub poetry_indent: Option<Indent>,
+    pub reference: ReferencePlacement,
+}
+
+/// The render function writes the verses as plain text, one paragraph per
+/// block, with each verse led by its number. Poetry is written a verse per
+/// line when it is indented, and the lines are wrapped to the width given.
+pub fn render(results: &[SearchResult], reference: &str, options: &TextOptions) -> String {
+    let body = paragraphs(results)
+        .into_iter()
+        .map(|paragraph| render_paragraph(paragraph, options))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    match options.reference {
+        ReferencePlacement::Prefix => format!("{}\n\n{}", reference, body),
+        ReferencePlacement::Suffix => format!("{}\n{}\n", body, reference),
+        ReferencePlacement::None => body,
+    }
+}
+
+fn render_paragraph(paragraph: &[SearchResult], options: &TextOptions) -> String {
+    let width = options.width.map(|Width(width)| width);
+    let poetry_indent = options.poetry_indent.filter(|_| {
+        paragraph
+            .first()
+            .is_some_and(|result| POETRY_BOOKS.contains(&result.title.as_str()))
+    });
+
+    match poetry_indent {
+        // Each line of poetry is a verse, and the lines it wraps onto are
+        // indented a little further so the verses stand out
+        Some(Indent(indent)) => paragraph
+            .iter()
+            .map(|result| match is_superscription(result) {
+                true => wrap(&render_verse(result), width, 0, 0),
+                false => wrap(&render_verse(result), width, indent, indent + 2),
+            })
+            .collect(),
+        None => wrap(
+            &paragraph
+                .iter()
+                .map(render_verse)
+                .collect::<Vec<String>>()
+                .join(" "),
+            width,
+            0,
+            0,
+        ),
+    }
+}
+
+fn render_verse(result: &SearchResult) -> String {
+    if is_superscription(result) {
+        result.text.clone()
+    } else {
+        format!("{} {}", result.verse, result.text)
+    }
+}
+
+// Wrap the text at spaces so no line is wider than the width, unless a
+// single word is. The first line is indented by the first indent and the
+// rest by the second. A verse number is never left at the end of a line.
+fn wrap(text: &str, width: Option<usize>, first_indent: usize, indent: usize) -> String {
+    let Some(width) = width else {
+        return format!("{}{}\n", " ".repeat(first_indent), text);
+    };
+
+    let mut lines: Vec<String> = vec![];
+    let mut line = " ".repeat(first_indent);
+    let mut line_width = first_indent;
+    let mut is_empty = true;
+
+    for word in glue_verse_numbers(text) {
+        let word_width = word.chars().count();
+
+        if !is_empty && line_width + 1 + word_width > width {
+            lines.push(line);
+            line = " ".repeat(indent);
+            line_width = indent;
+            is_empty = true;
+        }
+        if !is_empty {
+            line.push(' ');
+            line_width += 1;
+        }
+        line.push_str(&word);
+        line_width += word_width;
+        is_empty = false;
+    }
+    lines.push(line);
+
+    lines.join("\n") + "\n"
+}
+
+// Split the text into the words a line can break between, keeping each verse
+// number with the word after it
+fn glue_verse_numbers(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    let mut number: Option<&str> = None;
+
+    for word in text.split(' ').filter(|word| !word.is_empty()) {
+        match number.take() {
+            Some(number) => words.push(format!("{} {}", number, word)),
+            None if word.chars().all(|c| c.is_ascii_digit()) => number = Some(word),
+            None => words.push(word.to_owned()),
+        }
+    }
+    words.extend(number.map(str::to_owned));
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::verse;
+
+    #[test]
+    fn render_breaks_the_text_at_paragraphs() {
+        let results = [
+            verse(1, "In the beginning.", true),
+            verse(2, "And the earth.", false),
+            verse(3, "And God said.", true),
+        ];
+
+        assert_eq!(
+            render(&results, "John 3:1-3", &TextOptions::default()),
+            "1 In the beginning. 2 And the earth.\n\n3 And God said.\n"
+        );
+    }
+
+    #[test]
+    fn render_wraps_the_text_without_stranding_verse_numbers() {
+        let results = [
+            verse(1, "In the beginning was the Word.", true),
+            verse(2, "The same was in the beginning with God.", false),
+        ];
+        let options = TextOptions {
+            width: Some(Width(32)),
+            ..TextOptions::default()
+        };
+
+        assert_eq!(
+            render(&results, "John 1:1-2", &options),
+            "1 In the beginning was the Word.\n2 The same was in the beginning\nwith God.\n"
+        );
+    }
+
+    #[test]
+    fn render_indents_poetry_a_verse_per_line() {
+        let mut results = [
+            verse(0, "A Psalm of David.", false),
+            verse(1, "The LORD is my shepherd; I shall not want.", true),
+        ];
+        for result in results.iter_mut() {
+            result.title = String::from("Psalms");
+        }
+        let options = TextOptions {
+            width: Some(Width(30)),
+            poetry_indent: Some(Indent(2)),
+            reference: ReferencePlacement::Prefix,
+        };
+
+        assert_eq!(
+            render(&results, "Psalms 23", &options),
+            "Psalms 23\n\nA Psalm of David.\n\n  1 The LORD is my shepherd; I\n    shall not want.\n"
+        );
+    }
+
+    #[test]
+    fn options_are_checked_when_parsed() {
+        assert_eq!("80".parse::<Width>(), Ok(Width(80)));
+        assert!("5".parse::<Width>().is_err());
+        assert!("17".parse::<Indent>().is_err());
+        assert_eq!(
+            "Suffix".parse::<ReferencePlacement>(),
+            Ok(ReferencePlacement::Suffix)
+        );
+    }
+}
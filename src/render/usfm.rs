@@ -0,0 +1,81 @@
+use super::is_superscription;
+use crate::{chapter::get_usfm_code, db::SearchResult};
+
+/// The render function writes the verses as USFM, with a marker line for
+/// each book (\id), chapter (\c), paragraph (\p) and verse (\v). A psalm's
+/// superscription is its descriptive title (\d). The first verse of each
+/// chapter starts a paragraph, so every verse is in one.
+pub fn render(results: &[SearchResult]) -> String {
+    let mut usfm = String::new();
+    let mut book = None;
+    let mut chapter = None;
+    let mut needs_paragraph = true;
+
+    for result in results {
+        if book != Some(result.title.as_str()) {
+            let code = get_usfm_code(&result.title).unwrap_or(&result.title);
+            usfm.push_str(&format!("\\id {}\n", code));
+            book = Some(result.title.as_str());
+            chapter = None;
+        }
+        if chapter != Some(result.chapter) {
+            usfm.push_str(&format!("\\c {}\n", result.chapter));
+            chapter = Some(result.chapter);
+            needs_paragraph = true;
+        }
+
+        if is_superscription(result) {
+            usfm.push_str(&format!("\\d {}\n", result.text));
+            continue;
+        }
+        if needs_paragraph || result.paragraph_start {
+            usfm.push_str("\\p\n");
+            needs_paragraph = false;
+        }
+        usfm.push_str(&format!("\\v {} {}\n", result.verse, result.text));
+    }
+
+    usfm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::tests::verse;
+
+    #[test]
+    fn render_marks_the_chapters_paragraphs_and_verses() {
+        let mut results = vec![
+            verse(16, "For God so loved the world", false),
+            verse(17, "For God sent not his Son", false),
+            verse(22, "After these things", true),
+            verse(1, "When therefore the Lord knew", true),
+        ];
+        results[3].chapter = 4;
+
+        assert_eq!(
+            render(&results),
+            "\\id JHN\n\\c 3\n\\p\n\
+             \\v 16 For God so loved the world\n\
+             \\v 17 For God sent not his Son\n\
+             \\p\n\\v 22 After these things\n\
+             \\c 4\n\\p\n\\v 1 When therefore the Lord knew\n"
+        );
+    }
+
+    #[test]
+    fn render_writes_the_superscription_as_a_title() {
+        let mut results = vec![
+            verse(0, "A Psalm of David.", false),
+            verse(1, "LORD, how are they increased", true),
+        ];
+        for result in &mut results {
+            result.title = String::from("Psalms");
+        }
+
+        assert_eq!(
+            render(&results),
+            "\\id PSA\n\\c 3\n\\d A Psalm of David.\n\\p\n\\v 1 LORD, how are they increased\n"
+        );
+    }
+}
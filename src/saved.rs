@@ -0,0 +1,558 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Redirect,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::postgres::PgPool;
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+use crate::{
+    api_keys::hash_key,
+    db::get_default_translation,
+    error::BibleApiError,
+    internal_error,
+    links::{get_href, SearchKind},
+    rate_limit::API_KEY_HEADER,
+    search,
+    validation::{check_reference, check_text, MAX_TEXT_QUERY_LEN},
+};
+
+/// The MAX_SAVED_SEARCHES is the most searches an API key can save.
+const MAX_SAVED_SEARCHES: i64 = 100;
+
+/// The MAX_NAME_LEN and MAX_WEBHOOK_LEN are the most characters a saved
+/// search's name and webhook can take.
+const MAX_NAME_LEN: usize = 64;
+const MAX_WEBHOOK_LEN: usize = 512;
+
+/// The CHECK_INTERVAL is how often the keyword searches with a webhook are
+/// run again to look for new matches.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// The SaveSearch is the body of PUT /saved/:name. The webhook is an https
+/// URL on a public address that is posted to when new verses match a
+/// keyword search.
+#[derive(Debug, Deserialize)]
+pub struct SaveSearch {
+    pub kind: SearchKind,
+    pub query: String,
+    pub webhook: Option<String>,
+}
+
+/// The SavedSearch is a search saved under a name, with the href that runs
+/// it again.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub kind: SearchKind,
+    pub query: String,
+    pub webhook: Option<String>,
+    pub href: String,
+    pub saved_at: String,
+}
+
+impl SavedSearch {
+    fn new(
+        name: String,
+        kind: &str,
+        query: String,
+        webhook: Option<String>,
+        saved_at: String,
+    ) -> Self {
+        // The kind is checked by the table, so it always parses
+        let kind = kind.parse().unwrap_or(SearchKind::Keyword);
+
+        SavedSearch {
+            href: get_href(kind, &query),
+            name,
+            kind,
+            query,
+            webhook,
+            saved_at,
+        }
+    }
+}
+
+// The hash of the API key the searches are saved under. The rate limiter
+// has already turned away keys it does not know.
+fn get_key_hash(headers: &HeaderMap) -> Result<String, BibleApiError> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(hash_key)
+        .ok_or(BibleApiError::from((
+            StatusCode::UNAUTHORIZED,
+            format!("saved searches need an API key in {}", API_KEY_HEADER),
+        )))
+}
+
+// Only https webhooks are taken, so notifications are not sent in the
+// clear, and an address has to be a public one
+fn check_webhook(webhook: &str) -> Result<(), String> {
+    let url = match reqwest::Url::parse(webhook) {
+        Ok(url) if url.scheme() == "https" => url,
+        _ => return Err(invalid_webhook(webhook)),
+    };
+
+    match url.host_str().map(|host| host.trim_matches(['[', ']'])) {
+        Some(host) => match host.parse::<IpAddr>() {
+            Ok(address) if !is_public(address) => Err(not_public(host)),
+            _ => Ok(()),
+        },
+        None => Err(invalid_webhook(webhook)),
+    }
+}
+
+fn invalid_webhook(webhook: &str) -> String {
+    format!(
+        "Invalid webhook: {} (ex: https://example.com/hook)",
+        webhook
+    )
+}
+
+fn not_public(host: &str) -> String {
+    format!("Invalid webhook: {} is not a public address", host)
+}
+
+// Resolve the host of a webhook, so one that points into our own network
+// is refused (ex: localhost, 10.0.0.1, or a name resolving to 169.254.169.254).
+// The addresses that were checked are returned, so the post goes to them and
+// not to what the name resolves to by then.
+async fn check_webhook_host(webhook: &str) -> Result<Vec<SocketAddr>, String> {
+    let url = reqwest::Url::parse(webhook).map_err(|_| invalid_webhook(webhook))?;
+    let host = url.host_str().ok_or_else(|| invalid_webhook(webhook))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let addresses = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .map_err(|_| format!("Invalid webhook: {} could not be resolved", host))?
+        .collect::<Vec<_>>();
+    match addresses.is_empty() || !addresses.iter().all(|address| is_public(address.ip())) {
+        true => Err(not_public(host)),
+        false => Ok(addresses),
+    }
+}
+
+// Whether an address is one on the internet, and not a loopback, private,
+// link-local or otherwise reserved one
+fn is_public(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => is_public_v4(address),
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(address),
+        },
+    }
+}
+
+fn is_public_v4(address: Ipv4Addr) -> bool {
+    let [first, second, ..] = address.octets();
+    !(address.is_private()
+        || address.is_loopback()
+        || address.is_link_local()
+        || address.is_unspecified()
+        || address.is_broadcast()
+        || address.is_documentation()
+        || address.is_multicast()
+        // This network (0.0.0.0/8), shared (100.64.0.0/10), benchmarking
+        // (198.18.0.0/15) and reserved (240.0.0.0/4)
+        || first == 0
+        || (first == 100 && (second & 0xc0) == 64)
+        || (first == 198 && (second & 0xfe) == 18)
+        || first >= 240)
+}
+
+fn is_public_v6(address: Ipv6Addr) -> bool {
+    let first = address.segments()[0];
+    !(address.is_loopback()
+        || address.is_unspecified()
+        || address.is_multicast()
+        // Unique local (fc00::/7), link-local (fe80::/10) and documentation
+        // (2001:db8::/32)
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first == 0x2001 && address.segments()[1] == 0xdb8))
+}
+
+fn check_search(name: &str, search: &SaveSearch) -> Result<(), BibleApiError> {
+    let bad_request = |err: String| BibleApiError::from((StatusCode::BAD_REQUEST, err));
+    let invalid = |err: crate::validation::ValidationError| bad_request(err.reason);
+
+    check_text("name", name, MAX_NAME_LEN).map_err(invalid)?;
+    match search.kind {
+        SearchKind::Reference => {
+            check_reference("query", &search.query).map_err(invalid)?;
+            search::search(&search.query).map_err(|err| bad_request(err.to_string()))?;
+        }
+        SearchKind::Keyword => {
+            check_text("query", &search.query, MAX_TEXT_QUERY_LEN).map_err(invalid)?;
+        }
+    }
+
+    match (&search.webhook, search.kind) {
+        (None, _) => Ok(()),
+        (Some(_), SearchKind::Reference) => Err(bad_request(
+            "Only keyword searches can have a webhook".to_string(),
+        )),
+        (Some(webhook), SearchKind::Keyword) => {
+            check_text("webhook", webhook, MAX_WEBHOOK_LEN).map_err(invalid)?;
+            check_webhook(webhook).map_err(bad_request)
+        }
+    }
+}
+
+/// The saved_searches handler serves GET /saved with the searches saved
+/// under the API key, by name.
+pub async fn saved_searches(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SavedSearch>>, BibleApiError> {
+    let key_hash = get_key_hash(&headers)?;
+
+    let searches = sqlx::query!(
+        r#"
+            SELECT name, kind, query, webhook,
+                   to_char(saved_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as "saved_at!"
+            FROM saved_searches
+            WHERE key_hash = $1
+          ORDER BY name
+        "#,
+        key_hash,
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?
+    .into_iter()
+    .map(|row| SavedSearch::new(row.name, &row.kind, row.query, row.webhook, row.saved_at))
+    .collect();
+
+    Ok(Json(searches))
+}
+
+/// The save_search handler serves PUT /saved/:name, saving a search under
+/// the API key or replacing the one saved under that name. A keyword search
+/// with a webhook is only notified of verses that match after it is saved.
+pub async fn save_search(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(search): Json<SaveSearch>,
+) -> Result<Json<SavedSearch>, BibleApiError> {
+    let key_hash = get_key_hash(&headers)?;
+    check_search(&name, &search)?;
+    if let Some(webhook) = &search.webhook {
+        check_webhook_host(webhook)
+            .await
+            .map_err(|err| BibleApiError::from((StatusCode::BAD_REQUEST, err)))?;
+    }
+
+    let saved = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM saved_searches WHERE key_hash = $1 AND name <> $2"#,
+        key_hash,
+        name,
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(internal_error)?;
+    if saved >= MAX_SAVED_SEARCHES {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("At most {} searches can be saved", MAX_SAVED_SEARCHES),
+        )
+            .into());
+    }
+
+    let total = match search.kind {
+        SearchKind::Keyword => count_matches(&pool, &search.query)
+            .await
+            .map_err(internal_error)?,
+        SearchKind::Reference => 0,
+    };
+
+    let row = sqlx::query!(
+        r#"
+            INSERT INTO saved_searches (key_hash, name, kind, query, webhook, total)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (key_hash, name) DO UPDATE
+                SET kind = $3, query = $4, webhook = $5, total = $6, saved_at = now()
+            RETURNING to_char(saved_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') as "saved_at!"
+        "#,
+        key_hash,
+        name,
+        search.kind.as_str(),
+        search.query,
+        search.webhook,
+        total,
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(internal_error)?;
+
+    Ok(Json(SavedSearch::new(
+        name,
+        search.kind.as_str(),
+        search.query,
+        search.webhook,
+        row.saved_at,
+    )))
+}
+
+/// The run_saved_search handler serves GET /saved/:name by redirecting to
+/// the search, so it is answered as it would be run by hand.
+pub async fn run_saved_search(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Redirect, BibleApiError> {
+    let key_hash = get_key_hash(&headers)?;
+
+    let row = sqlx::query!(
+        "SELECT kind, query FROM saved_searches WHERE key_hash = $1 AND name = $2",
+        key_hash,
+        name,
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(internal_error)?
+    .ok_or(BibleApiError::from((
+        StatusCode::NOT_FOUND,
+        format!("No Saved Search Found: {}", name),
+    )))?;
+
+    let kind = row
+        .kind
+        .parse()
+        .map_err(|err| BibleApiError::from((StatusCode::INTERNAL_SERVER_ERROR, err)))?;
+    Ok(Redirect::to(&get_href(kind, &row.query)))
+}
+
+/// The delete_saved_search handler serves DELETE /saved/:name.
+pub async fn delete_saved_search(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<StatusCode, BibleApiError> {
+    let key_hash = get_key_hash(&headers)?;
+
+    let deleted = sqlx::query!(
+        "DELETE FROM saved_searches WHERE key_hash = $1 AND name = $2",
+        key_hash,
+        name,
+    )
+    .execute(&pool)
+    .await
+    .map_err(internal_error)?
+    .rows_affected();
+
+    match deleted {
+        0 => Err((
+            StatusCode::NOT_FOUND,
+            format!("No Saved Search Found: {}", name),
+        )
+            .into()),
+        _ => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+/// The spawn_notifier function starts a background task that runs the
+/// keyword searches with a webhook every CHECK_INTERVAL, and posts to the
+/// webhook when more verses match than last time (ex: after an import). A
+/// webhook that fails is tried again at the next check.
+pub fn spawn_notifier(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(err) = notify(&pool).await {
+                tracing::warn!("could not check the saved searches: {}", err);
+            }
+        }
+    });
+}
+
+async fn notify(pool: &PgPool) -> Result<(), sqlx::Error> {
+    let searches = sqlx::query!(
+        r#"
+            SELECT key_hash, name, query, webhook as "webhook!", total
+            FROM saved_searches
+            WHERE kind = 'keyword' AND webhook IS NOT NULL
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for search in searches {
+        let total = count_matches(pool, &search.query).await?;
+
+        if total > search.total {
+            let body = json!({
+                "name": search.name,
+                "query": search.query,
+                "total": total,
+                "new": total - search.total,
+                "href": get_href(SearchKind::Keyword, &search.query),
+            });
+            if let Err(err) = post_webhook(&search.webhook, &body).await {
+                tracing::warn!("could not notify saved search {}: {}", search.name, err);
+                continue;
+            }
+        }
+
+        if total != search.total {
+            sqlx::query!(
+                "UPDATE saved_searches SET total = $3 WHERE key_hash = $1 AND name = $2",
+                search.key_hash,
+                search.name,
+                total,
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// The client a webhook is posted with, which connects to the addresses its
+// host was checked at, does not follow a redirect to somewhere that was not
+// checked, nor go through a proxy
+fn get_webhook_client(host: &str, addresses: &[SocketAddr]) -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .resolve_to_addrs(host, addresses)
+        .redirect(reqwest::redirect::Policy::none())
+        .no_proxy()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|err| err.to_string())
+}
+
+// The host is checked again before each post, as what it resolves to can
+// change after the webhook is saved
+async fn post_webhook(webhook: &str, body: &serde_json::Value) -> Result<(), String> {
+    let addresses = check_webhook_host(webhook).await?;
+    let url = reqwest::Url::parse(webhook).map_err(|_| invalid_webhook(webhook))?;
+    let host = url.host_str().ok_or_else(|| invalid_webhook(webhook))?;
+
+    let response = get_webhook_client(host, &addresses)?
+        .post(url.as_str())
+        .json(body)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("webhook failed with status {}", response.status()))
+    }
+}
+
+// Count the verses of the active version that match a keyword search
+async fn count_matches(pool: &PgPool, query: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+            SELECT COUNT(*) as "count!"
+            FROM verses v
+            JOIN translation_versions t ON t.version = v.version
+            WHERE t.translation = $1 AND t.state = 'active'
+                AND v.search_vector @@ websearch_to_tsquery('english', $2)
+        "#,
+        get_default_translation(),
+        query,
+    )
+    .fetch_one(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save(kind: SearchKind, query: &str, webhook: Option<&str>) -> SaveSearch {
+        SaveSearch {
+            kind,
+            query: query.to_owned(),
+            webhook: webhook.map(String::from),
+        }
+    }
+
+    #[test]
+    fn check_search_checks_the_query_and_webhook() {
+        let hook = Some("https://example.com/hook");
+
+        assert!(check_search("faith", &save(SearchKind::Keyword, "faith", hook)).is_ok());
+        assert!(check_search("john", &save(SearchKind::Reference, "John 3:16", None)).is_ok());
+        assert!(check_search("john", &save(SearchKind::Reference, "Hezekiah 1", None)).is_err());
+        assert!(check_search("john", &save(SearchKind::Reference, "John 3:16", hook)).is_err());
+        assert!(check_search(
+            "faith",
+            &save(
+                SearchKind::Keyword,
+                "faith",
+                Some("http://example.com/hook")
+            )
+        )
+        .is_err());
+        assert!(check_search("faith\n", &save(SearchKind::Keyword, "faith", None)).is_err());
+    }
+
+    #[test]
+    fn check_webhook_refuses_an_address_that_is_not_public() {
+        assert!(check_webhook("https://93.184.216.34/hook").is_ok());
+        assert!(check_webhook("https://127.0.0.1/hook").is_err());
+        assert!(check_webhook("https://10.0.0.1/hook").is_err());
+        assert!(check_webhook("https://169.254.169.254/latest/meta-data").is_err());
+        assert!(check_webhook("https://[::1]/hook").is_err());
+        assert!(check_webhook("https://[::ffff:192.168.0.1]/hook").is_err());
+    }
+
+    #[test]
+    fn is_public_refuses_reserved_addresses() {
+        let is_public = |address: &str| is_public(address.parse().unwrap());
+
+        assert!(is_public("8.8.8.8"));
+        assert!(is_public("2606:4700::1111"));
+        assert!(!is_public("0.0.0.0"));
+        assert!(!is_public("100.64.0.1"));
+        assert!(!is_public("172.16.5.4"));
+        assert!(!is_public("192.168.1.1"));
+        assert!(!is_public("fd00::1"));
+        assert!(!is_public("fe80::1"));
+    }
+
+    #[tokio::test]
+    async fn check_webhook_host_refuses_a_name_that_resolves_to_loopback() {
+        assert!(check_webhook_host("https://localhost/hook").await.is_err());
+        assert!(check_webhook_host("https://127.0.0.1:8443/hook")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn get_webhook_client_posts_to_the_checked_address() {
+        // hook.invalid never resolves, so the post can only reach the
+        // listener through the address it was checked at
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let checked = listener.local_addr().unwrap();
+        let client = get_webhook_client("hook.invalid", &[checked]).unwrap();
+
+        let post = tokio::spawn(async move {
+            client
+                .post(format!("http://hook.invalid:{}/hook", checked.port()))
+                .send()
+                .await
+        });
+        let accepted = tokio::time::timeout(Duration::from_secs(5), listener.accept()).await;
+
+        assert!(accepted.is_ok_and(|accepted| accepted.is_ok()));
+        post.abort();
+    }
+}
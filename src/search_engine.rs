@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::postgres::PgPool;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    chapter::{get_testament, Testament},
+    sitemap::get_book_slug,
+    text_search::{Facets, TextSearch, TextSearchHit, TextSearchResults},
+};
+
+/// The DEFAULT_INDEX is the index the verses are pushed into when
+/// SEARCH_ENGINE_INDEX is not set.
+const DEFAULT_INDEX: &str = "verses";
+
+/// The INDEX_BATCH_SIZE is how many verses are sent to the search engine in
+/// a single request when indexing.
+const INDEX_BATCH_SIZE: usize = 1000;
+
+/// The SearchEngine is a Meilisearch instance the verses are indexed in, for
+/// deployments whose text searches have outgrown the Postgres full text
+/// search. It adds typo tolerance and counts of the matches by book and
+/// testament. Verses are pushed into it by the index-search command.
+#[derive(Debug, Clone)]
+pub struct SearchEngine {
+    pub url: String,
+    pub api_key: Option<String>,
+    pub index: String,
+    pub client: reqwest::Client,
+}
+
+/// The VerseDocument is a verse as it is stored in the search engine.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct VerseDocument {
+    pub id: String,
+    pub translation: String,
+    pub title: String,
+    pub testament: Option<Testament>,
+    pub chapter: i32,
+    pub verse: i32,
+    pub text: String,
+}
+
+impl VerseDocument {
+    pub fn new(translation: &str, hit: TextSearchHit) -> Self {
+        VerseDocument {
+            id: get_document_id(translation, &hit.title, hit.chapter, hit.verse),
+            translation: translation.to_owned(),
+            testament: get_testament(&hit.title),
+            title: hit.title,
+            chapter: hit.chapter,
+            verse: hit.verse,
+            text: hit.text,
+        }
+    }
+}
+
+// The search response, of which only the hits, the total and the facets are
+// read
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    hits: Vec<TextSearchHit>,
+    estimated_total_hits: u64,
+    #[serde(default)]
+    facet_distribution: HashMap<String, BTreeMap<String, u64>>,
+}
+
+impl SearchEngine {
+    /// Returns None unless SEARCH_ENGINE_URL is set, as the search engine is
+    /// optional. SEARCH_ENGINE_API_KEY is only needed when the instance has a
+    /// master key. The requests are sent with the client given.
+    pub fn from_env(client: &reqwest::Client) -> Option<Self> {
+        Some(SearchEngine {
+            url: std::env::var("SEARCH_ENGINE_URL")
+                .ok()?
+                .trim_end_matches('/')
+                .to_owned(),
+            api_key: std::env::var("SEARCH_ENGINE_API_KEY").ok(),
+            index: std::env::var("SEARCH_ENGINE_INDEX").unwrap_or(DEFAULT_INDEX.to_owned()),
+            client: client.clone(),
+        })
+    }
+
+    /// The search function runs a text search against the index.
+    pub async fn search(&self, text_search: &TextSearch) -> Result<TextSearchResults, String> {
+        let body = json!({
+            "q": text_search.query,
+            "filter": get_filter(text_search),
+            "facets": ["title", "testament"],
+            "limit": text_search.limit,
+            "offset": text_search.offset,
+        });
+
+        let response = self
+            .request(reqwest::Method::POST, "search")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let mut response: SearchResponse = check_status(response)
+            .await?
+            .json()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        Ok(TextSearchResults {
+            engine: "meilisearch",
+            total: response.estimated_total_hits,
+            // The ids are not stored in the index, so they are numbered here
+            hits: response
+                .hits
+                .into_iter()
+                .map(|hit| TextSearchHit::new(hit.title, hit.chapter, hit.verse, hit.text))
+                .collect(),
+            facets: Facets {
+                book: response
+                    .facet_distribution
+                    .remove("title")
+                    .unwrap_or_default(),
+                testament: response
+                    .facet_distribution
+                    .remove("testament")
+                    .unwrap_or_default(),
+            },
+        })
+    }
+
+    /// The index_translation function pushes every verse of the active
+    /// version of a translation into the index, returning how many were
+    /// sent. The search engine indexes them in the background, and verses
+    /// pushed again replace the ones it has.
+    pub async fn index_translation(
+        &self,
+        pool: &PgPool,
+        translation: &str,
+    ) -> Result<usize, String> {
+        self.configure().await?;
+
+        let verses = sqlx::query!(
+            "
+                SELECT v.title, v.chapter_num as chapter, v.num as verse, v.contents as text
+                FROM verses v
+                JOIN translation_versions t ON t.version = v.version
+                WHERE t.translation = $1 AND t.state = 'active'
+            ",
+            translation
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+
+        let documents = verses
+            .into_iter()
+            .map(|verse| TextSearchHit::new(verse.title, verse.chapter, verse.verse, verse.text))
+            .map(|verse| VerseDocument::new(translation, verse))
+            .collect::<Vec<VerseDocument>>();
+
+        for batch in documents.chunks(INDEX_BATCH_SIZE) {
+            let response = self
+                .request(reqwest::Method::POST, "documents?primaryKey=id")
+                .json(batch)
+                .send()
+                .await
+                .map_err(|err| err.to_string())?;
+            check_status(response).await?;
+        }
+
+        Ok(documents.len())
+    }
+
+    // Only the text is searched, and the verses are filtered and counted by
+    // translation, book and testament
+    async fn configure(&self) -> Result<(), String> {
+        let settings = json!({
+            "searchableAttributes": ["text"],
+            "filterableAttributes": ["translation", "title", "testament"],
+            "typoTolerance": { "enabled": true },
+        });
+
+        let response = self
+            .request(reqwest::Method::PATCH, "settings")
+            .json(&settings)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        check_status(response).await.map(|_| ())
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/indexes/{}/{}", self.url, self.index, path);
+        let request = self.client.request(method, url);
+
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(format!(
+        "search engine failed with status {}: {}",
+        status, body
+    ))
+}
+
+/// The get_document_id function returns the id of a verse in the index (ex:
+/// kjv-1-john-3-16). Ids may only hold letters, digits, dashes and
+/// underscores.
+pub fn get_document_id(translation: &str, title: &str, chapter: i32, verse: i32) -> String {
+    format!(
+        "{}-{}-{}-{}",
+        translation.to_lowercase(),
+        get_book_slug(title),
+        chapter,
+        verse
+    )
+}
+
+// The filter expression for a search (ex: translation = "kjv" AND title =
+// "John")
+fn get_filter(text_search: &TextSearch) -> String {
+    let mut filters = vec![format!("translation = {}", quote(&text_search.translation))];
+
+    if let Some(book) = &text_search.book {
+        filters.push(format!("title = {}", quote(book)));
+    }
+    if let Some(testament) = text_search.testament {
+        filters.push(format!("testament = {}", quote(testament.as_str())));
+    }
+
+    filters.join(" AND ")
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_document_id_uses_only_letters_digits_and_dashes() {
+        assert_eq!(
+            get_document_id("KJV", "Song of Solomon", 2, 1),
+            "kjv-song-of-solomon-2-1"
+        );
+    }
+
+    #[test]
+    fn get_filter_filters_by_translation_book_and_testament() {
+        let text_search = TextSearch {
+            query: String::from("love"),
+            translation: String::from("kjv"),
+            book: Some(String::from("1 John")),
+            testament: Some(Testament::New),
+            within: vec![],
+            limit: 10,
+            offset: 0,
+        };
+
+        assert_eq!(
+            get_filter(&text_search),
+            "translation = \"kjv\" AND title = \"1 John\" AND testament = \"new\""
+        );
+    }
+}
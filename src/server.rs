@@ -0,0 +1,287 @@
+use axum::{extract::ConnectInfo, Router};
+use hyper::{body::Incoming, service::service_fn, Request};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+};
+use std::{str::FromStr, time::Duration};
+use tokio::net::TcpListener;
+use tower::Service;
+
+use crate::privacy::get_client_address;
+
+/// The DEFAULT_MAX_BODY_BYTES is the largest request body read, the same as
+/// axum's own default.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// The DEFAULT_HEADER_READ_TIMEOUT is how long an HTTP/1 client has to send
+/// the headers of a request, including the next one on a kept alive
+/// connection.
+const DEFAULT_HEADER_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The DEFAULT_HTTP2_KEEP_ALIVE_TIMEOUT is how long an HTTP/2 keep alive
+/// ping is waited on before the connection is closed.
+const DEFAULT_HTTP2_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The DEFAULT_DRAIN_TIMEOUT is how long the requests in flight are given
+/// to finish on shutdown.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The ServerConfig tunes how connections are served. Each setting can be
+/// changed with an environment variable.
+/// - http2 (HTTP2) serves HTTP/2 as well as HTTP/1, on by default
+/// - max_concurrent_streams (HTTP2_MAX_CONCURRENT_STREAMS) is how many
+///   requests an HTTP/2 connection can have open at once, hyper's default
+///   when not set
+/// - http2_keep_alive_interval (HTTP2_KEEP_ALIVE_INTERVAL_SECS) is how often
+///   an idle HTTP/2 connection is pinged, never when not set
+/// - http2_keep_alive_timeout (HTTP2_KEEP_ALIVE_TIMEOUT_SECS) is how long a
+///   ping is waited on
+/// - keep_alive (HTTP1_KEEP_ALIVE) keeps HTTP/1 connections open between
+///   requests, on by default
+/// - header_read_timeout (HTTP1_HEADER_READ_TIMEOUT_SECS) is how long an
+///   HTTP/1 connection waits for the headers of its next request
+/// - max_body_bytes (MAX_BODY_BYTES) is the largest request body read
+/// - drain_timeout (SHUTDOWN_DRAIN_TIMEOUT_SECS) is how long the requests in
+///   flight are given to finish on shutdown, before their connections are
+///   dropped
+#[derive(Debug, PartialEq, Clone)]
+pub struct ServerConfig {
+    pub http2: bool,
+    pub max_concurrent_streams: Option<u32>,
+    pub http2_keep_alive_interval: Option<Duration>,
+    pub http2_keep_alive_timeout: Duration,
+    pub keep_alive: bool,
+    pub header_read_timeout: Duration,
+    pub max_body_bytes: usize,
+    pub drain_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            http2: true,
+            max_concurrent_streams: None,
+            http2_keep_alive_interval: None,
+            http2_keep_alive_timeout: DEFAULT_HTTP2_KEEP_ALIVE_TIMEOUT,
+            keep_alive: true,
+            header_read_timeout: DEFAULT_HEADER_READ_TIMEOUT,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    // Read the settings with the lookup given, keeping the default of any
+    // that is not set or cannot be read
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let parse_bool = |key: &str| {
+            var(key).map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1"))
+        };
+        let parse_secs = |key: &str| parse_setting::<u64>(&var, key).map(Duration::from_secs);
+        let defaults = ServerConfig::default();
+
+        ServerConfig {
+            http2: parse_bool("HTTP2").unwrap_or(defaults.http2),
+            max_concurrent_streams: parse_setting(&var, "HTTP2_MAX_CONCURRENT_STREAMS"),
+            http2_keep_alive_interval: parse_secs("HTTP2_KEEP_ALIVE_INTERVAL_SECS"),
+            http2_keep_alive_timeout: parse_secs("HTTP2_KEEP_ALIVE_TIMEOUT_SECS")
+                .unwrap_or(defaults.http2_keep_alive_timeout),
+            keep_alive: parse_bool("HTTP1_KEEP_ALIVE").unwrap_or(defaults.keep_alive),
+            header_read_timeout: parse_secs("HTTP1_HEADER_READ_TIMEOUT_SECS")
+                .unwrap_or(defaults.header_read_timeout),
+            max_body_bytes: parse_setting(&var, "MAX_BODY_BYTES")
+                .unwrap_or(defaults.max_body_bytes),
+            drain_timeout: parse_secs("SHUTDOWN_DRAIN_TIMEOUT_SECS")
+                .unwrap_or(defaults.drain_timeout),
+        }
+    }
+
+    // The connection builder with the settings applied
+    fn get_builder(&self) -> Builder<TokioExecutor> {
+        let mut builder = Builder::new(TokioExecutor::new());
+
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .keep_alive(self.keep_alive)
+            .header_read_timeout(self.header_read_timeout);
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .max_concurrent_streams(self.max_concurrent_streams)
+            .keep_alive_interval(self.http2_keep_alive_interval)
+            .keep_alive_timeout(self.http2_keep_alive_timeout);
+
+        match self.http2 {
+            true => builder,
+            false => builder.http1_only(),
+        }
+    }
+}
+
+fn parse_setting<T: FromStr>(var: &impl Fn(&str) -> Option<String>, key: &str) -> Option<T> {
+    let value = var(key)?;
+    let parsed = value.trim().parse().ok();
+    if parsed.is_none() {
+        tracing::warn!(
+            "{} is not a valid value for {}, using the default",
+            value,
+            key
+        );
+    }
+
+    parsed
+}
+
+/// The serve function serves the app on the listener with the config's
+/// settings, a task for each connection. Each request is given the address
+/// of its client, the way axum::serve does with connect info. Once the
+/// server is asked to stop, no more connections are accepted, and it
+/// returns when the requests in flight have finished, or the drain timeout
+/// has run out. Idle connections are closed straight away.
+pub async fn serve(listener: TcpListener, app: Router, config: ServerConfig) {
+    let builder = config.get_builder();
+    let graceful = GracefulShutdown::new();
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+        let (stream, address) = match accepted {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                tracing::warn!("could not accept a connection: {}", err);
+                continue;
+            }
+        };
+
+        // The router is shared by every connection, and only the request is
+        // given the address, so nothing is rebuilt for a connection
+        let app = app.clone();
+        let service = service_fn(move |mut request: Request<Incoming>| {
+            request.extensions_mut().insert(ConnectInfo(address));
+            app.clone().call(request)
+        });
+        let connection = builder
+            .serve_connection_with_upgrades(TokioIo::new(stream), service)
+            .into_owned();
+        let connection = graceful.watch(connection);
+
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                tracing::debug!(
+                    "connection from {} ended with: {}",
+                    get_client_address(address.ip()),
+                    err
+                );
+            }
+        });
+    }
+
+    drop(listener);
+    tracing::info!("shutting down, draining {} connections", graceful.count());
+    if tokio::time::timeout(config.drain_timeout, graceful.shutdown())
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            "dropping the connections still open after {:?}",
+            config.drain_timeout
+        );
+    }
+}
+
+/// The shutdown_signal function waits until the server is asked to stop,
+/// with Ctrl+C (SIGINT) or, on a deploy, SIGTERM.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::warn!("could not listen for Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                tracing::warn!("could not listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from_vars(vars: &[(&str, &str)]) -> ServerConfig {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        ServerConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn server_config_reads_each_setting() {
+        let config = from_vars(&[
+            ("HTTP2", "false"),
+            ("HTTP2_MAX_CONCURRENT_STREAMS", "500"),
+            ("HTTP2_KEEP_ALIVE_INTERVAL_SECS", "15"),
+            ("HTTP2_KEEP_ALIVE_TIMEOUT_SECS", "5"),
+            ("HTTP1_KEEP_ALIVE", "0"),
+            ("HTTP1_HEADER_READ_TIMEOUT_SECS", "10"),
+            ("MAX_BODY_BYTES", "65536"),
+            ("SHUTDOWN_DRAIN_TIMEOUT_SECS", "5"),
+        ]);
+
+        assert_eq!(
+            config,
+            ServerConfig {
+                http2: false,
+                max_concurrent_streams: Some(500),
+                http2_keep_alive_interval: Some(Duration::from_secs(15)),
+                http2_keep_alive_timeout: Duration::from_secs(5),
+                keep_alive: false,
+                header_read_timeout: Duration::from_secs(10),
+                max_body_bytes: 65536,
+                drain_timeout: Duration::from_secs(5),
+            }
+        );
+    }
+
+    #[test]
+    fn server_config_keeps_the_default_of_a_bad_setting() {
+        let config = from_vars(&[
+            ("MAX_BODY_BYTES", "lots"),
+            ("HTTP2_KEEP_ALIVE_TIMEOUT_SECS", "-1"),
+        ]);
+
+        assert_eq!(config, ServerConfig::default());
+    }
+}
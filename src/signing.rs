@@ -0,0 +1,186 @@
+use axum::{extract::State, http::StatusCode, Json};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::{
+    digest::{digest, SHA256},
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use serde::Serialize;
+use std::{fmt::Write, sync::Arc};
+
+use crate::{db::SearchResult, error::BibleApiError};
+
+/// The SIGNATURE_HEADER holds the base64 Ed25519 signature of a signed
+/// response's passage (see get_signed_payload).
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// The SIGNATURE_KEY_HEADER holds the id of the key a response was signed
+/// with, as listed at /signing-key.
+pub const SIGNATURE_KEY_HEADER: &str = "x-signature-key";
+
+/// The Signer signs passages so that apps keeping them offline can check
+/// they were not changed since they were served. It is only set up when
+/// SIGNING_KEY is set.
+#[derive(Debug, Clone)]
+pub struct Signer {
+    key_pair: Arc<Ed25519KeyPair>,
+    key_id: String,
+}
+
+/// The SigningKey is the public half of the signing key, for checking
+/// signatures.
+/// - key_id is sent with each signature, so a key can be rotated
+/// - algorithm is always Ed25519
+/// - public_key is the raw 32 byte public key in base64
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SigningKey {
+    pub key_id: String,
+    pub algorithm: &'static str,
+    pub public_key: String,
+}
+
+impl Signer {
+    /// The key is read from SIGNING_KEY, a base64 Ed25519 seed of 32 bytes
+    /// (ex: from openssl rand -base64 32). Returns None when it is not set,
+    /// or with a warning when it can not be read, as signing is optional.
+    pub fn from_env() -> Option<Self> {
+        let seed = std::env::var("SIGNING_KEY").ok()?;
+
+        Signer::from_seed(&seed)
+            .map_err(|err| tracing::warn!("signing is off: {}", err))
+            .ok()
+    }
+
+    fn from_seed(seed: &str) -> Result<Self, String> {
+        let seed = STANDARD
+            .decode(seed.trim())
+            .map_err(|err| format!("SIGNING_KEY is not base64: {}", err))?;
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&seed)
+            .map_err(|err| format!("SIGNING_KEY is not an Ed25519 seed: {}", err))?;
+
+        // The id is the start of the public key's digest, in hex
+        let key_id = digest(&SHA256, key_pair.public_key().as_ref()).as_ref()[..8]
+            .iter()
+            .fold(String::new(), |mut key_id, byte| {
+                let _ = write!(key_id, "{:02x}", byte);
+                key_id
+            });
+
+        Ok(Signer {
+            key_pair: Arc::new(key_pair),
+            key_id,
+        })
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// The sign function returns the base64 signature of the passage of a
+    /// translation.
+    pub fn sign(&self, translation: &str, results: &[SearchResult]) -> String {
+        let payload = get_signed_payload(translation, results);
+
+        STANDARD.encode(self.key_pair.sign(payload.as_bytes()))
+    }
+
+    pub fn signing_key(&self) -> SigningKey {
+        SigningKey {
+            key_id: self.key_id.clone(),
+            algorithm: "Ed25519",
+            public_key: STANDARD.encode(self.key_pair.public_key()),
+        }
+    }
+}
+
+/// The get_signed_payload function returns the canonical form of a passage
+/// that is signed, so a signature can be checked against the verses however
+/// they were stored. It is the translation (ex: kjv) on the first line,
+/// followed by a line for each verse in order of the book title, the
+/// chapter and verse, and the text, separated by tabs (ex: John\t3\t16\tFor
+/// God so loved ...). Every line ends with a newline.
+pub fn get_signed_payload(translation: &str, results: &[SearchResult]) -> String {
+    results
+        .iter()
+        .fold(format!("{}\n", translation), |mut payload, result| {
+            let _ = writeln!(
+                payload,
+                "{}\t{}\t{}\t{}",
+                result.title, result.chapter, result.verse, result.text
+            );
+            payload
+        })
+}
+
+/// The signing_key handler serves /signing-key with the public key that
+/// signed responses can be checked with.
+pub async fn signing_key(
+    State(signer): State<Option<Signer>>,
+) -> Result<Json<SigningKey>, BibleApiError> {
+    signer
+        .map(|signer| Json(signer.signing_key()))
+        .ok_or(BibleApiError::from((
+            StatusCode::NOT_FOUND,
+            "Signing Is Not Enabled".to_string(),
+        )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{UnparsedPublicKey, ED25519};
+
+    const SEED: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+    fn get_results() -> Vec<SearchResult> {
+        vec![
+            SearchResult::new(
+                "kjv",
+                "John".to_owned(),
+                3,
+                16,
+                "For God so loved".to_owned(),
+                true,
+            ),
+            SearchResult::new(
+                "kjv",
+                "John".to_owned(),
+                3,
+                17,
+                "For God sent not".to_owned(),
+                false,
+            ),
+        ]
+    }
+
+    #[test]
+    fn get_signed_payload_has_a_line_for_each_verse() {
+        assert_eq!(
+            get_signed_payload("kjv", &get_results()),
+            "kjv\nJohn\t3\t16\tFor God so loved\nJohn\t3\t17\tFor God sent not\n"
+        );
+    }
+
+    #[test]
+    fn sign_can_be_checked_with_the_signing_key() {
+        let signer = Signer::from_seed(SEED).unwrap();
+        let signing_key = signer.signing_key();
+        let signature = STANDARD.decode(signer.sign("kjv", &get_results())).unwrap();
+        let public_key =
+            UnparsedPublicKey::new(&ED25519, STANDARD.decode(signing_key.public_key).unwrap());
+        let payload = get_signed_payload("kjv", &get_results());
+
+        assert_eq!(signing_key.key_id.len(), 16);
+        assert!(public_key.verify(payload.as_bytes(), &signature).is_ok());
+
+        let mut changed = get_results();
+        changed[1].text.push('!');
+        let changed = get_signed_payload("kjv", &changed);
+        assert!(public_key.verify(changed.as_bytes(), &signature).is_err());
+    }
+
+    #[test]
+    fn from_seed_rejects_a_bad_key() {
+        assert!(Signer::from_seed("not base64!").is_err());
+        assert!(Signer::from_seed("AAEC").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::{
+    chapter::{get_books, get_chapter_count_by_book},
+    error::BibleApiError,
+    links::{get_href, SearchKind},
+    render::escape,
+};
+
+/// The DEFAULT_SITE_URL is used to build the canonical passage URLs when the
+/// SITE_URL environment variable is not set.
+const DEFAULT_SITE_URL: &str = "http://127.0.0.1:3000";
+
+/// The sitemap_index handler serves /sitemap.xml. The sitemap is sharded by
+/// book, so the index only lists the location of each book's shard.
+pub async fn sitemap_index() -> impl IntoResponse {
+    xml_response(build_sitemap_index(&get_site_url()))
+}
+
+/// The sitemap_shard handler serves /sitemaps/:shard (ex: /sitemaps/1-john.xml)
+/// and lists the canonical URL of every chapter in that book.
+pub async fn sitemap_shard(Path(shard): Path<String>) -> Result<impl IntoResponse, BibleApiError> {
+    let book = shard
+        .strip_suffix(".xml")
+        .and_then(get_book_by_slug)
+        .ok_or_else(|| shard_not_found(&shard))?;
+
+    let sitemap =
+        build_book_sitemap(&get_site_url(), book).ok_or_else(|| shard_not_found(&shard))?;
+
+    Ok(xml_response(sitemap))
+}
+
+fn shard_not_found(shard: &str) -> BibleApiError {
+    BibleApiError::from((
+        StatusCode::NOT_FOUND,
+        format!("No Matching Sitemap Found: {}", shard),
+    ))
+}
+
+fn xml_response(body: String) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        body,
+    )
+}
+
+fn get_site_url() -> String {
+    std::env::var("SITE_URL")
+        .unwrap_or_else(|_| DEFAULT_SITE_URL.to_owned())
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+/// The get_book_slug function turns a book title into the lowercase, dash
+/// separated form used for shard names (ex: Song of Solomon -> song-of-solomon).
+pub fn get_book_slug(book: &str) -> String {
+    book.to_lowercase().replace(' ', "-")
+}
+
+fn get_book_by_slug(slug: &str) -> Option<&'static str> {
+    get_books()
+        .into_iter()
+        .find(|book| get_book_slug(book) == slug)
+}
+
+/// The get_chapter_url function returns the canonical URL for a chapter page,
+/// which is the search for the chapter (ex: /search?query=1+John+5).
+pub fn get_chapter_url(site_url: &str, book: &str, chapter: u8) -> String {
+    format!(
+        "{}{}",
+        site_url,
+        get_href(SearchKind::Reference, &format!("{} {}", book, chapter))
+    )
+}
+
+fn build_sitemap_index(site_url: &str) -> String {
+    let entries: String = get_books()
+        .iter()
+        .map(|book| {
+            format!(
+                "  <sitemap><loc>{}/sitemaps/{}.xml</loc></sitemap>\n",
+                escape(site_url),
+                get_book_slug(book)
+            )
+        })
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n\
+         {}</sitemapindex>\n",
+        entries
+    )
+}
+
+fn build_book_sitemap(site_url: &str, book: &str) -> Option<String> {
+    let num_chapters = get_chapter_count_by_book(book)?;
+
+    let entries: String = (1..=num_chapters)
+        .map(|chapter| {
+            format!(
+                "  <url><loc>{}</loc></url>\n",
+                escape(&get_chapter_url(site_url, book, chapter))
+            )
+        })
+        .collect();
+
+    Some(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n\
+         {}</urlset>\n",
+        entries
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_book_slug_lowercases_and_dashes_the_title() {
+        assert_eq!(get_book_slug("Song of Solomon"), "song-of-solomon");
+        assert_eq!(get_book_slug("1 John"), "1-john");
+    }
+
+    #[test]
+    fn get_book_by_slug_returns_none_for_unknown_slug() {
+        assert_eq!(get_book_by_slug("book-of-robert"), None);
+    }
+
+    #[test]
+    fn build_sitemap_index_lists_a_shard_per_book() {
+        let index = build_sitemap_index("https://example.com");
+
+        assert_eq!(index.matches("<sitemap>").count(), 66);
+        assert!(index.contains("<loc>https://example.com/sitemaps/1-john.xml</loc>"));
+    }
+
+    #[test]
+    fn build_book_sitemap_lists_every_chapter() {
+        let sitemap = build_book_sitemap("https://example.com", "1 John").unwrap();
+
+        assert_eq!(sitemap.matches("<url>").count(), 5);
+        assert!(sitemap.contains("<loc>https://example.com/search?query=1+John+5</loc>"));
+    }
+
+    #[test]
+    fn sitemaps_escape_the_site_url() {
+        let site_url = "https://example.com/r&d";
+
+        assert!(build_sitemap_index(site_url)
+            .contains("<loc>https://example.com/r&amp;d/sitemaps/john.xml</loc>"));
+        assert!(build_book_sitemap(site_url, "John")
+            .unwrap()
+            .contains("<loc>https://example.com/r&amp;d/search?query=John+3</loc>"));
+    }
+}
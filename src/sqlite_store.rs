@@ -0,0 +1,258 @@
+use axum::async_trait;
+use futures::{StreamExt, TryStreamExt};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow},
+    Row,
+};
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    db::{get_default_translation, get_verses, SearchOptions, SearchResult, STREAM_BUFFER},
+    error::BibleApiError,
+    internal_error,
+    search::BibleSearch,
+    store::VerseStore,
+};
+
+// The verses of a book wanted, given as a JSON list of [chapter, verse]
+// pairs, in the order they are read
+const SEARCH_QUERY: &str = r#"
+    SELECT
+        v.chapterNumber AS chapter,
+        v.number AS verse,
+        v.text AS text
+    FROM json_each(?2) AS w
+        INNER JOIN verses v ON v.bookName = ?1
+            AND v.chapterNumber = json_extract(w.value, '$[0]')
+            AND v.number = json_extract(w.value, '$[1]')
+    ORDER BY v.chapterNumber, v.number
+"#;
+
+/// The SqliteStore reads the verses of the default translation from a SQLite
+/// file laid out like db/bible.db, a verses table with the number, text,
+/// chapterNumber and bookName of each verse. The file holds no other
+/// translation, no formatted text and no paragraphs, and superscriptions
+/// only where it has a verse 0.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// The connect function opens the SQLite file of the database url (ex:
+    /// sqlite:db/bible.db), read only.
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(database_url)?.read_only(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+
+        Ok(SqliteStore { pool })
+    }
+}
+
+#[async_trait]
+impl VerseStore for SqliteStore {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn ping(&self) -> Result<(), BibleApiError> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn is_active(&self, translation: &str) -> Result<bool, BibleApiError> {
+        Ok(translation == get_default_translation())
+    }
+
+    async fn search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> Result<Vec<SearchResult>, BibleApiError> {
+        self.stream_search(translation, bible_search, options)
+            .try_collect()
+            .await
+            .map_err(internal_error)
+    }
+
+    // The file is local, so a statement per search costs next to nothing
+    async fn search_many(
+        &self,
+        translation: &str,
+        searches: &[(BibleSearch, SearchOptions)],
+    ) -> Result<Vec<Vec<SearchResult>>, BibleApiError> {
+        let mut results = Vec::with_capacity(searches.len());
+        for (bible_search, options) in searches {
+            results.push(
+                self.search(translation, bible_search.clone(), *options)
+                    .await?,
+            );
+        }
+
+        Ok(results)
+    }
+
+    fn stream_search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> ReceiverStream<Result<SearchResult, sqlx::Error>> {
+        let (pool, translation) = (self.pool.clone(), translation.to_owned());
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+
+        tokio::spawn(async move {
+            // Any other translation has no verses
+            if translation != get_default_translation() {
+                return;
+            }
+
+            let wanted = get_wanted_verses(&bible_search, options.superscription);
+            let mut rows = sqlx::query(SEARCH_QUERY)
+                .bind(&bible_search.title)
+                .bind(wanted)
+                .fetch(&pool);
+
+            // Stop fetching as soon as the receiving side has gone away
+            while let Some(row) = rows.next().await {
+                let verse =
+                    row.and_then(|row| get_search_result(&translation, &bible_search.title, &row));
+                if sender.send(verse).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        ReceiverStream::new(receiver)
+    }
+}
+
+// The verses of a search as the JSON list of [chapter, verse] pairs the
+// query reads
+fn get_wanted_verses(bible_search: &BibleSearch, superscription: bool) -> String {
+    let verses = get_verses(bible_search, superscription)
+        .into_iter()
+        .map(|(chapter, verse)| format!("[{},{}]", chapter, verse))
+        .collect::<Vec<String>>();
+
+    format!("[{}]", verses.join(","))
+}
+
+fn get_search_result(
+    translation: &str,
+    title: &str,
+    row: &SqliteRow,
+) -> Result<SearchResult, sqlx::Error> {
+    Ok(SearchResult::new(
+        translation,
+        title.to_owned(),
+        row.try_get("chapter")?,
+        row.try_get("verse")?,
+        row.try_get("text")?,
+        false,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::TextFormat, search::search};
+
+    const PLAIN: SearchOptions = SearchOptions {
+        superscription: false,
+        format: TextFormat::Plain,
+    };
+
+    // A store of a few verses, kept in memory on a single connection
+    async fn get_store() -> SqliteStore {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+                CREATE TABLE "verses" (
+                    "number" INTEGER NOT NULL,
+                    "text" TEXT NOT NULL,
+                    "chapterNumber" INTEGER NOT NULL,
+                    "bookName" TEXT NOT NULL
+                );
+                INSERT INTO verses VALUES
+                    (16, 'For God so loved the world', 3, 'John'),
+                    (17, 'For God sent not his Son', 3, 'John'),
+                    (18, 'He that believeth on him', 3, 'John'),
+                    (1, 'The LORD is my shepherd', 23, 'Psalms');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        SqliteStore { pool }
+    }
+
+    #[tokio::test]
+    async fn search_reads_the_verses_of_a_search_in_order() {
+        let store = get_store().await;
+        let verses = store
+            .search(
+                get_default_translation(),
+                search("John 3:17-18,16").unwrap(),
+                PLAIN,
+            )
+            .await
+            .unwrap();
+
+        let read = verses
+            .iter()
+            .map(|verse| (verse.title.as_str(), verse.chapter, verse.verse))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            read,
+            vec![("John", 3, 16), ("John", 3, 17), ("John", 3, 18)]
+        );
+        assert_eq!(verses[0].text, "For God so loved the world");
+        assert_eq!(verses[0].translation, get_default_translation());
+    }
+
+    #[tokio::test]
+    async fn search_many_keeps_the_order_of_the_searches() {
+        let store = get_store().await;
+        let searches = [
+            (search("Psalm 23:1").unwrap(), PLAIN),
+            (search("John 3:16").unwrap(), PLAIN),
+        ];
+
+        let verses = store
+            .search_many(get_default_translation(), &searches)
+            .await
+            .unwrap();
+        assert_eq!(verses.len(), 2);
+        assert_eq!(verses[0][0].text, "The LORD is my shepherd");
+        assert_eq!(verses[1][0].text, "For God so loved the world");
+    }
+
+    #[tokio::test]
+    async fn other_translations_have_no_verses() {
+        let store = get_store().await;
+
+        assert!(!store.is_active("not-a-translation").await.unwrap());
+        let verses = store
+            .search("not-a-translation", search("John 3:16").unwrap(), PLAIN)
+            .await
+            .unwrap();
+        assert!(verses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn ping_fails_once_the_file_is_closed() {
+        let store = get_store().await;
+        assert!(store.ping().await.is_ok());
+
+        store.pool.close().await;
+        assert!(store.ping().await.is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use axum::extract::FromRef;
+use sqlx::postgres::PgPool;
+
+use crate::{
+    auth::ApiKeys, breaker::CircuitBreaker, cache_control::CachePolicy, cdn::CdnConfig,
+    oauth::OAuth, popularity::Popularity, rate_limit::RateLimiter, reindex::ReindexJob,
+    search_engine::SearchEngine, signing::Signer, stats::UsageStats, trending::Trending,
+    versification::Versifications,
+};
+
+/// The AppState is shared by every handler. Handlers that only need part of
+/// it (ex: the pool) can extract that part directly thanks to FromRef. The
+/// http client is shared too, so its connections are reused by every call
+/// out (ex: to the CDN or an audio file).
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: PgPool,
+    pub cdn: Option<CdnConfig>,
+    pub api_keys: ApiKeys,
+    pub rate_limiter: RateLimiter,
+    pub reindex: ReindexJob,
+    pub popularity: Popularity,
+    pub trending: Trending,
+    pub stats: UsageStats,
+    pub versifications: Versifications,
+    pub breaker: CircuitBreaker,
+    pub cache_policy: CachePolicy,
+    pub search_engine: Option<SearchEngine>,
+    pub signer: Option<Signer>,
+    pub oauth: Option<OAuth>,
+    pub http: reqwest::Client,
+    #[cfg(feature = "tantivy")]
+    pub offline_index: Option<crate::offline_index::OfflineIndex>,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> PgPool {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for ApiKeys {
+    fn from_ref(state: &AppState) -> ApiKeys {
+        state.api_keys.clone()
+    }
+}
+
+impl FromRef<AppState> for Popularity {
+    fn from_ref(state: &AppState) -> Popularity {
+        state.popularity.clone()
+    }
+}
+
+impl FromRef<AppState> for Trending {
+    fn from_ref(state: &AppState) -> Trending {
+        state.trending.clone()
+    }
+}
+
+impl FromRef<AppState> for UsageStats {
+    fn from_ref(state: &AppState) -> UsageStats {
+        state.stats.clone()
+    }
+}
+
+impl FromRef<AppState> for Versifications {
+    fn from_ref(state: &AppState) -> Versifications {
+        state.versifications.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<Signer> {
+    fn from_ref(state: &AppState) -> Option<Signer> {
+        state.signer.clone()
+    }
+}
+
+impl FromRef<AppState> for CircuitBreaker {
+    fn from_ref(state: &AppState) -> CircuitBreaker {
+        state.breaker.clone()
+    }
+}
@@ -0,0 +1,119 @@
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use crate::render::Format;
+
+/// The UsageCounts are what the usage stats keep: how many searches were
+/// answered and missed, and how many were answered in each format and
+/// translation. No query, client or verse is ever recorded.
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct UsageCounts {
+    pub queries: u64,
+    pub not_found: u64,
+    pub formats: BTreeMap<&'static str, u64>,
+    pub translations: BTreeMap<String, u64>,
+}
+
+/// The UsageReport is the usage counts along with when counting started.
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub since: String,
+    #[serde(flatten)]
+    pub counts: UsageCounts,
+}
+
+/// The UsageStats count how the API is used, in memory, for the operator to
+/// look at through GET /admin/stats. They are off unless USAGE_STATS is set
+/// to true, and are never sent anywhere.
+#[derive(Clone, Default)]
+pub struct UsageStats {
+    counts: Option<Arc<Mutex<UsageCounts>>>,
+    since: Option<SystemTime>,
+}
+
+impl UsageStats {
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("USAGE_STATS")
+            .map(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "1"))
+            .unwrap_or(false);
+
+        match enabled {
+            true => UsageStats::enabled(),
+            false => UsageStats::default(),
+        }
+    }
+
+    fn enabled() -> Self {
+        UsageStats {
+            counts: Some(Arc::default()),
+            since: Some(SystemTime::now()),
+        }
+    }
+
+    /// The record_query function counts a search that was answered.
+    pub fn record_query(&self, format: Format, translation: &str) {
+        self.update(|counts| {
+            counts.queries += 1;
+            *counts.formats.entry(format.name()).or_insert(0) += 1;
+            *counts
+                .translations
+                .entry(translation.to_lowercase())
+                .or_insert(0) += 1;
+        });
+    }
+
+    /// The record_not_found function counts a search that matched nothing.
+    pub fn record_not_found(&self) {
+        self.update(|counts| counts.not_found += 1);
+    }
+
+    /// The report function returns the counts so far, or None when the usage
+    /// stats are off.
+    pub fn report(&self) -> Option<UsageReport> {
+        Some(UsageReport {
+            since: httpdate::fmt_http_date(self.since?),
+            counts: self.counts.as_ref()?.lock().unwrap().clone(),
+        })
+    }
+
+    fn update(&self, change: impl FnOnce(&mut UsageCounts)) {
+        if let Some(counts) = &self.counts {
+            change(&mut counts.lock().unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_stats_count_nothing_when_off() {
+        let stats = UsageStats::default();
+        stats.record_query(Format::Json, "kjv");
+        stats.record_not_found();
+
+        assert!(stats.report().is_none());
+    }
+
+    #[test]
+    fn usage_stats_count_queries_by_format_and_translation() {
+        let stats = UsageStats::enabled();
+        stats.record_query(Format::Json, "kjv");
+        stats.record_query(Format::Text, "KJV");
+        stats.record_not_found();
+
+        let counts = stats.report().unwrap().counts;
+        assert_eq!(counts.queries, 2);
+        assert_eq!(counts.not_found, 1);
+        assert_eq!(counts.formats, BTreeMap::from([("json", 1), ("text", 1)]));
+        assert_eq!(
+            counts.translations,
+            BTreeMap::from([(String::from("kjv"), 2)])
+        );
+    }
+}
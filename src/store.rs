@@ -0,0 +1,180 @@
+use axum::async_trait;
+use sqlx::postgres::PgPool;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    db::{self, SearchOptions, SearchResult},
+    error::BibleApiError,
+    search::BibleSearch,
+    versions,
+};
+
+type Fetched<T> = Result<T, BibleApiError>;
+
+/// The VerseStore is where the verses of the translations are read from.
+/// Postgres holds every translation, along with everything else the service
+/// keeps (ex: the API keys). SQLite, with the sqlite feature, holds only the
+/// verses of the default translation, for small deployments that would
+/// rather not run Postgres. With the embedded feature the KJV is built into
+/// the binary and read from memory, so no database is needed at all. Which
+/// one is used is picked by DATABASE_URL (see Backend).
+#[async_trait]
+pub trait VerseStore: Send + Sync {
+    /// The name function returns the name of the store (ex: postgres).
+    fn name(&self) -> &'static str;
+
+    /// The ping function makes the cheapest round trip the store has, so the
+    /// readiness probe can tell whether verses can be read from it.
+    async fn ping(&self) -> Result<(), BibleApiError>;
+
+    /// The is_active function returns true when the store has a version of
+    /// the translation that can be read.
+    async fn is_active(&self, translation: &str) -> Result<bool, BibleApiError>;
+
+    /// The search function fetches the verses of a search from a
+    /// translation. A translation the store does not have has no verses.
+    async fn search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> Fetched<Vec<SearchResult>>;
+
+    /// The search_many function fetches the verses of several searches from
+    /// a translation, in the order the searches were given.
+    async fn search_many(
+        &self,
+        translation: &str,
+        searches: &[(BibleSearch, SearchOptions)],
+    ) -> Fetched<Vec<Vec<SearchResult>>>;
+
+    /// The stream_search function fetches the verses of a search one at a
+    /// time, so they can be written to the response as they arrive.
+    fn stream_search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> ReceiverStream<Result<SearchResult, sqlx::Error>>;
+}
+
+/// The Backend is the store the verses are read from, picked by the scheme
+/// of the database url.
+/// - Postgres (postgres://...) keeps everything the service needs
+/// - Sqlite (sqlite:...) is a SQLite file of the verses, with the sqlite
+///   feature
+/// - Embedded (embedded:, or no url at all) is the KJV built into the
+///   binary, with the embedded feature
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Backend {
+    Postgres,
+    Sqlite,
+    Embedded,
+}
+
+impl Backend {
+    /// The from_url function returns the backend of a database url. Without
+    /// a url the text built in is served, if the binary has one.
+    pub fn from_url(database_url: Option<&str>) -> Self {
+        match database_url.map(str::trim_start) {
+            Some(url) if url.starts_with("sqlite:") => Backend::Sqlite,
+            Some(url) if url.starts_with("embedded:") => Backend::Embedded,
+            Some(_) => Backend::Postgres,
+            None if cfg!(feature = "embedded") => Backend::Embedded,
+            None => Backend::Postgres,
+        }
+    }
+
+    /// The name function returns the name of the backend, which is also the
+    /// name of the feature it needs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Postgres => "postgres",
+            Backend::Sqlite => "sqlite",
+            Backend::Embedded => "embedded",
+        }
+    }
+}
+
+/// The PostgresStore reads the verses of the active version of each
+/// translation from Postgres.
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+impl PostgresStore {
+    pub fn new(pool: PgPool) -> Self {
+        PostgresStore { pool }
+    }
+}
+
+#[async_trait]
+impl VerseStore for PostgresStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn ping(&self) -> Result<(), BibleApiError> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn is_active(&self, translation: &str) -> Result<bool, BibleApiError> {
+        versions::is_active(&self.pool, translation).await
+    }
+
+    async fn search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> Fetched<Vec<SearchResult>> {
+        db::search(self.pool.clone(), translation, bible_search, options).await
+    }
+
+    async fn search_many(
+        &self,
+        translation: &str,
+        searches: &[(BibleSearch, SearchOptions)],
+    ) -> Fetched<Vec<Vec<SearchResult>>> {
+        db::search_many(self.pool.clone(), translation, searches).await
+    }
+
+    fn stream_search(
+        &self,
+        translation: &str,
+        bible_search: BibleSearch,
+        options: SearchOptions,
+    ) -> ReceiverStream<Result<SearchResult, sqlx::Error>> {
+        db::stream_search(self.pool.clone(), translation, bible_search, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_goes_by_the_scheme_of_the_url() {
+        let backend = |url| Backend::from_url(Some(url));
+
+        assert_eq!(backend("sqlite:db/bible.db"), Backend::Sqlite);
+        assert_eq!(backend("sqlite://db/bible.db?mode=ro"), Backend::Sqlite);
+        assert_eq!(backend("embedded:"), Backend::Embedded);
+        assert_eq!(
+            backend("postgres://postgres@localhost/bible"),
+            Backend::Postgres
+        );
+        assert_eq!(backend("postgresql://localhost/sqlite"), Backend::Postgres);
+    }
+
+    #[test]
+    fn backend_without_a_url_is_the_text_built_in_when_there_is_one() {
+        let expected = match cfg!(feature = "embedded") {
+            true => Backend::Embedded,
+            false => Backend::Postgres,
+        };
+
+        assert_eq!(Backend::from_url(None), expected);
+    }
+}
@@ -0,0 +1,490 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use std::{collections::BTreeMap, fmt::Write, str::FromStr};
+
+use crate::{
+    book,
+    breaker::get_degraded_headers,
+    chapter::{get_books, get_books_in_testament, get_testament, Testament},
+    db::get_default_translation,
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error,
+    rate_limit::VerseCount,
+    state::AppState,
+    validation::{check_text, MAX_TEXT_QUERY_LEN},
+    verse_id::VerseIds,
+};
+
+/// The DEFAULT_LIMIT and MAX_LIMIT bound how many verses a text search
+/// returns at once.
+const DEFAULT_LIMIT: u8 = 20;
+const MAX_LIMIT: u8 = 100;
+
+/// The MAX_QUERIES is the most searches a search can be refined through,
+/// counting the search itself.
+const MAX_QUERIES: usize = 8;
+
+#[derive(Debug, Deserialize)]
+pub struct TextSearchParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub q: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub book: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub testament: Option<Testament>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub limit: Option<u8>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub offset: Option<u32>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub within: Option<Scope>,
+}
+
+/// The Scope is what a text search was narrowed to: the words of every
+/// search it was refined through, and the book and testament. It is handed
+/// out as an opaque token with the results, so that the next search can be
+/// run within them (ex: q=faith, then q=works&within=<token>).
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Scope {
+    pub queries: Vec<String>,
+    pub book: Option<String>,
+    pub testament: Option<Testament>,
+}
+
+impl Scope {
+    /// The to_token function writes the scope as a token that can be passed
+    /// back in a URL.
+    pub fn to_token(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+
+        json.bytes().fold(String::new(), |mut token, byte| {
+            let _ = write!(token, "{:02x}", byte);
+            token
+        })
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let invalid = || "Invalid within: pass back the within of earlier results".to_string();
+
+        let bytes = (0..token.len())
+            .step_by(2)
+            .map(|index| {
+                token
+                    .get(index..index + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+/// The TextSearch is a search for verses by the words in them, optionally
+/// narrowed to a book or a testament. Within are the words of the earlier
+/// searches it refines, which every verse has to match as well.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextSearch {
+    pub query: String,
+    pub translation: String,
+    pub book: Option<String>,
+    pub testament: Option<Testament>,
+    pub within: Vec<String>,
+    pub limit: u8,
+    pub offset: u32,
+}
+
+impl TextSearch {
+    /// The from_params function checks the params of a text search, turning
+    /// the book given into its title (ex: jn is John). A search within the
+    /// results of another keeps its scope, and can leave out the q to only
+    /// narrow the book or testament. The book and testament can narrow the
+    /// scope but not widen it.
+    pub fn from_params(params: TextSearchParams) -> Result<Self, BibleApiError> {
+        let mut within = params.within.unwrap_or_default();
+
+        let query = params
+            .q
+            .or_else(|| within.queries.pop())
+            .ok_or(BibleApiError::from((
+                StatusCode::BAD_REQUEST,
+                "The q param is required (ex: q=love one another)".to_string(),
+            )))?;
+        if within.queries.len() >= MAX_QUERIES {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("A search can only be refined {} times", MAX_QUERIES - 1),
+            )
+                .into());
+        }
+
+        let book = match params.book {
+            Some(book) => Some(book::get_title(&book).ok_or(BibleApiError::from((
+                StatusCode::BAD_REQUEST,
+                format!("Unknown book: {}", book),
+            )))?),
+            None => None,
+        };
+        let outside = |narrowed: &str| {
+            BibleApiError::from((
+                StatusCode::BAD_REQUEST,
+                format!("{} is outside the results being searched", narrowed),
+            ))
+        };
+        let book = match (within.book, book) {
+            (Some(within), Some(book)) if within != book => return Err(outside(&book)),
+            (within, book) => book.or(within),
+        };
+        let testament = match (within.testament, params.testament) {
+            (Some(within), Some(testament)) if within != testament => {
+                return Err(outside(testament.as_str()))
+            }
+            (within, testament) => testament.or(within),
+        };
+
+        Ok(TextSearch {
+            query,
+            translation: get_default_translation().to_owned(),
+            book,
+            testament,
+            within: within.queries,
+            limit: params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            offset: params.offset.unwrap_or(0),
+        })
+    }
+
+    /// The get_scope function returns the scope of the results of the
+    /// search, to search within them.
+    pub fn get_scope(&self) -> Scope {
+        Scope {
+            queries: self.within.iter().chain([&self.query]).cloned().collect(),
+            book: self.book.clone(),
+            testament: self.testament,
+        }
+    }
+
+    // The books the search is narrowed to, which is none at all when the book
+    // is not in the testament
+    fn get_books(&self) -> Vec<String> {
+        let books = match self.testament {
+            Some(testament) => get_books_in_testament(testament),
+            None => get_books(),
+        };
+
+        books
+            .iter()
+            .filter(|book| self.book.as_deref().is_none_or(|wanted| wanted == **book))
+            .map(|book| book.to_string())
+            .collect()
+    }
+}
+
+/// The TextSearchHit is a verse that matched a text search.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct TextSearchHit {
+    #[serde(flatten)]
+    #[cfg_attr(feature = "graphql", graphql(flatten))]
+    pub ids: VerseIds,
+    pub title: String,
+    pub chapter: i32,
+    pub verse: i32,
+    pub text: String,
+}
+
+impl TextSearchHit {
+    /// The new function makes a hit, with the ids of its verse.
+    pub fn new(title: String, chapter: i32, verse: i32, text: String) -> Self {
+        TextSearchHit {
+            ids: VerseIds::new(&title, chapter, verse),
+            title,
+            chapter,
+            verse,
+            text,
+        }
+    }
+}
+
+/// The Facets count the verses that matched by book and by testament.
+#[derive(Debug, PartialEq, Default, Serialize)]
+pub struct Facets {
+    pub book: BTreeMap<String, u64>,
+    pub testament: BTreeMap<String, u64>,
+}
+
+/// The TextSearchResults are a page of the verses that matched a text
+/// search, how many matched in all, and which engine answered.
+#[derive(Debug, Serialize)]
+pub struct TextSearchResults {
+    pub engine: &'static str,
+    pub total: u64,
+    pub hits: Vec<TextSearchHit>,
+    pub facets: Facets,
+}
+
+// The TextSearchResults with the token to search within them
+#[derive(Debug, Serialize)]
+struct RefinableResults {
+    #[serde(flatten)]
+    results: TextSearchResults,
+    within: String,
+}
+
+/// The text_search handler serves /search/text (ex: /search/text?q=charity
+/// &testament=new) with the verses that have the words searched for. It
+/// queries the search engine when one is configured and the Postgres full
+/// text search otherwise, or when the search engine fails. The search engine
+/// is passed over for a search within earlier results, which it can not
+/// narrow to. Every response has the within to refine it further. While the
+/// database is down, the offline index answers if there is one, and the
+/// response is flagged with the degraded header.
+pub async fn text_search(
+    State(state): State<AppState>,
+    Query(params): Query<TextSearchParams>,
+) -> Result<Response, Response> {
+    if let Some(q) = &params.q {
+        check_text("q", q, MAX_TEXT_QUERY_LEN).map_err(IntoResponse::into_response)?;
+    }
+    let text_search = TextSearch::from_params(params).map_err(IntoResponse::into_response)?;
+
+    let (results, degraded) = run(&state, &text_search)
+        .await
+        .map_err(|err| internal_error(err).into_response())?;
+
+    let verse_count = Extension(VerseCount(results.hits.len()));
+    let degraded = get_degraded_headers(degraded);
+    let results = RefinableResults {
+        results,
+        within: text_search.get_scope().to_token(),
+    };
+    Ok((verse_count, degraded, Json(results)).into_response())
+}
+
+/// The run function answers a text search from the search engine, Postgres
+/// or the offline index, as described for the text_search handler. The flag
+/// returned is true when the offline index answered.
+pub async fn run(
+    state: &AppState,
+    text_search: &TextSearch,
+) -> Result<(TextSearchResults, bool), sqlx::Error> {
+    let engine_results = match &state.search_engine {
+        Some(engine) if text_search.within.is_empty() => match engine.search(text_search).await {
+            Ok(results) => Some(results),
+            Err(err) => {
+                tracing::warn!("search engine failed, using postgres: {}", err);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    match engine_results {
+        Some(results) => Ok((results, false)),
+        None if !state.breaker.is_closed() => match search_offline(state, text_search) {
+            Some(results) => Ok((results, true)),
+            None => Ok((search_postgres(&state.pool, text_search).await?, false)),
+        },
+        None => match search_postgres(&state.pool, text_search).await {
+            Ok(results) => Ok((results, false)),
+            Err(err) => search_offline(state, text_search)
+                .map(|results| (results, true))
+                .ok_or(err),
+        },
+    }
+}
+
+#[cfg(feature = "tantivy")]
+fn search_offline(state: &AppState, text_search: &TextSearch) -> Option<TextSearchResults> {
+    let results = state.offline_index.as_ref()?.search(text_search);
+
+    results
+        .map_err(|err| tracing::warn!("offline index failed: {}", err))
+        .ok()
+}
+
+#[cfg(not(feature = "tantivy"))]
+fn search_offline(_state: &AppState, _text_search: &TextSearch) -> Option<TextSearchResults> {
+    None
+}
+
+async fn search_postgres(
+    pool: &PgPool,
+    text_search: &TextSearch,
+) -> Result<TextSearchResults, sqlx::Error> {
+    let books = text_search.get_books();
+
+    let hits = sqlx::query!(
+        "
+            SELECT v.title, v.chapter_num as chapter, v.num as verse, v.contents as text
+            FROM verses v
+            JOIN translation_versions t ON t.version = v.version
+            WHERE t.translation = $1 AND t.state = 'active'
+                AND v.search_vector @@ websearch_to_tsquery('english', $2)
+                AND v.title = ANY($3)
+                AND (
+                    SELECT bool_and(v.search_vector @@ websearch_to_tsquery('english', w))
+                    FROM unnest($6::text[]) w
+                ) IS NOT FALSE
+          ORDER BY ts_rank(v.search_vector, websearch_to_tsquery('english', $2)) DESC,
+                v.title, v.chapter_num, v.num
+            LIMIT $4 OFFSET $5
+        ",
+        text_search.translation,
+        text_search.query,
+        &books[..],
+        i64::from(text_search.limit),
+        i64::from(text_search.offset),
+        &text_search.within[..],
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| TextSearchHit::new(row.title, row.chapter, row.verse, row.text))
+    .collect();
+
+    let counts = sqlx::query!(
+        r#"
+            SELECT v.title, COUNT(*) as "count!"
+            FROM verses v
+            JOIN translation_versions t ON t.version = v.version
+            WHERE t.translation = $1 AND t.state = 'active'
+                AND v.search_vector @@ websearch_to_tsquery('english', $2)
+                AND v.title = ANY($3)
+                AND (
+                    SELECT bool_and(v.search_vector @@ websearch_to_tsquery('english', w))
+                    FROM unnest($4::text[]) w
+                ) IS NOT FALSE
+          GROUP BY v.title
+        "#,
+        text_search.translation,
+        text_search.query,
+        &books[..],
+        &text_search.within[..],
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let facets = get_facets(
+        counts
+            .into_iter()
+            .map(|row| (row.title, u64::try_from(row.count).unwrap_or(0))),
+    );
+
+    Ok(TextSearchResults {
+        engine: "postgres",
+        total: facets.book.values().sum(),
+        hits,
+        facets,
+    })
+}
+
+/// The get_facets function counts the matches by testament from the matches
+/// by book.
+pub fn get_facets(book_counts: impl IntoIterator<Item = (String, u64)>) -> Facets {
+    let mut facets = Facets::default();
+
+    for (book, count) in book_counts {
+        if let Some(testament) = get_testament(&book) {
+            let testament = testament.as_str().to_owned();
+            *facets.testament.entry(testament).or_insert(0) += count;
+        }
+        facets.book.insert(book, count);
+    }
+
+    facets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(book: Option<&str>, testament: Option<Testament>) -> TextSearchParams {
+        TextSearchParams {
+            q: Some(String::from("charity")),
+            book: book.map(String::from),
+            testament,
+            limit: Some(200),
+            offset: None,
+            within: None,
+        }
+    }
+
+    #[test]
+    fn from_params_resolves_the_book_and_bounds_the_limit() {
+        let text_search = TextSearch::from_params(params(Some("1 cor"), None)).unwrap();
+
+        assert_eq!(text_search.book.as_deref(), Some("1 Corinthians"));
+        assert_eq!(text_search.limit, MAX_LIMIT);
+        assert!(TextSearch::from_params(params(Some("Hezekiah"), None)).is_err());
+    }
+
+    #[test]
+    fn from_params_searches_within_the_scope_given() {
+        let faith = TextSearch::from_params(params(None, Some(Testament::New))).unwrap();
+        let scope = faith.get_scope().to_token().parse::<Scope>().unwrap();
+        assert_eq!(scope, faith.get_scope());
+
+        let in_romans = TextSearch::from_params(TextSearchParams {
+            q: None,
+            within: Some(scope.clone()),
+            ..params(Some("Romans"), None)
+        })
+        .unwrap();
+        assert_eq!(in_romans.query, "charity");
+        assert!(in_romans.within.is_empty());
+        assert_eq!(in_romans.book.as_deref(), Some("Romans"));
+        assert_eq!(in_romans.testament, Some(Testament::New));
+
+        let works = TextSearch::from_params(TextSearchParams {
+            q: Some(String::from("works")),
+            within: Some(in_romans.get_scope()),
+            ..params(None, None)
+        })
+        .unwrap();
+        assert_eq!(works.within, vec![String::from("charity")]);
+        assert_eq!(works.book.as_deref(), Some("Romans"));
+
+        let widened = TextSearchParams {
+            within: Some(scope),
+            ..params(None, Some(Testament::Old))
+        };
+        assert!(TextSearch::from_params(widened).is_err());
+        assert!("c0ffee".parse::<Scope>().is_err());
+        assert!("abc".parse::<Scope>().is_err());
+    }
+
+    #[test]
+    fn get_books_narrows_to_the_book_within_the_testament() {
+        let new = TextSearch::from_params(params(None, Some(Testament::New))).unwrap();
+        let in_new = TextSearch::from_params(params(Some("John"), Some(Testament::New))).unwrap();
+        let in_old = TextSearch::from_params(params(Some("John"), Some(Testament::Old))).unwrap();
+
+        assert_eq!(new.get_books().len(), 27);
+        assert_eq!(in_new.get_books(), vec![String::from("John")]);
+        assert!(in_old.get_books().is_empty());
+    }
+
+    #[test]
+    fn get_facets_adds_up_the_books_of_each_testament() {
+        let facets = get_facets([
+            (String::from("Genesis"), 2),
+            (String::from("John"), 3),
+            (String::from("Romans"), 1),
+        ]);
+
+        assert_eq!(facets.book.values().sum::<u64>(), 6);
+        assert_eq!(facets.testament.get("old"), Some(&2));
+        assert_eq!(facets.testament.get("new"), Some(&4));
+    }
+}
@@ -0,0 +1,158 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use std::str::FromStr;
+
+use crate::{empty_string_as_none, error::BibleApiError, internal_error};
+
+/// The Year is a year of the calendar, with the years BC as negative numbers
+/// (ex: 586 BC is -586). There is no year 0. It is read as a number or with
+/// its era (ex: 586 BC, 586 BCE, AD 30, 30 AD, 30 CE).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Year(i32);
+
+impl FromStr for Year {
+    type Err = String;
+
+    fn from_str(year: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid year: {} (ex: 586 BC or AD 30)", year);
+        let upper = year.trim().to_uppercase();
+
+        let (number, is_bc) = if let Some(number) = upper
+            .strip_suffix("BCE")
+            .or_else(|| upper.strip_suffix("BC"))
+        {
+            (number, true)
+        } else if let Some(number) = upper
+            .strip_prefix("AD")
+            .or_else(|| upper.strip_suffix("AD"))
+            .or_else(|| upper.strip_suffix("CE"))
+        {
+            (number, false)
+        } else {
+            (upper.as_str(), false)
+        };
+
+        let number = number.trim();
+        // An era is written with a year that has no sign of its own
+        if is_bc && number.starts_with(['-', '+']) {
+            return Err(invalid());
+        }
+
+        match number.parse::<i32>() {
+            Ok(0) | Err(_) => Err(invalid()),
+            Ok(number) if is_bc => Ok(Year(-number)),
+            Ok(number) => Ok(Year(number)),
+        }
+    }
+}
+
+/// The get_dating function writes the years an event spans the way they are
+/// read (ex: c. 1010–970 BC, c. 6 BC–AD 30).
+pub fn get_dating(start_year: i32, end_year: i32) -> String {
+    let era = |year: i32| match year < 0 {
+        true => format!("{} BC", -year),
+        false => format!("AD {}", year),
+    };
+
+    match (start_year < 0, end_year < 0) {
+        _ if start_year == end_year => format!("c. {}", era(start_year)),
+        (true, true) => format!("c. {}–{} BC", -start_year, -end_year),
+        (false, false) => format!("c. AD {}–{}", start_year, end_year),
+        _ => format!("c. {}–{}", era(start_year), era(end_year)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    from: Option<Year>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    to: Option<Year>,
+}
+
+/// The TimelineEvent is an event of the text, dated to the years it spans,
+/// with the passage it is told in.
+#[derive(Debug, Serialize)]
+pub struct TimelineEvent {
+    pub name: String,
+    pub reference: String,
+    pub start_year: i32,
+    pub end_year: i32,
+    pub dating: String,
+}
+
+/// The timeline handler serves /timeline (ex: /timeline?from=1100 BC&to=900 BC)
+/// with the events that took place in any of the years from and to, in the
+/// order they happened. Either end can be left open.
+pub async fn timeline(
+    State(pool): State<PgPool>,
+    Query(params): Query<TimelineParams>,
+) -> Result<Json<Vec<TimelineEvent>>, BibleApiError> {
+    let from = params.from.map(|Year(year)| year);
+    let to = params.to.map(|Year(year)| year);
+
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The timeline must start before it ends".to_string(),
+            )
+                .into());
+        }
+    }
+
+    let events = sqlx::query!(
+        r#"
+            SELECT name, reference, start_year, end_year
+            FROM timeline_events
+            WHERE ($1::int IS NULL OR end_year >= $1) AND ($2::int IS NULL OR start_year <= $2)
+          ORDER BY start_year, end_year, name
+        "#,
+        from,
+        to,
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(internal_error)?
+    .into_iter()
+    .map(|row| TimelineEvent {
+        dating: get_dating(row.start_year, row.end_year),
+        name: row.name,
+        reference: row.reference,
+        start_year: row.start_year,
+        end_year: row.end_year,
+    })
+    .collect();
+
+    Ok(Json(events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn year_reads_each_era() {
+        assert_eq!("586 BC".parse::<Year>(), Ok(Year(-586)));
+        assert_eq!("586bce".parse::<Year>(), Ok(Year(-586)));
+        assert_eq!("AD 30".parse::<Year>(), Ok(Year(30)));
+        assert_eq!("30 ce".parse::<Year>(), Ok(Year(30)));
+        assert_eq!("-1446".parse::<Year>(), Ok(Year(-1446)));
+        assert!("0".parse::<Year>().is_err());
+        assert!("-5 BC".parse::<Year>().is_err());
+        assert!("soon".parse::<Year>().is_err());
+    }
+
+    #[test]
+    fn get_dating_writes_the_era_once_when_it_can() {
+        assert_eq!(get_dating(-586, -586), "c. 586 BC");
+        assert_eq!(get_dating(-1010, -970), "c. 1010–970 BC");
+        assert_eq!(get_dating(30, 33), "c. AD 30–33");
+        assert_eq!(get_dating(-6, 30), "c. 6 BC–AD 30");
+    }
+}
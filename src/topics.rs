@@ -0,0 +1,196 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use sqlx::postgres::PgPool;
+
+use crate::{
+    breaker::CircuitBreaker,
+    db::get_default_translation,
+    empty_string_as_none,
+    error::BibleApiError,
+    render::{Format, RenderOptions},
+    search, PassageOptions,
+};
+
+/// The TOPICS constant maps each topic to the curated passages it picks from.
+/// Every passage has to be a reference search::search can resolve.
+const TOPICS: [(&str, &[&str]); 8] = [
+    (
+        "comfort",
+        &[
+            "Psalms 23:4",
+            "Psalms 34:18",
+            "Isaiah 41:10",
+            "Matthew 5:4",
+            "John 14:27",
+            "2 Corinthians 1:3-4",
+            "Revelation 21:4",
+        ],
+    ),
+    (
+        "anxiety",
+        &[
+            "Psalms 55:22",
+            "Isaiah 26:3",
+            "Matthew 6:34",
+            "John 14:1",
+            "Philippians 4:6-7",
+            "1 Peter 5:7",
+        ],
+    ),
+    (
+        "hope",
+        &[
+            "Psalms 42:11",
+            "Jeremiah 29:11",
+            "Lamentations 3:22-23",
+            "Romans 8:28",
+            "Romans 15:13",
+            "Hebrews 11:1",
+        ],
+    ),
+    (
+        "peace",
+        &[
+            "Numbers 6:24-26",
+            "Isaiah 9:6",
+            "John 16:33",
+            "Romans 5:1",
+            "Colossians 3:15",
+        ],
+    ),
+    (
+        "love",
+        &[
+            "John 3:16",
+            "John 15:13",
+            "Romans 8:38-39",
+            "1 Corinthians 13:4-7",
+            "1 John 4:7-8",
+        ],
+    ),
+    (
+        "strength",
+        &[
+            "Joshua 1:9",
+            "Nehemiah 8:10",
+            "Psalms 46:1",
+            "Isaiah 40:31",
+            "2 Corinthians 12:9",
+            "Philippians 4:13",
+        ],
+    ),
+    (
+        "forgiveness",
+        &[
+            "Psalms 103:12",
+            "Matthew 6:14-15",
+            "Ephesians 4:32",
+            "Colossians 3:13",
+            "1 John 1:9",
+        ],
+    ),
+    (
+        "wisdom",
+        &[
+            "Proverbs 2:6",
+            "Proverbs 3:5-6",
+            "Proverbs 9:10",
+            "James 1:5",
+        ],
+    ),
+];
+
+#[derive(Debug, Deserialize)]
+pub struct TopicParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    format: Option<Format>,
+    #[serde(flatten)]
+    render_options: RenderOptions,
+}
+
+/// The get_topic function takes a topic name, in any case, and returns the
+/// passages curated for it in an Option. If the topic is not found None is
+/// returned.
+pub fn get_topic(topic: &str) -> Option<&'static [&'static str]> {
+    TOPICS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(topic.trim()))
+        .map(|(_, passages)| *passages)
+}
+
+/// The random handler serves /topics/:topic/random (ex: /topics/hope/random)
+/// with a passage picked at random from the topic. A different passage can
+/// come back every time, so the response is never cached.
+pub async fn random(
+    State(pool): State<PgPool>,
+    State(breaker): State<CircuitBreaker>,
+    Path(topic): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<TopicParams>,
+) -> Result<Response, Response> {
+    let reference = get_topic(&topic)
+        .and_then(|passages| passages.choose(&mut rand::thread_rng()))
+        .ok_or(
+            BibleApiError::from((StatusCode::NOT_FOUND, "No Matching Topic Found".to_string()))
+                .into_response(),
+        )?;
+
+    // The passages are curated, so one that does not resolve is our mistake
+    let bible_search = search::search(reference).map_err(|err| {
+        BibleApiError::from((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("{} does not resolve: {}", reference, err),
+        ))
+        .into_response()
+    })?;
+
+    let format = Format::negotiate(params.format, &headers);
+    let mut response = crate::search_response(
+        pool,
+        breaker,
+        get_default_translation(),
+        bible_search,
+        format,
+        params.render_options,
+        PassageOptions::default(),
+    )
+    .await?;
+    response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_topic_ignores_case() {
+        assert_eq!(get_topic(" Hope "), get_topic("hope"));
+        assert!(get_topic("hope").is_some());
+        assert_eq!(get_topic("gardening"), None);
+    }
+
+    #[test]
+    fn every_topic_passage_resolves() {
+        for (topic, passages) in TOPICS {
+            assert!(!passages.is_empty(), "{} has no passages", topic);
+
+            for passage in passages {
+                let bible_search = search::search(passage).unwrap();
+                assert!(
+                    !search::is_whole_chapter(&bible_search),
+                    "{} is a whole chapter",
+                    passage
+                );
+            }
+        }
+    }
+}
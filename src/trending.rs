@@ -0,0 +1,305 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    empty_string_as_none,
+    error::BibleApiError,
+    internal_error,
+    search::{get_reference_with, BibleSearch},
+    versification::Versification,
+};
+
+/// The ROLLUP_INTERVAL is how often the searches counted in memory are added
+/// to the hourly counts in the database.
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The DEFAULT_WINDOW is the number of hours looked at when no window is given.
+const DEFAULT_WINDOW: Window = Window(24);
+
+/// The MAX_WINDOW is the most hours that can be looked at (30 days). Counts
+/// are kept for twice as long, so the longest window can still be compared
+/// to the one before it.
+const MAX_WINDOW: u32 = 30 * 24;
+
+/// The DEFAULT_LIMIT and MAX_LIMIT bound how many references are listed.
+const DEFAULT_LIMIT: u8 = 10;
+const MAX_LIMIT: u8 = 100;
+
+/// The Scope is how precisely searches are grouped.
+/// - Book (book) counts every search of a book together (ex: John)
+/// - Chapter (chapter) counts every search of a chapter together (ex: John 3)
+/// - Passage (passage) counts the passage as it was resolved (ex: John 3:16)
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum Scope {
+    Book,
+    Chapter,
+    #[default]
+    Passage,
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope.trim().to_lowercase().as_str() {
+            "book" => Ok(Scope::Book),
+            "chapter" => Ok(Scope::Chapter),
+            "passage" => Ok(Scope::Passage),
+            other => Err(format!("Unknown scope: {}", other)),
+        }
+    }
+}
+
+impl Scope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Scope::Book => "book",
+            Scope::Chapter => "chapter",
+            Scope::Passage => "passage",
+        }
+    }
+}
+
+/// The Trending tracker counts searches by their canonical reference at every
+/// scope. The counts are rolled up into the database every ROLLUP_INTERVAL;
+/// the query that was typed is never kept.
+#[derive(Clone, Default)]
+pub struct Trending {
+    searches: Arc<Mutex<HashMap<(Scope, String), i64>>>,
+}
+
+impl Trending {
+    /// The record function counts a search at every scope. A passage that
+    /// runs across chapters counts for each of them. The passage is counted
+    /// under its reference in the versification it was searched in.
+    pub fn record(&self, bible_search: &BibleSearch, versification: &Versification) {
+        let references = [
+            (Scope::Book, bible_search.title.clone()),
+            (
+                Scope::Passage,
+                get_reference_with(bible_search, versification),
+            ),
+        ];
+        let chapters = bible_search.chapters.iter().map(|chapter| {
+            (
+                Scope::Chapter,
+                format!("{} {}", bible_search.title, chapter.chapter),
+            )
+        });
+
+        let mut searches = self.searches.lock().unwrap();
+        for key in references.into_iter().chain(chapters) {
+            *searches.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    /// The spawn_rollup function starts a background task that adds the
+    /// searches counted so far to the current hour every ROLLUP_INTERVAL, and
+    /// drops the hours that no window can reach any more.
+    pub fn spawn_rollup(&self, pool: PgPool) {
+        let trending = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(ROLLUP_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+                trending.save(&pool).await;
+            }
+        });
+    }
+
+    /// The save function adds the searches counted so far to the current
+    /// hour, as the rollup does, and is called once more on shutdown so they
+    /// are not lost.
+    pub async fn save(&self, pool: &PgPool) {
+        let searches = std::mem::take(&mut *self.searches.lock().unwrap());
+        if let Err(err) = rollup(pool, &searches).await {
+            tracing::warn!("could not roll up trending searches: {}", err);
+            self.restore(searches);
+        }
+    }
+
+    fn restore(&self, unsaved: HashMap<(Scope, String), i64>) {
+        let mut searches = self.searches.lock().unwrap();
+
+        for (key, count) in unsaved {
+            *searches.entry(key).or_insert(0) += count;
+        }
+    }
+}
+
+async fn rollup(
+    pool: &PgPool,
+    searches: &HashMap<(Scope, String), i64>,
+) -> Result<(), sqlx::Error> {
+    let mut scopes = vec![];
+    let mut references = vec![];
+    let mut counts = vec![];
+
+    for ((scope, reference), count) in searches {
+        scopes.push(scope.as_str().to_owned());
+        references.push(reference.clone());
+        counts.push(*count);
+    }
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "
+            INSERT INTO search_trends (hour, scope, reference, searches)
+            SELECT date_trunc('hour', now()), * FROM UNNEST($1::text[], $2::text[], $3::bigint[])
+            ON CONFLICT (hour, scope, reference)
+            DO UPDATE SET searches = search_trends.searches + EXCLUDED.searches
+        ",
+        &scopes[..],
+        &references[..],
+        &counts[..]
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM search_trends WHERE hour < now() - make_interval(hours => $1)",
+        (MAX_WINDOW * 2) as i32
+    )
+    .execute(&mut transaction)
+    .await?;
+
+    transaction.commit().await
+}
+
+/// The Window is a number of hours, counting back from now (ex: 6h or 7d).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Window(u32);
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let window = window.trim();
+        let hours = if let Some(hours) = window.strip_suffix('h') {
+            hours.parse::<u32>().ok()
+        } else if let Some(days) = window.strip_suffix('d') {
+            days.parse::<u32>()
+                .ok()
+                .and_then(|days| days.checked_mul(24))
+        } else {
+            None
+        }
+        .ok_or(format!("Invalid window: {} (ex: 24h or 7d)", window))?;
+
+        if hours == 0 || hours > MAX_WINDOW {
+            return Err(format!("The window must be 1h to {}d", MAX_WINDOW / 24));
+        }
+
+        Ok(Window(hours))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingParams {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    window: Option<Window>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    scope: Option<Scope>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    limit: Option<u8>,
+}
+
+/// The TrendingReference is a reference with the number of searches for it
+/// in the window, and in the window of the same length before that.
+#[derive(Debug, Serialize)]
+pub struct TrendingReference {
+    pub reference: String,
+    pub searches: i64,
+    pub previous: i64,
+}
+
+/// The trending handler serves /trending (ex: /trending?window=6h&scope=book)
+/// with the references whose searches grew the most over the window. The
+/// window defaults to 24 hours and the scope to the passage.
+pub async fn trending(
+    State(pool): State<PgPool>,
+    Query(params): Query<TrendingParams>,
+) -> Result<Json<Vec<TrendingReference>>, BibleApiError> {
+    let Window(hours) = params.window.unwrap_or(DEFAULT_WINDOW);
+    let scope = params.scope.unwrap_or_default();
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    // A window covers the current hour and the ones before it, so 1h is the
+    // hour so far and previous is the whole hour before it
+    sqlx::query_as!(
+        TrendingReference,
+        r#"
+            WITH counts AS (
+                SELECT
+                    reference,
+                    COALESCE(SUM(searches) FILTER (
+                        WHERE hour > date_trunc('hour', now()) - make_interval(hours => $2)
+                    ), 0)::bigint as searches,
+                    COALESCE(SUM(searches) FILTER (
+                        WHERE hour <= date_trunc('hour', now()) - make_interval(hours => $2)
+                    ), 0)::bigint as previous
+                FROM search_trends
+                WHERE scope = $1
+                    AND hour > date_trunc('hour', now()) - make_interval(hours => $2 * 2)
+              GROUP BY reference
+            )
+            SELECT reference as "reference!", searches as "searches!", previous as "previous!"
+            FROM counts
+            WHERE searches > 0
+          ORDER BY searches - previous DESC, searches DESC, reference
+            LIMIT $3
+        "#,
+        scope.as_str(),
+        hours as i32,
+        i64::from(limit),
+    )
+    .fetch_all(&pool)
+    .await
+    .map(Json)
+    .map_err(internal_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_parses_hours_and_days() {
+        assert_eq!("6h".parse::<Window>(), Ok(Window(6)));
+        assert_eq!(" 7d ".parse::<Window>(), Ok(Window(168)));
+        assert!("7".parse::<Window>().is_err());
+        assert!("0h".parse::<Window>().is_err());
+        assert!("31d".parse::<Window>().is_err());
+    }
+
+    #[test]
+    fn record_counts_a_search_at_every_scope() {
+        let trending = Trending::default();
+        let versification = Versification::default();
+        trending.record(
+            &crate::search::search("John 3:16-17").unwrap(),
+            &versification,
+        );
+        trending.record(&crate::search::search("John 3").unwrap(), &versification);
+
+        let searches = trending.searches.lock().unwrap();
+        let count = |scope, reference: &str| searches.get(&(scope, reference.to_owned())).copied();
+        assert_eq!(count(Scope::Book, "John"), Some(2));
+        assert_eq!(count(Scope::Chapter, "John 3"), Some(2));
+        assert_eq!(count(Scope::Passage, "John 3:16-17"), Some(1));
+        assert_eq!(count(Scope::Passage, "John 3"), Some(1));
+    }
+}
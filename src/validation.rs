@@ -0,0 +1,169 @@
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+/// The MAX_URI_LEN is the longest request URI let through to the handlers.
+/// It stops an oversized query string before it is even deserialized.
+pub const MAX_URI_LEN: usize = 4096;
+
+/// The MAX_QUERY_LEN is the most characters a reference query can take.
+pub const MAX_QUERY_LEN: usize = 256;
+
+/// The MAX_TRANSLATION_LEN is the most characters a translation code can
+/// take (ex: kjv).
+pub const MAX_TRANSLATION_LEN: usize = 15;
+
+/// The MAX_TEXT_QUERY_LEN is the most characters a text search can take.
+pub const MAX_TEXT_QUERY_LEN: usize = 256;
+
+/// The MAX_SUB_QUERIES is the most comma separated parts a reference query
+/// can have (ex: John 3:16, 18, 20-22 has 3).
+pub const MAX_SUB_QUERIES: usize = 32;
+
+/// The MAX_PASSAGES is the most semicolon separated passages a reference
+/// query can have (ex: John 3:16; Romans 8:28 has 2).
+pub const MAX_PASSAGES: usize = 16;
+
+/// The ValidationError is the body of the 422 returned for input that is
+/// refused before it is looked at. The limit is given when the input went
+/// over one.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ValidationError {
+    pub error: &'static str,
+    pub field: &'static str,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl ValidationError {
+    fn new(field: &'static str, reason: String, limit: Option<usize>) -> Self {
+        ValidationError {
+            error: "invalid input",
+            field,
+            reason,
+            limit,
+        }
+    }
+}
+
+impl IntoResponse for ValidationError {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(self)).into_response()
+    }
+}
+
+/// The check_text function refuses a value longer than the max_len, counted
+/// in characters, or with control characters in it.
+pub fn check_text(field: &'static str, value: &str, max_len: usize) -> Result<(), ValidationError> {
+    if value.chars().count() > max_len {
+        return Err(ValidationError::new(
+            field,
+            format!("{} is longer than {} characters", field, max_len),
+            Some(max_len),
+        ));
+    }
+
+    if let Some(c) = value.chars().find(|c| c.is_control()) {
+        return Err(ValidationError::new(
+            field,
+            format!("{} contains the control character {:?}", field, c),
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+/// The check_reference function refuses a reference query that check_text
+/// refuses, or that has more than MAX_SUB_QUERIES parts or MAX_PASSAGES
+/// passages.
+pub fn check_reference(field: &'static str, value: &str) -> Result<(), ValidationError> {
+    check_text(field, value, MAX_QUERY_LEN)?;
+
+    let passages = value.split(';').count();
+    if passages > MAX_PASSAGES {
+        return Err(ValidationError::new(
+            field,
+            format!(
+                "{} has {} passages, more than {}",
+                field, passages, MAX_PASSAGES
+            ),
+            Some(MAX_PASSAGES),
+        ));
+    }
+
+    let sub_queries = value.split(',').count();
+    if sub_queries > MAX_SUB_QUERIES {
+        return Err(ValidationError::new(
+            field,
+            format!(
+                "{} has {} parts, more than {}",
+                field, sub_queries, MAX_SUB_QUERIES
+            ),
+            Some(MAX_SUB_QUERIES),
+        ));
+    }
+
+    Ok(())
+}
+
+/// The limit_uri middleware refuses requests whose URI is longer than
+/// MAX_URI_LEN.
+pub async fn limit_uri(request: Request, next: Next) -> Response {
+    let uri_len = request.uri().to_string().len();
+
+    if uri_len > MAX_URI_LEN {
+        return ValidationError::new(
+            "uri",
+            format!("the URI is longer than {} bytes", MAX_URI_LEN),
+            Some(MAX_URI_LEN),
+        )
+        .into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_text_refuses_long_values_and_control_characters() {
+        assert!(check_text("q", "love one another", 20).is_ok());
+        assert_eq!(
+            check_text("q", &"a".repeat(21), 20).unwrap_err().limit,
+            Some(20)
+        );
+        assert!(check_text("q", "love\u{0}", 20).is_err());
+        assert!(check_text("q", "line\nbreak", 20).is_err());
+    }
+
+    #[test]
+    fn check_reference_caps_the_sub_queries() {
+        assert!(check_reference("query", "John 3:16, 18, 20-22").is_ok());
+
+        let too_many = format!("John 3:1{}", ", 2".repeat(MAX_SUB_QUERIES));
+        assert_eq!(
+            check_reference("query", &too_many).unwrap_err().limit,
+            Some(MAX_SUB_QUERIES)
+        );
+    }
+
+    #[test]
+    fn check_reference_caps_the_passages() {
+        assert!(check_reference("query", "John 3:16; Rom 8:28; Ps 23").is_ok());
+
+        let too_many = format!("Ps 1{}", "; Ps 2".repeat(MAX_PASSAGES));
+        assert_eq!(
+            check_reference("query", &too_many).unwrap_err().limit,
+            Some(MAX_PASSAGES)
+        );
+    }
+}
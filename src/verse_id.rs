@@ -0,0 +1,185 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, str::FromStr};
+
+use crate::{
+    chapter::{get_book_by_number, get_book_number},
+    db::{get_default_translation, SearchOptions, TextFormat},
+    error::BibleApiError,
+    rate_limit::VerseCount,
+    search::{BibleSearch, Chapter},
+    state::AppState,
+    verse::SUPERSCRIPTION_VERSE,
+};
+
+/// The VerseId is the number of a verse, written BBCCCVVV: the book's number
+/// (see get_book_number), then the chapter and the verse (ex: John 3:16 is
+/// 43003016). A chapter's superscription is its verse 0. The id only depends
+/// on the reference, so it stays the same however the text is loaded.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct VerseId(pub i32);
+
+impl VerseId {
+    /// The new function returns the id of a verse, or None when the book is
+    /// not found or the chapter or verse do not fit in the id.
+    pub fn new(title: &str, chapter: i32, verse: i32) -> Option<Self> {
+        let book = get_book_number(title)? as i32;
+
+        match (1..=999).contains(&chapter) && (0..=999).contains(&verse) {
+            true => Some(VerseId(book * 1_000_000 + chapter * 1000 + verse)),
+            false => None,
+        }
+    }
+
+    /// The get_reference function returns the book, chapter and verse the id
+    /// is for, or None when there is no such book.
+    pub fn get_reference(self) -> Option<(&'static str, i32, i32)> {
+        let VerseId(id) = self;
+        let book = usize::try_from(id / 1_000_000).ok()?;
+
+        Some((get_book_by_number(book)?, id / 1000 % 1000, id % 1000))
+    }
+}
+
+impl FromStr for VerseId {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid verse id: {} (ex: 43003016 for John 3:16)", id);
+
+        match id.trim().parse::<i32>() {
+            Ok(id) if id > 0 => VerseId(id)
+                .get_reference()
+                .map(|_| VerseId(id))
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The VerseIds are the ids of a verse, of its chapter (BBCCC) and of its
+/// book (BB), for downstream databases to join on (ex: John 3:16 is 43003016,
+/// 43003 and 43). They are 0 for a verse whose book is not known.
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[serde(default)]
+pub struct VerseIds {
+    pub verse_id: i32,
+    pub chapter_id: i32,
+    pub book_id: i32,
+}
+
+impl VerseIds {
+    pub fn new(title: &str, chapter: i32, verse: i32) -> Self {
+        match VerseId::new(title, chapter, verse) {
+            Some(VerseId(id)) => VerseIds {
+                verse_id: id,
+                chapter_id: id / 1000,
+                book_id: id / 1_000_000,
+            },
+            None => VerseIds::default(),
+        }
+    }
+}
+
+/// The verse handler serves /verses/:id (ex: /verses/43003016) with the
+/// verse the id is for.
+pub async fn verse(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, BibleApiError> {
+    let not_found = || {
+        BibleApiError::from((
+            StatusCode::NOT_FOUND,
+            format!("No Matching Verse Found: {}", id),
+        ))
+    };
+    let (title, chapter, verse) = id
+        .parse::<VerseId>()
+        .ok()
+        .and_then(VerseId::get_reference)
+        .ok_or_else(not_found)?;
+    let (Ok(chapter), Ok(verse)) = (u8::try_from(chapter), u8::try_from(verse)) else {
+        return Err(not_found());
+    };
+
+    // The superscription is not one of the chapter's numbered verses
+    let is_superscription = verse == SUPERSCRIPTION_VERSE;
+    let versification = state.versifications.get(get_default_translation());
+    if !is_superscription && !versification.verse_exists(title, chapter, verse) {
+        return Err(not_found());
+    }
+    let bible_search = BibleSearch {
+        title: title.to_owned(),
+        chapters: vec![Chapter {
+            chapter,
+            verses: match is_superscription {
+                true => HashSet::new(),
+                false => HashSet::from([verse]),
+            },
+        }],
+    };
+    let options = SearchOptions {
+        superscription: is_superscription,
+        format: TextFormat::Plain,
+    };
+
+    let (results, _) = state
+        .breaker
+        .search(get_default_translation(), bible_search, options)
+        .await?;
+    let result = results
+        .into_iter()
+        .find(|result| result.verse == i32::from(verse))
+        .ok_or_else(not_found)?;
+
+    Ok((Extension(VerseCount(1)), Json(result)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verse_id_numbers_the_book_chapter_and_verse() {
+        assert_eq!(VerseId::new("Genesis", 1, 1), Some(VerseId(1_001_001)));
+        assert_eq!(VerseId::new("John", 3, 16), Some(VerseId(43_003_016)));
+        assert_eq!(VerseId::new("Psalms", 119, 176), Some(VerseId(19_119_176)));
+        assert_eq!(
+            VerseId::new("Revelation", 22, 21),
+            Some(VerseId(66_022_021))
+        );
+        assert_eq!(VerseId::new("Hezekiah", 1, 1), None);
+        assert_eq!(VerseId::new("John", 1000, 1), None);
+    }
+
+    #[test]
+    fn verse_ids_number_the_chapter_and_book_too() {
+        assert_eq!(
+            VerseIds::new("1 John", 4, 8),
+            VerseIds {
+                verse_id: 62_004_008,
+                chapter_id: 62_004,
+                book_id: 62,
+            }
+        );
+        assert_eq!(VerseIds::new("Hezekiah", 1, 1), VerseIds::default());
+    }
+
+    #[test]
+    fn verse_id_reads_back_the_reference() {
+        assert_eq!(
+            "43003016".parse::<VerseId>().unwrap().get_reference(),
+            Some(("John", 3, 16))
+        );
+        assert_eq!(VerseId(19_003_000).get_reference(), Some(("Psalms", 3, 0)));
+        assert!("67001001".parse::<VerseId>().is_err());
+        assert!("1001".parse::<VerseId>().is_err());
+        assert!("John 3:16".parse::<VerseId>().is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use sqlx::postgres::PgPool;
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, OnceLock, RwLock},
+    time::Duration,
+};
+
+pub use bible_ref::versification::Versification;
+
+/// The REFRESH_INTERVAL is how often the versification of every active
+/// translation is reloaded, so a new import is picked up without a restart.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// The ValidationMode decides what the books, chapters and verses of a
+/// search are checked against. It is read once from VERSE_VALIDATION.
+/// - BuiltIn (built-in) uses the built in tables for every translation
+/// - Counts (counts) uses how many verses each chapter of a translation has
+///   in the database, and the built in tables for books it does not have
+///   (the default)
+/// - Database (database) uses the verses of a translation in the database,
+///   so a verse it leaves out (ex: Acts 8:37) or a book it does not have is
+///   not found
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ValidationMode {
+    BuiltIn,
+    Counts,
+    Database,
+}
+
+impl FromStr for ValidationMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode.trim().to_lowercase().as_str() {
+            "built-in" | "builtin" => Ok(ValidationMode::BuiltIn),
+            "counts" => Ok(ValidationMode::Counts),
+            "database" => Ok(ValidationMode::Database),
+            other => Err(format!("Unknown verse validation mode: {}", other)),
+        }
+    }
+}
+
+static VALIDATION_MODE: OnceLock<ValidationMode> = OnceLock::new();
+
+fn get_validation_mode() -> ValidationMode {
+    *VALIDATION_MODE.get_or_init(|| match std::env::var("VERSE_VALIDATION") {
+        Ok(mode) => mode.parse().unwrap_or_else(|err| {
+            tracing::warn!("{}, using counts", err);
+            ValidationMode::Counts
+        }),
+        Err(_) => ValidationMode::Counts,
+    })
+}
+
+/// The load function reads the versification of the active version of a
+/// translation from its verses, the way the ValidationMode says to. None is
+/// returned when the translation has no active version, or when the built
+/// in tables are used.
+pub async fn load(pool: &PgPool, translation: &str) -> Result<Option<Versification>, sqlx::Error> {
+    match get_validation_mode() {
+        ValidationMode::BuiltIn => Ok(None),
+        ValidationMode::Counts => load_counts(pool, translation).await,
+        ValidationMode::Database => load_verses(pool, translation).await,
+    }
+}
+
+async fn load_counts(
+    pool: &PgPool,
+    translation: &str,
+) -> Result<Option<Versification>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+            SELECT v.title, v.chapter_num, MAX(COALESCE(v.bridged_to, v.num)) as "verse_count!"
+            FROM verses v
+            JOIN translation_versions t ON t.version = v.version
+            WHERE t.translation = $1 AND t.state = 'active'
+          GROUP BY v.title, v.chapter_num
+        "#,
+        translation
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let counts = rows.into_iter().filter_map(|row| {
+        Some((
+            row.title,
+            u8::try_from(row.chapter_num).ok()?,
+            u8::try_from(row.verse_count).ok()?,
+        ))
+    });
+
+    Ok(Some(Versification::from_counts(counts)))
+}
+
+async fn load_verses(
+    pool: &PgPool,
+    translation: &str,
+) -> Result<Option<Versification>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+            SELECT v.title, v.chapter_num, v.num, v.bridged_to
+            FROM verses v
+            JOIN translation_versions t ON t.version = v.version
+            WHERE t.translation = $1 AND t.state = 'active' AND v.num >= 0
+        "#,
+        translation
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let verses = rows.into_iter().filter_map(|row| {
+        Some((
+            row.title,
+            u8::try_from(row.chapter_num).ok()?,
+            u8::try_from(row.num).ok()?,
+            row.bridged_to.and_then(|verse| u8::try_from(verse).ok()),
+        ))
+    });
+
+    Ok(Some(Versification::from_verses(verses)))
+}
+
+/// The Versifications hold the versification of every active translation,
+/// for searches to be checked against. A translation that has not been
+/// loaded uses the built in versification.
+#[derive(Clone, Default)]
+pub struct Versifications {
+    loaded: Arc<RwLock<HashMap<String, Arc<Versification>>>>,
+}
+
+impl Versifications {
+    /// The get function returns the versification of a translation.
+    pub fn get(&self, translation: &str) -> Arc<Versification> {
+        self.loaded
+            .read()
+            .unwrap()
+            .get(translation)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The load function loads the versification of every active
+    /// translation, so searches are checked against what is in the database
+    /// from the first one on. When they can not be loaded, the ones loaded
+    /// before are kept.
+    pub async fn load(&self, pool: &PgPool) {
+        match load_all(pool).await {
+            Ok(loaded) => {
+                tracing::debug!("loaded the versifications of {} translations", loaded.len());
+                *self.loaded.write().unwrap() = loaded;
+            }
+            Err(err) => tracing::warn!("could not load versifications: {}", err),
+        }
+    }
+
+    /// The spawn_refresh function starts a background task that loads the
+    /// versification of every active translation every REFRESH_INTERVAL,
+    /// starting one REFRESH_INTERVAL from now.
+    pub fn spawn_refresh(&self, pool: PgPool) {
+        let versifications = self.clone();
+
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + REFRESH_INTERVAL;
+            let mut ticker = tokio::time::interval_at(start, REFRESH_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+                versifications.load(&pool).await;
+            }
+        });
+    }
+}
+
+async fn load_all(pool: &PgPool) -> Result<HashMap<String, Arc<Versification>>, sqlx::Error> {
+    let translations =
+        sqlx::query_scalar!("SELECT translation FROM translation_versions WHERE state = 'active'")
+            .fetch_all(pool)
+            .await?;
+
+    let mut loaded = HashMap::new();
+    for translation in translations {
+        if let Some(versification) = load(pool, &translation).await? {
+            loaded.insert(translation, Arc::new(versification));
+        }
+    }
+
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation_mode_parses_each_mode_name() {
+        assert_eq!("built-in".parse(), Ok(ValidationMode::BuiltIn));
+        assert_eq!("Counts".parse(), Ok(ValidationMode::Counts));
+        assert_eq!(" database ".parse(), Ok(ValidationMode::Database));
+        assert!("strict".parse::<ValidationMode>().is_err());
+    }
+}
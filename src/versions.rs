@@ -0,0 +1,262 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use sqlx::postgres::PgPool;
+
+use crate::{
+    cdn::{self, get_translation_key, CdnConfig},
+    error::BibleApiError,
+    internal_error,
+    passage_cache::PassageCache,
+};
+
+/// The TranslationVersion is one import of a translation. The state is one of
+/// pending (still being imported), active (being served), previous (kept in
+/// case the active import has to be rolled back) or rolled-back.
+#[derive(Debug, Serialize)]
+pub struct TranslationVersion {
+    pub translation: String,
+    pub version: i32,
+    pub state: String,
+    pub imported_at: String,
+}
+
+/// The Translation is a translation the deployment knows of, with the
+/// version of it being served. A translation without an active version has
+/// not been imported yet, and can not be searched.
+#[derive(Debug, Serialize)]
+pub struct Translation {
+    pub translation: String,
+    pub name: String,
+    pub language: String,
+    pub version: Option<i32>,
+}
+
+/// The translations handler serves /translations with every translation,
+/// by code.
+pub async fn translations(
+    State(pool): State<PgPool>,
+) -> Result<Json<Vec<Translation>>, BibleApiError> {
+    sqlx::query_as!(
+        Translation,
+        r#"
+            SELECT
+                t.translation,
+                t.name,
+                t.language,
+                v.version as "version?"
+            FROM translations t
+                LEFT JOIN translation_versions v ON v.translation = t.translation
+                    AND v.state = 'active'
+          ORDER BY t.translation
+        "#
+    )
+    .fetch_all(&pool)
+    .await
+    .map(Json)
+    .map_err(internal_error)
+}
+
+/// The is_active function returns whether a translation has a version
+/// being served.
+pub async fn is_active(pool: &PgPool, translation: &str) -> Result<bool, BibleApiError> {
+    let active = sqlx::query_scalar!(
+        r#"
+            SELECT EXISTS (
+                SELECT 1 FROM translation_versions
+                WHERE translation = $1 AND state = 'active'
+            ) as "active!"
+        "#,
+        translation
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(active)
+}
+
+/// The get_versions function lists every import of a translation, newest
+/// first.
+pub async fn get_versions(
+    pool: &PgPool,
+    translation: &str,
+) -> Result<Vec<TranslationVersion>, BibleApiError> {
+    sqlx::query_as!(
+        TranslationVersion,
+        r#"
+            SELECT
+                translation,
+                version,
+                state,
+                imported_at::text as "imported_at!"
+            FROM translation_versions
+            WHERE translation = $1
+          ORDER BY version DESC
+        "#,
+        translation
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(internal_error)
+}
+
+/// The get_active_versions function lists the version of every translation
+/// that is being served.
+pub async fn get_active_versions(pool: &PgPool) -> Result<Vec<TranslationVersion>, sqlx::Error> {
+    sqlx::query_as!(
+        TranslationVersion,
+        r#"
+            SELECT
+                translation,
+                version,
+                state,
+                imported_at::text as "imported_at!"
+            FROM translation_versions
+            WHERE state = 'active'
+          ORDER BY translation
+        "#
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// The clear_cached function drops what is cached of a translation once the
+/// version being served changes: the passages in the passage cache, then the
+/// responses at the CDN, so the CDN does not fetch the old verses again. The
+/// responses are cached as immutable, so they would otherwise be served long
+/// after. A cache that can not be cleared is logged.
+pub async fn clear_cached(cache: &PassageCache, cdn_config: Option<&CdnConfig>, translation: &str) {
+    if let Err(err) = cache.invalidate(Some(translation), None).await {
+        tracing::warn!(
+            "could not clear the cached passages of {}: {}",
+            translation,
+            err
+        );
+    }
+
+    if let Some(cdn_config) = cdn_config {
+        if let Err(err) = cdn::purge(cdn_config, &[get_translation_key(translation)]).await {
+            tracing::warn!("could not purge {} from the CDN: {}", translation, err);
+        }
+    }
+}
+
+/// The rollback function makes the newest previous version of a translation
+/// the active one again, and marks the version it replaces as rolled back.
+/// The verses of both versions are left in place.
+pub async fn rollback(
+    pool: &PgPool,
+    translation: &str,
+) -> Result<TranslationVersion, BibleApiError> {
+    let mut transaction = pool.begin().await.map_err(internal_error)?;
+
+    let active = sqlx::query_scalar!(
+        "
+            SELECT version FROM translation_versions
+            WHERE translation = $1 AND state = 'active'
+            FOR UPDATE
+        ",
+        translation
+    )
+    .fetch_optional(&mut transaction)
+    .await
+    .map_err(internal_error)?
+    .ok_or(BibleApiError::from((
+        StatusCode::NOT_FOUND,
+        "No Matching Translation Found".to_string(),
+    )))?;
+
+    let previous = sqlx::query_scalar!(
+        "
+            SELECT version FROM translation_versions
+            WHERE translation = $1 AND state = 'previous'
+          ORDER BY version DESC
+            LIMIT 1
+            FOR UPDATE
+        ",
+        translation
+    )
+    .fetch_optional(&mut transaction)
+    .await
+    .map_err(internal_error)?
+    .ok_or(BibleApiError::from((
+        StatusCode::CONFLICT,
+        format!(
+            "there is no previous version of {} to roll back to",
+            translation
+        ),
+    )))?;
+
+    // The active version has to step aside first, as only one can be active
+    sqlx::query!(
+        "
+            UPDATE translation_versions SET state = 'rolled-back'
+            WHERE translation = $1 AND version = $2
+        ",
+        translation,
+        active
+    )
+    .execute(&mut transaction)
+    .await
+    .map_err(internal_error)?;
+
+    let restored = sqlx::query_as!(
+        TranslationVersion,
+        r#"
+            UPDATE translation_versions SET state = 'active'
+            WHERE translation = $1 AND version = $2
+            RETURNING translation, version, state, imported_at::text as "imported_at!"
+        "#,
+        translation,
+        previous
+    )
+    .fetch_one(&mut transaction)
+    .await
+    .map_err(internal_error)?;
+
+    transaction.commit().await.map_err(internal_error)?;
+
+    tracing::info!(
+        "rolled {} back from version {} to version {}",
+        translation,
+        active,
+        previous
+    );
+
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{SearchOptions, SearchResult, TextFormat},
+        passage_cache::PassageKey,
+        search::search,
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn clear_cached_drops_only_the_passages_of_the_translation() {
+        let cache = PassageCache::in_memory(8, Duration::from_secs(60));
+        let options = SearchOptions {
+            superscription: false,
+            format: TextFormat::Plain,
+        };
+        let key = |translation| PassageKey::new(translation, &search("John 1:1").unwrap(), options);
+        let verses = vec![SearchResult::new(
+            "kjv",
+            String::from("John"),
+            1,
+            1,
+            String::from("In the beginning was the Word"),
+            true,
+        )];
+        cache.insert(&key("kjv"), &verses).await;
+        cache.insert(&key("web"), &verses).await;
+
+        clear_cached(&cache, None, "kjv").await;
+
+        assert!(cache.get(&key("kjv")).await.is_none());
+        assert!(cache.get(&key("web")).await.is_some());
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Instant;
+
+use crate::{
+    db::{get_default_translation, SearchOptions},
+    render::Format,
+    search::{is_whole_chapter_with, search_with},
+    state::AppState,
+};
+
+/// The DEFAULT_PASSAGES are the passages warmed up when WARM_UP_PASSAGES is
+/// not set. They are among the most read.
+pub const DEFAULT_PASSAGES: &str = "John 3; Psalms 23; Romans 8";
+
+/// The get_passages function returns the passages to warm up on boot, read
+/// from WARM_UP_PASSAGES as references split by semicolons (ex: John 3;
+/// Psalms 23), since a reference can itself have commas. An empty list
+/// turns the warm up off.
+pub fn get_passages() -> Vec<String> {
+    let passages =
+        std::env::var("WARM_UP_PASSAGES").unwrap_or_else(|_| DEFAULT_PASSAGES.to_owned());
+
+    split_passages(&passages)
+}
+
+fn split_passages(passages: &str) -> Vec<String> {
+    passages
+        .split(';')
+        .map(str::trim)
+        .filter(|passage| !passage.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The spawn_warm_up function starts a background task that fetches each
+/// passage the way /search does, so the first readers after a deploy do not
+/// wait on a cold database. The server does not wait for it, and a passage
+/// that cannot be found or fetched is only logged.
+pub fn spawn_warm_up(state: AppState, passages: Vec<String>) {
+    if passages.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let mut warmed = 0;
+
+        for passage in &passages {
+            let versification = state.versifications.get(get_default_translation());
+            let bible_search = match search_with(passage, &versification) {
+                Ok(bible_search) => bible_search,
+                Err(err) => {
+                    tracing::warn!("could not warm up {}: {}", passage, err);
+                    continue;
+                }
+            };
+            let options = SearchOptions {
+                superscription: is_whole_chapter_with(&bible_search, &versification),
+                format: Format::Json.text_format(),
+            };
+
+            match state
+                .breaker
+                .search(get_default_translation(), bible_search, options)
+                .await
+            {
+                Ok(_) => warmed += 1,
+                Err(err) => tracing::warn!("could not warm up {}: {:?}", passage, err),
+            }
+        }
+
+        tracing::info!(
+            "warmed up {} of {} passages in {:?}",
+            warmed,
+            passages.len(),
+            started.elapsed()
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_passages_keeps_commas_within_a_reference() {
+        assert_eq!(
+            split_passages(" John 3:16, 18; Psalms 23 ;; "),
+            ["John 3:16, 18", "Psalms 23"]
+        );
+        assert!(split_passages("").is_empty());
+    }
+}